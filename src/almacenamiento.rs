@@ -0,0 +1,484 @@
+use crate::ancho_fijo::TablaAnchoFijo;
+use crate::archivo_temporal::ArchivoTemporal;
+use crate::binario::TablaBinaria;
+use crate::bloqueo::Bloqueo;
+use crate::condicion::Condicion;
+use crate::esquema::Esquema;
+use crate::formato::Formato;
+use crate::gzip::{self, TablaGzip};
+use crate::jsonl::TablaJsonl;
+use crate::memoria::{self, TablaMemoria};
+use crate::my_error::MyError;
+use crate::operadores::{Fila, Operador, Recorrido};
+use crate::particiones::{self, TablaParticionada};
+use crate::transaccion;
+use crate::wal;
+use std::fs::{self, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+
+///# Almacenamiento
+///Es la interfaz con la que las instrucciones leen y escriben una tabla, sin depender de cómo se
+///guarda. Cada formato de tabla la implementa; *almacenamiento::abrir()* elige cuál usar según la
+///dirección de la tabla.
+///
+///**Ejemplo**
+///let almacenamiento = almacenamiento::abrir(direccion_tabla);
+///let _bloqueo = almacenamiento.bloquear(false)?;
+///let mut filas = almacenamiento.recorrer(None)?;
+///while let Some(fila) = filas.siguiente()? { ... }
+pub trait Almacenamiento {
+    ///# Almacenamiento.archivo()
+    ///Devuelve la dirección de la tabla.
+    fn archivo(&self) -> &str;
+
+    ///# Almacenamiento.bloquear()
+    ///Bloquea la tabla para leerla (compartido) o modificarla (exclusivo) hasta que se descarte el
+    ///bloqueo devuelto. Devuelve *None* si el formato no necesita bloquear nada.
+    fn bloquear(&self, exclusivo: bool) -> Result<Option<Bloqueo>, MyError>;
+
+    ///# Almacenamiento.esquema()
+    ///Devuelve el esquema de la tabla.
+    fn esquema(&self) -> Result<Esquema, MyError>;
+
+    ///# Almacenamiento.recorrer()
+    ///Devuelve un operador que entrega las filas de la tabla, con las columnas del encabezado.
+    ///
+    ///**Parámetros**
+    ///- 'posiciones': Son las posiciones de las filas a leer que devolvió un índice, o *None* para
+    ///  leer la tabla completa.
+    fn recorrer(&self, posiciones: Option<Vec<u64>>) -> Result<Box<dyn Operador>, MyError>;
+
+    ///# Almacenamiento.recorrer_donde()
+    ///Es *recorrer()* para una consulta con WHERE. Los formatos que guardan la tabla en varias
+    ///partes pueden saltear las que no pueden cumplir la condición; el resto lee igual que
+    ///*recorrer()*. La condición se vuelve a verificar sobre cada fila devuelta.
+    ///
+    ///**Parámetros**
+    ///- 'posiciones': Son las posiciones de las filas a leer que devolvió un índice, o *None* para
+    ///  leer la tabla completa.
+    ///- 'condicion': Es la condición del WHERE.
+    fn recorrer_donde(
+        &self,
+        posiciones: Option<Vec<u64>>,
+        _condicion: &Condicion,
+    ) -> Result<Box<dyn Operador>, MyError> {
+        self.recorrer(posiciones)
+    }
+
+    ///# Almacenamiento.anexar()
+    ///Agrega filas al final de la tabla. Si falla la escritura la tabla queda como estaba.
+    ///
+    ///**Parámetros**
+    ///- 'filas': Son las filas a agregar, con los valores en el orden de las columnas.
+    fn anexar(&self, filas: &[Fila]) -> Result<(), MyError>;
+
+    ///# Almacenamiento.reescribir()
+    ///Empieza a reescribir la tabla completa. Las filas escritas reemplazan a las de la tabla
+    ///recién al publicar la reescritura; si se descarta antes la tabla queda como estaba.
+    ///
+    ///**Parámetros**
+    ///- 'columnas': Son los nombres de las columnas de la tabla nueva.
+    fn reescribir(&self, columnas: &[String]) -> Result<Box<dyn Reescritura>, MyError>;
+
+    ///# Almacenamiento.largo()
+    ///Devuelve el tamaño en bytes de la tabla, que es la posición que toman en los índices las
+    ///filas que se anexan.
+    fn largo(&self) -> Result<u64, MyError>;
+
+    ///# Almacenamiento.tablas_con_esquema()
+    ///Devuelve las direcciones de las demás tablas de la misma base que tienen un esquema propio,
+    ///que son las que pueden declarar una FOREIGN KEY hacia esta.
+    fn tablas_con_esquema(&self) -> Result<Vec<String>, MyError>;
+
+    ///# Almacenamiento.ubicacion_temporal()
+    ///Devuelve la dirección junto a la que se crean los archivos auxiliares de una instrucción,
+    ///como los tramos del ordenamiento en disco.
+    fn ubicacion_temporal(&self) -> String;
+}
+
+///# Reescritura
+///Es una tabla nueva que se está escribiendo para reemplazar a otra. Ver
+///*Almacenamiento.reescribir()*.
+pub trait Reescritura {
+    ///# Reescritura.escribir()
+    ///Agrega una fila al final de la tabla nueva.
+    fn escribir(&mut self, fila: &[String]) -> Result<(), MyError>;
+
+    ///# Reescritura.origen()
+    ///Indica la posición en la tabla original de la fila que se escribe a continuación, para que
+    ///los formatos que guardan algo más que el texto de cada valor lo conserven en las columnas
+    ///que no cambian.
+    ///
+    ///**Parámetros**
+    ///- 'posicion': Es la posición de la fila en la tabla original, si se conoce.
+    fn origen(&mut self, _posicion: Option<u64>) -> Result<(), MyError> {
+        Ok(())
+    }
+
+    ///# Reescritura.publicar()
+    ///Reemplaza la tabla original por la nueva.
+    fn publicar(self: Box<Self>) -> Result<(), MyError>;
+}
+
+///Son las extensiones de los archivos de tabla que se reconocen, en el orden en el que se buscan.
+const EXTENSIONES: [&str; 6] = [".csv", ".tsv", ".jsonl", ".bin", ".csv.gz", ".dat"];
+
+///# almacenamiento::abrir()
+///Esta función devuelve el almacenamiento que corresponde a la dirección de una tabla: las que
+///empiezan con *memoria://* son tablas de una *BaseEnMemoria*, las que terminan en *.jsonl*
+///archivos JSON Lines, las que terminan en *.bin* archivos binarios, las que terminan en
+///*.csv.gz* archivos CSV comprimidos, las que terminan en *.dat* archivos de ancho fijo y el resto
+///archivos CSV. Los directorios y los patrones con
+///comodines son tablas de varios archivos.
+///
+///**Parámetros**
+///- 'archivo': Es la dirección de la tabla.
+///
+///**Return**
+///Devuelve un *Box<dyn Almacenamiento>* con el que operar sobre la tabla.
+pub fn abrir(archivo: &str) -> Box<dyn Almacenamiento> {
+    if archivo.starts_with(memoria::PREFIJO) {
+        return Box::new(TablaMemoria::new(archivo));
+    }
+    if particiones::es_particionada(archivo) {
+        return Box::new(TablaParticionada::new(archivo));
+    }
+    match extension(archivo) {
+        ".jsonl" => Box::new(TablaJsonl::new(archivo)),
+        ".bin" => Box::new(TablaBinaria::new(archivo)),
+        ".csv.gz" => Box::new(TablaGzip::new(archivo)),
+        ".dat" => Box::new(TablaAnchoFijo::new(archivo)),
+        _ => Box::new(TablaCsv::new(archivo)),
+    }
+}
+
+///# almacenamiento::resolver()
+///Esta función arma la dirección de una tabla a partir de su nombre, eligiendo el archivo del
+///directorio que tenga una extensión conocida (*.csv*, *.tsv*, *.jsonl*, *.bin*, *.csv.gz* o *.dat*,
+///en ese orden). Si no existe ninguno pero hay un directorio con ese nombre, la tabla es el directorio;
+///si no, se usa *.csv*. Los nombres con comodines se usan tal cual, como patrón de archivos. Si el
+///directorio es el de trabajo de una transacción, antes se copian a él los archivos de la tabla.
+///
+///**Parámetros**
+///- 'directorio': Es el directorio (o la base en memoria) de la tabla.
+///- 'tabla': Es el nombre de la tabla.
+///
+///**Return**
+///Devuelve un *Result<String, MyError>*, en caso de que no se pueda copiar la tabla a la
+///  transacción se devuelve un error del tipo *MyError*.
+pub fn resolver(directorio: &str, tabla: &str) -> Result<String, MyError> {
+    let base = format!("{}/{}", directorio, tabla);
+    if directorio.starts_with(memoria::PREFIJO) {
+        return Ok(base + ".csv");
+    }
+    transaccion::preparar(directorio, tabla)?;
+    if particiones::es_patron(tabla) {
+        return Ok(base);
+    }
+    for extension in EXTENSIONES {
+        let archivo = base.clone() + extension;
+        if Path::new(&archivo).exists() {
+            return Ok(archivo);
+        }
+    }
+    match Path::new(&base).is_dir() {
+        true => Ok(base),
+        false => Ok(base + ".csv"),
+    }
+}
+
+///# almacenamiento::sin_extension()
+///Esta función devuelve la dirección de una tabla sin la extensión de su formato, que es la base
+///de los archivos que la acompañan (esquema, secuencia).
+///
+///**Parámetros**
+///- 'archivo': Es la dirección de la tabla.
+pub fn sin_extension(archivo: &str) -> &str {
+    EXTENSIONES
+        .iter()
+        .find_map(|e| archivo.strip_suffix(e))
+        .unwrap_or(archivo)
+}
+
+///# almacenamiento::es_csv()
+///Esta función indica si la tabla se guarda en un archivo CSV (o TSV), que es el único formato
+///sobre el que se pueden crear índices o alterar la tabla.
+///
+///**Parámetros**
+///- 'archivo': Es la dirección de la tabla.
+pub fn es_csv(archivo: &str) -> bool {
+    !archivo.starts_with(memoria::PREFIJO)
+        && (archivo.ends_with(".csv") || archivo.ends_with(".tsv"))
+}
+
+///# almacenamiento::extension()
+///Esta función devuelve la extensión del formato de una tabla, o *.csv* si no tiene ninguna
+///conocida.
+///
+///**Parámetros**
+///- 'archivo': Es la dirección de la tabla.
+pub fn extension(archivo: &str) -> &'static str {
+    EXTENSIONES
+        .iter()
+        .find(|e| archivo.ends_with(*e))
+        .unwrap_or(&EXTENSIONES[0])
+}
+
+///# TablaCsv
+///Es el almacenamiento de una tabla en un archivo CSV: la primera línea tiene los nombres de las
+///columnas y cada una de las siguientes una fila, con los valores separados por el delimitador de
+///su *Formato* (por defecto comas). El esquema se guarda en el archivo *.esquema* de al lado.
+#[derive(Debug, PartialEq)]
+pub struct TablaCsv {
+    archivo: String,
+}
+
+impl TablaCsv {
+    ///# TablaCsv::new()
+    ///Esta función crea una nueva instancia de TablaCsv.
+    ///
+    ///**Parámetros**
+    ///- 'archivo': Es la dirección del archivo CSV de la tabla.
+    ///
+    ///**Return**
+    ///Devuelve un *struct* de tipo *TablaCsv*.
+    pub fn new(archivo: &str) -> Self {
+        TablaCsv {
+            archivo: archivo.to_string(),
+        }
+    }
+
+    ///# TablaCsv::crear()
+    ///Esta función empieza a escribir una tabla CSV nueva (o a reemplazar una existente) con el
+    ///encabezado y el formato indicados. La tabla aparece recién al publicar la reescritura.
+    ///
+    ///**Parámetros**
+    ///- 'archivo': Es la dirección del archivo de la tabla.
+    ///- 'columnas': Son los nombres de las columnas de la tabla.
+    ///- 'formato': Es el formato con el que se escriben las filas.
+    ///
+    ///**Return**
+    ///Devuelve un *Result<Box<dyn Reescritura>, MyError>*, en caso de que no se pueda crear el
+    ///  archivo se devuelve un error del tipo *MyError*.
+    pub fn crear(
+        archivo: &str,
+        columnas: &[String],
+        formato: Formato,
+    ) -> Result<Box<dyn Reescritura>, MyError> {
+        let mut temporal = ArchivoTemporal::crear(archivo)?;
+        temporal.escribir(&(formato.unir_encabezado(columnas) + "\n"))?;
+        Ok(Box::new(ReescrituraCsv { temporal, formato }))
+    }
+
+    fn formato(&self) -> Result<Formato, MyError> {
+        Formato::de_tabla(&self.archivo)
+    }
+}
+
+impl Almacenamiento for TablaCsv {
+    fn archivo(&self) -> &str {
+        &self.archivo
+    }
+
+    fn bloquear(&self, exclusivo: bool) -> Result<Option<Bloqueo>, MyError> {
+        bloquear_archivo(&self.archivo, exclusivo)
+    }
+
+    fn esquema(&self) -> Result<Esquema, MyError> {
+        Esquema::cargar(&self.archivo)
+    }
+
+    fn recorrer(&self, posiciones: Option<Vec<u64>>) -> Result<Box<dyn Operador>, MyError> {
+        Ok(Box::new(
+            Recorrido::abrir(&self.archivo, self.formato()?)?.con_posiciones(posiciones),
+        ))
+    }
+
+    fn anexar(&self, filas: &[Fila]) -> Result<(), MyError> {
+        let formato = self.formato()?;
+        let lineas: String = filas.iter().map(|f| formato.unir(f) + "\n").collect();
+        anexar_lineas(&self.archivo, &lineas)
+    }
+
+    fn reescribir(&self, columnas: &[String]) -> Result<Box<dyn Reescritura>, MyError> {
+        TablaCsv::crear(&self.archivo, columnas, self.formato()?)
+    }
+
+    fn largo(&self) -> Result<u64, MyError> {
+        largo_archivo(&self.archivo)
+    }
+
+    fn tablas_con_esquema(&self) -> Result<Vec<String>, MyError> {
+        tablas_del_directorio(&self.archivo)
+    }
+
+    fn ubicacion_temporal(&self) -> String {
+        String::from(&self.archivo)
+    }
+}
+
+///Reescribe una tabla CSV en un *ArchivoTemporal* que se renombra sobre la tabla al publicarlo.
+struct ReescrituraCsv {
+    temporal: ArchivoTemporal,
+    formato: Formato,
+}
+
+impl Reescritura for ReescrituraCsv {
+    fn escribir(&mut self, fila: &[String]) -> Result<(), MyError> {
+        self.temporal.escribir(&(self.formato.unir(fila) + "\n"))
+    }
+
+    fn publicar(self: Box<Self>) -> Result<(), MyError> {
+        self.temporal.publicar()
+    }
+}
+
+///# almacenamiento::bloquear_archivo()
+///Esta función bloquea el archivo de una tabla, de forma compartida o exclusiva.
+pub(crate) fn bloquear_archivo(archivo: &str, exclusivo: bool) -> Result<Option<Bloqueo>, MyError> {
+    match exclusivo {
+        true => Ok(Some(Bloqueo::exclusivo(archivo)?)),
+        false => Ok(Some(Bloqueo::compartido(archivo)?)),
+    }
+}
+
+///# almacenamiento::anexar_lineas()
+///Esta función agrega texto al final del archivo de una tabla, dejándolo registrado para poder
+///deshacerlo. Si falla la escritura el archivo vuelve a su largo original.
+pub(crate) fn anexar_lineas(archivo: &str, lineas: &str) -> Result<(), MyError> {
+    anexar_bytes(archivo, lineas.as_bytes())
+}
+
+///# almacenamiento::anexar_bytes()
+///Esta función es *anexar_lineas()* para las tablas cuyo archivo no es texto.
+pub(crate) fn anexar_bytes(archivo: &str, bytes: &[u8]) -> Result<(), MyError> {
+    let mut tabla = match OpenOptions::new().append(true).open(archivo) {
+        Ok(a) => a,
+        Err(_e) => {
+            return Err(MyError::Error(String::from(
+                "Fallo en la edición de la tabla",
+            )))
+        }
+    };
+    let largo_original = largo_archivo(archivo)?;
+
+    wal::registrar_anexo(archivo, largo_original)?;
+    if tabla
+        .write_all(bytes)
+        .and_then(|_| tabla.sync_data())
+        .is_err()
+    {
+        let _ = tabla.set_len(largo_original);
+        return Err(MyError::Error(String::from(
+            "Fallo en la escritura de la tabla",
+        )));
+    }
+    Ok(())
+}
+
+///# almacenamiento::primera_linea()
+///Esta función lee el encabezado de una tabla de texto, descomprimiéndolo si la tabla es un
+///*.csv.gz*.
+pub(crate) fn primera_linea(archivo: &str) -> Result<String, MyError> {
+    let mut lector: Box<dyn BufRead> = match archivo.ends_with(".csv.gz") {
+        true => Box::new(gzip::descomprimir(archivo)?),
+        false => match fs::File::open(archivo) {
+            Ok(f) => Box::new(BufReader::new(f)),
+            Err(_e) => {
+                return Err(MyError::InvalidTable(
+                    "Directorio o nombre de la tabla incorrecto".to_string(),
+                ))
+            }
+        },
+    };
+
+    let mut linea = String::new();
+    match lector.read_line(&mut linea) {
+        Ok(_) => Ok(linea),
+        Err(_e) => Err(MyError::Error(
+            "Fallo en la lectura de la tabla".to_string(),
+        )),
+    }
+}
+
+///# almacenamiento::largo_archivo()
+///Esta función devuelve el tamaño en bytes del archivo de una tabla.
+pub(crate) fn largo_archivo(archivo: &str) -> Result<u64, MyError> {
+    match fs::metadata(archivo) {
+        Ok(m) => Ok(m.len()),
+        Err(_e) => Err(MyError::InvalidTable(
+            "Directorio o nombre de la tabla incorrecto".to_string(),
+        )),
+    }
+}
+
+///# almacenamiento::tablas_del_directorio()
+///Esta función devuelve las demás tablas del directorio de una tabla que tienen un archivo
+///*.esquema*, con la extensión de su formato. Si el directorio es el de trabajo de una
+///transacción, antes se copian a él las tablas que hacen referencia a la tabla.
+pub(crate) fn tablas_del_directorio(archivo: &str) -> Result<Vec<String>, MyError> {
+    let directorio = match Path::new(archivo).parent() {
+        Some(d) if !d.as_os_str().is_empty() => d,
+        _ => Path::new("."),
+    };
+    if let Some(tabla) = Path::new(sin_extension(archivo)).file_name() {
+        transaccion::preparar_referencias(&directorio.to_string_lossy(), &tabla.to_string_lossy())?;
+    }
+
+    let entradas = match fs::read_dir(directorio) {
+        Ok(e) => e,
+        Err(_e) => {
+            return Err(MyError::InvalidTable(
+                "Directorio o nombre de la tabla incorrecto".to_string(),
+            ))
+        }
+    };
+
+    let mut tablas = Vec::new();
+    for entrada in entradas.flatten() {
+        let nombre = entrada.file_name().to_string_lossy().to_string();
+        if let Some(tabla) = nombre.strip_suffix(".esquema") {
+            let otra = resolver(&directorio.to_string_lossy(), tabla)?;
+            if otra != archivo && Path::new(&otra).exists() {
+                tablas.push(otra);
+            }
+        }
+    }
+    Ok(tablas)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::operadores;
+
+    #[test]
+    pub fn test01_se_anexan_y_se_reescriben_las_filas_de_una_tabla_csv() {
+        let archivo = "./test/indice/almacenamiento_csv.csv";
+        let _ = fs::create_dir_all("./test/indice");
+        let _ = fs::copy("./test/indice/clientes_copia.csv", archivo);
+        let tabla = abrir(archivo);
+        let fila = |valores: &[&str]| -> Fila { valores.iter().map(|v| v.to_string()).collect() };
+
+        tabla
+            .anexar(&[fila(&["4", "Eva", "eva@mail.com", "19"])])
+            .unwrap();
+        let columnas = tabla.recorrer(None).unwrap().columnas().to_vec();
+        let mut reescritura = tabla.reescribir(&columnas).unwrap();
+        reescritura
+            .escribir(&fila(&["2", "Ana", "ana@mail.com", "30"]))
+            .unwrap();
+        let antes_de_publicar = operadores::recolectar(tabla.recorrer(None).unwrap().as_mut())
+            .unwrap()
+            .len();
+        reescritura.publicar().unwrap();
+
+        assert_eq!(antes_de_publicar, 4);
+        assert_eq!(
+            fs::read_to_string(archivo).unwrap(),
+            "id,nombre,email,edad\n2,Ana,ana@mail.com,30\n"
+        );
+        assert_eq!(tabla.largo().unwrap(), 43);
+    }
+}
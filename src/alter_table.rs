@@ -0,0 +1,462 @@
+use crate::almacenamiento;
+use crate::archivo_temporal::ArchivoTemporal;
+use crate::bloqueo::Bloqueo;
+use crate::esquema::{Columna, Esquema};
+use crate::formato::Formato;
+use crate::indice;
+use crate::my_error::MyError;
+use crate::secuencia::{self, Secuencia};
+use crate::transaccion;
+use crate::wal;
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+///# Alteracion
+///Representa los cambios que se le pueden hacer a la estructura de una tabla.
+///
+///**Tipos**
+///- 'AgregarColumna': Agrega la columna al final de la tabla (ADD COLUMN).
+///- 'EliminarColumna': Elimina la columna con ese nombre (DROP COLUMN).
+///- 'RenombrarColumna': Cambia el nombre de una columna por otro (RENAME COLUMN a TO b).
+///- 'RenombrarTabla': Cambia el nombre de la tabla (RENAME TO nuevo).
+#[derive(Debug, PartialEq)]
+pub enum Alteracion {
+    AgregarColumna(Columna),
+    EliminarColumna(String),
+    RenombrarColumna(String, String),
+    RenombrarTabla(String),
+}
+
+///# AlterTable
+///Esta estructura proporciona toda la funcionalidad para implementar la instrucción ALTER TABLE
+///de sql.
+///
+///**Ejemplo**
+///let alter = AlterTable::new(direccion_archivo, Alteracion::EliminarColumna(columna));
+///alter.alterar();
+///
+///**Parámetros**
+///- 'archivo': Es la dirección del archivo que representa a la tabla que se quiere modificar.
+///- 'alteracion': Es el cambio que se le quiere hacer a la tabla.
+#[derive(Debug, PartialEq)]
+pub struct AlterTable {
+    archivo: String,
+    alteracion: Alteracion,
+}
+
+impl AlterTable {
+    ///# AlterTable.new()
+    ///Esta función crea una nueva instacia de AlterTable.
+    ///
+    ///**Parámetros**
+    ///- 'archivo': Es la dirección del archivo que representa a la tabla que se quiere modificar.
+    ///- 'alteracion': Es el cambio que se le quiere hacer a la tabla.
+    ///
+    ///**Return**
+    ///Devuelve un *struct* del tipo *AlterTable*.
+    pub fn new(archivo: String, alteracion: Alteracion) -> Self {
+        AlterTable {
+            archivo,
+            alteracion,
+        }
+    }
+
+    ///# AlterTable.alterar()
+    ///Esta función realiza la instrucción ALTER TABLE de sql. Los cambios sobre las columnas
+    ///reescriben la tabla en un archivo temporal que luego reemplaza al original, y actualizan el
+    ///esquema y los índices de la tabla. Al eliminar una columna también se eliminan sus índices.
+    ///
+    ///**Return**
+    ///Devuelve un *Result<String, MyError>* en caso que durante la ejecución de la función no haya
+    ///  ocurrido ningún error se devuelve un *String* para indicar que la operación se realizó
+    ///  correctamente, en caso contrario se devuelve un error del tipo *MyError*.
+    pub fn alterar(&self) -> Result<String, MyError> {
+        if !almacenamiento::es_csv(&self.archivo) {
+            return Err(MyError::InvalidTable(
+                "ALTER TABLE solo se puede usar sobre tablas guardadas en archivos CSV".to_string(),
+            ));
+        }
+        let _bloqueo = Bloqueo::exclusivo(&self.archivo)?;
+        let mut esquema = Esquema::cargar(&self.archivo)?;
+
+        match &self.alteracion {
+            Alteracion::AgregarColumna(columna) => {
+                if esquema.columna(columna.nombre()).is_some() {
+                    return Err(MyError::InvalidColumn(format!(
+                        "La columna {} ya existe en la tabla",
+                        columna.nombre()
+                    )));
+                }
+
+                if columna.es_auto_incremental() && esquema.auto_incremental().is_some() {
+                    return Err(MyError::InvalidColumn(
+                        "La tabla ya tiene una columna AUTO_INCREMENT".to_string(),
+                    ));
+                }
+
+                let valor = columna.resolver_valor_por_defecto();
+                if !columna.es_auto_incremental() {
+                    self.verificar_columna_nueva(columna, &valor)?;
+                }
+
+                let nombre = columna.nombre().to_string();
+                let mut contador = 0;
+                self.reescribir_tabla(|fila, es_encabezado| match es_encabezado {
+                    true => fila.push(String::from(&nombre)),
+                    false if columna.es_auto_incremental() => {
+                        contador += 1;
+                        fila.push(contador.to_string());
+                    }
+                    false => fila.push(String::from(&valor)),
+                })?;
+
+                if columna.es_auto_incremental() {
+                    secuencia::eliminar(&self.archivo)?;
+                }
+
+                esquema.agregar_columna(columna.clone());
+            }
+            Alteracion::EliminarColumna(nombre) => {
+                let pos = self.posicion_columna(&esquema, nombre)?;
+
+                if esquema.usa_en_check(nombre) {
+                    return Err(MyError::InvalidColumn(format!(
+                        "No se puede eliminar la columna {} porque la usa una restricción CHECK",
+                        nombre
+                    )));
+                }
+
+                if esquema.columnas().len() == 1 {
+                    return Err(MyError::InvalidColumn(
+                        "No se puede eliminar la única columna de la tabla".to_string(),
+                    ));
+                }
+
+                self.reescribir_tabla(|fila, _| {
+                    if pos < fila.len() {
+                        fila.remove(pos);
+                    }
+                })?;
+
+                if esquema.auto_incremental() == Some(pos) {
+                    secuencia::eliminar(&self.archivo)?;
+                }
+                for eliminado in esquema.eliminar_indices_de(nombre) {
+                    let ruta = eliminado.ruta(&self.archivo);
+                    wal::registrar(&ruta)?;
+                    let _ = fs::remove_file(ruta);
+                }
+                esquema.eliminar_columna(pos);
+            }
+            Alteracion::RenombrarColumna(anterior, nuevo) => {
+                let pos = self.posicion_columna(&esquema, anterior)?;
+
+                if esquema.columna(nuevo).is_some() {
+                    return Err(MyError::InvalidColumn(format!(
+                        "La columna {} ya existe en la tabla",
+                        nuevo
+                    )));
+                }
+
+                self.reescribir_tabla(|fila, es_encabezado| {
+                    if es_encabezado {
+                        fila[pos] = String::from(nuevo);
+                    }
+                })?;
+
+                esquema.renombrar_columna(pos, nuevo);
+            }
+            Alteracion::RenombrarTabla(nuevo) => return self.renombrar_tabla(&esquema, nuevo),
+        }
+
+        esquema.guardar(&self.archivo)?;
+        indice::reconstruir(&self.archivo, &esquema)?;
+        Ok(String::from("Se modificó la tabla correctamente"))
+    }
+
+    fn posicion_columna(&self, esquema: &Esquema, nombre: &str) -> Result<usize, MyError> {
+        match esquema.posicion(nombre) {
+            Some(p) => Ok(p),
+            None => Err(MyError::InvalidColumn(format!(
+                "La columna {} no existe en la tabla",
+                nombre
+            ))),
+        }
+    }
+
+    fn verificar_columna_nueva(&self, columna: &Columna, valor: &str) -> Result<(), MyError> {
+        if !columna.es_no_nula() && !columna.es_unica() {
+            return Ok(());
+        }
+
+        let filas = match fs::read_to_string(&self.archivo) {
+            Ok(c) => c.lines().count().saturating_sub(1),
+            Err(_e) => {
+                return Err(MyError::InvalidTable(
+                    "Directorio o nombre de la tabla incorrecto".to_string(),
+                ))
+            }
+        };
+
+        if filas > 0 && valor.is_empty() && columna.es_no_nula() {
+            return Err(MyError::ConstraintViolation(format!(
+                "La columna {} no puede ser vacía y la tabla ya tiene filas, debe indicarse un valor por defecto",
+                columna.nombre()
+            )));
+        }
+
+        if filas > 1 && !valor.is_empty() && columna.es_unica() {
+            return Err(MyError::ConstraintViolation(format!(
+                "La columna {} debe tener valores únicos y el valor por defecto se repetiría en todas las filas",
+                columna.nombre()
+            )));
+        }
+
+        Ok(())
+    }
+
+    fn renombrar_tabla(&self, esquema: &Esquema, nuevo: &str) -> Result<String, MyError> {
+        let directorio = Path::new(&self.archivo)
+            .parent()
+            .unwrap_or_else(|| Path::new("."));
+        transaccion::preparar(&directorio.to_string_lossy(), nuevo)?;
+        let archivo_nuevo = directorio
+            .join(format!(
+                "{}{}",
+                nuevo,
+                almacenamiento::extension(&self.archivo)
+            ))
+            .to_string_lossy()
+            .to_string();
+
+        if Path::new(&archivo_nuevo).exists() {
+            return Err(MyError::InvalidTable(format!(
+                "La tabla {} ya existe",
+                nuevo
+            )));
+        }
+
+        for ruta in [
+            String::from(&self.archivo),
+            String::from(&archivo_nuevo),
+            Esquema::ruta(&self.archivo),
+            Secuencia::ruta(&self.archivo),
+            Secuencia::ruta(&archivo_nuevo),
+        ] {
+            wal::registrar(&ruta)?;
+        }
+
+        if fs::rename(&self.archivo, &archivo_nuevo).is_err() {
+            return Err(MyError::Error(
+                "Fallo en el proceso de renombrar la tabla".to_string(),
+            ));
+        }
+
+        let _ = fs::remove_file(Esquema::ruta(&self.archivo));
+        esquema.guardar(&archivo_nuevo)?;
+
+        if Path::new(&Secuencia::ruta(&self.archivo)).exists()
+            && fs::rename(
+                Secuencia::ruta(&self.archivo),
+                Secuencia::ruta(&archivo_nuevo),
+            )
+            .is_err()
+        {
+            return Err(MyError::Error(
+                "Fallo en el proceso de renombrar la secuencia de la tabla".to_string(),
+            ));
+        }
+
+        Ok(String::from("Se renombró la tabla correctamente"))
+    }
+
+    fn reescribir_tabla<F>(&self, mut transformar: F) -> Result<String, MyError>
+    where
+        F: FnMut(&mut Vec<String>, bool),
+    {
+        let archivo = match File::open(&self.archivo) {
+            Ok(f) => f,
+            Err(_e) => {
+                return Err(MyError::InvalidTable(
+                    "Directorio o nombre de la tabla incorrecto".to_string(),
+                ))
+            }
+        };
+        let buffer = BufReader::new(archivo);
+        let formato = Formato::de_tabla(&self.archivo)?;
+
+        let mut archivo_temporal = ArchivoTemporal::crear(&self.archivo)?;
+        let mut columnas = 0;
+
+        for (i, line) in buffer.lines().enumerate() {
+            let linea = match line {
+                Ok(l) => l,
+                Err(_e) => {
+                    return Err(MyError::Error(
+                        "Fallo en el proceso de modificación de la tabla".to_string(),
+                    ))
+                }
+            };
+
+            let mut fila = match i {
+                0 => formato.separar(&linea),
+                _ => formato.separar_fila(&linea, columnas)?,
+            };
+            if i == 0 {
+                columnas = fila.len();
+            }
+            transformar(&mut fila, i == 0);
+
+            let escrita = match i {
+                0 => formato.unir_encabezado(&fila),
+                _ => formato.unir(&fila),
+            };
+            archivo_temporal.escribir(&(escrita + "\n"))?;
+        }
+
+        archivo_temporal.publicar()?;
+        Ok(String::from("Tabla reescrita"))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::esquema::TipoDato;
+
+    fn preparar_tabla(destino: &str) {
+        let _ = fs::copy("./test/alter/alter_copia.csv", destino);
+        let _ = fs::remove_file(Esquema::ruta(destino));
+    }
+
+    #[test]
+    pub fn test01_se_agrega_una_columna_con_valor_por_defecto() {
+        preparar_tabla("./test/alter/alter_agregar.csv");
+
+        let columna = Columna::desde_definicion(&[
+            "stock".to_string(),
+            "INT".to_string(),
+            "DEFAULT".to_string(),
+            "0".to_string(),
+        ])
+        .unwrap();
+        let operacion = AlterTable::new(
+            "./test/alter/alter_agregar.csv".to_string(),
+            Alteracion::AgregarColumna(columna),
+        );
+
+        assert!(operacion.alterar().is_ok());
+
+        let contenido = fs::read_to_string("./test/alter/alter_agregar.csv").unwrap();
+        assert!(contenido.starts_with("id,producto,cantidad,stock\n101,Laptop,1,0\n"));
+
+        let esquema = Esquema::cargar("./test/alter/alter_agregar.csv").unwrap();
+        assert_eq!(*esquema.columna("stock").unwrap().tipo(), TipoDato::Entero);
+    }
+
+    #[test]
+    pub fn test02_se_elimina_una_columna() {
+        preparar_tabla("./test/alter/alter_eliminar.csv");
+
+        let operacion = AlterTable::new(
+            "./test/alter/alter_eliminar.csv".to_string(),
+            Alteracion::EliminarColumna("producto".to_string()),
+        );
+
+        assert!(operacion.alterar().is_ok());
+
+        let contenido = fs::read_to_string("./test/alter/alter_eliminar.csv").unwrap();
+        assert!(contenido.starts_with("id,cantidad\n101,1\n"));
+    }
+
+    #[test]
+    pub fn test03_se_renombra_una_columna() {
+        preparar_tabla("./test/alter/alter_renombrar_columna.csv");
+
+        let operacion = AlterTable::new(
+            "./test/alter/alter_renombrar_columna.csv".to_string(),
+            Alteracion::RenombrarColumna("cantidad".to_string(), "unidades".to_string()),
+        );
+
+        assert!(operacion.alterar().is_ok());
+
+        let esquema = Esquema::cargar("./test/alter/alter_renombrar_columna.csv").unwrap();
+        assert_eq!(esquema.nombres(), vec!["id", "producto", "unidades"]);
+    }
+
+    #[test]
+    pub fn test04_se_renombra_una_tabla() {
+        preparar_tabla("./test/alter/alter_renombrar.csv");
+        let _ = fs::remove_file("./test/alter/alter_renombrada.csv");
+
+        let operacion = AlterTable::new(
+            "./test/alter/alter_renombrar.csv".to_string(),
+            Alteracion::RenombrarTabla("alter_renombrada".to_string()),
+        );
+
+        assert!(operacion.alterar().is_ok());
+        assert!(!Path::new("./test/alter/alter_renombrar.csv").exists());
+        assert!(Path::new("./test/alter/alter_renombrada.csv").exists());
+        assert!(Path::new("./test/alter/alter_renombrada.esquema").exists());
+    }
+
+    #[test]
+    pub fn test05_falla_al_agregar_una_columna_not_null_sin_valor_por_defecto() {
+        let columna = Columna::desde_definicion(&[
+            "stock".to_string(),
+            "INT".to_string(),
+            "NOT".to_string(),
+            "NULL".to_string(),
+        ])
+        .unwrap();
+        let operacion = AlterTable::new(
+            "./test/alter/alter_copia.csv".to_string(),
+            Alteracion::AgregarColumna(columna),
+        );
+
+        let resultado = operacion.alterar();
+
+        assert!(matches!(resultado, Err(MyError::ConstraintViolation(_))));
+    }
+
+    #[test]
+    pub fn test06_falla_al_eliminar_una_columna_que_no_existe() {
+        let operacion = AlterTable::new(
+            "./test/alter/alter_copia.csv".to_string(),
+            Alteracion::EliminarColumna("no_existe".to_string()),
+        );
+
+        let resultado = operacion.alterar();
+
+        assert!(matches!(resultado, Err(MyError::InvalidColumn(_))));
+    }
+
+    #[test]
+    pub fn test07_se_agrega_una_columna_auto_incremental_numerando_las_filas() {
+        preparar_tabla("./test/alter/alter_auto_incremental.csv");
+
+        let columna = Columna::desde_definicion(&[
+            "numero".to_string(),
+            "INT".to_string(),
+            "UNIQUE".to_string(),
+            "AUTO_INCREMENT".to_string(),
+        ])
+        .unwrap();
+        let operacion = AlterTable::new(
+            "./test/alter/alter_auto_incremental.csv".to_string(),
+            Alteracion::AgregarColumna(columna),
+        );
+
+        assert!(operacion.alterar().is_ok());
+
+        let contenido = fs::read_to_string("./test/alter/alter_auto_incremental.csv").unwrap();
+        assert_eq!(
+            contenido,
+            "id,producto,cantidad,numero\n101,Laptop,1,1\n103,Monitor,1,2\n105,Teclado,3,3\n"
+        );
+
+        let esquema = Esquema::cargar("./test/alter/alter_auto_incremental.csv").unwrap();
+        assert_eq!(esquema.auto_incremental(), Some(3));
+    }
+}
@@ -0,0 +1,240 @@
+use crate::almacenamiento::{self, Almacenamiento, Reescritura};
+use crate::bloqueo::Bloqueo;
+use crate::esquema::Esquema;
+use crate::my_error::MyError;
+use crate::operadores::{Fila, Operador};
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+///# TablaAnchoFijo
+///Es el almacenamiento de una tabla en un archivo de ancho fijo (*tabla.dat*), como los que se
+///exportan desde un mainframe: cada línea es una fila y cada columna ocupa siempre los mismos
+///caracteres de la línea, sin separadores ni encabezado. Dónde empieza y cuánto ocupa cada columna
+///lo indica el esquema, por ejemplo *nombre TEXT POSITION 6 20*, por lo que la tabla no se puede
+///leer sin él. Los espacios que completan cada valor se quitan al leerlo.
+///
+///Las tablas de ancho fijo solo se pueden leer; para modificarlas se exportan a CSV con
+///*COPY tabla TO 'archivo.csv'*.
+#[derive(Debug, PartialEq)]
+pub struct TablaAnchoFijo {
+    archivo: String,
+}
+
+impl TablaAnchoFijo {
+    ///# TablaAnchoFijo::new()
+    ///Esta función crea una nueva instancia de TablaAnchoFijo.
+    ///
+    ///**Parámetros**
+    ///- 'archivo': Es la dirección del archivo *.dat* de la tabla.
+    ///
+    ///**Return**
+    ///Devuelve un *struct* de tipo *TablaAnchoFijo*.
+    pub fn new(archivo: &str) -> Self {
+        TablaAnchoFijo {
+            archivo: archivo.to_string(),
+        }
+    }
+}
+
+impl Almacenamiento for TablaAnchoFijo {
+    fn archivo(&self) -> &str {
+        &self.archivo
+    }
+
+    fn bloquear(&self, exclusivo: bool) -> Result<Option<Bloqueo>, MyError> {
+        almacenamiento::bloquear_archivo(&self.archivo, exclusivo)
+    }
+
+    fn esquema(&self) -> Result<Esquema, MyError> {
+        if !Path::new(&Esquema::ruta(&self.archivo)).exists() {
+            return Err(MyError::InvalidTable(
+                "Las tablas de ancho fijo necesitan un esquema con la posición de cada columna"
+                    .to_string(),
+            ));
+        }
+        let esquema = Esquema::cargar(&self.archivo)?;
+        if let Some(columna) = esquema.columnas().iter().find(|c| c.ubicacion().is_none()) {
+            return Err(MyError::InvalidColumn(format!(
+                "La columna {} no tiene POSITION en el esquema de la tabla de ancho fijo",
+                columna.nombre()
+            )));
+        }
+        Ok(esquema)
+    }
+
+    fn recorrer(&self, posiciones: Option<Vec<u64>>) -> Result<Box<dyn Operador>, MyError> {
+        let esquema = self.esquema()?;
+        let lector = match File::open(&self.archivo) {
+            Ok(f) => BufReader::new(f),
+            Err(_e) => {
+                return Err(MyError::InvalidTable(
+                    "Directorio o nombre de la tabla incorrecto".to_string(),
+                ))
+            }
+        };
+
+        Ok(Box::new(RecorridoAnchoFijo {
+            columnas: esquema.nombres(),
+            ubicaciones: esquema
+                .columnas()
+                .iter()
+                .filter_map(|c| c.ubicacion())
+                .collect(),
+            lector,
+            posiciones,
+            siguiente_posicion: 0,
+            ultima_posicion: 0,
+        }))
+    }
+
+    fn anexar(&self, _filas: &[Fila]) -> Result<(), MyError> {
+        Err(error_de_solo_lectura())
+    }
+
+    fn reescribir(&self, _columnas: &[String]) -> Result<Box<dyn Reescritura>, MyError> {
+        Err(error_de_solo_lectura())
+    }
+
+    fn largo(&self) -> Result<u64, MyError> {
+        almacenamiento::largo_archivo(&self.archivo)
+    }
+
+    fn tablas_con_esquema(&self) -> Result<Vec<String>, MyError> {
+        almacenamiento::tablas_del_directorio(&self.archivo)
+    }
+
+    fn ubicacion_temporal(&self) -> String {
+        String::from(&self.archivo)
+    }
+}
+
+///Lee las líneas de la tabla y corta cada una en los valores de sus columnas. La posición de cada
+///fila es el byte del archivo en el que empieza. Las líneas vacías no son filas.
+struct RecorridoAnchoFijo {
+    columnas: Vec<String>,
+    ubicaciones: Vec<(usize, usize)>,
+    lector: BufReader<File>,
+    posiciones: Option<Vec<u64>>,
+    siguiente_posicion: u64,
+    ultima_posicion: u64,
+}
+
+impl RecorridoAnchoFijo {
+    fn cortar(&self, linea: &str) -> Fila {
+        let caracteres: Vec<char> = linea.chars().collect();
+        self.ubicaciones
+            .iter()
+            .map(|(inicio, largo)| {
+                let desde = (inicio - 1).min(caracteres.len());
+                let hasta = (inicio - 1 + largo).min(caracteres.len());
+                caracteres[desde..hasta]
+                    .iter()
+                    .collect::<String>()
+                    .trim()
+                    .to_string()
+            })
+            .collect()
+    }
+}
+
+impl Operador for RecorridoAnchoFijo {
+    fn columnas(&self) -> &[String] {
+        &self.columnas
+    }
+
+    fn siguiente(&mut self) -> Result<Option<Fila>, MyError> {
+        loop {
+            let mut linea = String::new();
+            let largo = match self.lector.read_line(&mut linea) {
+                Ok(0) => return Ok(None),
+                Ok(l) => l as u64,
+                Err(_e) => {
+                    return Err(MyError::Error(
+                        "Fallo en la lectura de la tabla de ancho fijo".to_string(),
+                    ))
+                }
+            };
+            let posicion = self.siguiente_posicion;
+            self.siguiente_posicion += largo;
+
+            let linea = linea.trim_end_matches('\n').trim_end_matches('\r');
+            let elegida = match &self.posiciones {
+                Some(p) => p.binary_search(&posicion).is_ok(),
+                None => !linea.is_empty(),
+            };
+            if elegida {
+                self.ultima_posicion = posicion;
+                return Ok(Some(self.cortar(linea)));
+            }
+        }
+    }
+
+    fn posicion(&self) -> Option<u64> {
+        Some(self.ultima_posicion)
+    }
+}
+
+fn error_de_solo_lectura() -> MyError {
+    MyError::InvalidTable(
+        "Las tablas de ancho fijo solo se pueden leer, para modificarlas se pueden exportar a CSV"
+            .to_string(),
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::pruebas::ejecutar;
+    use std::fs;
+
+    #[test]
+    pub fn test01_se_lee_una_tabla_de_ancho_fijo_y_se_exporta_a_csv() {
+        let directorio = "./test/ancho_fijo";
+        let archivo = format!("{}/extracto.dat", directorio);
+        let exportado = format!("{}/extracto_exportado.csv", directorio);
+        let _ = fs::create_dir_all(directorio);
+        let _ = fs::remove_file(&exportado);
+        fs::write(
+            &archivo,
+            "00001Perez, Juan         0150\n00002Gomez Ana           0020\n\n00003Ruiz\n",
+        )
+        .unwrap();
+        fs::write(
+            Esquema::ruta(&archivo),
+            "id INT POSITION 1 5\nnombre TEXT POSITION 6 20\nsaldo INT POSITION 26 4\n",
+        )
+        .unwrap();
+
+        let filas = crate::operadores::recolectar(
+            almacenamiento::abrir(&almacenamiento::resolver(directorio, "extracto").unwrap())
+                .recorrer(None)
+                .unwrap()
+                .as_mut(),
+        )
+        .unwrap();
+        let insert = ejecutar(
+            directorio,
+            "INSERT INTO extracto (id, nombre, saldo) VALUES (4, Sosa, 1)",
+        );
+        ejecutar(directorio, &format!("COPY extracto TO '{}'", exportado)).unwrap();
+        let repetido = ejecutar(directorio, &format!("COPY extracto TO '{}'", exportado));
+        let convertida = ejecutar(directorio, "CONVERT TABLE extracto TO CSV");
+
+        assert_eq!(
+            filas,
+            vec![
+                vec!["00001", "Perez, Juan", "0150"],
+                vec!["00002", "Gomez Ana", "0020"],
+                vec!["00003", "Ruiz", ""],
+            ]
+        );
+        assert!(matches!(insert, Err(MyError::InvalidTable(_))));
+        assert!(matches!(repetido, Err(MyError::InvalidTable(_))));
+        assert_eq!(
+            fs::read_to_string(&exportado).unwrap(),
+            "\"id\",\"nombre\",\"saldo\"\n00001,\"Perez, Juan\",0150\n00002,Gomez Ana,0020\n00003,Ruiz,\n"
+        );
+        assert!(matches!(convertida, Err(MyError::InvalidTable(_))));
+    }
+}
@@ -0,0 +1,230 @@
+use crate::my_error::MyError;
+use crate::wal;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufWriter, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static CONTADOR: AtomicUsize = AtomicUsize::new(0);
+
+///# ArchivoTemporal
+///Esta estructura se usa para reescribir un archivo de forma segura. El contenido nuevo se
+///escribe en un archivo temporal con nombre único en el mismo directorio que el archivo original,
+///y recién al publicarlo se sincroniza con el disco y se renombra sobre el original. Si se
+///descarta sin publicar (por un error o un return anticipado) el archivo temporal se borra.
+///
+///**Ejemplo**
+///let mut temporal = ArchivoTemporal::crear(direccion_tabla)?;
+///temporal.escribir("id,nombre\n")?;
+///temporal.publicar()?;
+///
+///**Parámetros**
+///- 'ruta': Es la dirección del archivo temporal.
+///- 'destino': Es la dirección del archivo que se reemplaza al publicar.
+///- 'escritor': Es el buffer de escritura sobre el archivo temporal.
+///- 'publicado': Indica si el archivo temporal ya reemplazó al original.
+#[derive(Debug)]
+pub struct ArchivoTemporal {
+    ruta: PathBuf,
+    destino: PathBuf,
+    escritor: BufWriter<File>,
+    publicado: bool,
+}
+
+impl ArchivoTemporal {
+    ///# ArchivoTemporal::crear()
+    ///Esta función crea un archivo temporal vacío al lado del archivo que se quiere reemplazar.
+    ///El nombre incluye el id del proceso y un contador, por lo que dos instrucciones que
+    ///modifican la misma tabla nunca comparten el archivo temporal. Si hay un registro de
+    ///escritura anticipada abierto se guarda antes el estado actual del archivo.
+    ///
+    ///**Parámetros**
+    ///- 'destino': Es la dirección del archivo que se va a reemplazar.
+    ///
+    ///**Return**
+    ///Devuelve un *Result<ArchivoTemporal, MyError>*, en caso de que no se pueda crear el archivo
+    ///  se devuelve un error del tipo *MyError*.
+    pub fn crear(destino: &str) -> Result<ArchivoTemporal, MyError> {
+        wal::registrar(destino)?;
+        let destino = PathBuf::from(destino);
+        let nombre = match destino.file_name() {
+            Some(n) => n.to_string_lossy().to_string(),
+            None => {
+                return Err(MyError::InvalidTable(
+                    "Directorio o nombre de la tabla incorrecto".to_string(),
+                ))
+            }
+        };
+
+        loop {
+            let ruta = destino.with_file_name(format!(
+                ".{}.{}.{}.tmp",
+                nombre,
+                process::id(),
+                CONTADOR.fetch_add(1, Ordering::Relaxed)
+            ));
+
+            match OpenOptions::new().write(true).create_new(true).open(&ruta) {
+                Ok(archivo) => {
+                    return Ok(ArchivoTemporal {
+                        ruta,
+                        destino,
+                        escritor: BufWriter::new(archivo),
+                        publicado: false,
+                    })
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => continue,
+                Err(_e) => {
+                    return Err(MyError::Error(
+                        "No se pudo crear el archivo temporal para modificar la tabla".to_string(),
+                    ))
+                }
+            }
+        }
+    }
+
+    ///# ArchivoTemporal.escribir()
+    ///Esta función agrega texto al final del archivo temporal.
+    ///
+    ///**Parámetros**
+    ///- 'texto': Es el texto que se quiere escribir.
+    ///
+    ///**Return**
+    ///Devuelve un *Result<(), MyError>*, en caso de que falle la escritura se devuelve un error
+    ///  del tipo *MyError*.
+    pub fn escribir(&mut self, texto: &str) -> Result<(), MyError> {
+        self.escribir_bytes(texto.as_bytes())
+    }
+
+    ///# ArchivoTemporal.escribir_bytes()
+    ///Esta función agrega bytes al final del archivo temporal, para copiar archivos que no
+    ///necesariamente son texto.
+    ///
+    ///**Parámetros**
+    ///- 'bytes': Son los bytes que se quieren escribir.
+    ///
+    ///**Return**
+    ///Devuelve un *Result<(), MyError>*, en caso de que falle la escritura se devuelve un error
+    ///  del tipo *MyError*.
+    pub fn escribir_bytes(&mut self, bytes: &[u8]) -> Result<(), MyError> {
+        match self.escritor.write_all(bytes) {
+            Ok(_) => Ok(()),
+            Err(_e) => Err(MyError::Error(
+                "Fallo en la escritura del archivo temporal".to_string(),
+            )),
+        }
+    }
+
+    ///# ArchivoTemporal.publicar()
+    ///Esta función sincroniza el archivo temporal con el disco y lo renombra sobre el archivo
+    ///original. Como ambos están en el mismo directorio el reemplazo es atómico: ante una caída
+    ///queda el contenido anterior o el nuevo completo, nunca uno a medias.
+    ///
+    ///**Return**
+    ///Devuelve un *Result<(), MyError>*, en caso de que falle algún paso se devuelve un error del
+    ///  tipo *MyError* y el archivo original queda sin cambios.
+    pub fn publicar(mut self) -> Result<(), MyError> {
+        let sincronizado = self
+            .escritor
+            .flush()
+            .and_then(|_| self.escritor.get_ref().sync_all());
+        if sincronizado.is_err() {
+            return Err(MyError::Error(
+                "Fallo en la escritura del archivo temporal".to_string(),
+            ));
+        }
+
+        if fs::rename(&self.ruta, &self.destino).is_err() {
+            return Err(MyError::Error(
+                "No se pudo reemplazar la tabla por su versión modificada".to_string(),
+            ));
+        }
+        self.publicado = true;
+
+        sincronizar_directorio(&self.destino);
+        Ok(())
+    }
+}
+
+///Permite usar el archivo temporal como destino de otro escritor, por ejemplo un compresor.
+impl Write for ArchivoTemporal {
+    fn write(&mut self, bytes: &[u8]) -> std::io::Result<usize> {
+        self.escritor.write(bytes)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.escritor.flush()
+    }
+}
+
+///Permite volver a escribir una parte del archivo temporal antes de publicarlo, por ejemplo un
+///encabezado que se completa al final.
+impl Seek for ArchivoTemporal {
+    fn seek(&mut self, posicion: SeekFrom) -> std::io::Result<u64> {
+        self.escritor.seek(posicion)
+    }
+}
+
+impl Drop for ArchivoTemporal {
+    fn drop(&mut self) {
+        if !self.publicado {
+            let _ = fs::remove_file(&self.ruta);
+        }
+    }
+}
+
+///# archivo_temporal::sincronizar_directorio()
+///Esta función sincroniza con el disco el directorio que contiene al archivo, para que un
+///renombre o un archivo nuevo sobrevivan a una caída. En los sistemas donde no se puede abrir un
+///directorio no hace nada.
+pub fn sincronizar_directorio(archivo: &Path) {
+    let directorio = match archivo.parent() {
+        Some(d) if !d.as_os_str().is_empty() => d,
+        _ => Path::new("."),
+    };
+
+    if let Ok(d) = File::open(directorio) {
+        let _ = d.sync_all();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn test01_se_publica_un_archivo_temporal() {
+        let _ = fs::create_dir_all("./test/archivo_temporal");
+        let destino = "./test/archivo_temporal/publicado.csv";
+        let _ = fs::write(destino, "id\n1\n");
+
+        let mut temporal = ArchivoTemporal::crear(destino).unwrap();
+        let ruta = temporal.ruta.clone();
+        temporal.escribir("id\n2\n").unwrap();
+        temporal.publicar().unwrap();
+
+        assert_eq!(fs::read_to_string(destino).unwrap(), "id\n2\n");
+        assert!(!ruta.exists());
+    }
+
+    #[test]
+    pub fn test02_un_archivo_temporal_descartado_se_borra_sin_tocar_el_original() {
+        let _ = fs::create_dir_all("./test/archivo_temporal");
+        let destino = "./test/archivo_temporal/descartado.csv";
+        let _ = fs::write(destino, "id\n1\n");
+
+        let mut primero = ArchivoTemporal::crear(destino).unwrap();
+        let segundo = ArchivoTemporal::crear(destino).unwrap();
+        let ruta = primero.ruta.clone();
+        primero.escribir("id\n2\n").unwrap();
+
+        assert_ne!(ruta, segundo.ruta);
+        assert_eq!(ruta.parent(), Path::new(destino).parent());
+
+        drop(primero);
+
+        assert_eq!(fs::read_to_string(destino).unwrap(), "id\n1\n");
+        assert!(!ruta.exists());
+    }
+}
@@ -0,0 +1,555 @@
+use crate::almacenamiento::{self, Almacenamiento, Reescritura};
+use crate::archivo_temporal::ArchivoTemporal;
+use crate::bloqueo::Bloqueo;
+use crate::esquema::{Columna, Esquema, TipoDato};
+use crate::my_error::MyError;
+use crate::operadores::{Fila, Operador};
+use std::fs::File;
+use std::io::{BufReader, ErrorKind, Read, Seek, SeekFrom};
+use std::path::Path;
+
+///Son los primeros bytes de todo archivo de tabla binaria.
+const MARCA: &[u8; 8] = b"SQLRBIN1";
+
+///Es el tamaño en bytes de cada página del archivo, incluida la primera, que tiene las columnas.
+pub const TAMANIO_PAGINA: usize = 4096;
+
+///Es el tamaño del encabezado de cada página de filas: la cantidad de filas y la cantidad de
+///bytes que ocupan, como dos enteros de 16 bits.
+const ENCABEZADO_PAGINA: usize = 4;
+
+///# TablaBinaria
+///Es el almacenamiento de una tabla en un archivo binario dividido en páginas de
+///*TAMANIO_PAGINA* bytes. La primera página tiene los nombres y los tipos de las columnas; cada
+///una de las siguientes empieza con la cantidad de filas que tiene y los bytes que ocupan, y a
+///continuación las filas una detrás de otra (una fila nunca ocupa dos páginas).
+///
+///Cada fila empieza con un byte por cada ocho columnas que marca los valores vacíos, que no se
+///escriben. Los valores de las columnas INT se guardan como enteros de 32 bits y los de las TEXT
+///con su largo en 16 bits seguido del texto. La posición de una fila es el byte del archivo en el
+///que empieza.
+///
+///Las filas que agrega INSERT se escriben en páginas nuevas al final del archivo, sin tocar las
+///que ya están, por lo que agregarlas se deshace igual que en un archivo CSV; UPDATE y DELETE
+///reescriben la tabla y vuelven a llenar las páginas.
+#[derive(Debug, PartialEq)]
+pub struct TablaBinaria {
+    archivo: String,
+}
+
+impl TablaBinaria {
+    ///# TablaBinaria::new()
+    ///Esta función crea una nueva instancia de TablaBinaria.
+    ///
+    ///**Parámetros**
+    ///- 'archivo': Es la dirección del archivo binario de la tabla.
+    ///
+    ///**Return**
+    ///Devuelve un *struct* de tipo *TablaBinaria*.
+    pub fn new(archivo: &str) -> Self {
+        TablaBinaria {
+            archivo: archivo.to_string(),
+        }
+    }
+
+    ///# TablaBinaria::crear()
+    ///Esta función empieza a escribir una tabla binaria nueva (o a reemplazar una existente) con
+    ///las columnas indicadas. La tabla aparece recién al publicar la reescritura.
+    ///
+    ///**Parámetros**
+    ///- 'archivo': Es la dirección del archivo de la tabla.
+    ///- 'columnas': Son los nombres y los tipos de las columnas de la tabla.
+    ///
+    ///**Return**
+    ///Devuelve un *Result<Box<dyn Reescritura>, MyError>*, en caso de que no se pueda crear el
+    ///  archivo o las columnas no entren en la primera página se devuelve un error del tipo
+    ///  *MyError*.
+    pub fn crear(archivo: &str, columnas: &[Columna]) -> Result<Box<dyn Reescritura>, MyError> {
+        let mut temporal = ArchivoTemporal::crear(archivo)?;
+        temporal.escribir_bytes(&codificar_columnas(columnas)?)?;
+        Ok(Box::new(ReescrituraBinaria {
+            temporal,
+            tipos: columnas.iter().map(|c| c.tipo().clone()).collect(),
+            pagina: Pagina::default(),
+        }))
+    }
+
+    fn tiene_esquema(&self) -> bool {
+        Path::new(&Esquema::ruta(&self.archivo)).exists()
+    }
+
+    ///Abre el archivo y lee la primera página, dejando el lector al comienzo de las filas.
+    fn abrir(&self) -> Result<(BufReader<File>, Vec<Columna>), MyError> {
+        let mut lector = match File::open(&self.archivo) {
+            Ok(f) => BufReader::new(f),
+            Err(_e) => {
+                return Err(MyError::InvalidTable(
+                    "Directorio o nombre de la tabla incorrecto".to_string(),
+                ))
+            }
+        };
+        let mut pagina = vec![0; TAMANIO_PAGINA];
+        if !leer_pagina(&mut lector, &mut pagina)? {
+            return Err(error_de_formato());
+        }
+        let columnas = decodificar_columnas(&pagina)?;
+        Ok((lector, columnas))
+    }
+
+    fn tipos(&self) -> Result<Vec<TipoDato>, MyError> {
+        Ok(self.abrir()?.1.iter().map(|c| c.tipo().clone()).collect())
+    }
+}
+
+impl Almacenamiento for TablaBinaria {
+    fn archivo(&self) -> &str {
+        &self.archivo
+    }
+
+    fn bloquear(&self, exclusivo: bool) -> Result<Option<Bloqueo>, MyError> {
+        almacenamiento::bloquear_archivo(&self.archivo, exclusivo)
+    }
+
+    fn esquema(&self) -> Result<Esquema, MyError> {
+        match self.tiene_esquema() {
+            true => Esquema::cargar(&self.archivo),
+            false => Ok(Esquema::new(self.abrir()?.1)),
+        }
+    }
+
+    fn recorrer(&self, posiciones: Option<Vec<u64>>) -> Result<Box<dyn Operador>, MyError> {
+        let (lector, columnas) = self.abrir()?;
+        Ok(Box::new(RecorridoBinario {
+            columnas: columnas.iter().map(|c| c.nombre().to_string()).collect(),
+            tipos: columnas.iter().map(|c| c.tipo().clone()).collect(),
+            lector,
+            pagina: vec![0; TAMANIO_PAGINA],
+            numero_pagina: 0,
+            filas_restantes: 0,
+            desplazamiento: 0,
+            fin_datos: 0,
+            posiciones,
+            ultima_posicion: 0,
+        }))
+    }
+
+    fn anexar(&self, filas: &[Fila]) -> Result<(), MyError> {
+        let tipos = self.tipos()?;
+        let mut paginas = Vec::new();
+        let mut pagina = Pagina::default();
+        for fila in filas {
+            let codificada = codificar_fila(&tipos, fila)?;
+            if !pagina.agregar(&codificada) {
+                paginas.extend(pagina.bytes());
+                pagina = Pagina::default();
+                pagina.agregar(&codificada);
+            }
+        }
+        if pagina.filas > 0 {
+            paginas.extend(pagina.bytes());
+        }
+        almacenamiento::anexar_bytes(&self.archivo, &paginas)
+    }
+
+    fn reescribir(&self, columnas: &[String]) -> Result<Box<dyn Reescritura>, MyError> {
+        let existentes = self.abrir()?.1;
+        let columnas: Vec<Columna> = columnas
+            .iter()
+            .map(
+                |nombre| match existentes.iter().find(|c| c.nombre() == nombre) {
+                    Some(c) => c.clone(),
+                    None => Columna::new(nombre.to_string(), TipoDato::Texto),
+                },
+            )
+            .collect();
+        TablaBinaria::crear(&self.archivo, &columnas)
+    }
+
+    fn largo(&self) -> Result<u64, MyError> {
+        almacenamiento::largo_archivo(&self.archivo)
+    }
+
+    fn tablas_con_esquema(&self) -> Result<Vec<String>, MyError> {
+        almacenamiento::tablas_del_directorio(&self.archivo)
+    }
+
+    fn ubicacion_temporal(&self) -> String {
+        String::from(&self.archivo)
+    }
+}
+
+///Lee las páginas de una tabla binaria de a una y entrega sus filas. Si se leen solo ciertas
+///posiciones, se saltean las páginas que no tienen ninguna.
+struct RecorridoBinario {
+    columnas: Vec<String>,
+    tipos: Vec<TipoDato>,
+    lector: BufReader<File>,
+    pagina: Vec<u8>,
+    numero_pagina: u64,
+    filas_restantes: u16,
+    desplazamiento: usize,
+    fin_datos: usize,
+    posiciones: Option<Vec<u64>>,
+    ultima_posicion: u64,
+}
+
+impl RecorridoBinario {
+    ///Carga la próxima página que hay que leer. Devuelve *false* si no quedan más.
+    fn siguiente_pagina(&mut self) -> Result<bool, MyError> {
+        let siguiente = match &self.posiciones {
+            Some(p) => match p
+                .iter()
+                .map(|posicion| posicion / TAMANIO_PAGINA as u64)
+                .find(|numero| *numero > self.numero_pagina)
+            {
+                Some(numero) => numero,
+                None => return Ok(false),
+            },
+            None => self.numero_pagina + 1,
+        };
+        if siguiente != self.numero_pagina + 1
+            && self
+                .lector
+                .seek(SeekFrom::Start(siguiente * TAMANIO_PAGINA as u64))
+                .is_err()
+        {
+            return Err(error_de_lectura());
+        }
+
+        self.numero_pagina = siguiente;
+        if !leer_pagina(&mut self.lector, &mut self.pagina)? {
+            return Ok(false);
+        }
+        let usados = leer_u16(&self.pagina, 2)? as usize;
+        if ENCABEZADO_PAGINA + usados > TAMANIO_PAGINA {
+            return Err(error_de_formato());
+        }
+        self.filas_restantes = leer_u16(&self.pagina, 0)?;
+        self.desplazamiento = ENCABEZADO_PAGINA;
+        self.fin_datos = ENCABEZADO_PAGINA + usados;
+        Ok(true)
+    }
+}
+
+impl Operador for RecorridoBinario {
+    fn columnas(&self) -> &[String] {
+        &self.columnas
+    }
+
+    fn siguiente(&mut self) -> Result<Option<Fila>, MyError> {
+        loop {
+            if self.filas_restantes == 0 {
+                if !self.siguiente_pagina()? {
+                    return Ok(None);
+                }
+                continue;
+            }
+
+            let posicion = self.numero_pagina * TAMANIO_PAGINA as u64 + self.desplazamiento as u64;
+            let (fila, largo) = decodificar_fila(
+                &self.tipos,
+                &self.pagina[self.desplazamiento..self.fin_datos],
+            )?;
+            self.desplazamiento += largo;
+            self.filas_restantes -= 1;
+
+            let elegida = match &self.posiciones {
+                Some(p) => p.binary_search(&posicion).is_ok(),
+                None => true,
+            };
+            if elegida {
+                self.ultima_posicion = posicion;
+                return Ok(Some(fila));
+            }
+        }
+    }
+
+    fn posicion(&self) -> Option<u64> {
+        Some(self.ultima_posicion)
+    }
+}
+
+///Escribe las filas en un *ArchivoTemporal*, llenando cada página antes de empezar la
+///siguiente, que se renombra sobre la tabla al publicarlo.
+struct ReescrituraBinaria {
+    temporal: ArchivoTemporal,
+    tipos: Vec<TipoDato>,
+    pagina: Pagina,
+}
+
+impl Reescritura for ReescrituraBinaria {
+    fn escribir(&mut self, fila: &[String]) -> Result<(), MyError> {
+        let codificada = codificar_fila(&self.tipos, fila)?;
+        if !self.pagina.agregar(&codificada) {
+            self.temporal.escribir_bytes(&self.pagina.bytes())?;
+            self.pagina = Pagina::default();
+            self.pagina.agregar(&codificada);
+        }
+        Ok(())
+    }
+
+    fn publicar(mut self: Box<Self>) -> Result<(), MyError> {
+        if self.pagina.filas > 0 {
+            self.temporal.escribir_bytes(&self.pagina.bytes())?;
+        }
+        self.temporal.publicar()
+    }
+}
+
+///Es una página de filas que se está armando para escribirla.
+#[derive(Default)]
+struct Pagina {
+    filas: u16,
+    datos: Vec<u8>,
+}
+
+impl Pagina {
+    ///Agrega una fila ya codificada. Devuelve *false* si no entra.
+    fn agregar(&mut self, fila: &[u8]) -> bool {
+        if ENCABEZADO_PAGINA + self.datos.len() + fila.len() > TAMANIO_PAGINA {
+            return false;
+        }
+        self.datos.extend_from_slice(fila);
+        self.filas += 1;
+        true
+    }
+
+    ///Devuelve la página completa, con su encabezado y rellena con ceros.
+    fn bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(TAMANIO_PAGINA);
+        bytes.extend_from_slice(&self.filas.to_le_bytes());
+        bytes.extend_from_slice(&(self.datos.len() as u16).to_le_bytes());
+        bytes.extend_from_slice(&self.datos);
+        bytes.resize(TAMANIO_PAGINA, 0);
+        bytes
+    }
+}
+
+///# binario::codificar_columnas()
+///Esta función arma la primera página de una tabla binaria: la marca del formato, la cantidad
+///de columnas y, por cada una, su tipo y su nombre.
+///
+///**Parámetros**
+///- 'columnas': Son las columnas de la tabla.
+///
+///**Return**
+///Devuelve un *Result<Vec<u8>, MyError>*, en caso de que las columnas no entren en la página se
+///  devuelve un error del tipo *MyError*.
+fn codificar_columnas(columnas: &[Columna]) -> Result<Vec<u8>, MyError> {
+    let mut bytes = MARCA.to_vec();
+    bytes.extend_from_slice(&(columnas.len() as u16).to_le_bytes());
+    for columna in columnas {
+        bytes.push(match columna.tipo() {
+            TipoDato::Texto => 0,
+            TipoDato::Entero => 1,
+        });
+        bytes.extend_from_slice(&(columna.nombre().len() as u16).to_le_bytes());
+        bytes.extend_from_slice(columna.nombre().as_bytes());
+    }
+
+    if bytes.len() > TAMANIO_PAGINA {
+        return Err(MyError::InvalidTable(
+            "Las columnas de la tabla no entran en la primera página del archivo binario"
+                .to_string(),
+        ));
+    }
+    bytes.resize(TAMANIO_PAGINA, 0);
+    Ok(bytes)
+}
+
+fn decodificar_columnas(pagina: &[u8]) -> Result<Vec<Columna>, MyError> {
+    if !pagina.starts_with(MARCA) {
+        return Err(error_de_formato());
+    }
+    let cantidad = leer_u16(pagina, MARCA.len())?;
+    let mut desplazamiento = MARCA.len() + 2;
+
+    let mut columnas = Vec::new();
+    for _ in 0..cantidad {
+        let tipo = match pagina.get(desplazamiento) {
+            Some(0) => TipoDato::Texto,
+            Some(1) => TipoDato::Entero,
+            _ => return Err(error_de_formato()),
+        };
+        let largo = leer_u16(pagina, desplazamiento + 1)? as usize;
+        desplazamiento += 3;
+        let nombre = leer_texto(pagina, desplazamiento, largo)?;
+        desplazamiento += largo;
+        columnas.push(Columna::new(nombre, tipo));
+    }
+    Ok(columnas)
+}
+
+///# binario::codificar_fila()
+///Esta función escribe los valores de una fila con la codificación de los tipos de sus columnas.
+///
+///**Parámetros**
+///- 'tipos': Son los tipos de las columnas de la tabla.
+///- 'fila': Son los valores de la fila, en el orden de las columnas.
+///
+///**Return**
+///Devuelve un *Result<Vec<u8>, MyError>*, en caso de que un valor no sea del tipo de su columna
+///  o la fila no entre en una página se devuelve un error del tipo *MyError*.
+fn codificar_fila(tipos: &[TipoDato], fila: &[String]) -> Result<Vec<u8>, MyError> {
+    let mut bytes = vec![0; tipos.len().div_ceil(8)];
+    for (i, tipo) in tipos.iter().enumerate() {
+        let valor = fila.get(i).map(String::as_str).unwrap_or_default();
+        if valor.is_empty() {
+            bytes[i / 8] |= 1 << (i % 8);
+            continue;
+        }
+
+        match tipo {
+            TipoDato::Entero => match valor.parse::<i32>() {
+                Ok(numero) => bytes.extend_from_slice(&numero.to_le_bytes()),
+                Err(_e) => {
+                    return Err(MyError::InvalidColumn(format!(
+                        "El valor {} no es del tipo INT",
+                        valor
+                    )))
+                }
+            },
+            TipoDato::Texto => {
+                let largo = valor.len().min(TAMANIO_PAGINA) as u16;
+                bytes.extend_from_slice(&largo.to_le_bytes());
+                bytes.extend_from_slice(valor.as_bytes());
+            }
+        }
+    }
+
+    if ENCABEZADO_PAGINA + bytes.len() > TAMANIO_PAGINA {
+        return Err(MyError::InvalidTable(
+            "Una fila no entra en una página de la tabla binaria".to_string(),
+        ));
+    }
+    Ok(bytes)
+}
+
+///Lee una fila del comienzo de los datos y devuelve sus valores y la cantidad de bytes que ocupa.
+fn decodificar_fila(tipos: &[TipoDato], datos: &[u8]) -> Result<(Fila, usize), MyError> {
+    let vacios = tipos.len().div_ceil(8);
+    if datos.len() < vacios {
+        return Err(error_de_formato());
+    }
+
+    let mut desplazamiento = vacios;
+    let mut fila = Vec::with_capacity(tipos.len());
+    for (i, tipo) in tipos.iter().enumerate() {
+        if datos[i / 8] & (1 << (i % 8)) != 0 {
+            fila.push(String::new());
+            continue;
+        }
+
+        match tipo {
+            TipoDato::Entero => match datos.get(desplazamiento..desplazamiento + 4) {
+                Some(b) => {
+                    fila.push(i32::from_le_bytes([b[0], b[1], b[2], b[3]]).to_string());
+                    desplazamiento += 4;
+                }
+                None => return Err(error_de_formato()),
+            },
+            TipoDato::Texto => {
+                let largo = leer_u16(datos, desplazamiento)? as usize;
+                fila.push(leer_texto(datos, desplazamiento + 2, largo)?);
+                desplazamiento += 2 + largo;
+            }
+        }
+    }
+    Ok((fila, desplazamiento))
+}
+
+///Lee una página completa. Devuelve *false* si el archivo ya terminó.
+fn leer_pagina(lector: &mut impl Read, pagina: &mut [u8]) -> Result<bool, MyError> {
+    let mut leidos = 0;
+    while leidos < pagina.len() {
+        match lector.read(&mut pagina[leidos..]) {
+            Ok(0) => break,
+            Ok(n) => leidos += n,
+            Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+            Err(_e) => return Err(error_de_lectura()),
+        }
+    }
+    match leidos {
+        0 => Ok(false),
+        n if n == pagina.len() => Ok(true),
+        _ => Err(error_de_formato()),
+    }
+}
+
+fn leer_u16(bytes: &[u8], desde: usize) -> Result<u16, MyError> {
+    match bytes.get(desde..desde + 2) {
+        Some(b) => Ok(u16::from_le_bytes([b[0], b[1]])),
+        None => Err(error_de_formato()),
+    }
+}
+
+fn leer_texto(bytes: &[u8], desde: usize, largo: usize) -> Result<String, MyError> {
+    match bytes.get(desde..desde + largo).map(std::str::from_utf8) {
+        Some(Ok(texto)) => Ok(texto.to_string()),
+        _ => Err(error_de_formato()),
+    }
+}
+
+fn error_de_lectura() -> MyError {
+    MyError::Error("Fallo en la lectura de la tabla".to_string())
+}
+
+fn error_de_formato() -> MyError {
+    MyError::InvalidTable("El archivo de la tabla binaria está dañado".to_string())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::operadores;
+    use std::fs;
+
+    #[test]
+    pub fn test01_las_filas_se_reparten_en_paginas_y_se_leen_por_posicion() {
+        let archivo = "./test/binario/paginas.bin";
+        let _ = fs::create_dir_all("./test/binario");
+        let _ = fs::remove_file(Esquema::ruta(archivo));
+        let columnas = vec![
+            Columna::new("id".to_string(), TipoDato::Entero),
+            Columna::new("nombre".to_string(), TipoDato::Texto),
+        ];
+        let filas: Vec<Fila> = (0..500)
+            .map(|i| vec![i.to_string(), format!("cliente número {}", i)])
+            .collect();
+
+        let mut reescritura = TablaBinaria::crear(archivo, &columnas).unwrap();
+        for fila in &filas {
+            reescritura.escribir(fila).unwrap();
+        }
+        reescritura.publicar().unwrap();
+        let tabla = TablaBinaria::new(archivo);
+        tabla
+            .anexar(&[vec![String::new(), "sin id".to_string()]])
+            .unwrap();
+
+        let bytes = fs::read(archivo).unwrap();
+        let paginas = bytes.len() / TAMANIO_PAGINA;
+        let por_pagina: Vec<u16> = (1..paginas)
+            .map(|p| leer_u16(&bytes, p * TAMANIO_PAGINA).unwrap())
+            .collect();
+        let mut recorrido = tabla.recorrer(None).unwrap();
+        let leidas = operadores::recolectar(recorrido.as_mut()).unwrap();
+        let posicion = recorrido.posicion().unwrap();
+        let mut elegida = tabla.recorrer(Some(vec![posicion])).unwrap();
+
+        assert_eq!(bytes.len() % TAMANIO_PAGINA, 0);
+        assert!(paginas > 3);
+        assert_eq!(por_pagina.iter().sum::<u16>(), 501);
+        assert_eq!(por_pagina.last(), Some(&1));
+        assert_eq!(&leidas[..500], &filas[..]);
+        assert_eq!(tabla.esquema().unwrap().columnas(), &columnas[..]);
+        assert_eq!(
+            elegida.siguiente().unwrap(),
+            Some(vec![String::new(), "sin id".to_string()])
+        );
+        assert!(matches!(
+            tabla.anexar(&[vec!["uno".to_string(), String::new()]]),
+            Err(MyError::InvalidColumn(_))
+        ));
+    }
+}
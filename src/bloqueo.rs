@@ -0,0 +1,185 @@
+use crate::my_error::MyError;
+use std::env;
+use std::fs::{File, OpenOptions, TryLockError};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant};
+
+const VARIABLE_ESPERA: &str = "SQL_RUSTICO_ESPERA_BLOQUEO";
+const ESPERA_POR_DEFECTO_MS: u64 = 5000;
+
+///# Bloqueo
+///Esta estructura representa un bloqueo sobre una tabla, para coordinar procesos que trabajan
+///sobre el mismo directorio. Las lecturas (SELECT) toman un bloqueo compartido y las escrituras
+///(INSERT, UPDATE, DELETE, ALTER TABLE) uno exclusivo. El bloqueo se toma sobre un archivo oculto
+///*.tabla.csv.lock* al lado de la tabla y se libera cuando la estructura se descarta. Si el
+///archivo de bloqueo no existe y no se puede crear (por ejemplo, porque el directorio es de solo
+///lectura) una lectura sigue sin bloquear, ya que en ese directorio tampoco puede escribir nadie.
+///
+///Si el bloqueo no se consigue dentro del tiempo de espera se devuelve un error. El tiempo de
+///espera se configura en milisegundos con la variable de entorno *SQL_RUSTICO_ESPERA_BLOQUEO*
+///(por defecto 5000).
+///
+///**Ejemplo**
+///let _bloqueo = Bloqueo::exclusivo(direccion_tabla)?;
+///
+///**Parámetros**
+///- '_archivo': Es el archivo de bloqueo abierto; mientras exista el bloqueo se mantiene. Es
+///  *None* en las lecturas que no pudieron crear el archivo de bloqueo.
+#[derive(Debug)]
+pub struct Bloqueo {
+    _archivo: Option<File>,
+}
+
+impl Bloqueo {
+    ///# Bloqueo::compartido()
+    ///Esta función toma un bloqueo compartido sobre una tabla, que puede convivir con otros
+    ///bloqueos compartidos pero no con uno exclusivo.
+    ///
+    ///**Parámetros**
+    ///- 'archivo_tabla': Es la dirección del archivo de la tabla.
+    ///
+    ///**Return**
+    ///Devuelve un *Result<Bloqueo, MyError>*, en caso de que la tabla no exista o no se consiga
+    ///  el bloqueo a tiempo se devuelve un error del tipo *MyError*.
+    pub fn compartido(archivo_tabla: &str) -> Result<Bloqueo, MyError> {
+        Bloqueo::adquirir(archivo_tabla, false, espera_configurada())
+    }
+
+    ///# Bloqueo::exclusivo()
+    ///Esta función toma un bloqueo exclusivo sobre una tabla, que no puede convivir con ningún
+    ///otro bloqueo.
+    ///
+    ///**Parámetros**
+    ///- 'archivo_tabla': Es la dirección del archivo de la tabla.
+    ///
+    ///**Return**
+    ///Devuelve un *Result<Bloqueo, MyError>*, en caso de que la tabla no exista o no se consiga
+    ///  el bloqueo a tiempo se devuelve un error del tipo *MyError*.
+    pub fn exclusivo(archivo_tabla: &str) -> Result<Bloqueo, MyError> {
+        Bloqueo::adquirir(archivo_tabla, true, espera_configurada())
+    }
+
+    ///# Bloqueo::ruta()
+    ///Esta función devuelve la dirección del archivo de bloqueo que le corresponde a una tabla.
+    ///
+    ///**Parámetros**
+    ///- 'archivo_tabla': Es la dirección del archivo de la tabla.
+    pub fn ruta(archivo_tabla: &str) -> PathBuf {
+        let ruta = Path::new(archivo_tabla);
+        let nombre = ruta
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        ruta.with_file_name(format!(".{}.lock", nombre))
+    }
+
+    fn adquirir(
+        archivo_tabla: &str,
+        exclusivo: bool,
+        espera: Duration,
+    ) -> Result<Bloqueo, MyError> {
+        if !Path::new(archivo_tabla).is_file() {
+            return Err(MyError::InvalidTable(
+                "Directorio o nombre de la tabla incorrecto".to_string(),
+            ));
+        }
+
+        let archivo = match OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(Bloqueo::ruta(archivo_tabla))
+        {
+            Ok(f) => f,
+            Err(_e) if !exclusivo && !Bloqueo::ruta(archivo_tabla).exists() => {
+                return Ok(Bloqueo { _archivo: None })
+            }
+            Err(_e) => {
+                return Err(MyError::Error(
+                    "No se pudo abrir el archivo de bloqueo de la tabla".to_string(),
+                ))
+            }
+        };
+
+        let inicio = Instant::now();
+        loop {
+            let intento = match exclusivo {
+                true => archivo.try_lock(),
+                false => archivo.try_lock_shared(),
+            };
+
+            match intento {
+                Ok(_) => {
+                    return Ok(Bloqueo {
+                        _archivo: Some(archivo),
+                    })
+                }
+                Err(TryLockError::WouldBlock) if inicio.elapsed() < espera => {
+                    thread::sleep(Duration::from_millis(10))
+                }
+                Err(TryLockError::WouldBlock) => {
+                    return Err(MyError::LockTimeout(format!(
+                        "No se pudo obtener el bloqueo {} de la tabla {} después de esperar {} ms; otro proceso la está usando",
+                        if exclusivo { "exclusivo" } else { "compartido" },
+                        archivo_tabla,
+                        espera.as_millis()
+                    )))
+                }
+                Err(TryLockError::Error(_e)) => {
+                    return Err(MyError::Error(
+                        "No se pudo bloquear la tabla".to_string(),
+                    ))
+                }
+            }
+        }
+    }
+}
+
+///# bloqueo::espera_configurada()
+///Esta función devuelve cuánto se espera como máximo para conseguir un bloqueo, según la
+///variable de entorno *SQL_RUSTICO_ESPERA_BLOQUEO* (en milisegundos).
+pub fn espera_configurada() -> Duration {
+    let milisegundos = env::var(VARIABLE_ESPERA)
+        .ok()
+        .and_then(|v| v.trim().parse::<u64>().ok())
+        .unwrap_or(ESPERA_POR_DEFECTO_MS);
+    Duration::from_millis(milisegundos)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    pub fn test01_los_bloqueos_compartidos_conviven_y_el_exclusivo_espera() {
+        let _ = fs::create_dir_all("./test/bloqueo");
+        let tabla = "./test/bloqueo/tabla.csv";
+        let _ = fs::write(tabla, "id\n1\n");
+
+        let primero = Bloqueo::compartido(tabla).unwrap();
+        let segundo = Bloqueo::compartido(tabla);
+        let exclusivo =
+            thread::spawn(move || Bloqueo::adquirir(tabla, true, Duration::from_millis(50)))
+                .join()
+                .unwrap();
+
+        assert!(segundo.is_ok());
+        assert!(matches!(exclusivo, Err(MyError::LockTimeout(m)) if m.contains("exclusivo")));
+
+        drop(primero);
+        drop(segundo);
+
+        assert!(Bloqueo::exclusivo(tabla).is_ok());
+    }
+
+    #[test]
+    pub fn test02_no_se_bloquea_una_tabla_que_no_existe() {
+        let resultado = Bloqueo::exclusivo("./test/bloqueo/inexistente.csv");
+
+        assert!(matches!(resultado, Err(MyError::InvalidTable(_))));
+        assert!(!Bloqueo::ruta("./test/bloqueo/inexistente.csv").exists());
+    }
+}
@@ -0,0 +1,615 @@
+use crate::almacenamiento;
+use crate::bloqueo::Bloqueo;
+use crate::condicion::Condicion;
+use crate::condicion_simple::CondicionSimple;
+use crate::delete::Delete;
+use crate::esquema::{AccionReferencial, Esquema, Referencia};
+use crate::my_error::MyError;
+use crate::update::Update;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+///# VerificadorReferencias
+///Esta estructura se encarga de corroborar que los valores de las columnas FOREIGN KEY de las
+///filas que se escriben en una tabla existan en la tabla padre. Mientras exista mantiene un
+///bloqueo compartido sobre cada tabla padre, para que nadie elimine o modifique las filas
+///referenciadas antes de que se publique la instrucción.
+///
+///**Ejemplo**
+///let verificador = VerificadorReferencias::new(direccion_tabla, &esquema, &columnas_tabla)?;
+///verificador.verificar(&fila_nueva)?;
+///
+///**Parámetros**
+///- 'referencias': Son las columnas FOREIGN KEY de la tabla con su posición, la referencia
+///  declarada y los valores que tiene la columna padre.
+///- '_bloqueos': Son los bloqueos compartidos sobre las tablas padre.
+#[derive(Debug)]
+pub struct VerificadorReferencias {
+    referencias: Vec<(usize, String, Referencia, HashSet<String>)>,
+    _bloqueos: Vec<Bloqueo>,
+}
+
+impl VerificadorReferencias {
+    ///# VerificadorReferencias::new()
+    ///Esta función crea una nueva instancia de VerificadorReferencias leyendo los valores de las
+    ///columnas padre de cada FOREIGN KEY de la tabla.
+    ///
+    ///**Parámetros**
+    ///- 'archivo': Es la dirección del archivo de la tabla hija.
+    ///- 'esquema': Es el esquema de la tabla hija.
+    ///- 'columnas_tabla': Son los nombres de las columnas en el orden en el que están en la tabla.
+    ///
+    ///**Return**
+    ///Devuelve un *Result<VerificadorReferencias, MyError>*, en caso de que no se pueda leer
+    ///  alguna de las tablas padre se devuelve un error del tipo *MyError*.
+    pub fn new(
+        archivo: &str,
+        esquema: &Esquema,
+        columnas_tabla: &[String],
+    ) -> Result<Self, MyError> {
+        let mut referencias = Vec::new();
+        let mut bloqueos = Vec::new();
+
+        for columna in esquema.columnas() {
+            let referencia = match columna.referencia() {
+                Some(r) => r,
+                None => continue,
+            };
+            let posicion = match columnas_tabla.iter().position(|c| c == columna.nombre()) {
+                Some(p) => p,
+                None => continue,
+            };
+
+            let archivo_padre = archivo_de_tabla(archivo, referencia.tabla())?;
+            if archivo_padre != archivo {
+                bloqueos.extend(bloquear_referenciada(&archivo_padre)?);
+            }
+            let valores = leer_columna(&archivo_padre, referencia.columna())?;
+            referencias.push((
+                posicion,
+                columna.nombre().to_string(),
+                referencia.clone(),
+                valores,
+            ));
+        }
+
+        Ok(VerificadorReferencias {
+            referencias,
+            _bloqueos: bloqueos,
+        })
+    }
+
+    ///# VerificadorReferencias.verificar()
+    ///Esta función corrobora que los valores de las columnas FOREIGN KEY de la fila existan en la
+    ///tabla padre. Los valores vacíos no hacen referencia a ninguna fila y se aceptan.
+    ///
+    ///**Parámetros**
+    ///- 'fila': Son los valores de la fila en el orden de las columnas de la tabla.
+    ///
+    ///**Return**
+    ///Devuelve un *Result<(), MyError>*, en caso de que algún valor no exista en la tabla padre
+    ///  se devuelve un error del tipo *MyError*.
+    pub fn verificar(&self, fila: &[String]) -> Result<(), MyError> {
+        for (posicion, nombre, referencia, valores) in &self.referencias {
+            let valor = match fila.get(*posicion) {
+                Some(v) if !v.is_empty() => v,
+                _ => continue,
+            };
+
+            if !valores.contains(valor) {
+                return Err(MyError::ConstraintViolation(format!(
+                    "Se viola la restricción FOREIGN KEY de la columna {}: el valor {} no existe en {}({})",
+                    nombre,
+                    valor,
+                    referencia.tabla(),
+                    referencia.columna()
+                )));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+///# TablaHija
+///Representa una tabla que tiene una columna FOREIGN KEY que hace referencia a otra tabla.
+///
+///**Parámetros**
+///- 'archivo': Es la dirección del archivo de la tabla hija.
+///- 'nombre': Es el nombre de la tabla hija.
+///- 'columna': Es el nombre de la columna FOREIGN KEY de la tabla hija.
+///- 'referencia': Es la referencia declarada en esa columna.
+#[derive(Debug, PartialEq)]
+pub struct TablaHija {
+    archivo: String,
+    nombre: String,
+    columna: String,
+    referencia: Referencia,
+}
+
+impl TablaHija {
+    ///# TablaHija::buscar()
+    ///Esta función busca en el directorio de la tabla todas las tablas cuyo esquema tenga una
+    ///FOREIGN KEY que le haga referencia. No se tienen en cuenta las referencias de una tabla a
+    ///sí misma.
+    ///
+    ///**Parámetros**
+    ///- 'archivo': Es la dirección del archivo de la tabla padre.
+    ///
+    ///**Return**
+    ///Devuelve un *Result<Vec<TablaHija>, MyError>*, en caso de que no se pueda leer el
+    ///  directorio o algún esquema se devuelve un error del tipo *MyError*.
+    pub fn buscar(archivo: &str) -> Result<Vec<TablaHija>, MyError> {
+        let nombre_padre = nombre_de_tabla(archivo);
+
+        let mut hijas = Vec::new();
+        for archivo_hija in almacenamiento::abrir(archivo).tablas_con_esquema()? {
+            let esquema = almacenamiento::abrir(&archivo_hija).esquema()?;
+            for columna in esquema.columnas() {
+                if let Some(referencia) = columna.referencia() {
+                    if referencia.tabla() == nombre_padre {
+                        hijas.push(TablaHija {
+                            nombre: nombre_de_tabla(&archivo_hija),
+                            archivo: String::from(&archivo_hija),
+                            columna: columna.nombre().to_string(),
+                            referencia: referencia.clone(),
+                        });
+                    }
+                }
+            }
+        }
+
+        Ok(hijas)
+    }
+
+    ///Bloquea la tabla hija de forma compartida, guardando el bloqueo en *bloqueos*, y se fija si
+    ///alguna de sus filas hace referencia a alguno de los valores.
+    fn tiene_referencias(
+        &self,
+        valores: &[String],
+        bloqueos: &mut Vec<Bloqueo>,
+    ) -> Result<bool, MyError> {
+        bloqueos.extend(bloquear_referenciada(&self.archivo)?);
+        let referenciados = leer_columna(&self.archivo, &self.columna)?;
+        Ok(valores.iter().any(|v| referenciados.contains(v)))
+    }
+
+    fn violacion(&self, evento: &str) -> MyError {
+        MyError::ConstraintViolation(format!(
+            "Se viola la restricción FOREIGN KEY de la columna {} de la tabla {}: hay filas que hacen referencia a las filas que se quieren {}",
+            self.columna, self.nombre, evento
+        ))
+    }
+}
+
+///# propagar_eliminacion()
+///Esta función aplica las acciones ON DELETE de las tablas hijas cuando se eliminan filas de una
+///tabla padre. Primero se corrobora que ninguna tabla con RESTRICT haga referencia a las filas
+///eliminadas y después se aplican las acciones CASCADE y SET NULL. Las tablas con RESTRICT
+///quedan bloqueadas de forma compartida hasta que se descartan los bloqueos devueltos, que se
+///tienen que mantener hasta publicar la tabla padre.
+///
+///**Parámetros**
+///- 'hijas': Son las tablas que hacen referencia a la tabla padre.
+///- 'columnas': Son los nombres de las columnas de la tabla padre.
+///- 'filas': Son las filas que se eliminan de la tabla padre.
+///
+///**Return**
+///Devuelve un *Result<Vec<Bloqueo>, MyError>* con los bloqueos de las tablas con RESTRICT, en
+///  caso de que alguna tabla hija impida la eliminación se devuelve un error del tipo *MyError*.
+pub fn propagar_eliminacion(
+    hijas: &[TablaHija],
+    columnas: &[String],
+    filas: &[Vec<String>],
+) -> Result<Vec<Bloqueo>, MyError> {
+    let mut acciones: Vec<(&TablaHija, Vec<String>)> = Vec::new();
+    let mut bloqueos = Vec::new();
+
+    for hija in hijas {
+        let posicion = posicion_referenciada(hija, columnas)?;
+        let valores: Vec<String> = filas
+            .iter()
+            .filter_map(|f| f.get(posicion))
+            .filter(|v| !v.is_empty())
+            .cloned()
+            .collect::<HashSet<String>>()
+            .into_iter()
+            .collect();
+
+        if valores.is_empty() {
+            continue;
+        }
+
+        if *hija.referencia.al_eliminar() == AccionReferencial::Restrict {
+            if hija.tiene_referencias(&valores, &mut bloqueos)? {
+                return Err(hija.violacion("eliminar"));
+            }
+            continue;
+        }
+
+        acciones.push((hija, valores));
+    }
+
+    for (hija, valores) in acciones {
+        let condicion = condicion_pertenece(&hija.columna, &valores);
+        match hija.referencia.al_eliminar() {
+            AccionReferencial::Cascade => {
+                Delete::new(String::from(&hija.archivo), condicion).eliminar()?;
+            }
+            AccionReferencial::SetNull => {
+                let reemplazos = valores.into_iter().map(|v| (v, String::new())).collect();
+                Update::new(String::from(&hija.archivo), Vec::new(), condicion)
+                    .en_cascada(&hija.columna, reemplazos)
+                    .update()?;
+            }
+            AccionReferencial::Restrict => {}
+        }
+    }
+
+    Ok(bloqueos)
+}
+
+///# propagar_actualizacion()
+///Esta función aplica las acciones ON UPDATE de las tablas hijas cuando se modifican los valores
+///referenciados de una tabla padre. Cada tabla hija se reescribe una sola vez y cada fila toma el
+///valor nuevo que le corresponde a su valor original, por lo que los cambios no se pisan entre sí.
+///Igual que en *propagar_eliminacion()*, las tablas con RESTRICT quedan bloqueadas de forma
+///compartida mientras existan los bloqueos devueltos.
+///
+///**Parámetros**
+///- 'hijas': Son las tablas que hacen referencia a la tabla padre.
+///- 'columnas': Son los nombres de las columnas de la tabla padre.
+///- 'cambios': Son los pares (fila anterior, fila nueva) de las filas modificadas.
+///
+///**Return**
+///Devuelve un *Result<Vec<Bloqueo>, MyError>* con los bloqueos de las tablas con RESTRICT, en
+///  caso de que alguna tabla hija impida la modificación se devuelve un error del tipo *MyError*.
+pub fn propagar_actualizacion(
+    hijas: &[TablaHija],
+    columnas: &[String],
+    cambios: &[(Vec<String>, Vec<String>)],
+) -> Result<Vec<Bloqueo>, MyError> {
+    let mut acciones: Vec<(&TablaHija, Vec<(String, String)>)> = Vec::new();
+    let mut bloqueos = Vec::new();
+
+    for hija in hijas {
+        let posicion = posicion_referenciada(hija, columnas)?;
+        let mut pares: Vec<(String, String)> = Vec::new();
+        for (anterior, nueva) in cambios {
+            let par = (
+                String::from(&anterior[posicion]),
+                String::from(&nueva[posicion]),
+            );
+            if !par.0.is_empty() && par.0 != par.1 && !pares.contains(&par) {
+                pares.push(par);
+            }
+        }
+
+        if pares.is_empty() {
+            continue;
+        }
+
+        if *hija.referencia.al_actualizar() == AccionReferencial::Restrict {
+            let anteriores: Vec<String> = pares.iter().map(|(a, _)| String::from(a)).collect();
+            if hija.tiene_referencias(&anteriores, &mut bloqueos)? {
+                return Err(hija.violacion("modificar"));
+            }
+            continue;
+        }
+
+        acciones.push((hija, pares));
+    }
+
+    for (hija, pares) in acciones {
+        let anteriores: Vec<String> = pares.iter().map(|(a, _)| String::from(a)).collect();
+        let condicion = condicion_pertenece(&hija.columna, &anteriores);
+        let reemplazos: HashMap<String, String> = pares
+            .into_iter()
+            .map(|(anterior, nuevo)| match hija.referencia.al_actualizar() {
+                AccionReferencial::SetNull => (anterior, String::new()),
+                _ => (anterior, nuevo),
+            })
+            .collect();
+        Update::new(String::from(&hija.archivo), Vec::new(), condicion)
+            .en_cascada(&hija.columna, reemplazos)
+            .update()?;
+    }
+
+    Ok(bloqueos)
+}
+
+fn posicion_referenciada(hija: &TablaHija, columnas: &[String]) -> Result<usize, MyError> {
+    match columnas.iter().position(|c| c == hija.referencia.columna()) {
+        Some(p) => Ok(p),
+        None => Err(MyError::InvalidColumn(format!(
+            "La columna {} a la que hace referencia la tabla {} no existe",
+            hija.referencia.columna(),
+            hija.nombre
+        ))),
+    }
+}
+
+///Arma la condición *columna = v1 OR columna = v2 OR ...* como un árbol balanceado para que su
+///profundidad no crezca con la cantidad de valores.
+fn condicion_pertenece(columna: &str, valores: &[String]) -> Condicion {
+    match valores.len() {
+        0 => Condicion::Not(Box::new(Condicion::SiempreTrue)),
+        1 => Condicion::CondicionSimple(CondicionSimple::new(
+            columna.to_string(),
+            "=".to_string(),
+            String::from(&valores[0]),
+        )),
+        n => {
+            let (izquierda, derecha) = valores.split_at(n / 2);
+            Condicion::Or(
+                Box::new(condicion_pertenece(columna, izquierda)),
+                Box::new(condicion_pertenece(columna, derecha)),
+            )
+        }
+    }
+}
+
+fn nombre_de_tabla(archivo: &str) -> String {
+    Path::new(archivo)
+        .file_stem()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+fn archivo_de_tabla(archivo: &str, tabla: &str) -> Result<String, MyError> {
+    let directorio = match Path::new(archivo).parent() {
+        Some(d) if !d.as_os_str().is_empty() => d,
+        _ => Path::new("."),
+    };
+    almacenamiento::resolver(&directorio.to_string_lossy(), tabla)
+}
+
+fn bloquear_referenciada(archivo: &str) -> Result<Option<Bloqueo>, MyError> {
+    match almacenamiento::abrir(archivo).bloquear(false) {
+        Err(MyError::InvalidTable(_)) => Err(tabla_referenciada_inexistente(archivo)),
+        resultado => resultado,
+    }
+}
+
+fn tabla_referenciada_inexistente(archivo: &str) -> MyError {
+    MyError::InvalidTable(format!(
+        "No existe la tabla {} a la que hace referencia la FOREIGN KEY",
+        nombre_de_tabla(archivo)
+    ))
+}
+
+fn leer_columna(archivo: &str, columna: &str) -> Result<HashSet<String>, MyError> {
+    let mut recorrido = match almacenamiento::abrir(archivo).recorrer(None) {
+        Ok(r) => r,
+        Err(_e) => return Err(tabla_referenciada_inexistente(archivo)),
+    };
+
+    let posicion = match recorrido.columnas().iter().position(|c| c == columna) {
+        Some(p) => p,
+        None => {
+            return Err(MyError::InvalidColumn(format!(
+                "La columna {} a la que hace referencia la FOREIGN KEY no existe en la tabla {}",
+                columna,
+                nombre_de_tabla(archivo)
+            )))
+        }
+    };
+
+    let mut valores = HashSet::new();
+    while let Some(fila) = recorrido.siguiente()? {
+        if let Some(valor) = fila.into_iter().nth(posicion) {
+            valores.insert(valor);
+        }
+    }
+
+    Ok(valores)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::insert::Insert;
+    use std::fs;
+
+    fn preparar_base(caso: &str, acciones: &str) -> String {
+        let directorio = format!("./test/clave_foranea/{}", caso);
+        let _ = fs::remove_dir_all(&directorio);
+        let _ = fs::create_dir_all(&directorio);
+
+        let origen = "./test/clave_foranea";
+        for archivo in ["clientes.csv", "clientes.esquema", "pedidos.csv"] {
+            let copia = archivo.replace(".", "_copia.");
+            let _ = fs::copy(
+                format!("{}/{}", origen, copia),
+                format!("{}/{}", directorio, archivo),
+            );
+        }
+        let _ = fs::write(
+            format!("{}/pedidos.esquema", directorio),
+            format!(
+                "id INT PRIMARY KEY\nid_cliente INT REFERENCES clientes(id) {}\nproducto TEXT\n",
+                acciones
+            ),
+        );
+
+        directorio
+    }
+
+    fn condicion_igual(columna: &str, valor: &str) -> Condicion {
+        Condicion::CondicionSimple(CondicionSimple::new(
+            columna.to_string(),
+            "=".to_string(),
+            valor.to_string(),
+        ))
+    }
+
+    #[test]
+    pub fn test01_no_se_inserta_una_fila_que_hace_referencia_a_un_valor_inexistente() {
+        let directorio = preparar_base("insert_inexistente", "");
+
+        let operacion = Insert::new(
+            format!("{}/pedidos.csv", directorio),
+            vec!["id".to_string(), "id_cliente".to_string()],
+            vec![vec!["104".to_string(), "9".to_string()]],
+        );
+
+        let resultado = operacion.insertar();
+
+        assert!(matches!(
+            resultado,
+            Err(MyError::ConstraintViolation(m)) if m.contains("FOREIGN KEY")
+        ));
+    }
+
+    #[test]
+    pub fn test02_se_inserta_una_fila_que_hace_referencia_a_un_valor_existente() {
+        let directorio = preparar_base("insert_existente", "");
+
+        let operacion = Insert::new(
+            format!("{}/pedidos.csv", directorio),
+            vec!["id".to_string(), "id_cliente".to_string()],
+            vec![vec!["104".to_string(), "3".to_string()]],
+        );
+
+        assert!(operacion.insertar().is_ok());
+    }
+
+    #[test]
+    pub fn test03_no_se_elimina_una_fila_referenciada_con_restrict() {
+        let directorio = preparar_base("delete_restrict", "ON DELETE RESTRICT");
+
+        let operacion = Delete::new(
+            format!("{}/clientes.csv", directorio),
+            condicion_igual("id", "1"),
+        );
+
+        let resultado = operacion.eliminar();
+        let clientes = fs::read_to_string(format!("{}/clientes.csv", directorio)).unwrap();
+
+        assert!(matches!(resultado, Err(MyError::ConstraintViolation(_))));
+        assert_eq!(clientes.lines().count(), 4);
+    }
+
+    #[test]
+    pub fn test04_se_eliminan_en_cascada_las_filas_que_hacen_referencia() {
+        let directorio = preparar_base("delete_cascade", "ON DELETE CASCADE");
+
+        let operacion = Delete::new(
+            format!("{}/clientes.csv", directorio),
+            condicion_igual("id", "1"),
+        );
+
+        let resultado = operacion.eliminar();
+        let pedidos = fs::read_to_string(format!("{}/pedidos.csv", directorio)).unwrap();
+
+        assert!(resultado.is_ok());
+        assert_eq!(pedidos, "id,id_cliente,producto\n102,2,Monitor\n");
+    }
+
+    #[test]
+    pub fn test05_se_vacian_las_referencias_con_set_null_al_eliminar() {
+        let directorio = preparar_base("delete_set_null", "ON DELETE SET NULL");
+
+        let operacion = Delete::new(
+            format!("{}/clientes.csv", directorio),
+            condicion_igual("id", "2"),
+        );
+
+        let resultado = operacion.eliminar();
+        let pedidos = fs::read_to_string(format!("{}/pedidos.csv", directorio)).unwrap();
+
+        assert!(resultado.is_ok());
+        assert!(pedidos.contains("102,,Monitor\n"));
+    }
+
+    #[test]
+    pub fn test06_se_actualizan_en_cascada_las_filas_que_hacen_referencia() {
+        let directorio = preparar_base("update_cascade", "ON UPDATE CASCADE");
+
+        let operacion = Update::new(
+            format!("{}/clientes.csv", directorio),
+            vec![vec!["id".to_string(), "7".to_string()]],
+            condicion_igual("id", "1"),
+        );
+
+        let resultado = operacion.update();
+        let pedidos = fs::read_to_string(format!("{}/pedidos.csv", directorio)).unwrap();
+
+        assert!(resultado.is_ok());
+        assert_eq!(
+            pedidos,
+            "id,id_cliente,producto\n101,7,Laptop\n102,2,Monitor\n103,7,Teclado\n"
+        );
+    }
+
+    #[test]
+    pub fn test07_no_se_actualiza_una_fila_hija_con_un_valor_inexistente() {
+        let directorio = preparar_base("update_hija", "");
+
+        let operacion = Update::new(
+            format!("{}/pedidos.csv", directorio),
+            vec![vec!["id_cliente".to_string(), "9".to_string()]],
+            condicion_igual("id", "101"),
+        );
+
+        let resultado = operacion.update();
+
+        assert!(matches!(resultado, Err(MyError::ConstraintViolation(_))));
+    }
+
+    #[test]
+    pub fn test08_se_intercambian_en_cascada_dos_valores_referenciados() {
+        let directorio = preparar_base("update_intercambio", "ON UPDATE CASCADE");
+        let hijas = TablaHija::buscar(&format!("{}/clientes.csv", directorio)).unwrap();
+        let columnas = vec!["id".to_string(), "nombre".to_string()];
+        let fila = |id: &str, nombre: &str| vec![id.to_string(), nombre.to_string()];
+        let cambios = vec![
+            (fila("1", "Ana"), fila("2", "Ana")),
+            (fila("2", "Juan"), fila("1", "Juan")),
+        ];
+
+        let resultado = propagar_actualizacion(&hijas, &columnas, &cambios);
+        let pedidos = fs::read_to_string(format!("{}/pedidos.csv", directorio)).unwrap();
+
+        assert!(resultado.is_ok());
+        assert_eq!(
+            pedidos,
+            "id,id_cliente,producto\n101,2,Laptop\n102,1,Monitor\n103,2,Teclado\n"
+        );
+    }
+
+    fn esta_bloqueada(archivo: &str) -> bool {
+        let intento = fs::File::open(Bloqueo::ruta(archivo)).map(|f| f.try_lock());
+        !matches!(intento, Ok(Ok(())))
+    }
+
+    #[test]
+    pub fn test09_las_tablas_referenciadas_quedan_bloqueadas_durante_la_verificacion() {
+        let directorio = preparar_base("bloqueos", "ON DELETE RESTRICT");
+        let clientes = format!("{}/clientes.csv", directorio);
+        let pedidos = format!("{}/pedidos.csv", directorio);
+        let esquema = almacenamiento::abrir(&pedidos).esquema().unwrap();
+        let columnas: Vec<String> = ["id", "id_cliente", "producto"]
+            .iter()
+            .map(|c| c.to_string())
+            .collect();
+
+        let verificador = VerificadorReferencias::new(&pedidos, &esquema, &columnas).unwrap();
+        let bloqueada_por_el_hijo = esta_bloqueada(&clientes);
+        drop(verificador);
+        let liberada = !esta_bloqueada(&clientes);
+
+        let hijas = TablaHija::buscar(&clientes).unwrap();
+        let filas = vec![vec!["3".to_string(), "Maria".to_string()]];
+        let bloqueos =
+            propagar_eliminacion(&hijas, &["id".to_string(), "nombre".to_string()], &filas)
+                .unwrap();
+        let bloqueada_por_el_padre = esta_bloqueada(&pedidos);
+        drop(bloqueos);
+
+        assert!(bloqueada_por_el_hijo);
+        assert!(liberada);
+        assert!(bloqueada_por_el_padre);
+        assert!(!esta_bloqueada(&pedidos));
+    }
+}
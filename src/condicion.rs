@@ -1,5 +1,6 @@
-use crate::condicion_simple::CondicionSimple;
+use crate::condicion_simple::{ComparacionCompilada, CondicionSimple};
 use crate::my_error::MyError;
+use std::fmt;
 
 ///# Condición
 ///Esta estructura contiene todo lo necesario para el soporte
@@ -41,49 +42,124 @@ impl Condicion {
     ///Retorna un Result<bool,MyError> si no hubo ningún error en el proceso retorna el bool en
     ///caso de haberlo devuelve el error de tipo MyError.
     ///
-    pub fn verificar(
-        &self,
-        columnas: &Vec<String>,
-        valores: &Vec<String>,
-    ) -> Result<bool, MyError> {
+    pub fn verificar(&self, columnas: &[String], valores: &[String]) -> Result<bool, MyError> {
+        self.compilar(columnas)?.verificar(valores)
+    }
+
+    ///
+    ///**Condicion.compilar()**
+    ///Resuelve la condición contra el encabezado de la tabla una única vez: cada columna se
+    ///reemplaza por su posición y cada operador se valida, de forma que al evaluar las filas no
+    ///se busque ninguna columna por nombre.
+    ///
+    ///**Parámetros**
+    ///- 'columnas': Es un array de los nombres de la columnas de la tabla.
+    ///
+    ///**Return**
+    ///Retorna un Result<CondicionCompilada,MyError>, en caso de que alguna columna no exista en
+    ///la tabla o algún operador no sea válido devuelve el error de tipo MyError.
+    ///
+    pub fn compilar(&self, columnas: &[String]) -> Result<CondicionCompilada, MyError> {
         match self {
-            Condicion::CondicionSimple(cond) => cond.verificar(columnas, valores),
-            Condicion::And(cond1, cond2) => {
-                let c1 = match cond1.verificar(columnas, valores) {
-                    Ok(c) => c,
-                    Err(e) => return Err(e),
-                };
-
-                let c2 = match cond2.verificar(columnas, valores) {
-                    Ok(c) => c,
-                    Err(e) => return Err(e),
-                };
-
-                Ok(c1 && c2)
+            Condicion::CondicionSimple(cond) => {
+                Ok(CondicionCompilada::Comparacion(cond.compilar(columnas)?))
             }
+            Condicion::And(cond1, cond2) => Ok(CondicionCompilada::And(
+                Box::new(cond1.compilar(columnas)?),
+                Box::new(cond2.compilar(columnas)?),
+            )),
+            Condicion::Or(cond1, cond2) => Ok(CondicionCompilada::Or(
+                Box::new(cond1.compilar(columnas)?),
+                Box::new(cond2.compilar(columnas)?),
+            )),
+            Condicion::Not(cond) => Ok(CondicionCompilada::Not(Box::new(cond.compilar(columnas)?))),
+            Condicion::SiempreTrue => Ok(CondicionCompilada::SiempreTrue),
+        }
+    }
+}
 
-            Condicion::Or(cond1, cond2) => {
-                let c1 = match cond1.verificar(columnas, valores) {
-                    Ok(c) => c,
-                    Err(e) => return Err(e),
-                };
+///# CondiciónCompilada
+///Es una *Condicion* ya resuelta contra el encabezado de una tabla mediante
+///*Condicion.compilar()*. Los operadores And y Or dejan de evaluar en cuanto el resultado queda
+///determinado por el primer operando.
+///
+///**Ejemplo**
+///let compilada = condicion.compilar(columnas)?;
+///let resultado:bool = compilada.verificar(valores)?;
+#[derive(Debug, PartialEq)]
+pub enum CondicionCompilada {
+    Comparacion(ComparacionCompilada),
+    And(Box<CondicionCompilada>, Box<CondicionCompilada>),
+    Or(Box<CondicionCompilada>, Box<CondicionCompilada>),
+    Not(Box<CondicionCompilada>),
+    SiempreTrue,
+}
 
-                let c2 = match cond2.verificar(columnas, valores) {
-                    Ok(c) => c,
-                    Err(e) => return Err(e),
-                };
+impl CondicionCompilada {
+    ///
+    ///**CondicionCompilada.verificar()**
+    ///Verificar si la fila cumple con la condición. Si el resultado es desconocido se considera
+    ///que no la cumple.
+    ///
+    ///**Parámetros**
+    ///- 'valores': Es un array con los valores que tiene esa fila para las columnas de la tabla
+    ///  con la que se compiló la condición.
+    ///
+    ///**Return**
+    ///Retorna un Result<bool,MyError> si no hubo ningún error en el proceso retorna el bool en
+    ///caso de haberlo devuelve el error de tipo MyError.
+    ///
+    pub fn verificar(&self, valores: &[String]) -> Result<bool, MyError> {
+        Ok(self.evaluar(valores)? == Some(true))
+    }
 
-                Ok(c1 || c2)
-            }
-            Condicion::Not(cond) => {
-                let c = match cond.verificar(columnas, valores) {
-                    Ok(c) => c,
-                    Err(e) => return Err(e),
-                };
+    ///
+    ///**CondicionCompilada.evaluar()**
+    ///Evalúa la condición sobre la fila con lógica de tres valores: una comparación numérica
+    ///contra un valor vacío (NULL) es desconocida, NOT de un desconocido sigue siendo desconocido,
+    ///*desconocido AND falso* es falso y *desconocido OR verdadero* es verdadero.
+    ///
+    ///**Parámetros**
+    ///- 'valores': Es un array con los valores que tiene esa fila para las columnas de la tabla
+    ///  con la que se compiló la condición.
+    ///
+    ///**Return**
+    ///Retorna un Result<Option<bool>,MyError> con *None* si el resultado es desconocido, en caso
+    ///de haber algún error devuelve el error de tipo MyError.
+    ///
+    pub fn evaluar(&self, valores: &[String]) -> Result<Option<bool>, MyError> {
+        match self {
+            CondicionCompilada::Comparacion(comparacion) => comparacion.evaluar(valores),
+            CondicionCompilada::And(cond1, cond2) => match cond1.evaluar(valores)? {
+                Some(false) => Ok(Some(false)),
+                primero => match (primero, cond2.evaluar(valores)?) {
+                    (_, Some(false)) => Ok(Some(false)),
+                    (Some(true), Some(true)) => Ok(Some(true)),
+                    _ => Ok(None),
+                },
+            },
+            CondicionCompilada::Or(cond1, cond2) => match cond1.evaluar(valores)? {
+                Some(true) => Ok(Some(true)),
+                primero => match (primero, cond2.evaluar(valores)?) {
+                    (_, Some(true)) => Ok(Some(true)),
+                    (Some(false), Some(false)) => Ok(Some(false)),
+                    _ => Ok(None),
+                },
+            },
+            CondicionCompilada::Not(cond) => Ok(cond.evaluar(valores)?.map(|r| !r)),
+            CondicionCompilada::SiempreTrue => Ok(Some(true)),
+        }
+    }
+}
 
-                Ok(!c)
-            }
-            Condicion::SiempreTrue => Ok(true),
+impl fmt::Display for Condicion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Condicion::CondicionSimple(cond) => write!(f, "{}", cond),
+            Condicion::And(cond1, cond2) => write!(f, "{} AND {}", cond1, cond2),
+            Condicion::Or(cond1, cond2) => write!(f, "({} OR {})", cond1, cond2),
+            Condicion::Not(cond) => write!(f, "NOT ({})", cond),
+            Condicion::SiempreTrue => write!(f, "TRUE"),
         }
     }
 }
@@ -97,10 +173,7 @@ pub fn test01_se_verifica_si_una_condicon_simple_devuelve_el_verdadero() {
     let columnas = vec!["nombre".to_string(), "apellido".to_string()];
     let valores = vec!["Tomas".to_string(), "Amundarain".to_string()];
 
-    let resultado = match condicion.verificar(&columnas, &valores) {
-        Ok(r) => r,
-        Err(_e) => false,
-    };
+    let resultado = condicion.verificar(&columnas, &valores).unwrap_or_default();
 
     assert!(resultado);
 }
@@ -122,10 +195,7 @@ pub fn test02_se_verifica_si_una_condicon_and_devuelve_el_verdadero() {
     let columnas = vec!["nombre".to_string(), "apellido".to_string()];
     let valores = vec!["Tomas".to_string(), "Amundarain".to_string()];
 
-    let resultado = match condicion.verificar(&columnas, &valores) {
-        Ok(r) => r,
-        Err(_e) => false,
-    };
+    let resultado = condicion.verificar(&columnas, &valores).unwrap_or_default();
 
     assert!(resultado);
 }
@@ -148,15 +218,13 @@ pub fn test03_se_verifica_si_una_condicon_or_devuelve_el_verdadero() {
     let valores1 = vec!["Francisco".to_string(), "Amundarain".to_string()];
     let valores2 = vec!["Tomas".to_string(), "Martinez".to_string()];
 
-    let resultado1 = match condicion.verificar(&columnas, &valores1) {
-        Ok(r) => r,
-        Err(_e) => false,
-    };
+    let resultado1 = condicion
+        .verificar(&columnas, &valores1)
+        .unwrap_or_default();
 
-    let resultado2 = match condicion.verificar(&columnas, &valores2) {
-        Ok(r) => r,
-        Err(_e) => false,
-    };
+    let resultado2 = condicion
+        .verificar(&columnas, &valores2)
+        .unwrap_or_default();
 
     assert!(resultado1);
     assert!(resultado2);
@@ -174,10 +242,78 @@ pub fn test04_se_verifica_si_una_condicon_not_devuelve_el_verdadero() {
     let columnas = vec!["nombre".to_string(), "apellido".to_string()];
     let valores = vec!["Tomas".to_string(), "Martinez".to_string()];
 
-    let resultado = match condicion.verificar(&columnas, &valores) {
-        Ok(r) => r,
-        Err(_e) => false,
-    };
+    let resultado = condicion.verificar(&columnas, &valores).unwrap_or_default();
 
     assert!(resultado);
 }
+
+#[test]
+pub fn test05_una_condicion_sobre_una_columna_inexistente_no_se_compila() {
+    let condicion = Condicion::And(
+        Box::new(Condicion::SiempreTrue),
+        Box::new(Condicion::CondicionSimple(CondicionSimple::new(
+            "telefono".to_string(),
+            "=".to_string(),
+            "1234".to_string(),
+        ))),
+    );
+
+    let columnas = vec!["nombre".to_string(), "apellido".to_string()];
+
+    assert!(matches!(
+        condicion.compilar(&columnas),
+        Err(MyError::InvalidColumn(_))
+    ));
+}
+
+#[test]
+pub fn test06_una_condicion_compilada_no_evalua_el_segundo_operando_si_no_hace_falta() {
+    let edad_mayor = || {
+        Box::new(Condicion::CondicionSimple(CondicionSimple::new(
+            "edad".to_string(),
+            ">".to_string(),
+            "18".to_string(),
+        )))
+    };
+    let nombre_tomas = || {
+        Box::new(Condicion::CondicionSimple(CondicionSimple::new(
+            "nombre".to_string(),
+            "=".to_string(),
+            "Tomas".to_string(),
+        )))
+    };
+    let and = Condicion::And(nombre_tomas(), edad_mayor());
+    let or = Condicion::Or(Box::new(Condicion::Not(nombre_tomas())), edad_mayor());
+
+    let columnas = vec!["nombre".to_string(), "edad".to_string()];
+    let valores = vec!["Ana".to_string(), "sin dato".to_string()];
+
+    let and = and.compilar(&columnas).unwrap();
+    let or = or.compilar(&columnas).unwrap();
+
+    assert!(matches!(and.verificar(&valores), Ok(false)));
+    assert!(matches!(or.verificar(&valores), Ok(true)));
+}
+
+#[test]
+pub fn test07_una_comparacion_numerica_contra_un_valor_vacio_es_desconocida() {
+    let edad_cuatro = || {
+        Box::new(Condicion::CondicionSimple(CondicionSimple::new(
+            "edad".to_string(),
+            "=".to_string(),
+            "4".to_string(),
+        )))
+    };
+    let not = Condicion::Not(edad_cuatro())
+        .compilar(&["edad".to_string()])
+        .unwrap();
+    let or = Condicion::Or(edad_cuatro(), Box::new(Condicion::SiempreTrue))
+        .compilar(&["edad".to_string()])
+        .unwrap();
+
+    let vacio = vec!["".to_string()];
+
+    assert!(matches!(not.evaluar(&vacio), Ok(None)));
+    assert!(matches!(not.verificar(&vacio), Ok(false)));
+    assert!(matches!(or.evaluar(&vacio), Ok(Some(true))));
+}
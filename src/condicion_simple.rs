@@ -1,5 +1,7 @@
 use crate::my_error::MyError;
 use crate::valor::Valor;
+use std::cmp::Ordering;
+use std::fmt;
 
 ///# CondicionSimple
 ///Esta es la estructura que proporciona soporte para las operaciones lógica básicas
@@ -53,8 +55,21 @@ impl CondicionSimple {
             },
         }
     }
+    pub fn columna(&self) -> &str {
+        &self.columna
+    }
+
+    pub fn simbolo(&self) -> &str {
+        &self.simbolo
+    }
+
+    pub fn valor(&self) -> &Valor {
+        &self.valor
+    }
+
     ///# CondicionSimple.verificar()
-    ///Esta función verifica si la fila que se le pasa cumple la condición o no
+    ///Esta función verifica si la fila que se le pasa cumple la condición o no. Si la condición
+    ///compara contra un número y el valor de la fila está vacío se considera que no la cumple.
     ///
     ///**Parámetros**
     ///- 'cols': Es un array que representa a los nombres de las columnas de la tabla a la que
@@ -67,7 +82,21 @@ impl CondicionSimple {
     ///  ejecución de la función se devuelve el *bool*, en caso contrario se devuelve un error de
     ///  tipo *MyError*.
     pub fn verificar(&self, cols: &[String], valores: &[String]) -> Result<bool, MyError> {
-        let index = match cols.iter().position(|p| *p == self.columna) {
+        self.compilar(cols)?.verificar(valores)
+    }
+
+    ///# CondicionSimple.compilar()
+    ///Esta función resuelve una única vez la posición de la columna dentro de *'cols'* y el
+    ///operador de la condición, de forma que evaluar cada fila no requiera buscar nada.
+    ///
+    ///**Parámetros**
+    ///- 'cols': Es un array que representa a los nombres de las columnas de la tabla.
+    ///
+    ///**Return**
+    ///Devuelve un Result<ComparacionCompilada, MyError>, en caso de que la columna no exista en
+    ///  la tabla o el operador no sea válido se devuelve un error de tipo *MyError*.
+    pub fn compilar(&self, cols: &[String]) -> Result<ComparacionCompilada, MyError> {
+        let posicion = match cols.iter().position(|p| *p == self.columna) {
             Some(p) => p,
             None => {
                 return Err(MyError::InvalidColumn(
@@ -76,32 +105,131 @@ impl CondicionSimple {
             }
         };
 
-        if self.es_int != valores[index].parse::<i32>().is_ok() {
-            return Err(MyError::InvalidColumn("El tipo de dato que le corresponde a la columna especificada en la condición no es el utilizado".to_string()));
+        let operador = match self.simbolo.as_str() {
+            "=" => Operador::Igual,
+            "!=" => Operador::Distinto,
+            "<" => Operador::Menor,
+            ">" => Operador::Mayor,
+            "<=" => Operador::MenorOIgual,
+            ">=" => Operador::MayorOIgual,
+            _ => {
+                return Err(MyError::InvalidSyntax(
+                    "El simbolo utilizado en la operación condicional no existe".to_string(),
+                ))
+            }
+        };
+
+        Ok(ComparacionCompilada {
+            posicion,
+            operador,
+            valor: self.valor.clone(),
+        })
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Operador {
+    Igual,
+    Distinto,
+    Menor,
+    Mayor,
+    MenorOIgual,
+    MayorOIgual,
+}
+
+impl Operador {
+    fn cumple(&self, orden: Ordering) -> bool {
+        match self {
+            Operador::Igual => orden == Ordering::Equal,
+            Operador::Distinto => orden != Ordering::Equal,
+            Operador::Menor => orden == Ordering::Less,
+            Operador::Mayor => orden == Ordering::Greater,
+            Operador::MenorOIgual => orden != Ordering::Greater,
+            Operador::MayorOIgual => orden != Ordering::Less,
         }
+    }
+}
 
-        let aux = match self.es_int {
-            true => Valor::Entero(match valores[index].parse::<i32>() {
-                Ok(v) => v,
-                Err(_e) => return Err(MyError::InvalidColumn("El tipo de dato que le corresponde a la columna especificada en la condición no es el utilizado".to_string())),
-            }),
-            false => Valor::Palabra(String::from(&valores[index])),
+///# ComparacionCompilada
+///Es una *CondicionSimple* ya resuelta contra el encabezado de una tabla: guarda la posición de
+///la columna y el operador, por lo que cada fila se evalúa sin buscar la columna por nombre.
+///
+///**Ejemplo**
+///let comparacion = cond.compilar(columnas)?;
+///let resultado:bool = comparacion.verificar(valores)?;
+#[derive(Debug, PartialEq)]
+pub struct ComparacionCompilada {
+    posicion: usize,
+    operador: Operador,
+    valor: Valor,
+}
+
+impl ComparacionCompilada {
+    ///# ComparacionCompilada.verificar()
+    ///Esta función verifica si la fila cumple la comparación. Si se compara contra un número y el
+    ///valor de la fila está vacío se considera que no la cumple.
+    ///
+    ///**Parámetros**
+    ///- 'valores': Es un array que contiene los valores por columna de una fila de la tabla.
+    ///
+    ///**Return**
+    ///Devuelve un Result<bool, MyError>, en caso de que el tipo del dato de la fila no coincida
+    ///  con el de la condición se devuelve un error de tipo *MyError*.
+    pub fn verificar(&self, valores: &[String]) -> Result<bool, MyError> {
+        Ok(self.evaluar(valores)? == Some(true))
+    }
+
+    ///# ComparacionCompilada.evaluar()
+    ///Esta función evalúa la comparación sobre la fila distinguiendo el caso en el que no se puede
+    ///decidir: si se compara contra un número y el valor de la fila está vacío (NULL) el resultado
+    ///es desconocido.
+    ///
+    ///**Parámetros**
+    ///- 'valores': Es un array que contiene los valores por columna de una fila de la tabla.
+    ///
+    ///**Return**
+    ///Devuelve un Result<Option<bool>, MyError>, con *None* si el resultado es desconocido. En
+    ///  caso de que el tipo del dato de la fila no coincida con el de la condición se devuelve un
+    ///  error de tipo *MyError*.
+    pub fn evaluar(&self, valores: &[String]) -> Result<Option<bool>, MyError> {
+        let dato = match valores.get(self.posicion) {
+            Some(d) => d.as_str(),
+            None => "",
         };
 
-        match self.simbolo {
-            _ if *"=" == self.simbolo => Ok(aux == self.valor),
-            _ if *">" == self.simbolo => Ok(aux > self.valor),
-            _ if *"<" == self.simbolo => Ok(aux < self.valor),
-            _ if *"!=" == self.simbolo => Ok(aux != self.valor),
-            _ if *"<=" == self.simbolo => Ok(aux <= self.valor),
-            _ if *">=" == self.simbolo => Ok(aux >= self.valor),
-            _ => Err(MyError::InvalidSyntax(
-                "El simbolo utilizado en la operación condicional no existe".to_string(),
-            )),
+        match &self.valor {
+            Valor::Entero(valor) => {
+                if dato.is_empty() {
+                    return Ok(None);
+                }
+                match dato.parse::<i32>() {
+                    Ok(d) => Ok(Some(self.operador.cumple(d.cmp(valor)))),
+                    Err(_e) => Err(error_de_tipo()),
+                }
+            }
+            Valor::Palabra(valor) => {
+                if dato.parse::<i32>().is_ok() {
+                    return Err(error_de_tipo());
+                }
+                Ok(Some(self.operador.cumple(dato.cmp(valor.as_str()))))
+            }
         }
     }
 }
 
+fn error_de_tipo() -> MyError {
+    MyError::InvalidColumn(
+        "El tipo de dato que le corresponde a la columna especificada en la condición no es el utilizado"
+            .to_string(),
+    )
+}
+
+impl fmt::Display for CondicionSimple {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {} {}", self.columna, self.simbolo, self.valor)
+    }
+}
+
 #[test]
 pub fn test01_se_crea_una_condicion_simple_correctamente() {
     let condicion =
@@ -125,10 +253,7 @@ pub fn test02_se_verifica_si_la_condicion_se_cumple_utilizando_igual_y_se_cumple
     let cols = vec!["nombre".to_string(), "apellido".to_string()];
     let valores = vec!["Tomas".to_string(), "Amundarain".to_string()];
 
-    let resultado = match condicion.verificar(&cols, &valores) {
-        Ok(r) => r,
-        Err(_r) => false,
-    };
+    let resultado = condicion.verificar(&cols, &valores).unwrap_or_default();
 
     assert!(resultado);
 }
@@ -140,10 +265,7 @@ pub fn test03_se_verifica_si_la_condicion_se_cumple_utilizando_mayor_y_se_cumple
     let cols = vec!["nombre".to_string(), "valor".to_string()];
     let valores = vec!["Tomas".to_string(), "4".to_string()];
 
-    let resultado = match condicion.verificar(&cols, &valores) {
-        Ok(r) => r,
-        Err(_r) => false,
-    };
+    let resultado = condicion.verificar(&cols, &valores).unwrap_or_default();
 
     assert!(resultado);
 }
@@ -155,10 +277,7 @@ pub fn test04_se_verifica_si_la_condicion_se_cumple_utilizando_menor_y_se_cumple
     let cols = vec!["nombre".to_string(), "valor".to_string()];
     let valores = vec!["Tomas".to_string(), "4".to_string()];
 
-    let resultado = match condicion.verificar(&cols, &valores) {
-        Ok(r) => r,
-        Err(_r) => false,
-    };
+    let resultado = condicion.verificar(&cols, &valores).unwrap_or_default();
 
     assert!(resultado);
 }
@@ -170,10 +289,7 @@ pub fn test05_se_verifica_si_la_condicion_se_cumple_utilizando_diferente_y_se_cu
     let cols = vec!["nombre".to_string(), "valor".to_string()];
     let valores = vec!["Tomas".to_string(), "4".to_string()];
 
-    let resultado = match condicion.verificar(&cols, &valores) {
-        Ok(r) => r,
-        Err(_r) => false,
-    };
+    let resultado = condicion.verificar(&cols, &valores).unwrap_or_default();
 
     assert!(resultado);
 }
@@ -185,10 +301,7 @@ pub fn test06_se_verifica_si_la_condicion_se_cumple_utilizando_menoroigualque_y_
     let cols = vec!["nombre".to_string(), "valor".to_string()];
     let valores = vec!["Tomas".to_string(), "4".to_string()];
 
-    let resultado = match condicion.verificar(&cols, &valores) {
-        Ok(r) => r,
-        Err(_r) => false,
-    };
+    let resultado = condicion.verificar(&cols, &valores).unwrap_or_default();
 
     assert!(resultado);
 }
@@ -200,10 +313,19 @@ pub fn test07_se_verifica_si_la_condicion_se_cumple_utilizando_mayoroigualque_y_
     let cols = vec!["nombre".to_string(), "valor".to_string()];
     let valores = vec!["Tomas".to_string(), "4".to_string()];
 
-    let resultado = match condicion.verificar(&cols, &valores) {
-        Ok(r) => r,
-        Err(_r) => false,
-    };
+    let resultado = condicion.verificar(&cols, &valores).unwrap_or_default();
 
     assert!(resultado);
 }
+
+#[test]
+pub fn test08_una_fila_con_un_valor_vacio_no_cumple_una_condicion_numerica() {
+    let condicion = CondicionSimple::new("valor".to_string(), "!=".to_string(), "4".to_string());
+
+    let cols = vec!["nombre".to_string(), "valor".to_string()];
+    let valores = vec!["Tomas".to_string(), "".to_string()];
+
+    let resultado = condicion.verificar(&cols, &valores);
+
+    assert!(matches!(resultado, Ok(false)));
+}
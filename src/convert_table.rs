@@ -0,0 +1,236 @@
+use crate::almacenamiento::{self, TablaCsv};
+use crate::binario::TablaBinaria;
+use crate::esquema::{Columna, TipoDato};
+use crate::formato::Formato;
+use crate::memoria;
+use crate::my_error::MyError;
+use crate::particiones;
+use crate::wal;
+use std::fs;
+use std::path::Path;
+
+///# FormatoTabla
+///Representa los formatos de archivo a los que se puede convertir una tabla.
+///
+///**Tipos**
+///- 'Csv': Es el archivo de texto *.csv* (CSV).
+///- 'Binario': Es el archivo paginado *.bin* (BINARY).
+#[derive(Debug, PartialEq)]
+pub enum FormatoTabla {
+    Csv,
+    Binario,
+}
+
+impl FormatoTabla {
+    ///# FormatoTabla::desde_nombre()
+    ///Esta función devuelve el formato que corresponde al nombre que se usa en la instrucción.
+    ///
+    ///**Parámetros**
+    ///- 'nombre': Es el nombre del formato (CSV o BINARY).
+    pub fn desde_nombre(nombre: &str) -> Option<FormatoTabla> {
+        match nombre.to_uppercase().as_str() {
+            "CSV" => Some(FormatoTabla::Csv),
+            "BINARY" => Some(FormatoTabla::Binario),
+            _ => None,
+        }
+    }
+
+    fn nombre(&self) -> &str {
+        match self {
+            FormatoTabla::Csv => "CSV",
+            FormatoTabla::Binario => "BINARY",
+        }
+    }
+
+    fn extension(&self) -> &str {
+        match self {
+            FormatoTabla::Csv => ".csv",
+            FormatoTabla::Binario => ".bin",
+        }
+    }
+}
+
+///# ConvertTable
+///Esta estructura proporciona toda la funcionalidad para implementar la instrucción
+///CONVERT TABLE, que cambia el formato del archivo en el que se guarda una tabla.
+///
+///**Ejemplo**
+///let convert = ConvertTable::new(direccion_archivo, FormatoTabla::Binario);
+///convert.convertir();
+///
+///**Parámetros**
+///- 'archivo': Es la dirección del archivo que representa a la tabla que se quiere convertir.
+///- 'formato': Es el formato al que se quiere convertir la tabla.
+#[derive(Debug, PartialEq)]
+pub struct ConvertTable {
+    archivo: String,
+    formato: FormatoTabla,
+}
+
+impl ConvertTable {
+    ///# ConvertTable.new()
+    ///Esta función crea una nueva instacia de ConvertTable.
+    ///
+    ///**Parámetros**
+    ///- 'archivo': Es la dirección del archivo que representa a la tabla que se quiere convertir.
+    ///- 'formato': Es el formato al que se quiere convertir la tabla.
+    ///
+    ///**Return**
+    ///Devuelve un *struct* del tipo *ConvertTable*.
+    pub fn new(archivo: String, formato: FormatoTabla) -> Self {
+        ConvertTable { archivo, formato }
+    }
+
+    ///# ConvertTable.convertir()
+    ///Esta función realiza la instrucción CONVERT TABLE: escribe las filas de la tabla en un
+    ///archivo nuevo con la extensión del formato pedido y borra el original. El esquema y la
+    ///secuencia de la tabla se conservan; como las tablas binarias no admiten índices, al
+    ///convertir a BINARY se eliminan los índices de la tabla. Los valores de las columnas INT
+    ///tienen que ser enteros para poder guardarlos en una tabla binaria.
+    ///
+    ///**Return**
+    ///Devuelve un *Result<String, MyError>* en caso que durante la ejecución de la función no haya
+    ///  ocurrido ningún error se devuelve un *String* para indicar que la operación se realizó
+    ///  correctamente, en caso contrario se devuelve un error del tipo *MyError*.
+    pub fn convertir(&self) -> Result<String, MyError> {
+        if self.archivo.starts_with(memoria::PREFIJO) || particiones::es_particionada(&self.archivo)
+        {
+            return Err(MyError::InvalidTable(
+                "CONVERT TABLE solo se puede usar sobre tablas guardadas en archivos".to_string(),
+            ));
+        }
+        if almacenamiento::extension(&self.archivo) == ".dat" {
+            return Err(MyError::InvalidTable(
+                "Las tablas de ancho fijo se exportan a CSV con COPY".to_string(),
+            ));
+        }
+        let destino = format!(
+            "{}{}",
+            almacenamiento::sin_extension(&self.archivo),
+            self.formato.extension()
+        );
+        if destino == self.archivo {
+            return Err(MyError::InvalidTable(format!(
+                "La tabla ya está guardada en formato {}",
+                self.formato.nombre()
+            )));
+        }
+        if Path::new(&destino).exists() {
+            return Err(MyError::InvalidTable(format!(
+                "Ya existe el archivo {}",
+                destino
+            )));
+        }
+
+        let origen = almacenamiento::abrir(&self.archivo);
+        let _bloqueo = origen.bloquear(true)?;
+        let mut esquema = origen.esquema()?;
+        let mut recorrido = origen.recorrer(None)?;
+        let columnas = recorrido.columnas().to_vec();
+
+        let mut reescritura = match self.formato {
+            FormatoTabla::Csv => TablaCsv::crear(
+                &destino,
+                &columnas,
+                Formato::default().con_esquema(&esquema),
+            )?,
+            FormatoTabla::Binario => {
+                let tipadas: Vec<Columna> = columnas
+                    .iter()
+                    .map(|nombre| match esquema.columna(nombre) {
+                        Some(c) => c.clone(),
+                        None => Columna::new(nombre.to_string(), TipoDato::Texto),
+                    })
+                    .collect();
+                TablaBinaria::crear(&destino, &tipadas)?
+            }
+        };
+        while let Some(fila) = recorrido.siguiente()? {
+            reescritura.escribir(&fila)?;
+        }
+        reescritura.publicar()?;
+
+        if self.formato == FormatoTabla::Binario && !esquema.indices().is_empty() {
+            let indexadas: Vec<String> = esquema
+                .indices()
+                .iter()
+                .map(|i| i.columna().to_string())
+                .collect();
+            for columna in indexadas {
+                for eliminado in esquema.eliminar_indices_de(&columna) {
+                    let ruta = eliminado.ruta(&self.archivo);
+                    wal::registrar(&ruta)?;
+                    let _ = fs::remove_file(ruta);
+                }
+            }
+            esquema.guardar(&destino)?;
+        }
+
+        wal::registrar(&self.archivo)?;
+        if fs::remove_file(&self.archivo).is_err() {
+            return Err(MyError::Error(
+                "Fallo en el proceso de convertir la tabla".to_string(),
+            ));
+        }
+
+        Ok(format!(
+            "Se convirtió la tabla a formato {}",
+            self.formato.nombre()
+        ))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::esquema::Esquema;
+    use crate::indice::Indice;
+    use crate::pruebas::ejecutar;
+
+    #[test]
+    pub fn test01_una_tabla_se_convierte_a_binario_se_modifica_y_vuelve_a_csv() {
+        let directorio = "./test/binario";
+        let archivo = format!("{}/productos.csv", directorio);
+        let _ = fs::create_dir_all(directorio);
+        let _ = fs::remove_file(format!("{}/productos.bin", directorio));
+        fs::write(&archivo, "id,nombre,stock\n1,mesa,3\n2,silla,\n3,banco,7\n").unwrap();
+        fs::write(
+            Esquema::ruta(&archivo),
+            "id INT PRIMARY KEY\nnombre TEXT\nstock INT\n",
+        )
+        .unwrap();
+        let indice = Indice::new("idx_productos_id".to_string(), "id".to_string(), true);
+        let _ = fs::remove_file(indice.ruta(&archivo));
+        ejecutar(
+            directorio,
+            "CREATE UNIQUE INDEX idx_productos_id ON productos(id)",
+        )
+        .unwrap();
+
+        ejecutar(directorio, "CONVERT TABLE productos TO BINARY").unwrap();
+        let repetida = ejecutar(directorio, "CONVERT TABLE productos TO BINARY");
+        ejecutar(
+            directorio,
+            "INSERT INTO productos (id, nombre, stock) VALUES (4, ratona, 2)",
+        )
+        .unwrap();
+        ejecutar(directorio, "UPDATE productos SET stock = 0 WHERE id = 1").unwrap();
+        ejecutar(directorio, "DELETE FROM productos WHERE nombre = silla").unwrap();
+        let duplicado = ejecutar(
+            directorio,
+            "INSERT INTO productos (id, nombre, stock) VALUES (3, otro, 1)",
+        );
+        let sin_indice = !Path::new(&indice.ruta(&archivo)).exists()
+            && Esquema::cargar(&archivo).unwrap().indices().is_empty();
+        ejecutar(directorio, "CONVERT TABLE productos TO CSV").unwrap();
+
+        assert!(matches!(repetida, Err(MyError::InvalidTable(_))));
+        assert!(matches!(duplicado, Err(MyError::ConstraintViolation(_))));
+        assert!(sin_indice);
+        assert!(!Path::new(&format!("{}/productos.bin", directorio)).exists());
+        assert_eq!(
+            fs::read_to_string(&archivo).unwrap(),
+            "id,nombre,stock\n1,mesa,0\n3,banco,7\n4,ratona,2\n"
+        );
+    }
+}
@@ -0,0 +1,73 @@
+use crate::almacenamiento::{self, TablaCsv};
+use crate::formato::Formato;
+use crate::my_error::MyError;
+use std::path::Path;
+
+///# CopyTo
+///Esta estructura proporciona toda la funcionalidad para implementar la instrucción COPY ... TO,
+///que exporta las filas de una tabla de cualquier formato a un archivo CSV nuevo sin modificar la
+///tabla. Los valores que contienen comas o comillas se escriben entre comillas dobles.
+///
+///**Ejemplo**
+///let copy = CopyTo::new(direccion_archivo, destino);
+///copy.copiar();
+///
+///**Parámetros**
+///- 'archivo': Es la dirección del archivo que representa a la tabla que se quiere exportar.
+///- 'destino': Es la dirección del archivo CSV que se quiere crear.
+#[derive(Debug, PartialEq)]
+pub struct CopyTo {
+    archivo: String,
+    destino: String,
+}
+
+impl CopyTo {
+    ///# CopyTo.new()
+    ///Esta función crea una nueva instacia de CopyTo.
+    ///
+    ///**Parámetros**
+    ///- 'archivo': Es la dirección del archivo que representa a la tabla que se quiere exportar.
+    ///- 'destino': Es la dirección del archivo CSV que se quiere crear.
+    ///
+    ///**Return**
+    ///Devuelve un *struct* del tipo *CopyTo*.
+    pub fn new(archivo: String, destino: String) -> Self {
+        CopyTo { archivo, destino }
+    }
+
+    ///# CopyTo.copiar()
+    ///Esta función realiza la instrucción COPY ... TO: escribe el encabezado y las filas de la
+    ///tabla en el archivo de destino, que no puede existir. El archivo aparece recién cuando se
+    ///escribieron todas las filas.
+    ///
+    ///**Return**
+    ///Devuelve un *Result<String, MyError>* en caso que durante la ejecución de la función no haya
+    ///  ocurrido ningún error se devuelve un *String* con la cantidad de filas copiadas, en caso
+    ///  contrario se devuelve un error del tipo *MyError*.
+    pub fn copiar(&self) -> Result<String, MyError> {
+        if Path::new(&self.destino).exists() {
+            return Err(MyError::InvalidTable(format!(
+                "Ya existe el archivo {}",
+                self.destino
+            )));
+        }
+
+        let origen = almacenamiento::abrir(&self.archivo);
+        let _bloqueo = origen.bloquear(false)?;
+        let mut recorrido = origen.recorrer(None)?;
+        let mut reescritura = TablaCsv::crear(
+            &self.destino,
+            recorrido.columnas(),
+            Formato::new(',', Some('"')),
+        )?;
+
+        let mut copiadas = 0;
+        while let Some(fila) = recorrido.siguiente()? {
+            reescritura.escribir(&fila)?;
+            copiadas += 1;
+        }
+        reescritura.publicar()?;
+
+        Ok(format!("Se copiaron {} filas a {}", copiadas, self.destino))
+    }
+}
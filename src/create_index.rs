@@ -0,0 +1,124 @@
+use crate::almacenamiento;
+use crate::bloqueo::Bloqueo;
+use crate::esquema::Esquema;
+use crate::indice::{self, ActualizadorIndices, Indice};
+use crate::my_error::MyError;
+use std::path::Path;
+
+///# CreateIndex
+///Esta estructura proporciona toda la funcionalidad para implementar la instrucción
+///CREATE [UNIQUE] INDEX de sql.
+///
+///**Ejemplo**
+///let create = CreateIndex::new(direccion_archivo, Indice::new(nombre, columna, unico));
+///create.crear();
+///
+///**Parámetros**
+///- 'archivo': Es la dirección del archivo que representa a la tabla sobre la que se crea el
+///  índice.
+///- 'indice': Es el índice que se quiere crear.
+#[derive(Debug, PartialEq)]
+pub struct CreateIndex {
+    archivo: String,
+    indice: Indice,
+}
+
+impl CreateIndex {
+    ///# CreateIndex.new()
+    ///Esta función crea una nueva instacia de CreateIndex.
+    ///
+    ///**Parámetros**
+    ///- 'archivo': Es la dirección del archivo que representa a la tabla sobre la que se crea el
+    ///  índice.
+    ///- 'indice': Es el índice que se quiere crear.
+    ///
+    ///**Return**
+    ///Devuelve un *struct* del tipo *CreateIndex*.
+    pub fn new(archivo: String, indice: Indice) -> Self {
+        CreateIndex { archivo, indice }
+    }
+
+    ///# CreateIndex.crear()
+    ///Esta función realiza la instrucción CREATE INDEX de sql: arma el índice con las filas que ya
+    ///tiene la tabla y agrega su definición al esquema. Si el índice es UNIQUE y la columna ya
+    ///repite algún valor no se crea.
+    ///
+    ///**Return**
+    ///Devuelve un *Result<String, MyError>* en caso que durante la ejecución de la función no haya
+    ///  ocurrido ningún error se devuelve un *String* para indicar que la operación se realizó
+    ///  correctamente, en caso contrario se devuelve un error del tipo *MyError*.
+    pub fn crear(&self) -> Result<String, MyError> {
+        if !almacenamiento::es_csv(&self.archivo) {
+            return Err(MyError::InvalidTable(
+                "CREATE INDEX solo se puede usar sobre tablas guardadas en archivos CSV"
+                    .to_string(),
+            ));
+        }
+        let _bloqueo = Bloqueo::exclusivo(&self.archivo)?;
+        let mut esquema = Esquema::cargar(&self.archivo)?;
+
+        if esquema.columna(self.indice.columna()).is_none() {
+            return Err(MyError::InvalidColumn(format!(
+                "La columna {} no existe en la tabla",
+                self.indice.columna()
+            )));
+        }
+
+        if esquema.indice(self.indice.nombre()).is_some()
+            || Path::new(&self.indice.ruta(&self.archivo)).exists()
+        {
+            return Err(MyError::InvalidTable(format!(
+                "El índice {} ya existe",
+                self.indice.nombre()
+            )));
+        }
+
+        esquema.agregar_indice(self.indice.clone());
+        let temporales = ActualizadorIndices::desde_tabla(&self.archivo, &esquema)?.preparar()?;
+        esquema.guardar(&self.archivo)?;
+        indice::publicar(&self.archivo, temporales)?;
+
+        Ok(format!("Se creó el índice {}", self.indice.nombre()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::pruebas::{preparar_tabla, CLIENTES, ESQUEMA_CLIENTES};
+    use std::fs;
+
+    #[test]
+    pub fn test01_se_crea_un_indice_y_se_guarda_en_el_esquema() {
+        let archivo = preparar_tabla("crear_indice", "clientes", CLIENTES, ESQUEMA_CLIENTES);
+        let indice = Indice::new("idx_nombre".to_string(), "nombre".to_string(), true);
+
+        let resultado = CreateIndex::new(archivo.to_string(), indice.clone()).crear();
+        let repetido = CreateIndex::new(archivo.to_string(), indice.clone()).crear();
+
+        assert!(resultado.is_ok());
+        assert!(matches!(repetido, Err(MyError::InvalidTable(_))));
+        assert_eq!(
+            Esquema::cargar(&archivo).unwrap().indice("idx_nombre"),
+            Some(&indice)
+        );
+        assert!(fs::read_to_string(indice.ruta(&archivo))
+            .unwrap()
+            .contains("\nAna,"));
+    }
+
+    #[test]
+    pub fn test02_no_se_crea_un_indice_sobre_una_columna_inexistente() {
+        let archivo = preparar_tabla(
+            "crear_indice_inexistente",
+            "clientes",
+            CLIENTES,
+            ESQUEMA_CLIENTES,
+        );
+        let indice = Indice::new("idx_inexistente".to_string(), "telefono".to_string(), false);
+
+        let resultado = CreateIndex::new(archivo.to_string(), indice).crear();
+
+        assert!(matches!(resultado, Err(MyError::InvalidColumn(_))));
+    }
+}
@@ -1,7 +1,9 @@
+use crate::almacenamiento;
+use crate::clave_foranea::{self, TablaHija};
 use crate::condicion::Condicion;
+use crate::indice::{self, ActualizadorIndices};
 use crate::my_error::MyError;
-use std::fs::{self, File, OpenOptions};
-use std::io::{BufRead, BufReader, Write};
+use crate::planificador;
 
 ///# Delete
 ///Esta estructura proporciona toda la funcionalidad necesaria para poder soportar la instrucción
@@ -37,76 +39,60 @@ impl Delete {
     }
     ///# Delete.eliminar()
     ///Esta función realiza la eliminación de las filas de una tabla que cumplan la condición que ya tiene
-    ///  definida el struct.
+    ///  definida el struct. Si otras tablas hacen referencia a las filas eliminadas se aplica la
+    ///  acción ON DELETE declarada en su FOREIGN KEY. Si la condición compara una columna que tiene
+    ///  índice y el índice indica que ninguna fila la cumple, la tabla no se reescribe; los índices
+    ///  de la tabla se actualizan con las filas que quedan.
     ///
     ///**Return**
     ///Devuelve un *Result<String, MyError>* en caso de que no haya ocurrido un error devuelve el
     ///  *String* para avisar que se realizo la operción y en caso contrario se devuelve un error de
     ///  tipo *MyError*.
     pub fn eliminar(&self) -> Result<String, MyError> {
-        let archivo = match File::open(&self.archivo) {
-            Ok(a) => a,
-            Err(_e) => {
-                return Err(MyError::InvalidTable(
-                    "Directorio o nombre de la tabla incorrecto".to_string(),
-                ))
-            }
-        };
-        let mut buffer = BufReader::new(archivo);
-
-        let mut archivo_temporal = match OpenOptions::new()
-            .write(true)
-            .truncate(true)
-            .open("temporal.csv")
-        {
-            Ok(f) => f,
-            Err(_e) => {
-                return Err(MyError::Error(
-                    "Fallo en el proceso de eliminacion del dato de la tabla".to_string(),
-                ))
-            }
-        };
+        let almacenamiento = almacenamiento::abrir(&self.archivo);
+        let _bloqueo = almacenamiento.bloquear(true)?;
+        let mut recorrido = almacenamiento.recorrer(None)?;
+        let columnas_tabla = recorrido.columnas().to_vec();
+
+        let condicion = self.condicion.compilar(&columnas_tabla)?;
+        let esquema = almacenamiento.esquema()?;
+        let candidatas = planificador::elegir_acceso(&self.archivo, &esquema, &self.condicion)
+            .filas(&self.archivo)?;
+        if candidatas.as_ref().is_some_and(|c| c.is_empty()) {
+            return Ok(String::from("Se elimino correctamente el valor"));
+        }
 
-        let mut linea: String = String::new();
-        match buffer.read_line(&mut linea) {
-            Ok(l) => l,
-            Err(_e) => {
-                return Err(MyError::Error(
-                    "Fallo en el proceso de eliminación del dato de la tabla".to_string(),
-                ))
-            }
-        };
-        let _ = archivo_temporal.write_all(linea.as_bytes());
-
-        let columnas_tabla: Vec<String> = linea
-            .replace("\n", "")
-            .split(',')
-            .map(|s| s.to_string())
-            .collect();
-
-        for line in buffer.lines() {
-            let linea_actual = match line {
-                Ok(l) => l + &String::from("\n"),
-                Err(_e) => {
-                    return Err(MyError::Error(
-                        "Fallo en el proceso de eliminacón del dato de la tabla".to_string(),
-                    ))
-                }
-            };
+        let mut reescritura = almacenamiento.reescribir(&columnas_tabla)?;
+        let mut indices = ActualizadorIndices::new(&self.archivo, &esquema, &columnas_tabla)?;
 
-            let valores: &Vec<String> = &linea_actual.split(',').map(|s| s.to_string()).collect();
+        let hijas = TablaHija::buscar(&self.archivo)?;
+        let mut filas_eliminadas: Vec<Vec<String>> = Vec::new();
 
-            let verificacion = match self.condicion.verificar(&columnas_tabla, valores) {
-                Ok(c) => c,
-                Err(e) => return Err(e),
+        while let Some(valores) = recorrido.siguiente()? {
+            let es_candidata = match &candidatas {
+                Some(c) => recorrido
+                    .posicion()
+                    .is_some_and(|p| c.binary_search(&p).is_ok()),
+                None => true,
             };
 
+            let verificacion = es_candidata && condicion.verificar(&valores)?;
+
             if !verificacion {
-                let _ = archivo_temporal.write_all(linea_actual.as_bytes());
+                reescritura.origen(recorrido.posicion())?;
+                reescritura.escribir(&valores)?;
+                indices.agregar(&valores);
+            } else if !hijas.is_empty() {
+                filas_eliminadas.push(valores);
             }
         }
 
-        let _ = fs::rename("temporal.csv", &self.archivo);
+        let indices_nuevos = indices.preparar()?;
+        let _referencias =
+            clave_foranea::propagar_eliminacion(&hijas, &columnas_tabla, &filas_eliminadas)?;
+
+        reescritura.publicar()?;
+        indice::publicar(&self.archivo, indices_nuevos)?;
         Ok(String::from("Se elimino correctamente el valor"))
     }
 }
@@ -115,6 +101,11 @@ impl Delete {
 mod test {
     use super::*;
     use crate::condicion_simple::CondicionSimple;
+    use crate::create_index::CreateIndex;
+    use crate::indice::Indice;
+    use crate::pruebas::{self, CLIENTES, ESQUEMA_CLIENTES};
+    use crate::valor::Valor;
+    use std::fs;
 
     #[test]
     pub fn test01_se_crea_un_delete_correctamente() {
@@ -158,4 +149,32 @@ mod test {
 
         assert!(resultado.is_err())
     }
+
+    #[test]
+    pub fn test04_se_hace_un_delete_usando_un_indice_y_el_indice_se_actualiza() {
+        let archivo =
+            pruebas::preparar_tabla("delete_indice", "clientes", CLIENTES, ESQUEMA_CLIENTES);
+        let indice = Indice::new("idx_edad".to_string(), "edad".to_string(), false);
+        CreateIndex::new(archivo.to_string(), indice.clone())
+            .crear()
+            .unwrap();
+
+        let condicion = Condicion::CondicionSimple(CondicionSimple::new(
+            "edad".to_string(),
+            "=".to_string(),
+            "30".to_string(),
+        ));
+        let resultado = Delete::new(archivo.to_string(), condicion).eliminar();
+        let posiciones = indice
+            .buscar(&archivo, ">", &Valor::Entero(25))
+            .unwrap()
+            .unwrap();
+
+        assert!(resultado.is_ok());
+        assert_eq!(
+            fs::read_to_string(&archivo).unwrap(),
+            "id,nombre,email,edad\n1,Tomas,tomas@mail.com,25\n3,Luis,luis@mail.com,41\n"
+        );
+        assert_eq!(posiciones, vec![47]);
+    }
 }
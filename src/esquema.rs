@@ -0,0 +1,1029 @@
+use crate::almacenamiento;
+use crate::archivo_temporal::ArchivoTemporal;
+use crate::formato::Formato;
+use crate::indice::Indice;
+use crate::my_error::MyError;
+use std::fs;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+///# TipoDato
+///Representa los tipos de datos que puede tener una columna de una tabla.
+///
+///**Tipos**
+///- 'Entero': Es el tipo de las columnas numéricas (INT).
+///- 'Texto': Es el tipo de las columnas de texto (TEXT).
+#[derive(Debug, PartialEq, Clone)]
+pub enum TipoDato {
+    Entero,
+    Texto,
+}
+
+impl TipoDato {
+    ///# TipoDato::desde_nombre()
+    ///Esta función devuelve el tipo de dato que corresponde al nombre que se usa en las
+    ///instrucciones.
+    ///
+    ///**Parámetros**
+    ///- 'nombre': Es el nombre del tipo (INT, INTEGER, TEXT o VARCHAR).
+    ///
+    ///**Return**
+    ///Devuelve un *Result<TipoDato, MyError>*, en caso de que el nombre no corresponda a ningún
+    ///  tipo se devuelve un error del tipo *MyError*.
+    pub fn desde_nombre(nombre: &str) -> Result<TipoDato, MyError> {
+        match nombre.to_uppercase().as_str() {
+            "INT" | "INTEGER" => Ok(TipoDato::Entero),
+            "TEXT" | "VARCHAR" => Ok(TipoDato::Texto),
+            _ => Err(MyError::InvalidSyntax(format!(
+                "El tipo de dato {} no existe. Los tipos válidos son: INT, TEXT",
+                nombre
+            ))),
+        }
+    }
+
+    ///# TipoDato.nombre()
+    ///Esta función devuelve el nombre con el que se guarda el tipo en el esquema.
+    pub fn nombre(&self) -> &str {
+        match self {
+            TipoDato::Entero => "INT",
+            TipoDato::Texto => "TEXT",
+        }
+    }
+
+    ///# TipoDato.acepta()
+    ///Esta función indica si un valor puede guardarse en una columna de este tipo. El valor vacío
+    ///se acepta siempre porque representa la ausencia de dato.
+    pub fn acepta(&self, valor: &str) -> bool {
+        match self {
+            TipoDato::Entero => valor.is_empty() || valor.parse::<i32>().is_ok(),
+            TipoDato::Texto => true,
+        }
+    }
+}
+
+///# AccionReferencial
+///Representa lo que se hace con las filas de una tabla hija cuando se elimina o modifica la fila
+///de la tabla padre a la que hacen referencia.
+///
+///**Tipos**
+///- 'Restrict': No se permite la operación sobre la tabla padre (RESTRICT o NO ACTION).
+///- 'Cascade': Se eliminan o modifican también las filas de la tabla hija (CASCADE).
+///- 'SetNull': Se deja vacía la columna de las filas de la tabla hija (SET NULL).
+#[derive(Debug, PartialEq, Clone)]
+pub enum AccionReferencial {
+    Restrict,
+    Cascade,
+    SetNull,
+}
+
+impl AccionReferencial {
+    pub fn nombre(&self) -> &str {
+        match self {
+            AccionReferencial::Restrict => "RESTRICT",
+            AccionReferencial::Cascade => "CASCADE",
+            AccionReferencial::SetNull => "SET NULL",
+        }
+    }
+}
+
+///# Referencia
+///Esta estructura representa una restricción FOREIGN KEY, es decir la columna de otra tabla a la
+///que tienen que hacer referencia los valores de una columna.
+///
+///**Parámetros**
+///- 'tabla': Es el nombre de la tabla padre.
+///- 'columna': Es el nombre de la columna de la tabla padre.
+///- 'al_eliminar': Es lo que se hace cuando se elimina la fila padre (ON DELETE).
+///- 'al_actualizar': Es lo que se hace cuando se modifica la fila padre (ON UPDATE).
+#[derive(Debug, PartialEq, Clone)]
+pub struct Referencia {
+    tabla: String,
+    columna: String,
+    al_eliminar: AccionReferencial,
+    al_actualizar: AccionReferencial,
+}
+
+impl Referencia {
+    pub fn tabla(&self) -> &str {
+        &self.tabla
+    }
+
+    pub fn columna(&self) -> &str {
+        &self.columna
+    }
+
+    pub fn al_eliminar(&self) -> &AccionReferencial {
+        &self.al_eliminar
+    }
+
+    pub fn al_actualizar(&self) -> &AccionReferencial {
+        &self.al_actualizar
+    }
+
+    ///Lee la parte de la definición de la columna que empieza en *REFERENCES tabla(columna)*
+    ///y devuelve la referencia junto con la cantidad de tokens consumidos.
+    fn desde_tokens(tokens: &[String]) -> Result<(Referencia, usize), MyError> {
+        let error = || {
+            MyError::InvalidSyntax(
+                "Error en la definición de la FOREIGN KEY, se espera REFERENCES tabla(columna)"
+                    .to_string(),
+            )
+        };
+
+        let destino = tokens.get(1).ok_or_else(error)?;
+        let (tabla, columna) = match destino.strip_suffix(')').and_then(|d| d.split_once('(')) {
+            Some((t, c)) if !t.is_empty() && !c.is_empty() => (t.to_string(), c.to_string()),
+            _ => return Err(error()),
+        };
+
+        let mut referencia = Referencia {
+            tabla,
+            columna,
+            al_eliminar: AccionReferencial::Restrict,
+            al_actualizar: AccionReferencial::Restrict,
+        };
+
+        let mut index = 2;
+        while index + 2 < tokens.len() && tokens[index].to_uppercase() == "ON" {
+            let evento = tokens[index + 1].to_uppercase();
+            let (accion, consumidos) = match tokens[index + 2].to_uppercase().as_str() {
+                "CASCADE" => (AccionReferencial::Cascade, 3),
+                "RESTRICT" => (AccionReferencial::Restrict, 3),
+                "SET" if tokens.get(index + 3).map(|t| t.to_uppercase()) == Some("NULL".into()) => {
+                    (AccionReferencial::SetNull, 4)
+                }
+                "NO" if tokens.get(index + 3).map(|t| t.to_uppercase())
+                    == Some("ACTION".into()) =>
+                {
+                    (AccionReferencial::Restrict, 4)
+                }
+                _ => return Err(error()),
+            };
+
+            match evento.as_str() {
+                "DELETE" => referencia.al_eliminar = accion,
+                "UPDATE" => referencia.al_actualizar = accion,
+                _ => return Err(error()),
+            }
+            index += consumidos;
+        }
+
+        Ok((referencia, index))
+    }
+
+    fn definicion(&self) -> String {
+        let mut definicion = format!("REFERENCES {}({})", self.tabla, self.columna);
+
+        if self.al_eliminar != AccionReferencial::Restrict {
+            definicion.push_str(" ON DELETE ");
+            definicion.push_str(self.al_eliminar.nombre());
+        }
+        if self.al_actualizar != AccionReferencial::Restrict {
+            definicion.push_str(" ON UPDATE ");
+            definicion.push_str(self.al_actualizar.nombre());
+        }
+
+        definicion
+    }
+}
+
+///# Columna
+///Esta estructura representa la definición de una columna de una tabla.
+///
+///**Parámetros**
+///- 'nombre': Es el nombre de la columna.
+///- 'tipo': Es el tipo de dato de la columna.
+///- 'valor_por_defecto': Es el valor que toma la columna cuando no se indica ninguno. Puede ser
+///  un literal o CURRENT_DATE / CURRENT_TIMESTAMP, que se calculan al momento de escribir.
+///- 'no_nulo': Indica si la columna tiene la restricción NOT NULL.
+///- 'unico': Indica si la columna tiene la restricción UNIQUE.
+///- 'clave_primaria': Indica si la columna es la PRIMARY KEY de la tabla (implica NOT NULL y
+///  UNIQUE).
+///- 'auto_incremental': Indica si la columna es AUTO_INCREMENT, es decir que si no se indica un
+///  valor se le asigna el siguiente de la secuencia de la tabla.
+///- 'referencia': Es la columna de otra tabla a la que hace referencia, si la columna es una
+///  FOREIGN KEY.
+///- 'check': Es la expresión de la restricción CHECK de la columna, por ejemplo
+///  *(cantidad > 0)*.
+///- 'ubicacion': Es el carácter en el que empieza la columna (contando desde 1) y cuántos ocupa,
+///  en las tablas de ancho fijo.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Columna {
+    nombre: String,
+    tipo: TipoDato,
+    valor_por_defecto: Option<String>,
+    no_nulo: bool,
+    unico: bool,
+    clave_primaria: bool,
+    auto_incremental: bool,
+    referencia: Option<Referencia>,
+    check: Option<String>,
+    ubicacion: Option<(usize, usize)>,
+}
+
+impl Columna {
+    ///# Columna::new()
+    ///Esta función crea una nueva instancia de Columna sin valor por defecto.
+    ///
+    ///**Parámetros**
+    ///- 'nombre': Es el nombre de la columna.
+    ///- 'tipo': Es el tipo de dato de la columna.
+    ///
+    ///**Return**
+    ///Devuelve un *struct* de tipo *Columna*.
+    pub fn new(nombre: String, tipo: TipoDato) -> Self {
+        Columna {
+            nombre,
+            tipo,
+            valor_por_defecto: None,
+            no_nulo: false,
+            unico: false,
+            clave_primaria: false,
+            auto_incremental: false,
+            referencia: None,
+            check: None,
+            ubicacion: None,
+        }
+    }
+
+    ///# Columna::desde_definicion()
+    ///Esta función crea una columna a partir de su definición, por ejemplo
+    ///*cantidad INT NOT NULL DEFAULT 0* o, en una tabla de ancho fijo, *cantidad INT POSITION 11 5*
+    ///(empieza en el carácter 11 y ocupa 5).
+    ///
+    ///**Parámetros**
+    ///- 'tokens': Son las palabras que forman la definición de la columna.
+    ///
+    ///**Return**
+    ///Devuelve un *Result<Columna, MyError>*, en caso de que la definición no sea válida se
+    ///  devuelve un error del tipo *MyError*.
+    pub fn desde_definicion(tokens: &[String]) -> Result<Columna, MyError> {
+        if tokens.len() < 2 {
+            return Err(MyError::InvalidSyntax(
+                "La definición de la columna debe tener un nombre y un tipo".to_string(),
+            ));
+        }
+
+        let mut columna = Columna::new(
+            String::from(&tokens[0]),
+            TipoDato::desde_nombre(&tokens[1])?,
+        );
+
+        let mut index = 2;
+        while index < tokens.len() {
+            match tokens[index].to_uppercase().as_str() {
+                "DEFAULT" if index + 1 < tokens.len() => {
+                    let valor = match tokens[index + 1].to_uppercase().as_str() {
+                        FECHA_ACTUAL | MARCA_DE_TIEMPO_ACTUAL => tokens[index + 1].to_uppercase(),
+                        _ => String::from(&tokens[index + 1]),
+                    };
+                    columna.valor_por_defecto = Some(valor);
+                    index += 2;
+                }
+                "NOT" if index + 1 < tokens.len() && tokens[index + 1].to_uppercase() == "NULL" => {
+                    columna.no_nulo = true;
+                    index += 2;
+                }
+                "UNIQUE" => {
+                    columna.unico = true;
+                    index += 1;
+                }
+                "PRIMARY"
+                    if index + 1 < tokens.len() && tokens[index + 1].to_uppercase() == "KEY" =>
+                {
+                    columna.clave_primaria = true;
+                    index += 2;
+                }
+                "AUTO_INCREMENT" => {
+                    columna.auto_incremental = true;
+                    index += 1;
+                }
+                "REFERENCES" => {
+                    let (referencia, consumidos) = Referencia::desde_tokens(&tokens[index..])?;
+                    columna.referencia = Some(referencia);
+                    index += consumidos;
+                }
+                "POSITION" if index + 2 < tokens.len() => {
+                    match (
+                        tokens[index + 1].parse::<usize>(),
+                        tokens[index + 2].parse::<usize>(),
+                    ) {
+                        (Ok(inicio), Ok(largo)) if inicio > 0 && largo > 0 => {
+                            columna.ubicacion = Some((inicio, largo));
+                            index += 3;
+                        }
+                        _ => {
+                            return Err(MyError::InvalidSyntax(format!(
+                                "La posición de la columna {} debe ser su inicio y su largo",
+                                columna.nombre
+                            )))
+                        }
+                    }
+                }
+                "CHECK" => {
+                    let (expresion, consumidos) = leer_check(&tokens[index..])?;
+                    columna.check = Some(expresion);
+                    index += consumidos;
+                }
+                _ => {
+                    return Err(MyError::InvalidSyntax(format!(
+                        "Error en la definición de la columna {}",
+                        columna.nombre
+                    )))
+                }
+            }
+        }
+
+        if columna.auto_incremental
+            && (columna.tipo != TipoDato::Entero || columna.valor_por_defecto.is_some())
+        {
+            return Err(MyError::InvalidColumn(format!(
+                "La columna AUTO_INCREMENT {} debe ser de tipo INT y no puede tener valor por defecto",
+                columna.nombre
+            )));
+        }
+
+        if let Some(valor) = &columna.valor_por_defecto {
+            if !columna.tipo.acepta(valor) || (es_fecha(valor) && columna.tipo != TipoDato::Texto) {
+                return Err(MyError::InvalidColumn(format!(
+                    "El valor por defecto de la columna {} no es del tipo {}",
+                    columna.nombre,
+                    columna.tipo.nombre()
+                )));
+            }
+        }
+
+        Ok(columna)
+    }
+
+    ///# Columna.definicion()
+    ///Esta función devuelve la definición de la columna tal como se guarda en el esquema.
+    pub fn definicion(&self) -> String {
+        let mut definicion = format!("{} {}", self.nombre, self.tipo.nombre());
+
+        if self.clave_primaria {
+            definicion.push_str(" PRIMARY KEY");
+        }
+        if self.auto_incremental {
+            definicion.push_str(" AUTO_INCREMENT");
+        }
+        if self.no_nulo {
+            definicion.push_str(" NOT NULL");
+        }
+        if self.unico {
+            definicion.push_str(" UNIQUE");
+        }
+        if let Some(valor) = &self.valor_por_defecto {
+            definicion.push_str(" DEFAULT ");
+            definicion.push_str(valor);
+        }
+        if let Some(referencia) = &self.referencia {
+            definicion.push(' ');
+            definicion.push_str(&referencia.definicion());
+        }
+        if let Some(check) = &self.check {
+            definicion.push_str(" CHECK ");
+            definicion.push_str(check);
+        }
+        if let Some((inicio, largo)) = self.ubicacion {
+            definicion.push_str(&format!(" POSITION {} {}", inicio, largo));
+        }
+
+        definicion
+    }
+
+    pub fn nombre(&self) -> &str {
+        &self.nombre
+    }
+
+    pub fn tipo(&self) -> &TipoDato {
+        &self.tipo
+    }
+
+    pub fn valor_por_defecto(&self) -> Option<&String> {
+        self.valor_por_defecto.as_ref()
+    }
+
+    ///# Columna.resolver_valor_por_defecto()
+    ///Esta función devuelve el valor que se escribe en la columna cuando no se indica ninguno.
+    ///CURRENT_DATE y CURRENT_TIMESTAMP se reemplazan por la fecha y hora actuales (UTC) con el
+    ///formato *AAAA-MM-DD* y *AAAA-MM-DD HH:MM:SS*. Si no tiene valor por defecto devuelve vacío.
+    pub fn resolver_valor_por_defecto(&self) -> String {
+        match self.valor_por_defecto.as_deref() {
+            Some(FECHA_ACTUAL) => fecha_y_hora_actual().0,
+            Some(MARCA_DE_TIEMPO_ACTUAL) => {
+                let (fecha, hora) = fecha_y_hora_actual();
+                format!("{} {}", fecha, hora)
+            }
+            Some(valor) => String::from(valor),
+            None => String::new(),
+        }
+    }
+
+    pub fn referencia(&self) -> Option<&Referencia> {
+        self.referencia.as_ref()
+    }
+
+    pub fn check(&self) -> Option<&String> {
+        self.check.as_ref()
+    }
+
+    pub fn ubicacion(&self) -> Option<(usize, usize)> {
+        self.ubicacion
+    }
+
+    pub fn es_clave_primaria(&self) -> bool {
+        self.clave_primaria
+    }
+
+    pub fn es_auto_incremental(&self) -> bool {
+        self.auto_incremental
+    }
+
+    ///# Columna.es_no_nula()
+    ///Esta función indica si la columna no admite valores vacíos, ya sea por tener la restricción
+    ///NOT NULL o por ser la PRIMARY KEY.
+    pub fn es_no_nula(&self) -> bool {
+        self.no_nulo || self.clave_primaria
+    }
+
+    ///# Columna.es_unica()
+    ///Esta función indica si la columna no admite valores repetidos, ya sea por tener la
+    ///restricción UNIQUE o por ser la PRIMARY KEY.
+    pub fn es_unica(&self) -> bool {
+        self.unico || self.clave_primaria
+    }
+}
+
+///# Esquema
+///Esta estructura contiene la metadata de una tabla. Se guarda en un archivo con el mismo nombre
+///que la tabla y extensión *.esquema* con una línea por columna, en el orden en el que aparecen en
+///la tabla. Las restricciones que abarcan a toda la tabla se escriben en líneas aparte, por
+///ejemplo *CHECK (cantidad > 0)*, al igual que los índices, por ejemplo *INDEX idx_id (id)*, y
+///el formato del archivo, por ejemplo *DELIMITER ;* o *QUOTE "* (*DELIMITER TAB* para tabulación).
+///
+///**Ejemplo**
+///let esquema = Esquema::cargar(direccion_tabla)?;
+///esquema.guardar(direccion_tabla)?;
+///
+///**Parámetros**
+///- 'columnas': Son las definiciones de las columnas de la tabla.
+///- 'checks': Son las expresiones de las restricciones CHECK de la tabla.
+///- 'indices': Son los índices de la tabla.
+///- 'delimitador': Es el separador de los valores que declara la línea *DELIMITER*, si la hay.
+///- 'comillas': Son las comillas de los valores que declara la línea *QUOTE*, si la hay.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Esquema {
+    columnas: Vec<Columna>,
+    checks: Vec<String>,
+    indices: Vec<Indice>,
+    delimitador: Option<char>,
+    comillas: Option<char>,
+}
+
+impl Esquema {
+    ///# Esquema::new()
+    ///Esta función crea una nueva instancia de Esquema.
+    ///
+    ///**Parámetros**
+    ///- 'columnas': Son las definiciones de las columnas de la tabla.
+    ///
+    ///**Return**
+    ///Devuelve un *struct* de tipo *Esquema*.
+    pub fn new(columnas: Vec<Columna>) -> Self {
+        Esquema {
+            columnas,
+            checks: Vec::new(),
+            indices: Vec::new(),
+            delimitador: None,
+            comillas: None,
+        }
+    }
+
+    ///# Esquema::ruta()
+    ///Esta función devuelve la dirección del archivo de esquema que le corresponde a una tabla.
+    ///
+    ///**Parámetros**
+    ///- 'archivo_tabla': Es la dirección del archivo de la tabla.
+    pub fn ruta(archivo_tabla: &str) -> String {
+        format!("{}.esquema", almacenamiento::sin_extension(archivo_tabla))
+    }
+
+    ///# Esquema::cargar()
+    ///Esta función carga el esquema de una tabla. Si la tabla no tiene archivo de esquema se arma
+    ///uno a partir de la primera línea de la tabla con todas las columnas de tipo TEXT.
+    ///
+    ///**Parámetros**
+    ///- 'archivo_tabla': Es la dirección del archivo de la tabla.
+    ///
+    ///**Return**
+    ///Devuelve un *Result<Esquema, MyError>*, en caso de que no se pueda leer la tabla o el
+    ///  esquema se devuelve un error del tipo *MyError*.
+    pub fn cargar(archivo_tabla: &str) -> Result<Esquema, MyError> {
+        let ruta = Esquema::ruta(archivo_tabla);
+
+        if !Path::new(&ruta).exists() {
+            return Esquema::desde_encabezado(archivo_tabla);
+        }
+
+        match fs::read_to_string(&ruta) {
+            Ok(contenido) => Esquema::desde_texto(&contenido),
+            Err(_e) => Err(MyError::Error(
+                "Fallo en la lectura del esquema de la tabla".to_string(),
+            )),
+        }
+    }
+
+    ///# Esquema::desde_texto()
+    ///Esta función arma un esquema a partir del contenido de un archivo de esquema: una línea por
+    ///columna, CHECK o índice.
+    ///
+    ///**Parámetros**
+    ///- 'contenido': Es el texto del esquema.
+    ///
+    ///**Return**
+    ///Devuelve un *Result<Esquema, MyError>*, en caso de que alguna definición sea inválida se
+    ///  devuelve un error del tipo *MyError*.
+    pub fn desde_texto(contenido: &str) -> Result<Esquema, MyError> {
+        let mut columnas: Vec<Columna> = Vec::new();
+        let mut checks: Vec<String> = Vec::new();
+        let mut indices: Vec<Indice> = Vec::new();
+        let mut delimitador = None;
+        let mut comillas = None;
+        for linea in contenido.lines().filter(|l| !l.trim().is_empty()) {
+            let tokens: Vec<String> = linea.split_whitespace().map(|s| s.to_string()).collect();
+
+            match tokens[0].to_uppercase().as_str() {
+                "DELIMITER" => {
+                    delimitador = Some(leer_caracter(&tokens)?);
+                    continue;
+                }
+                "QUOTE" => {
+                    comillas = Some(leer_caracter(&tokens)?);
+                    continue;
+                }
+                _ => {}
+            }
+
+            if tokens[0].to_uppercase() == "CHECK" {
+                let (expresion, consumidos) = leer_check(&tokens)?;
+                if consumidos != tokens.len() {
+                    return Err(MyError::InvalidSyntax(
+                        "Error en la definición de un CHECK de la tabla".to_string(),
+                    ));
+                }
+                checks.push(expresion);
+                continue;
+            }
+
+            if ["INDEX", "UNIQUE"].contains(&tokens[0].to_uppercase().as_str()) {
+                indices.push(Indice::desde_definicion(&tokens)?);
+                continue;
+            }
+
+            columnas.push(Columna::desde_definicion(&tokens)?);
+        }
+
+        if columnas.iter().filter(|c| c.clave_primaria).count() > 1 {
+            return Err(MyError::InvalidTable(
+                "El esquema de la tabla tiene más de una PRIMARY KEY".to_string(),
+            ));
+        }
+
+        if columnas.iter().filter(|c| c.auto_incremental).count() > 1 {
+            return Err(MyError::InvalidTable(
+                "El esquema de la tabla tiene más de una columna AUTO_INCREMENT".to_string(),
+            ));
+        }
+
+        let mut esquema = Esquema::new(columnas);
+        esquema.checks = checks;
+        esquema.indices = indices;
+        esquema.delimitador = delimitador;
+        esquema.comillas = comillas;
+        Ok(esquema)
+    }
+
+    fn desde_encabezado(archivo_tabla: &str) -> Result<Esquema, MyError> {
+        let linea = almacenamiento::primera_linea(archivo_tabla)?;
+        Ok(Esquema::sin_tipos(
+            &Formato::de_tabla(archivo_tabla)?.separar(&linea),
+        ))
+    }
+
+    ///# Esquema::sin_tipos()
+    ///Esta función arma el esquema de una tabla que no declara uno: todas sus columnas son de tipo
+    ///TEXT y no tienen restricciones.
+    ///
+    ///**Parámetros**
+    ///- 'columnas': Son los nombres de las columnas del encabezado de la tabla.
+    pub fn sin_tipos(columnas: &[String]) -> Esquema {
+        Esquema::new(
+            columnas
+                .iter()
+                .map(|c| Columna::new(c.to_string(), TipoDato::Texto))
+                .collect(),
+        )
+    }
+
+    ///# Esquema.guardar()
+    ///Esta función escribe el esquema en el archivo que le corresponde a la tabla.
+    ///
+    ///**Parámetros**
+    ///- 'archivo_tabla': Es la dirección del archivo de la tabla.
+    ///
+    ///**Return**
+    ///Devuelve un *Result<String, MyError>*, en caso de que no se pueda escribir el archivo se
+    ///  devuelve un error del tipo *MyError*.
+    pub fn guardar(&self, archivo_tabla: &str) -> Result<String, MyError> {
+        let mut contenido = String::new();
+        for columna in &self.columnas {
+            contenido.push_str(&columna.definicion());
+            contenido.push('\n');
+        }
+        for check in &self.checks {
+            contenido.push_str("CHECK ");
+            contenido.push_str(check);
+            contenido.push('\n');
+        }
+        for indice in &self.indices {
+            contenido.push_str(&indice.definicion());
+            contenido.push('\n');
+        }
+        if let Some(delimitador) = self.delimitador {
+            contenido.push_str(&format!("DELIMITER {}\n", escribir_caracter(delimitador)));
+        }
+        if let Some(comillas) = self.comillas {
+            contenido.push_str(&format!("QUOTE {}\n", escribir_caracter(comillas)));
+        }
+
+        let mut archivo_temporal = ArchivoTemporal::crear(&Esquema::ruta(archivo_tabla))?;
+        archivo_temporal.escribir(&contenido)?;
+        archivo_temporal.publicar()?;
+        Ok("Esquema guardado".to_string())
+    }
+
+    pub fn columnas(&self) -> &[Columna] {
+        &self.columnas
+    }
+
+    pub fn delimitador(&self) -> Option<char> {
+        self.delimitador
+    }
+
+    pub fn comillas(&self) -> Option<char> {
+        self.comillas
+    }
+
+    ///# Esquema.auto_incremental()
+    ///Esta función devuelve la posición de la columna AUTO_INCREMENT de la tabla, si tiene una.
+    pub fn auto_incremental(&self) -> Option<usize> {
+        self.columnas.iter().position(|c| c.auto_incremental)
+    }
+
+    ///# Esquema.columna()
+    ///Esta función devuelve la definición de la columna con el nombre indicado, si existe.
+    pub fn columna(&self, nombre: &str) -> Option<&Columna> {
+        self.columnas.iter().find(|c| c.nombre == nombre)
+    }
+
+    ///# Esquema.posicion()
+    ///Esta función devuelve la posición en la tabla de la columna con el nombre indicado, si
+    ///existe.
+    pub fn posicion(&self, nombre: &str) -> Option<usize> {
+        self.columnas.iter().position(|c| c.nombre == nombre)
+    }
+
+    ///# Esquema.nombres()
+    ///Esta función devuelve los nombres de las columnas en el orden en el que están en la tabla.
+    pub fn nombres(&self) -> Vec<String> {
+        self.columnas
+            .iter()
+            .map(|c| String::from(&c.nombre))
+            .collect()
+    }
+
+    pub fn checks(&self) -> &[String] {
+        &self.checks
+    }
+
+    pub fn agregar_check(&mut self, expresion: String) {
+        self.checks.push(expresion);
+    }
+
+    pub fn indices(&self) -> &[Indice] {
+        &self.indices
+    }
+
+    pub fn indice(&self, nombre: &str) -> Option<&Indice> {
+        self.indices.iter().find(|i| i.nombre() == nombre)
+    }
+
+    pub fn agregar_indice(&mut self, indice: Indice) {
+        self.indices.push(indice);
+    }
+
+    ///# Esquema.eliminar_indices_de()
+    ///Esta función quita de la tabla los índices que tienen la columna indicada y los devuelve.
+    pub fn eliminar_indices_de(&mut self, columna: &str) -> Vec<Indice> {
+        let (eliminados, restantes) = self.indices.drain(..).partition(|i| i.columna() == columna);
+        self.indices = restantes;
+        eliminados
+    }
+
+    ///# Esquema.renombrar_columna()
+    ///Esta función cambia el nombre de una columna, también en las expresiones CHECK y en los
+    ///índices que la usan.
+    pub fn renombrar_columna(&mut self, posicion: usize, nuevo: &str) {
+        let anterior = self.columnas[posicion].nombre.to_string();
+        self.columnas[posicion].nombre = nuevo.to_string();
+
+        let renombrar = |check: &String| renombrar_en_expresion(check, &anterior, nuevo);
+        self.checks = self.checks.iter().map(renombrar).collect();
+        for columna in &mut self.columnas {
+            columna.check = columna.check.as_ref().map(renombrar);
+        }
+        for indice in &mut self.indices {
+            if indice.columna() == anterior {
+                indice.renombrar_columna(nuevo);
+            }
+        }
+    }
+
+    ///# Esquema.usa_en_check()
+    ///Esta función indica si alguna restricción CHECK de la tabla usa la columna indicada.
+    pub fn usa_en_check(&self, nombre: &str) -> bool {
+        self.columnas
+            .iter()
+            .filter_map(|c| c.check.as_ref())
+            .chain(self.checks.iter())
+            .any(|check| {
+                check
+                    .split(' ')
+                    .any(|t| t.trim_matches(['(', ')']) == nombre)
+            })
+    }
+
+    pub fn agregar_columna(&mut self, columna: Columna) {
+        self.columnas.push(columna);
+    }
+
+    pub fn eliminar_columna(&mut self, posicion: usize) -> Columna {
+        self.columnas.remove(posicion)
+    }
+}
+
+const FECHA_ACTUAL: &str = "CURRENT_DATE";
+const MARCA_DE_TIEMPO_ACTUAL: &str = "CURRENT_TIMESTAMP";
+
+fn es_fecha(valor: &str) -> bool {
+    valor == FECHA_ACTUAL || valor == MARCA_DE_TIEMPO_ACTUAL
+}
+
+///Devuelve la fecha (*AAAA-MM-DD*) y la hora (*HH:MM:SS*) actuales en UTC. Los días desde 1970
+///se convierten a fecha con el algoritmo de calendario civil de Howard Hinnant.
+fn fecha_y_hora_actual() -> (String, String) {
+    let segundos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0);
+    let dias = segundos.div_euclid(86400);
+    let resto = segundos.rem_euclid(86400);
+
+    let z = dias + 719468;
+    let era = z.div_euclid(146097);
+    let dia_de_era = z - era * 146097;
+    let anio_de_era =
+        (dia_de_era - dia_de_era / 1460 + dia_de_era / 36524 - dia_de_era / 146096) / 365;
+    let dia_del_anio = dia_de_era - (365 * anio_de_era + anio_de_era / 4 - anio_de_era / 100);
+    let mp = (5 * dia_del_anio + 2) / 153;
+    let dia = dia_del_anio - (153 * mp + 2) / 5 + 1;
+    let mes = if mp < 10 { mp + 3 } else { mp - 9 };
+    let anio = anio_de_era + era * 400 + if mes <= 2 { 1 } else { 0 };
+
+    (
+        format!("{:04}-{:02}-{:02}", anio, mes, dia),
+        format!(
+            "{:02}:{:02}:{:02}",
+            resto / 3600,
+            (resto % 3600) / 60,
+            resto % 60
+        ),
+    )
+}
+
+///Lee el carácter de una línea *DELIMITER* o *QUOTE*: un único carácter o *TAB*.
+fn leer_caracter(tokens: &[String]) -> Result<char, MyError> {
+    let error = || {
+        MyError::InvalidSyntax(format!(
+            "Error en la definición de {} del esquema",
+            tokens[0].to_uppercase()
+        ))
+    };
+    if tokens.len() != 2 {
+        return Err(error());
+    }
+
+    let mut caracteres = tokens[1].chars();
+    match (
+        tokens[1].to_uppercase().as_str(),
+        caracteres.next(),
+        caracteres.next(),
+    ) {
+        ("TAB", _, _) => Ok('\t'),
+        (_, Some(c), None) if !c.is_alphanumeric() => Ok(c),
+        _ => Err(error()),
+    }
+}
+
+fn escribir_caracter(caracter: char) -> String {
+    match caracter {
+        '\t' => "TAB".to_string(),
+        c => c.to_string(),
+    }
+}
+
+///Lee la expresión de un CHECK a partir del token *CHECK*, hasta que se cierran todos los
+///paréntesis, y devuelve la expresión junto con la cantidad de tokens consumidos.
+fn leer_check(tokens: &[String]) -> Result<(String, usize), MyError> {
+    let error = || {
+        MyError::InvalidSyntax(
+            "Error en la definición del CHECK, se espera CHECK (condición)".to_string(),
+        )
+    };
+
+    if tokens.len() < 2 || !tokens[1].starts_with('(') {
+        return Err(error());
+    }
+
+    let mut abiertos = 0;
+    let mut index = 1;
+    while index < tokens.len() {
+        abiertos += tokens[index].matches('(').count() as i32;
+        abiertos -= tokens[index].matches(')').count() as i32;
+        index += 1;
+
+        if abiertos <= 0 {
+            break;
+        }
+    }
+
+    if abiertos != 0 {
+        return Err(error());
+    }
+
+    Ok((tokens[1..index].join(" "), index))
+}
+
+fn renombrar_en_expresion(expresion: &str, anterior: &str, nuevo: &str) -> String {
+    expresion
+        .split(' ')
+        .map(|t| match t.trim_matches(['(', ')']) == anterior {
+            true => t.replace(anterior, nuevo),
+            false => t.to_string(),
+        })
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn test01_se_crea_una_columna_a_partir_de_su_definicion() {
+        let tokens: Vec<String> = ["cantidad", "INT", "DEFAULT", "0"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+
+        let columna = Columna::desde_definicion(&tokens).unwrap();
+
+        let mut columna_esperada = Columna::new("cantidad".to_string(), TipoDato::Entero);
+        columna_esperada.valor_por_defecto = Some("0".to_string());
+
+        assert_eq!(columna, columna_esperada);
+        assert_eq!(columna.definicion(), "cantidad INT DEFAULT 0");
+    }
+
+    #[test]
+    pub fn test02_falla_la_definicion_si_el_valor_por_defecto_no_es_del_tipo() {
+        let tokens: Vec<String> = ["cantidad", "INT", "DEFAULT", "mucho"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+
+        let resultado = Columna::desde_definicion(&tokens);
+
+        assert!(matches!(resultado, Err(MyError::InvalidColumn(_))));
+    }
+
+    #[test]
+    pub fn test03_se_crea_una_columna_con_restricciones() {
+        let tokens: Vec<String> = ["id", "INT", "PRIMARY", "KEY"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+
+        let columna = Columna::desde_definicion(&tokens).unwrap();
+
+        assert!(columna.es_clave_primaria());
+        assert!(columna.es_no_nula());
+        assert!(columna.es_unica());
+        assert_eq!(columna.definicion(), "id INT PRIMARY KEY");
+    }
+
+    #[test]
+    pub fn test04_se_crea_una_columna_que_hace_referencia_a_otra_tabla() {
+        let tokens: Vec<String> = [
+            "id_cliente",
+            "INT",
+            "REFERENCES",
+            "clientes(id)",
+            "ON",
+            "DELETE",
+            "SET",
+            "NULL",
+            "ON",
+            "UPDATE",
+            "CASCADE",
+        ]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+
+        let columna = Columna::desde_definicion(&tokens).unwrap();
+        let referencia = columna.referencia().unwrap();
+
+        assert_eq!(referencia.tabla(), "clientes");
+        assert_eq!(referencia.columna(), "id");
+        assert_eq!(*referencia.al_eliminar(), AccionReferencial::SetNull);
+        assert_eq!(*referencia.al_actualizar(), AccionReferencial::Cascade);
+        assert_eq!(columna.definicion(), tokens.join(" "));
+    }
+
+    #[test]
+    pub fn test05_se_carga_el_esquema_de_una_tabla_sin_archivo_de_esquema() {
+        let esquema = Esquema::cargar("./test/select.csv").unwrap();
+
+        assert_eq!(
+            esquema.nombres(),
+            vec!["id", "id_cliente", "producto", "cantidad"]
+        );
+        assert!(esquema
+            .columnas()
+            .iter()
+            .all(|c| *c.tipo() == TipoDato::Texto));
+    }
+
+    #[test]
+    pub fn test06_se_carga_un_esquema_con_checks_y_se_renombra_una_columna() {
+        let mut esquema = Esquema::cargar("./test/restricciones/productos_copia.csv").unwrap();
+
+        assert_eq!(
+            esquema.columna("cantidad").unwrap().check(),
+            Some(&"(cantidad > 0)".to_string())
+        );
+        assert_eq!(esquema.checks(), ["(maximo <= 100)".to_string()]);
+
+        esquema.renombrar_columna(2, "unidades");
+
+        assert_eq!(
+            esquema.columna("unidades").unwrap().definicion(),
+            "unidades INT CHECK (unidades > 0)"
+        );
+        assert_eq!(esquema.checks(), ["(maximo <= 100)".to_string()]);
+        assert!(esquema.usa_en_check("maximo"));
+        assert!(!esquema.usa_en_check("producto"));
+    }
+
+    #[test]
+    pub fn test07_se_crea_una_columna_auto_incremental_y_con_fecha_por_defecto() {
+        let definiciones = [
+            vec!["id", "INT", "PRIMARY", "KEY", "AUTO_INCREMENT"],
+            vec!["alta", "TEXT", "DEFAULT", "current_timestamp"],
+            vec!["codigo", "TEXT", "AUTO_INCREMENT"],
+            vec!["dia", "INT", "DEFAULT", "CURRENT_DATE"],
+        ];
+        let columnas: Vec<Result<Columna, MyError>> = definiciones
+            .iter()
+            .map(|d| {
+                let tokens: Vec<String> = d.iter().map(|s| s.to_string()).collect();
+                Columna::desde_definicion(&tokens)
+            })
+            .collect();
+
+        let id = columnas[0].as_ref().unwrap();
+        let alta = columnas[1].as_ref().unwrap();
+        let valor = alta.resolver_valor_por_defecto();
+
+        assert!(id.es_auto_incremental());
+        assert_eq!(id.definicion(), "id INT PRIMARY KEY AUTO_INCREMENT");
+        assert_eq!(alta.definicion(), "alta TEXT DEFAULT CURRENT_TIMESTAMP");
+        assert_eq!(valor.len(), "2024-01-01 10:00:00".len());
+        assert_eq!(&valor[4..5], "-");
+        assert_eq!(&valor[10..11], " ");
+        assert!(columnas[2].is_err());
+        assert!(columnas[3].is_err());
+    }
+}
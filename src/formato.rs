@@ -0,0 +1,393 @@
+use crate::almacenamiento;
+use crate::esquema::Esquema;
+use crate::my_error::MyError;
+use crate::operadores::Fila;
+use std::path::Path;
+
+///Son los separadores que se reconocen al detectar el formato de una tabla por su encabezado.
+const DELIMITADORES: [char; 4] = [',', ';', '\t', '|'];
+
+///Son las comillas que se usan cuando la tabla no declara otras.
+const COMILLAS: char = '"';
+
+///# Formato
+///Es la forma en la que se escriben las filas de una tabla de texto: el carácter que separa los
+///valores y el carácter con el que se encierran los valores que contienen al separador, a las
+///comillas (que dentro del valor se escriben dos veces) o un salto de línea. Si la tabla no
+///declara ni empieza con comillas se usa *"*.
+///
+///**Ejemplo**
+///let formato = Formato::de_tabla(direccion_tabla)?;
+///let fila = formato.separar(&linea);
+///let linea = formato.unir(&fila);
+///
+///**Parámetros**
+///- 'delimitador': Es el carácter que separa los valores.
+///- 'comillas': Es el carácter con el que se encierran los valores, si la tabla declara uno.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Formato {
+    delimitador: char,
+    comillas: Option<char>,
+}
+
+impl Default for Formato {
+    fn default() -> Self {
+        Formato::new(',', None)
+    }
+}
+
+impl Formato {
+    ///# Formato::new()
+    ///Esta función crea una nueva instancia de Formato.
+    ///
+    ///**Parámetros**
+    ///- 'delimitador': Es el carácter que separa los valores.
+    ///- 'comillas': Es el carácter con el que se encierran los valores, si se usa alguno.
+    ///
+    ///**Return**
+    ///Devuelve un *struct* de tipo *Formato*.
+    pub const fn new(delimitador: char, comillas: Option<char>) -> Self {
+        Formato {
+            delimitador,
+            comillas,
+        }
+    }
+
+    ///# Formato::detectar()
+    ///Esta función deduce el formato de una tabla a partir de su encabezado: el separador es el
+    ///de *,*, *;*, tabulación o *|* que más aparece fuera de comillas (*,* si no aparece ninguno) y
+    ///las comillas son *"* o *'* si el encabezado empieza con alguna de ellas.
+    ///
+    ///**Parámetros**
+    ///- 'encabezado': Es la primera línea de la tabla.
+    pub fn detectar(encabezado: &str) -> Self {
+        let comillas = encabezado
+            .chars()
+            .next()
+            .filter(|c| ['"', '\''].contains(c));
+        let encierro = comillas.unwrap_or(COMILLAS);
+
+        let mut cantidades = [0; DELIMITADORES.len()];
+        let mut entre_comillas = false;
+        for c in encabezado.chars() {
+            if c == encierro {
+                entre_comillas = !entre_comillas;
+            } else if let Some(i) = DELIMITADORES.iter().position(|d| *d == c) {
+                if !entre_comillas {
+                    cantidades[i] += 1;
+                }
+            }
+        }
+
+        let mut elegido = 0;
+        for (i, cantidad) in cantidades.iter().enumerate() {
+            if *cantidad > cantidades[elegido] {
+                elegido = i;
+            }
+        }
+        Formato::new(DELIMITADORES[elegido], comillas)
+    }
+
+    ///# Formato::de_tabla()
+    ///Esta función devuelve el formato del archivo de una tabla: el que declara su esquema con
+    ///*DELIMITER* y *QUOTE* y, lo que no declare, el que se detecta en su encabezado. Los archivos
+    ///*.tsv* se separan siempre con tabulaciones salvo que el esquema diga otra cosa.
+    ///
+    ///**Parámetros**
+    ///- 'archivo_tabla': Es la dirección del archivo de la tabla.
+    ///
+    ///**Return**
+    ///Devuelve un *Result<Formato, MyError>*, en caso de que no se pueda leer la tabla o su
+    ///  esquema se devuelve un error del tipo *MyError*.
+    pub fn de_tabla(archivo_tabla: &str) -> Result<Self, MyError> {
+        let mut detectado = Formato::detectar(&almacenamiento::primera_linea(archivo_tabla)?);
+        if archivo_tabla.ends_with(".tsv") {
+            detectado.delimitador = '\t';
+        }
+        match Path::new(&Esquema::ruta(archivo_tabla)).exists() {
+            true => Ok(detectado.con_esquema(&Esquema::cargar(archivo_tabla)?)),
+            false => Ok(detectado),
+        }
+    }
+
+    ///# Formato.con_esquema()
+    ///Esta función reemplaza el separador y las comillas por los que declare el esquema.
+    ///
+    ///**Parámetros**
+    ///- 'esquema': Es el esquema de la tabla.
+    pub fn con_esquema(self, esquema: &Esquema) -> Self {
+        Formato {
+            delimitador: esquema.delimitador().unwrap_or(self.delimitador),
+            comillas: esquema.comillas().or(self.comillas),
+        }
+    }
+
+    ///# Formato.con_delimitador()
+    ///Esta función reemplaza el separador por el indicado, conservando las comillas.
+    ///
+    ///**Parámetros**
+    ///- 'delimitador': Es el carácter que separa los valores.
+    pub fn con_delimitador(self, delimitador: char) -> Self {
+        Formato {
+            delimitador,
+            comillas: self.comillas,
+        }
+    }
+
+    ///# Formato.separar()
+    ///Esta función separa una línea de la tabla en sus valores, quitando las comillas que los
+    ///encierran y el salto de línea final.
+    ///
+    ///**Parámetros**
+    ///- 'linea': Es el texto de la fila.
+    pub fn separar(&self, linea: &str) -> Fila {
+        let linea = linea.trim_end_matches('\n').trim_end_matches('\r');
+        let comillas = self.comillas.unwrap_or(COMILLAS);
+
+        let mut fila = Vec::new();
+        let mut valor = String::new();
+        let mut caracteres = linea.chars().peekable();
+        let mut entre_comillas = false;
+        let mut al_comienzo = true;
+        while let Some(c) = caracteres.next() {
+            if entre_comillas {
+                if c != comillas {
+                    valor.push(c);
+                } else if caracteres.peek() == Some(&comillas) {
+                    valor.push(comillas);
+                    caracteres.next();
+                } else {
+                    entre_comillas = false;
+                }
+            } else if c == self.delimitador {
+                fila.push(std::mem::take(&mut valor));
+                al_comienzo = true;
+                continue;
+            } else if c == comillas && al_comienzo {
+                entre_comillas = true;
+            } else {
+                valor.push(c);
+            }
+            al_comienzo = false;
+        }
+        fila.push(valor);
+        fila
+    }
+
+    ///# Formato.separar_fila()
+    ///Esta función separa una fila de datos de la tabla como *separar()* y corrobora que tenga un
+    ///valor por cada columna.
+    ///
+    ///**Parámetros**
+    ///- 'linea': Es el texto de la fila.
+    ///- 'columnas': Es la cantidad de columnas del encabezado de la tabla.
+    ///
+    ///**Return**
+    ///Devuelve un *Result<Fila, MyError>*, en caso de que la fila no tenga la misma cantidad de
+    ///  valores que columnas se devuelve un error del tipo *MyError*.
+    pub fn separar_fila(&self, linea: &str, columnas: usize) -> Result<Fila, MyError> {
+        let fila = self.separar(linea);
+        match fila.len() == columnas {
+            true => Ok(fila),
+            false => Err(MyError::InvalidTable(format!(
+                "Una fila de la tabla tiene {} valores y la tabla tiene {} columnas",
+                fila.len(),
+                columnas
+            ))),
+        }
+    }
+
+    ///# Formato.unir_encabezado()
+    ///Esta función escribe el encabezado de la tabla. Si el formato usa comillas, todos los
+    ///nombres se encierran entre ellas para que el formato se pueda volver a detectar.
+    ///
+    ///**Parámetros**
+    ///- 'columnas': Son los nombres de las columnas.
+    pub fn unir_encabezado(&self, columnas: &[String]) -> String {
+        match self.comillas {
+            Some(c) => columnas
+                .iter()
+                .map(|nombre| {
+                    let doble = format!("{}{}", c, c);
+                    format!("{}{}{}", c, nombre.replace(c, &doble), c)
+                })
+                .collect::<Vec<String>>()
+                .join(&self.delimitador.to_string()),
+            None => self.unir(columnas),
+        }
+    }
+
+    ///# Formato.unir()
+    ///Esta función escribe los valores de una fila separados por el delimitador, sin salto de
+    ///línea. Encierra entre comillas los valores que contienen al separador, a las comillas o un
+    ///salto de línea.
+    ///
+    ///**Parámetros**
+    ///- 'fila': Son los valores de la fila.
+    pub fn unir(&self, fila: &[String]) -> String {
+        let delimitador = self.delimitador.to_string();
+        let comillas = self.comillas.unwrap_or(COMILLAS);
+
+        fila.iter()
+            .map(
+                |valor| match valor.contains([self.delimitador, comillas, '\n', '\r']) {
+                    true => {
+                        let doble = format!("{}{}", comillas, comillas);
+                        format!(
+                            "{}{}{}",
+                            comillas,
+                            valor.replace(comillas, &doble),
+                            comillas
+                        )
+                    }
+                    false => String::from(valor),
+                },
+            )
+            .collect::<Vec<String>>()
+            .join(&delimitador)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::create_index::CreateIndex;
+    use crate::indice::Indice;
+    use crate::memoria::BaseEnMemoria;
+    use crate::operacion::Operacion;
+    use crate::parser::Parser;
+    use crate::valor::Valor;
+    use std::fs;
+
+    #[test]
+    pub fn test01_se_detecta_el_formato_por_el_encabezado() {
+        assert_eq!(Formato::detectar("id,nombre\n"), Formato::new(',', None));
+        assert_eq!(
+            Formato::detectar("id;nombre;email\n"),
+            Formato::new(';', None)
+        );
+        assert_eq!(
+            Formato::detectar("id\tnombre,apellido\tedad"),
+            Formato::new('\t', None)
+        );
+        assert_eq!(
+            Formato::detectar("\"id\";\"a,b\"\n"),
+            Formato::new(';', Some('"'))
+        );
+        assert_eq!(Formato::detectar("id\n"), Formato::new(',', None));
+    }
+
+    #[test]
+    pub fn test02_los_valores_con_separador_o_comillas_se_leen_como_se_escribieron() {
+        let formato = Formato::new(';', Some('"'));
+        let fila: Fila = vec![
+            "1".to_string(),
+            "Perez; Juan".to_string(),
+            "dijo \"hola\"".to_string(),
+            String::new(),
+        ];
+
+        let linea = formato.unir(&fila);
+
+        assert_eq!(linea, "1;\"Perez; Juan\";\"dijo \"\"hola\"\"\";");
+        assert_eq!(formato.separar(&(linea + "\r\n")), fila);
+    }
+
+    #[test]
+    pub fn test03_las_instrucciones_respetan_el_formato_de_la_tabla() {
+        let directorio = "./test/formato";
+        let archivo = format!("{}/ventas.csv", directorio);
+        let _ = fs::create_dir_all(directorio);
+        fs::write(
+            &archivo,
+            "\"id\";\"cliente\";\"nota\"\n1;\"Perez; Juan\";ok\n2;Ana;\"dijo \"\"hola\"\"\"\n",
+        )
+        .unwrap();
+        fs::write(Esquema::ruta(&archivo), "id INT\ncliente TEXT\nnota TEXT\n").unwrap();
+        let indice = Indice::new("idx_ventas_id".to_string(), "id".to_string(), false);
+        let _ = fs::remove_file(indice.ruta(&archivo));
+        CreateIndex::new(archivo.clone(), indice.clone())
+            .crear()
+            .unwrap();
+
+        for instruccion in [
+            "INSERT INTO ventas (id, cliente, nota) VALUES (3, Gomez;Eva, nueva)",
+            "UPDATE ventas SET nota = cambiada WHERE id = 1",
+            "DELETE FROM ventas WHERE cliente = Ana",
+        ] {
+            Parser::new()
+                .crear_operacion(directorio.to_string(), instruccion.to_string())
+                .unwrap()
+                .realizar_operacion()
+                .unwrap();
+        }
+        let contenido = fs::read_to_string(&archivo).unwrap();
+        let posicion = indice
+            .buscar(&archivo, "=", &Valor::Entero(3))
+            .unwrap()
+            .unwrap()[0] as usize;
+
+        assert_eq!(
+            contenido,
+            "\"id\";\"cliente\";\"nota\"\n1;\"Perez; Juan\";cambiada\n3;\"Gomez;Eva\";nueva\n"
+        );
+        assert!(contenido[posicion..].starts_with("3;"));
+    }
+
+    #[test]
+    pub fn test04_sin_comillas_declaradas_se_usan_comillas_dobles() {
+        let base = BaseEnMemoria::new("formato_sin_comillas");
+        base.cargar("clientes", "id;nombre;ciudad\n1;\"Lu;is\";Salta\n");
+        let ejecutar = |instruccion: &str| {
+            Parser::new()
+                .crear_operacion(base.direccion().to_string(), instruccion.to_string())?
+                .realizar_operacion()
+        };
+
+        ejecutar("INSERT INTO clientes (id, nombre, ciudad) VALUES (5, a;b, c)").unwrap();
+        ejecutar("UPDATE clientes SET ciudad = Jujuy WHERE nombre = Lu;is").unwrap();
+        let (_, filas) = match Parser::new().crear_operacion(
+            base.direccion().to_string(),
+            "SELECT id, nombre, ciudad FROM clientes".to_string(),
+        ) {
+            Ok(Operacion::Select(select)) => select.filas().unwrap(),
+            _ => panic!("Se esperaba un SELECT"),
+        };
+
+        assert_eq!(
+            Formato::detectar("id;nombre;ciudad"),
+            Formato::new(';', None)
+        );
+        assert_eq!(
+            base.volcar("clientes").unwrap(),
+            "id;nombre;ciudad\n1;\"Lu;is\";Jujuy\n5;\"a;b\";c\n"
+        );
+        assert_eq!(
+            filas,
+            vec![
+                vec!["1".to_string(), "Lu;is".to_string(), "Jujuy".to_string()],
+                vec!["5".to_string(), "a;b".to_string(), "c".to_string()],
+            ]
+        );
+    }
+
+    #[test]
+    pub fn test05_una_fila_con_otra_cantidad_de_valores_que_columnas_es_invalida() {
+        let base = BaseEnMemoria::new("formato_fila_invalida");
+        base.cargar("clientes", "id;nombre;ciudad\n1;Lu;is;Salta\n");
+
+        let resultado = Parser::new()
+            .crear_operacion(
+                base.direccion().to_string(),
+                "DELETE FROM clientes WHERE id = 1".to_string(),
+            )
+            .unwrap()
+            .realizar_operacion();
+
+        assert!(matches!(resultado, Err(MyError::InvalidTable(_))));
+        assert!(matches!(
+            Formato::new(';', None).separar_fila("1;Lu;is;Salta", 3),
+            Err(MyError::InvalidTable(_))
+        ));
+    }
+}
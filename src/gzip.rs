@@ -0,0 +1,286 @@
+use crate::almacenamiento::{self, Almacenamiento, Reescritura};
+use crate::archivo_temporal::ArchivoTemporal;
+use crate::bloqueo::Bloqueo;
+use crate::esquema::Esquema;
+use crate::formato::Formato;
+use crate::my_error::MyError;
+use crate::operadores::{Fila, Operador};
+use flate2::read::MultiGzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Write};
+
+///# TablaGzip
+///Es el almacenamiento de una tabla CSV comprimida con gzip (*tabla.csv.gz*). Se lee
+///descomprimiéndola a medida que se recorre, sin escribir nunca la versión descomprimida, y tiene
+///el mismo formato que una tabla CSV (encabezado, separador y comillas).
+///
+///Las filas que agrega INSERT se comprimen por separado y se escriben al final del archivo como
+///un nuevo miembro gzip (un archivo gzip puede tener varios, que se leen uno detrás de otro), así
+///no hace falta reescribir la tabla. UPDATE y DELETE la reescriben comprimida completa, en un
+///único miembro.
+#[derive(Debug, PartialEq)]
+pub struct TablaGzip {
+    archivo: String,
+}
+
+impl TablaGzip {
+    ///# TablaGzip::new()
+    ///Esta función crea una nueva instancia de TablaGzip.
+    ///
+    ///**Parámetros**
+    ///- 'archivo': Es la dirección del archivo *.csv.gz* de la tabla.
+    ///
+    ///**Return**
+    ///Devuelve un *struct* de tipo *TablaGzip*.
+    pub fn new(archivo: &str) -> Self {
+        TablaGzip {
+            archivo: archivo.to_string(),
+        }
+    }
+
+    fn formato(&self) -> Result<Formato, MyError> {
+        Formato::de_tabla(&self.archivo)
+    }
+}
+
+impl Almacenamiento for TablaGzip {
+    fn archivo(&self) -> &str {
+        &self.archivo
+    }
+
+    fn bloquear(&self, exclusivo: bool) -> Result<Option<Bloqueo>, MyError> {
+        almacenamiento::bloquear_archivo(&self.archivo, exclusivo)
+    }
+
+    fn esquema(&self) -> Result<Esquema, MyError> {
+        Esquema::cargar(&self.archivo)
+    }
+
+    fn recorrer(&self, posiciones: Option<Vec<u64>>) -> Result<Box<dyn Operador>, MyError> {
+        let formato = self.formato()?;
+        let mut lector = descomprimir(&self.archivo)?;
+        let mut encabezado = String::new();
+        let largo = match lector.read_line(&mut encabezado) {
+            Ok(l) => l as u64,
+            Err(_e) => return Err(error_de_lectura()),
+        };
+
+        Ok(Box::new(RecorridoGzip {
+            columnas: formato.separar(&encabezado),
+            formato,
+            lector,
+            posiciones,
+            siguiente_posicion: largo,
+            ultima_posicion: largo,
+        }))
+    }
+
+    fn anexar(&self, filas: &[Fila]) -> Result<(), MyError> {
+        let formato = self.formato()?;
+        let mut codificador = GzEncoder::new(Vec::new(), Compression::default());
+        for fila in filas {
+            if codificador
+                .write_all((formato.unir(fila) + "\n").as_bytes())
+                .is_err()
+            {
+                return Err(error_de_compresion());
+            }
+        }
+        match codificador.finish() {
+            Ok(miembro) => almacenamiento::anexar_bytes(&self.archivo, &miembro),
+            Err(_e) => Err(error_de_compresion()),
+        }
+    }
+
+    fn reescribir(&self, columnas: &[String]) -> Result<Box<dyn Reescritura>, MyError> {
+        let formato = self.formato()?;
+        let temporal = ArchivoTemporal::crear(&self.archivo)?;
+        let mut reescritura = ReescrituraGzip {
+            codificador: GzEncoder::new(temporal, Compression::default()),
+            formato,
+        };
+        reescritura.escribir_linea(&formato.unir_encabezado(columnas))?;
+        Ok(Box::new(reescritura))
+    }
+
+    fn largo(&self) -> Result<u64, MyError> {
+        almacenamiento::largo_archivo(&self.archivo)
+    }
+
+    fn tablas_con_esquema(&self) -> Result<Vec<String>, MyError> {
+        almacenamiento::tablas_del_directorio(&self.archivo)
+    }
+
+    fn ubicacion_temporal(&self) -> String {
+        String::from(&self.archivo)
+    }
+}
+
+///# gzip::descomprimir()
+///Esta función abre una tabla comprimida para leer su contenido descomprimido, de todos sus
+///miembros gzip.
+///
+///**Parámetros**
+///- 'archivo': Es la dirección del archivo *.csv.gz* de la tabla.
+///
+///**Return**
+///Devuelve un *Result<BufReader<MultiGzDecoder<File>>, MyError>*, en caso de que no se pueda
+///  abrir el archivo se devuelve un error del tipo *MyError*.
+pub fn descomprimir(archivo: &str) -> Result<BufReader<MultiGzDecoder<File>>, MyError> {
+    match File::open(archivo) {
+        Ok(f) => Ok(BufReader::new(MultiGzDecoder::new(f))),
+        Err(_e) => Err(MyError::InvalidTable(
+            "Directorio o nombre de la tabla incorrecto".to_string(),
+        )),
+    }
+}
+
+///Lee las líneas descomprimidas de la tabla. La posición de cada fila es el byte del contenido
+///descomprimido en el que empieza.
+struct RecorridoGzip {
+    columnas: Vec<String>,
+    formato: Formato,
+    lector: BufReader<MultiGzDecoder<File>>,
+    posiciones: Option<Vec<u64>>,
+    siguiente_posicion: u64,
+    ultima_posicion: u64,
+}
+
+impl Operador for RecorridoGzip {
+    fn columnas(&self) -> &[String] {
+        &self.columnas
+    }
+
+    fn siguiente(&mut self) -> Result<Option<Fila>, MyError> {
+        loop {
+            let mut linea = String::new();
+            let largo = match self.lector.read_line(&mut linea) {
+                Ok(0) => return Ok(None),
+                Ok(l) => l as u64,
+                Err(_e) => return Err(error_de_lectura()),
+            };
+            let posicion = self.siguiente_posicion;
+            self.siguiente_posicion += largo;
+
+            let elegida = match &self.posiciones {
+                Some(p) => p.binary_search(&posicion).is_ok(),
+                None => true,
+            };
+            if elegida {
+                self.ultima_posicion = posicion;
+                return Ok(Some(
+                    self.formato.separar_fila(&linea, self.columnas.len())?,
+                ));
+            }
+        }
+    }
+
+    fn posicion(&self) -> Option<u64> {
+        Some(self.ultima_posicion)
+    }
+}
+
+///Comprime las filas a medida que se escriben en un *ArchivoTemporal*, que se renombra sobre la
+///tabla al publicarlo.
+struct ReescrituraGzip {
+    codificador: GzEncoder<ArchivoTemporal>,
+    formato: Formato,
+}
+
+impl ReescrituraGzip {
+    fn escribir_linea(&mut self, linea: &str) -> Result<(), MyError> {
+        match self
+            .codificador
+            .write_all((linea.to_string() + "\n").as_bytes())
+        {
+            Ok(_) => Ok(()),
+            Err(_e) => Err(error_de_compresion()),
+        }
+    }
+}
+
+impl Reescritura for ReescrituraGzip {
+    fn escribir(&mut self, fila: &[String]) -> Result<(), MyError> {
+        let linea = self.formato.unir(fila);
+        self.escribir_linea(&linea)
+    }
+
+    fn publicar(self: Box<Self>) -> Result<(), MyError> {
+        match self.codificador.finish() {
+            Ok(temporal) => temporal.publicar(),
+            Err(_e) => Err(error_de_compresion()),
+        }
+    }
+}
+
+fn error_de_lectura() -> MyError {
+    MyError::Error("Fallo en la lectura de la tabla comprimida".to_string())
+}
+
+fn error_de_compresion() -> MyError {
+    MyError::Error("Fallo en la compresión de la tabla".to_string())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::operadores;
+    use crate::pruebas::ejecutar;
+    use std::fs;
+    use std::io::Read;
+
+    fn contenido(archivo: &str) -> String {
+        let mut texto = String::new();
+        descomprimir(archivo)
+            .unwrap()
+            .read_to_string(&mut texto)
+            .unwrap();
+        texto
+    }
+
+    #[test]
+    pub fn test01_se_ejecutan_instrucciones_sobre_una_tabla_comprimida() {
+        let directorio = "./test/gzip";
+        let archivo = format!("{}/pedidos.csv.gz", directorio);
+        let _ = fs::create_dir_all(directorio);
+        let _ = fs::remove_file(format!("{}/pedidos.csv", directorio));
+        let mut codificador = GzEncoder::new(Vec::new(), Compression::default());
+        codificador
+            .write_all(b"\"id\";\"producto\"\n1;mesa\n2;\"silla; roja\"\n")
+            .unwrap();
+        fs::write(&archivo, codificador.finish().unwrap()).unwrap();
+
+        ejecutar(
+            directorio,
+            "INSERT INTO pedidos (id, producto) VALUES (3, banco)",
+        )
+        .unwrap();
+        let despues_del_insert = contenido(&archivo);
+        let leidas = operadores::recolectar(
+            almacenamiento::abrir(&almacenamiento::resolver(directorio, "pedidos").unwrap())
+                .recorrer(None)
+                .unwrap()
+                .as_mut(),
+        )
+        .unwrap();
+        ejecutar(
+            directorio,
+            "UPDATE pedidos SET producto = sillon WHERE id = 2",
+        )
+        .unwrap();
+        ejecutar(directorio, "DELETE FROM pedidos WHERE id = 1").unwrap();
+
+        assert_eq!(
+            despues_del_insert,
+            "\"id\";\"producto\"\n1;mesa\n2;\"silla; roja\"\n3;banco\n"
+        );
+        assert_eq!(leidas[1], vec!["2", "silla; roja"]);
+        assert!(fs::read(&archivo).unwrap().starts_with(&[0x1f, 0x8b]));
+        assert_eq!(
+            contenido(&archivo),
+            "\"id\";\"producto\"\n2;sillon\n3;banco\n"
+        );
+    }
+}
@@ -0,0 +1,875 @@
+use crate::almacenamiento;
+use crate::archivo_temporal::ArchivoTemporal;
+use crate::esquema::Esquema;
+use crate::formato::Formato;
+use crate::my_error::MyError;
+use crate::valor::Valor;
+use std::cmp::Ordering;
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, Seek, SeekFrom, Write};
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+
+///Una entrada del índice: el valor de la columna y el byte en el que empieza la fila.
+type Entrada = (String, u64);
+
+///El archivo de un índice abierto para leer, ubicado en alguna de sus entradas.
+type Lector = BufReader<File>;
+
+///# Indice
+///Esta estructura representa un índice secundario sobre una columna de una tabla, creado con
+///*CREATE [UNIQUE] INDEX nombre ON tabla(columna)*. La definición se guarda en el esquema de la
+///tabla y las entradas en un archivo *nombre.indice* en el directorio de la base.
+///
+///El archivo del índice empieza con una línea con el largo en bytes y la fecha de modificación
+///(en nanosegundos) que tenía la tabla cuando se escribió, y sigue con una línea
+///*valor,posición* por fila, ordenadas por valor, donde la posición es el byte en el que empieza
+///la fila en la tabla. Si el largo o la fecha no coinciden con los de la tabla el índice está
+///desactualizado y no se usa, aunque la tabla se haya modificado sin cambiar de largo.
+///
+///**Ejemplo**
+///let indice = Indice::new(nombre, columna, false);
+///let posiciones = indice.buscar(direccion_tabla, "=", &valor)?;
+///
+///**Parámetros**
+///- 'nombre': Es el nombre del índice.
+///- 'columna': Es la columna de la tabla sobre la que está el índice.
+///- 'unico': Indica si el índice es UNIQUE, en cuyo caso la columna no puede repetir valores
+///  (salvo los vacíos).
+#[derive(Debug, PartialEq, Clone)]
+pub struct Indice {
+    nombre: String,
+    columna: String,
+    unico: bool,
+}
+
+impl Indice {
+    ///# Indice::new()
+    ///Esta función crea una nueva instancia de Indice.
+    ///
+    ///**Parámetros**
+    ///- 'nombre': Es el nombre del índice.
+    ///- 'columna': Es la columna de la tabla sobre la que está el índice.
+    ///- 'unico': Indica si el índice es UNIQUE.
+    ///
+    ///**Return**
+    ///Devuelve un *struct* de tipo *Indice*.
+    pub fn new(nombre: String, columna: String, unico: bool) -> Self {
+        Indice {
+            nombre,
+            columna,
+            unico,
+        }
+    }
+
+    ///# Indice::desde_definicion()
+    ///Esta función crea un índice a partir de la línea que lo define en el esquema, por ejemplo
+    ///*UNIQUE INDEX idx_email (email)*.
+    ///
+    ///**Parámetros**
+    ///- 'tokens': Son las palabras de la definición.
+    ///
+    ///**Return**
+    ///Devuelve un *Result<Indice, MyError>*, en caso de que la definición no sea válida se
+    ///  devuelve un error del tipo *MyError*.
+    pub fn desde_definicion(tokens: &[String]) -> Result<Indice, MyError> {
+        let (unico, resto) = match tokens.first() {
+            Some(t) if t.eq_ignore_ascii_case("UNIQUE") => (true, &tokens[1..]),
+            _ => (false, tokens),
+        };
+
+        match resto {
+            [indice, nombre, columna]
+                if indice.eq_ignore_ascii_case("INDEX")
+                    && columna.len() > 2
+                    && columna.starts_with('(')
+                    && columna.ends_with(')') =>
+            {
+                Ok(Indice::new(
+                    String::from(nombre),
+                    columna[1..columna.len() - 1].to_string(),
+                    unico,
+                ))
+            }
+            _ => Err(MyError::InvalidSyntax(
+                "Error en la definición de un índice, se espera [UNIQUE] INDEX nombre (columna)"
+                    .to_string(),
+            )),
+        }
+    }
+
+    ///# Indice.definicion()
+    ///Esta función devuelve la línea con la que se guarda el índice en el esquema de la tabla.
+    pub fn definicion(&self) -> String {
+        let prefijo = match self.unico {
+            true => "UNIQUE INDEX",
+            false => "INDEX",
+        };
+        format!("{} {} ({})", prefijo, self.nombre, self.columna)
+    }
+
+    pub fn nombre(&self) -> &str {
+        &self.nombre
+    }
+
+    pub fn columna(&self) -> &str {
+        &self.columna
+    }
+
+    pub fn es_unico(&self) -> bool {
+        self.unico
+    }
+
+    pub(crate) fn renombrar_columna(&mut self, nuevo: &str) {
+        self.columna = nuevo.to_string();
+    }
+
+    ///# Indice.ruta()
+    ///Esta función devuelve la dirección del archivo con las entradas del índice, que está en el
+    ///mismo directorio que la tabla.
+    ///
+    ///**Parámetros**
+    ///- 'archivo_tabla': Es la dirección del archivo de la tabla.
+    pub fn ruta(&self, archivo_tabla: &str) -> String {
+        Path::new(archivo_tabla)
+            .with_file_name(format!("{}.indice", self.nombre))
+            .to_string_lossy()
+            .to_string()
+    }
+
+    ///# Indice.buscar()
+    ///Esta función busca en el índice las filas que cumplen la comparación *columna simbolo
+    ///valor*, con la misma semántica que *CondicionSimple*. Solo se resuelven los operadores =,
+    ///<, >, <= y >=. Si el índice está desactualizado, el operador no se puede resolver con el
+    ///índice o algún valor de la columna no es del tipo del valor buscado (en cuyo caso recorrer
+    ///la tabla daría un error) se devuelve *None* para que se recorra la tabla completa. Como las
+    ///entradas están ordenadas, los límites del rango se buscan con búsqueda binaria sobre el
+    ///archivo y solo se leen completas las entradas del resultado.
+    ///
+    ///**Parámetros**
+    ///- 'archivo_tabla': Es la dirección del archivo de la tabla.
+    ///- 'simbolo': Es el operador de la comparación.
+    ///- 'valor': Es el valor contra el que se compara.
+    ///
+    ///**Return**
+    ///Devuelve un *Result<Option<Vec<u64>>, MyError>* con las posiciones de las filas, en el
+    ///  orden en el que están en la tabla.
+    pub fn buscar(
+        &self,
+        archivo_tabla: &str,
+        simbolo: &str,
+        valor: &Valor,
+    ) -> Result<Option<Vec<u64>>, MyError> {
+        let mut lector = match self.abrir(archivo_tabla)? {
+            Some(l) => l,
+            None => return Ok(None),
+        };
+        let (inicio, fin) = match (lector.stream_position(), lector.get_ref().metadata()) {
+            (Ok(i), Ok(m)) => (i, m.len()),
+            _ => return Err(self.danado()),
+        };
+
+        let es_int = matches!(valor, Valor::Entero(_));
+        let fin_enteros =
+            self.limite(&mut lector, inicio, fin, |c| matches!(c, Valor::Entero(_)))?;
+        let vacio = Valor::Palabra(String::new());
+        let compatibles = match es_int {
+            true => self.limite(&mut lector, fin_enteros, fin, |c| *c <= vacio)? == fin,
+            false => fin_enteros == inicio,
+        };
+        if !compatibles {
+            return Ok(None);
+        }
+
+        let comparables = match es_int {
+            true => fin_enteros,
+            false => fin,
+        };
+        let menores = self.limite(&mut lector, inicio, comparables, |c| c < valor)?;
+        let hasta_iguales = self.limite(&mut lector, menores, comparables, |c| c <= valor)?;
+
+        let (desde, hasta) = match simbolo {
+            "=" => (menores, hasta_iguales),
+            "<" => (inicio, menores),
+            "<=" => (inicio, hasta_iguales),
+            ">" => (hasta_iguales, comparables),
+            ">=" => (menores, comparables),
+            _ => return Ok(None),
+        };
+
+        let mut posiciones = Vec::new();
+        let mut actual = desde;
+        if lector.seek(SeekFrom::Start(desde)).is_err() {
+            return Err(self.danado());
+        }
+        while actual < hasta {
+            match siguiente_entrada(&mut lector, &self.nombre)? {
+                Some(((_, posicion), largo)) => {
+                    posiciones.push(posicion);
+                    actual += largo;
+                }
+                None => return Err(self.danado()),
+            }
+        }
+        posiciones.sort();
+        Ok(Some(posiciones))
+    }
+
+    ///# Indice.esta_actualizado()
+    ///Esta función indica si el archivo del índice existe y corresponde al contenido actual de la
+    ///tabla, leyendo solo su primera línea.
+    ///
+    ///**Parámetros**
+    ///- 'archivo_tabla': Es la dirección del archivo de la tabla.
+    pub fn esta_actualizado(&self, archivo_tabla: &str) -> bool {
+        let mut linea = String::new();
+        let leido = File::open(self.ruta(archivo_tabla))
+            .and_then(|f| BufReader::new(f).read_line(&mut linea));
+        match (leido, firma(archivo_tabla)) {
+            (Ok(_), Some(f)) => leer_encabezado(&linea) == Some(f),
+            _ => false,
+        }
+    }
+
+    ///Abre el archivo del índice y lee su encabezado, o devuelve *None* si el archivo no existe o
+    ///no corresponde al largo y a la fecha de modificación actuales de la tabla. El lector queda
+    ///al principio de la primera entrada.
+    fn abrir(&self, archivo_tabla: &str) -> Result<Option<Lector>, MyError> {
+        let mut lector = match File::open(self.ruta(archivo_tabla)) {
+            Ok(f) => BufReader::new(f),
+            Err(_e) => return Ok(None),
+        };
+        let firma_tabla = match firma(archivo_tabla) {
+            Some(f) => f,
+            None => {
+                return Err(MyError::InvalidTable(
+                    "Directorio o nombre de la tabla incorrecto".to_string(),
+                ))
+            }
+        };
+
+        let mut linea = String::new();
+        if lector.read_line(&mut linea).is_err() {
+            return Err(self.danado());
+        }
+        match linea.trim_end() {
+            l if leer_encabezado(l) == Some(firma_tabla) => Ok(Some(lector)),
+            l if l.split(' ').all(|n| n.parse::<u64>().is_ok()) => Ok(None),
+            _ => Err(self.danado()),
+        }
+    }
+
+    ///Busca en el archivo del índice, entre dos bytes en los que empiezan entradas, dónde empieza
+    ///la primera entrada cuyo valor no cumple *antes*, sabiendo que lo cumplen todas las
+    ///anteriores. Es una búsqueda binaria por bytes: en cada paso salta a la mitad del rango y
+    ///lee solo la entrada que empieza después de ese byte.
+    fn limite<F>(
+        &self,
+        lector: &mut Lector,
+        desde: u64,
+        hasta: u64,
+        antes: F,
+    ) -> Result<u64, MyError>
+    where
+        F: Fn(&Valor) -> bool,
+    {
+        let (mut desde, mut hasta) = (desde, hasta);
+        let mut descartado = Vec::new();
+
+        while desde < hasta {
+            let mitad = desde + (hasta - desde) / 2;
+            let mut inicio = desde;
+            if mitad > desde {
+                descartado.clear();
+                match lector
+                    .seek(SeekFrom::Start(mitad - 1))
+                    .and_then(|_| lector.read_until(b'\n', &mut descartado))
+                {
+                    Ok(leido) => inicio = mitad - 1 + leido as u64,
+                    Err(_e) => return Err(self.danado()),
+                }
+            }
+            if inicio >= hasta {
+                inicio = desde;
+            }
+
+            if lector.seek(SeekFrom::Start(inicio)).is_err() {
+                return Err(self.danado());
+            }
+            match siguiente_entrada(lector, &self.nombre)? {
+                Some(((valor, _), largo)) if antes(&clave(&valor)) => desde = inicio + largo,
+                Some(_) => hasta = inicio,
+                None => return Err(self.danado()),
+            }
+        }
+
+        Ok(desde)
+    }
+
+    fn danado(&self) -> MyError {
+        MyError::Error(format!("El índice {} está dañado", self.nombre))
+    }
+}
+
+///# ActualizadorIndices
+///Esta estructura arma las entradas de todos los índices de una tabla mientras se escriben sus
+///filas, para que los índices queden actualizados después de un INSERT, UPDATE, DELETE o ALTER
+///TABLE. Las filas se tienen que agregar en el orden en el que quedan en la tabla.
+///
+///**Ejemplo**
+///let mut indices = ActualizadorIndices::new(direccion_tabla, &esquema, &columnas)?;
+///indices.agregar(&fila);
+///let temporales = indices.preparar()?;
+///indice::publicar(direccion_tabla, temporales)?;
+///
+///**Parámetros**
+///- 'archivo': Es la dirección del archivo de la tabla.
+///- 'indices': Son los índices de la tabla, con la posición de su columna, sus entradas nuevas y,
+///  si se agregan filas al final, el archivo con las entradas que ya tenía.
+///- 'posicion': Es el byte en el que empieza la próxima fila que se agregue.
+///- 'formato': Es el formato de la tabla, con el que se calcula el largo de cada fila.
+#[derive(Debug)]
+pub struct ActualizadorIndices {
+    archivo: String,
+    indices: Vec<(Indice, usize, Vec<Entrada>, Option<Lector>)>,
+    posicion: u64,
+    formato: Formato,
+}
+
+impl ActualizadorIndices {
+    ///# ActualizadorIndices::new()
+    ///Esta función crea un actualizador sin entradas, para una tabla que se reescribe completa:
+    ///la primera fila empieza después del encabezado.
+    ///
+    ///**Parámetros**
+    ///- 'archivo_tabla': Es la dirección del archivo de la tabla.
+    ///- 'esquema': Es el esquema de la tabla, con la definición de sus índices.
+    ///- 'columnas_tabla': Son los nombres de las columnas en el orden en el que están en la tabla.
+    ///
+    ///**Return**
+    ///Devuelve un *Result<ActualizadorIndices, MyError>*, en caso de que la columna de algún
+    ///  índice no exista en la tabla se devuelve un error del tipo *MyError*.
+    pub fn new(
+        archivo_tabla: &str,
+        esquema: &Esquema,
+        columnas_tabla: &[String],
+    ) -> Result<Self, MyError> {
+        let mut indices = Vec::new();
+        for indice in esquema.indices() {
+            match columnas_tabla.iter().position(|c| c == indice.columna()) {
+                Some(p) => indices.push((indice.clone(), p, Vec::new(), None)),
+                None => {
+                    return Err(MyError::InvalidColumn(format!(
+                        "La columna {} del índice {} no existe en la tabla",
+                        indice.columna(),
+                        indice.nombre()
+                    )))
+                }
+            }
+        }
+
+        let formato = match indices.is_empty() {
+            true => Formato::default(),
+            false => Formato::de_tabla(archivo_tabla)?,
+        };
+
+        Ok(ActualizadorIndices {
+            archivo: archivo_tabla.to_string(),
+            indices,
+            posicion: formato.unir_encabezado(columnas_tabla).len() as u64 + 1,
+            formato,
+        })
+    }
+
+    ///# ActualizadorIndices::anexando()
+    ///Esta función crea un actualizador para agregarle filas al final de la tabla. Las entradas
+    ///que ya tiene cada índice no se cargan: se intercalan con las nuevas al preparar el índice,
+    ///leyendo el archivo en orden. Si algún índice está desactualizado se reconstruyen todos a
+    ///partir de la tabla.
+    ///
+    ///**Parámetros**
+    ///- 'archivo_tabla': Es la dirección del archivo de la tabla.
+    ///- 'esquema': Es el esquema de la tabla, con la definición de sus índices.
+    ///- 'columnas_tabla': Son los nombres de las columnas en el orden en el que están en la tabla.
+    ///- 'largo': Es el largo actual de la tabla, donde empieza la primera fila nueva.
+    ///
+    ///**Return**
+    ///Devuelve un *Result<ActualizadorIndices, MyError>*, en caso de que no se puedan leer los
+    ///  índices o la tabla se devuelve un error del tipo *MyError*.
+    pub fn anexando(
+        archivo_tabla: &str,
+        esquema: &Esquema,
+        columnas_tabla: &[String],
+        largo: u64,
+    ) -> Result<Self, MyError> {
+        let mut actualizador = ActualizadorIndices::new(archivo_tabla, esquema, columnas_tabla)?;
+
+        for (indice, _, _, anteriores) in &mut actualizador.indices {
+            match indice.abrir(archivo_tabla)? {
+                Some(lector) => *anteriores = Some(lector),
+                None => return ActualizadorIndices::desde_tabla(archivo_tabla, esquema),
+            }
+        }
+
+        actualizador.posicion = largo;
+        Ok(actualizador)
+    }
+
+    ///# ActualizadorIndices::desde_tabla()
+    ///Esta función crea un actualizador con las entradas de todas las filas que tiene la tabla.
+    ///
+    ///**Parámetros**
+    ///- 'archivo_tabla': Es la dirección del archivo de la tabla.
+    ///- 'esquema': Es el esquema de la tabla, con la definición de sus índices.
+    ///
+    ///**Return**
+    ///Devuelve un *Result<ActualizadorIndices, MyError>*, en caso de que no se pueda leer la
+    ///  tabla se devuelve un error del tipo *MyError*.
+    pub fn desde_tabla(archivo_tabla: &str, esquema: &Esquema) -> Result<Self, MyError> {
+        let almacenamiento = almacenamiento::abrir(archivo_tabla);
+        let mut recorrido = almacenamiento.recorrer(None)?;
+        let columnas = recorrido.columnas().to_vec();
+        let mut actualizador = ActualizadorIndices::new(archivo_tabla, esquema, &columnas)?;
+
+        while let Some(fila) = recorrido.siguiente()? {
+            actualizador.posicion = recorrido.posicion().unwrap_or_default();
+            actualizador.agregar(&fila);
+        }
+
+        actualizador.posicion = almacenamiento.largo()?;
+        Ok(actualizador)
+    }
+
+    ///# ActualizadorIndices.agregar()
+    ///Esta función registra una fila que se escribe en la tabla a continuación de las anteriores.
+    ///
+    ///**Parámetros**
+    ///- 'fila': Son los valores de la fila, en el orden de las columnas de la tabla.
+    pub fn agregar(&mut self, fila: &[String]) {
+        for (_, posicion_columna, entradas, _) in &mut self.indices {
+            let valor = fila.get(*posicion_columna).cloned().unwrap_or_default();
+            entradas.push((valor, self.posicion));
+        }
+        self.posicion += self.formato.unir(fila).len() as u64 + 1;
+    }
+
+    ///# ActualizadorIndices.preparar()
+    ///Esta función ordena las entradas nuevas de cada índice, las intercala con las que ya tenía
+    ///si se agregan filas al final, corrobora los índices UNIQUE y escribe cada índice en un
+    ///archivo temporal que todavía no reemplaza al actual, para publicarlo recién después de
+    ///escribir la tabla.
+    ///
+    ///**Return**
+    ///Devuelve un *Result<Vec<ArchivoTemporal>, MyError>*, en caso de que se repita un valor en
+    ///  un índice UNIQUE o no se pueda escribir algún índice se devuelve un error del tipo
+    ///  *MyError*.
+    pub fn preparar(self) -> Result<Vec<ArchivoTemporal>, MyError> {
+        let mut temporales = Vec::new();
+
+        for (indice, _, mut entradas, lector) in self.indices {
+            entradas.sort_by(comparar);
+            let mut nuevas = entradas.into_iter().peekable();
+            let mut anteriores = lector
+                .into_iter()
+                .flat_map(|mut l| {
+                    let nombre = indice.nombre.clone();
+                    std::iter::from_fn(move || siguiente_entrada(&mut l, &nombre).transpose())
+                })
+                .peekable();
+
+            let mut temporal = ArchivoTemporal::crear(&indice.ruta(&self.archivo))?;
+            temporal.escribir(&encabezado(self.posicion, 0))?;
+            let mut previo: Option<String> = None;
+            loop {
+                let usar_anterior = match (anteriores.peek(), nuevas.peek()) {
+                    (Some(Ok((a, _))), Some(n)) => comparar(a, n) != Ordering::Greater,
+                    (Some(_), _) => true,
+                    (None, Some(_)) => false,
+                    (None, None) => break,
+                };
+                let (valor, posicion) = match usar_anterior {
+                    true => match anteriores.next() {
+                        Some(Ok((e, _))) => e,
+                        Some(Err(e)) => return Err(e),
+                        None => break,
+                    },
+                    false => match nuevas.next() {
+                        Some(e) => e,
+                        None => break,
+                    },
+                };
+
+                if indice.unico && !valor.is_empty() && previo.as_ref() == Some(&valor) {
+                    return Err(MyError::ConstraintViolation(format!(
+                        "El valor {} se repite en la columna {} y el índice UNIQUE {} no lo permite",
+                        valor, indice.columna, indice.nombre
+                    )));
+                }
+                temporal.escribir(&format!("{},{}\n", valor, posicion))?;
+                previo = Some(valor);
+            }
+            temporales.push(temporal);
+        }
+
+        Ok(temporales)
+    }
+}
+
+///# indice::publicar()
+///Esta función reemplaza los índices de una tabla por los archivos temporales que armó
+///*ActualizadorIndices.preparar()*. Se tiene que llamar después de escribir la tabla, porque
+///completa el encabezado de cada índice con la fecha de modificación que quedó en la tabla.
+///
+///**Parámetros**
+///- 'archivo_tabla': Es la dirección del archivo de la tabla.
+///- 'temporales': Son los archivos temporales de los índices.
+pub fn publicar(archivo_tabla: &str, temporales: Vec<ArchivoTemporal>) -> Result<(), MyError> {
+    if temporales.is_empty() {
+        return Ok(());
+    }
+    let modificada = match firma(archivo_tabla) {
+        Some((_, m)) => m,
+        None => {
+            return Err(MyError::InvalidTable(
+                "Directorio o nombre de la tabla incorrecto".to_string(),
+            ))
+        }
+    };
+
+    for mut temporal in temporales {
+        let escrito = temporal
+            .seek(SeekFrom::Start(ANCHO_NUMERO as u64 + 1))
+            .and_then(|_| {
+                temporal
+                    .write_all(format!("{:0ancho$}", modificada, ancho = ANCHO_NUMERO).as_bytes())
+            });
+        if escrito.is_err() {
+            return Err(MyError::Error(
+                "Fallo en la escritura del archivo temporal".to_string(),
+            ));
+        }
+        temporal.publicar()?;
+    }
+    Ok(())
+}
+
+///# indice::reconstruir()
+///Esta función vuelve a armar todos los índices de una tabla a partir de su contenido, por
+///ejemplo después de un ALTER TABLE que cambia el largo de las filas.
+///
+///**Parámetros**
+///- 'archivo_tabla': Es la dirección del archivo de la tabla.
+///- 'esquema': Es el esquema de la tabla, con la definición de sus índices.
+pub fn reconstruir(archivo_tabla: &str, esquema: &Esquema) -> Result<(), MyError> {
+    if esquema.indices().is_empty() {
+        return Ok(());
+    }
+    publicar(
+        archivo_tabla,
+        ActualizadorIndices::desde_tabla(archivo_tabla, esquema)?.preparar()?,
+    )
+}
+
+///# indice::leer_fila()
+///Esta función lee la fila que empieza en una posición de la tabla, sin el salto de línea.
+///
+///**Parámetros**
+///- 'buffer': Es el lector de la tabla.
+///- 'posicion': Es el byte en el que empieza la fila.
+pub fn leer_fila(buffer: &mut (impl BufRead + Seek), posicion: u64) -> Result<String, MyError> {
+    let mut linea = String::new();
+    match buffer
+        .seek(SeekFrom::Start(posicion))
+        .and_then(|_| buffer.read_line(&mut linea))
+    {
+        Ok(_) => Ok(linea.trim_end_matches('\n').to_string()),
+        Err(_e) => Err(MyError::Error(
+            "Fallo en la lectura de la tabla".to_string(),
+        )),
+    }
+}
+
+///Cantidad de dígitos de cada número del encabezado, que tiene ancho fijo para poder completar
+///la fecha de modificación sin reescribir el índice.
+const ANCHO_NUMERO: usize = 20;
+
+fn encabezado(largo: u64, modificada: u64) -> String {
+    format!(
+        "{:0ancho$} {:0ancho$}\n",
+        largo,
+        modificada,
+        ancho = ANCHO_NUMERO
+    )
+}
+
+fn leer_encabezado(linea: &str) -> Option<(u64, u64)> {
+    let (largo, modificada) = linea.trim_end().split_once(' ')?;
+    Some((largo.parse().ok()?, modificada.parse().ok()?))
+}
+
+///Devuelve el largo y la fecha de modificación (en nanosegundos) de la tabla.
+fn firma(archivo_tabla: &str) -> Option<(u64, u64)> {
+    let metadatos = fs::metadata(archivo_tabla).ok()?;
+    let modificada = metadatos.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+    Some((
+        metadatos.len(),
+        u64::try_from(modificada.as_nanos()).unwrap_or(u64::MAX),
+    ))
+}
+
+///Lee la próxima entrada del archivo de un índice junto con su largo en bytes, o devuelve *None*
+///si el archivo terminó.
+fn siguiente_entrada(
+    lector: &mut impl BufRead,
+    nombre: &str,
+) -> Result<Option<(Entrada, u64)>, MyError> {
+    let error = || MyError::Error(format!("El índice {} está dañado", nombre));
+    let mut linea = String::new();
+    let largo = match lector.read_line(&mut linea) {
+        Ok(0) => return Ok(None),
+        Ok(l) => l as u64,
+        Err(_e) => return Err(error()),
+    };
+
+    match linea
+        .trim_end_matches('\n')
+        .rsplit_once(',')
+        .and_then(|(v, p)| p.parse::<u64>().ok().map(|p| (v.to_string(), p)))
+    {
+        Some(entrada) => Ok(Some((entrada, largo))),
+        None => Err(error()),
+    }
+}
+
+///Ordena las entradas de un índice por su valor, interpretado con *clave*, y después por la
+///posición de la fila.
+fn comparar((a, pa): &Entrada, (b, pb): &Entrada) -> Ordering {
+    clave(a)
+        .partial_cmp(&clave(b))
+        .unwrap_or(Ordering::Equal)
+        .then(pa.cmp(pb))
+}
+
+///Interpreta un valor de la columna igual que *CondicionSimple*: como número si se puede y si no
+///como texto.
+fn clave(valor: &str) -> Valor {
+    match valor.parse::<i32>() {
+        Ok(v) => Valor::Entero(v),
+        Err(_e) => Valor::Palabra(valor.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::pruebas::{preparar_tabla, CLIENTES, ESQUEMA_CLIENTES};
+
+    #[test]
+    pub fn test01_se_lee_y_se_escribe_la_definicion_de_un_indice() {
+        let tokens: Vec<String> = ["UNIQUE", "INDEX", "idx_email", "(email)"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+
+        let indice = Indice::desde_definicion(&tokens).unwrap();
+
+        assert_eq!(
+            indice,
+            Indice::new("idx_email".to_string(), "email".to_string(), true)
+        );
+        assert_eq!(indice.definicion(), "UNIQUE INDEX idx_email (email)");
+        assert!(Indice::desde_definicion(&tokens[1..3]).is_err());
+    }
+
+    #[test]
+    pub fn test02_se_buscan_filas_por_igualdad_y_por_rango() {
+        let archivo = preparar_tabla("indice_buscar", "clientes", CLIENTES, ESQUEMA_CLIENTES);
+        let mut esquema = Esquema::cargar(&archivo).unwrap();
+        let indice = Indice::new("idx_edad".to_string(), "edad".to_string(), false);
+        esquema.agregar_indice(indice.clone());
+        reconstruir(&archivo, &esquema).unwrap();
+
+        let mut buffer = BufReader::new(File::open(&archivo).unwrap());
+        let mut filas = |simbolo: &str, valor: i32| -> Vec<String> {
+            indice
+                .buscar(&archivo, simbolo, &Valor::Entero(valor))
+                .unwrap()
+                .unwrap()
+                .iter()
+                .map(|p| leer_fila(&mut buffer, *p).unwrap())
+                .collect()
+        };
+
+        assert_eq!(filas("=", 30), vec!["2,Ana,ana@mail.com,30"]);
+        assert_eq!(
+            filas(">=", 30),
+            vec!["2,Ana,ana@mail.com,30", "3,Luis,luis@mail.com,41"]
+        );
+        assert_eq!(filas("<", 25), Vec::<String>::new());
+        assert!(indice
+            .buscar(&archivo, "=", &Valor::Palabra("Ana".to_string()))
+            .unwrap()
+            .is_none());
+
+        fs::write(&archivo, "id,nombre,email,edad\n").unwrap();
+        assert!(indice
+            .buscar(&archivo, "=", &Valor::Entero(30))
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    pub fn test03_un_indice_unique_no_admite_valores_repetidos() {
+        let archivo = preparar_tabla("indice_unico", "clientes", CLIENTES, ESQUEMA_CLIENTES);
+        let mut esquema = Esquema::cargar(&archivo).unwrap();
+        let indice = Indice::new("idx_unico_edad".to_string(), "edad".to_string(), true);
+        esquema.agregar_indice(indice.clone());
+
+        let mut actualizador = ActualizadorIndices::desde_tabla(&archivo, &esquema).unwrap();
+        actualizador.agregar(&[
+            "4".to_string(),
+            "Eva".to_string(),
+            "eva@mail.com".to_string(),
+            "30".to_string(),
+        ]);
+
+        assert!(matches!(
+            actualizador.preparar(),
+            Err(MyError::ConstraintViolation(m)) if m.contains("idx_unico_edad")
+        ));
+        assert!(!Path::new(&indice.ruta(&archivo)).exists());
+    }
+
+    #[test]
+    pub fn test04_un_indice_no_se_usa_si_la_tabla_cambia_sin_cambiar_de_largo() {
+        let archivo = preparar_tabla("indice_mismo_largo", "clientes", CLIENTES, ESQUEMA_CLIENTES);
+        let mut esquema = Esquema::cargar(&archivo).unwrap();
+        let indice = Indice::new("idx_edad".to_string(), "edad".to_string(), false);
+        esquema.agregar_indice(indice.clone());
+        reconstruir(&archivo, &esquema).unwrap();
+        let actualizado = indice.esta_actualizado(&archivo);
+
+        let contenido = fs::read_to_string(&archivo)
+            .unwrap()
+            .replace(",25\n", ",26\n");
+        fs::write(&archivo, contenido).unwrap();
+        File::options()
+            .write(true)
+            .open(&archivo)
+            .unwrap()
+            .set_modified(UNIX_EPOCH + std::time::Duration::from_secs(60))
+            .unwrap();
+
+        assert!(actualizado);
+        assert!(!indice.esta_actualizado(&archivo));
+        assert!(indice
+            .buscar(&archivo, "=", &Valor::Entero(25))
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    pub fn test05_la_busqueda_binaria_en_el_archivo_encuentra_las_mismas_filas_que_recorrer() {
+        let mut csv = String::from("id,nombre,email,edad\n");
+        for id in 0..200 {
+            let edad = match id % 17 {
+                0 => String::new(),
+                _ => ((id * 37) % 50).to_string(),
+            };
+            csv.push_str(&format!("{},Cliente,cliente{}@mail.com,{}\n", id, id, edad));
+        }
+        let archivo = preparar_tabla("indice_binaria", "clientes", &csv, ESQUEMA_CLIENTES);
+        let mut esquema = Esquema::cargar(&archivo).unwrap();
+        let indice = Indice::new("idx_edad".to_string(), "edad".to_string(), false);
+        esquema.agregar_indice(indice.clone());
+        reconstruir(&archivo, &esquema).unwrap();
+
+        let mut buffer = BufReader::new(File::open(&archivo).unwrap());
+        for simbolo in ["=", "<", "<=", ">", ">="] {
+            for valor in [-1, 0, 13, 24, 49, 50] {
+                let encontrados: Vec<String> = indice
+                    .buscar(&archivo, simbolo, &Valor::Entero(valor))
+                    .unwrap()
+                    .unwrap()
+                    .iter()
+                    .map(|p| leer_fila(&mut buffer, *p).unwrap())
+                    .collect();
+                let esperados: Vec<&str> = csv
+                    .lines()
+                    .skip(1)
+                    .filter(
+                        |fila| match fila.rsplit(',').next().unwrap().parse::<i32>() {
+                            Ok(edad) => match simbolo {
+                                "=" => edad == valor,
+                                "<" => edad < valor,
+                                "<=" => edad <= valor,
+                                ">" => edad > valor,
+                                _ => edad >= valor,
+                            },
+                            Err(_e) => false,
+                        },
+                    )
+                    .collect();
+
+                assert_eq!(encontrados, esperados, "edad {} {}", simbolo, valor);
+            }
+        }
+    }
+
+    #[test]
+    pub fn test06_las_filas_agregadas_se_intercalan_en_el_indice_existente() {
+        let archivo = preparar_tabla("indice_anexando", "clientes", CLIENTES, ESQUEMA_CLIENTES);
+        let mut esquema = Esquema::cargar(&archivo).unwrap();
+        esquema.agregar_indice(Indice::new(
+            "idx_edad".to_string(),
+            "edad".to_string(),
+            false,
+        ));
+        esquema.agregar_indice(Indice::new(
+            "idx_unico_email".to_string(),
+            "email".to_string(),
+            true,
+        ));
+        reconstruir(&archivo, &esquema).unwrap();
+        let columnas: Vec<String> = ["id", "nombre", "email", "edad"]
+            .iter()
+            .map(|c| c.to_string())
+            .collect();
+        let fila = |datos: &str| -> Vec<String> { datos.split(',').map(String::from).collect() };
+        let largo = fs::metadata(&archivo).unwrap().len();
+
+        let mut repetido =
+            ActualizadorIndices::anexando(&archivo, &esquema, &columnas, largo).unwrap();
+        repetido.agregar(&fila("4,Eva,ana@mail.com,28"));
+        let error = repetido.preparar();
+
+        let mut indices =
+            ActualizadorIndices::anexando(&archivo, &esquema, &columnas, largo).unwrap();
+        let nuevas = "4,Eva,eva@mail.com,28\n5,Juan,juan@mail.com,41\n6,Sol,sol@mail.com,7\n";
+        for datos in nuevas.lines() {
+            indices.agregar(&fila(datos));
+        }
+        let temporales = indices.preparar().unwrap();
+        let mut tabla = File::options().append(true).open(&archivo).unwrap();
+        tabla.write_all(nuevas.as_bytes()).unwrap();
+        publicar(&archivo, temporales).unwrap();
+        let intercalados: Vec<String> = esquema
+            .indices()
+            .iter()
+            .map(|i| fs::read_to_string(i.ruta(&archivo)).unwrap())
+            .collect();
+        reconstruir(&archivo, &esquema).unwrap();
+        let reconstruidos: Vec<String> = esquema
+            .indices()
+            .iter()
+            .map(|i| fs::read_to_string(i.ruta(&archivo)).unwrap())
+            .collect();
+
+        assert!(matches!(
+            error,
+            Err(MyError::ConstraintViolation(m)) if m.contains("idx_unico_email")
+        ));
+        assert_eq!(intercalados, reconstruidos);
+        assert!(intercalados[0].ends_with("7,139\n25,21\n28,93\n30,47\n41,69\n41,115\n"));
+    }
+}
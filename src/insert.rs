@@ -1,6 +1,10 @@
+use crate::almacenamiento;
+use crate::clave_foranea::VerificadorReferencias;
+use crate::indice::{self, ActualizadorIndices};
 use crate::my_error::MyError;
-use std::fs::{File, OpenOptions};
-use std::io::{self, BufRead, Write};
+use crate::restricciones::VerificadorRestricciones;
+use crate::secuencia::Secuencia;
+use crate::select::Select;
 
 ///# Insert
 ///Esta estructura proporciona toda la funcionalidad para implementar la operación INSERT en sql.
@@ -9,17 +13,23 @@ use std::io::{self, BufRead, Write};
 ///let insert = Insert::new(ruta_archivo, columnas_tabla, valores);
 ///insert.insertar();
 ///
+///let insert = Insert::desde_select(ruta_archivo, columnas_tabla, select);
+///insert.insertar();
+///
 ///**Parámetros**
 ///- 'archivo': Contiene la dirección del archivo al que representa a la tabla que se quiere
 ///  modificar.
 ///- 'columnas': Contiene el nombre de todas las columnas que tiene la tabla (tienen que estar en
 ///  el orden en que estan en la tabla).
 ///- 'valores': Contiene todas la filas que se quieren agregar a la tabla.
+///- 'consulta': Es el SELECT cuyas filas se agregan a la tabla en INSERT ... SELECT, en lugar de
+///  *'valores'*.
 #[derive(Debug, PartialEq)]
 pub struct Insert {
     archivo: String,
     columnas: Vec<String>,
     valores: Vec<Vec<String>>,
+    consulta: Option<Select>,
 }
 
 impl Insert {
@@ -39,41 +49,64 @@ impl Insert {
             archivo,
             columnas,
             valores,
+            consulta: None,
+        }
+    }
+
+    ///# Insert::desde_select()
+    ///Esta función crea una nueva instancia de Insert que agrega a la tabla las filas que devuelve
+    ///un SELECT (INSERT ... SELECT).
+    ///
+    ///**Parámetros**
+    ///- 'archivo': Es la dirección en donde se encuentra el archivo que se quiere modificar.
+    ///- 'columnas': Son los nombres de las columnas de la tabla que reciben, en orden, las
+    ///  columnas del SELECT.
+    ///- 'consulta': Es el SELECT cuyas filas se quieren agregar.
+    ///
+    ///**Return**
+    ///Devuelve un *struct* del tipo *Insert*.
+    pub fn desde_select(archivo: String, columnas: Vec<String>, consulta: Select) -> Self {
+        Insert {
+            archivo,
+            columnas,
+            valores: Vec::new(),
+            consulta: Some(consulta),
         }
     }
     ///# Insert.insertar()
-    ///Esta función realiza la operación de INSERT.
+    ///Esta función realiza la operación de INSERT. Antes de escribir se corrobora que todas las
+    ///filas cumplan las restricciones del esquema de la tabla; si alguna no las cumple no se
+    ///agrega ninguna. Las columnas que no se indican toman su valor por defecto, y la columna
+    ///AUTO_INCREMENT, si no se indica o está vacía, el siguiente valor de la secuencia de la tabla.
+    ///Las filas nuevas se escriben juntas y se sincronizan con el disco; si la escritura falla la
+    ///tabla se recorta a su largo original. Después se agregan las filas nuevas a los índices de la
+    ///tabla. En INSERT ... SELECT las filas a agregar se obtienen ejecutando el SELECT antes de
+    ///bloquear la tabla, por lo que puede leer de la misma tabla en la que se inserta.
     ///
     ///**Return**
     ///Devuelve un *Result<String, MyError>* en caso de que durante la ejecución no haya ocurrido
     ///  ningún error se devuelve el *String* de lo contrario se devuelve un error del tipo *MyError*.
     pub fn insertar(&self) -> Result<String, MyError> {
-        let archivo = match File::open(&self.archivo) {
-            Ok(f) => f,
-            Err(_e) => {
-                return Err(MyError::InvalidTable(
-                    "Directorio o nombre de la tabla incorrecto".to_string(),
-                ))
-            }
-        };
-
-        let mut buffer = io::BufReader::new(archivo);
-
-        let mut linea = String::new();
-        match buffer.read_line(&mut linea) {
-            Ok(l) => l,
-            Err(_e) => {
-                return Err(MyError::Error(String::from(
-                    "Fallo en la lectura de la tabla",
-                )))
+        let filas_consulta;
+        let valores = match &self.consulta {
+            Some(consulta) => {
+                let (columnas_consulta, filas) = consulta.filas()?;
+                if columnas_consulta.len() != self.columnas.len() {
+                    return Err(MyError::InvalidColumn(
+                        "La cantidad de columnas del SELECT no coincide con la de las columnas del INSERT"
+                            .to_string(),
+                    ));
+                }
+                filas_consulta = filas;
+                &filas_consulta
             }
+            None => &self.valores,
         };
 
-        let columnas_tablas: Vec<String> = linea
-            .replace("\n", "")
-            .split(',')
-            .map(|s| s.to_string())
-            .collect();
+        let almacenamiento = almacenamiento::abrir(&self.archivo);
+        let _bloqueo = almacenamiento.bloquear(true)?;
+        let mut existentes = almacenamiento.recorrer(None)?;
+        let columnas_tablas = existentes.columnas().to_vec();
 
         if !self
             .columnas
@@ -86,18 +119,36 @@ impl Insert {
             ));
         };
 
-        let mut archivo_escritura = match OpenOptions::new().append(true).open(&self.archivo) {
-            Ok(a) => a,
-            Err(_e) => {
-                return Err(MyError::Error(String::from(
-                    "Fallo en la edición de la tabla",
-                )))
-            }
+        let esquema = almacenamiento.esquema()?;
+        let valores_por_defecto: Vec<String> = columnas_tablas
+            .iter()
+            .map(|col| match esquema.columna(col) {
+                Some(c) => c.resolver_valor_por_defecto(),
+                None => String::new(),
+            })
+            .collect();
+        let auto_incremental = esquema.auto_incremental().and_then(|p| {
+            columnas_tablas
+                .iter()
+                .position(|c| c == esquema.columnas()[p].nombre())
+        });
+        let mut secuencia = match auto_incremental {
+            Some(pos) => Some(Secuencia::abrir(&self.archivo, pos)?),
+            None => None,
         };
+        let mut verificador = VerificadorRestricciones::new(&esquema, &columnas_tablas)?;
+        let referencias = VerificadorReferencias::new(&self.archivo, &esquema, &columnas_tablas)?;
+
+        if verificador.necesita_filas_existentes() {
+            while let Some(fila) = existentes.siguiente()? {
+                verificador.registrar(&fila)?;
+            }
+        }
 
-        for dato in &self.valores {
+        let mut filas_nuevas: Vec<Vec<String>> = Vec::new();
+        for dato in valores {
             let mut v: Vec<String> = Vec::new();
-            for col in &columnas_tablas {
+            for (i, col) in columnas_tablas.iter().enumerate() {
                 if self.columnas.contains(col) {
                     let pos: usize = self
                         .columnas
@@ -106,13 +157,39 @@ impl Insert {
                         .expect("Error inesperado");
                     v.push(String::from(&dato[pos]));
                 } else {
-                    v.push("".to_string());
+                    v.push(String::from(&valores_por_defecto[i]));
+                }
+            }
+
+            if let (Some(pos), Some(s)) = (auto_incremental, secuencia.as_mut()) {
+                match v[pos].is_empty() {
+                    true => v[pos] = s.siguiente(),
+                    false => s.observar(&v[pos]),
                 }
             }
 
-            let mut linea_nueva: String = v.join(",");
-            linea_nueva += "\n";
-            let _ = archivo_escritura.write_all(linea_nueva.as_bytes());
+            verificador.verificar(&v)?;
+            referencias.verificar(&v)?;
+            filas_nuevas.push(v);
+        }
+
+        let largo_original = almacenamiento.largo()?;
+        let mut indices = ActualizadorIndices::anexando(
+            &self.archivo,
+            &esquema,
+            &columnas_tablas,
+            largo_original,
+        )?;
+        for fila in &filas_nuevas {
+            indices.agregar(fila);
+        }
+        let indices_nuevos = indices.preparar()?;
+
+        almacenamiento.anexar(&filas_nuevas)?;
+
+        indice::publicar(&self.archivo, indices_nuevos)?;
+        if let Some(s) = secuencia {
+            s.guardar()?;
         }
         Ok(String::from("Insert exitoso"))
     }
@@ -121,6 +198,14 @@ impl Insert {
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::condicion::Condicion;
+    use crate::condicion_simple::CondicionSimple;
+    use crate::create_index::CreateIndex;
+    use crate::indice::Indice;
+    use crate::memoria::{self, BaseEnMemoria};
+    use crate::parser::Parser;
+    use crate::pruebas::{self, CLIENTES, ESQUEMA_CLIENTES};
+    use crate::valor::Valor;
     use std::fs;
 
     #[test]
@@ -135,6 +220,7 @@ mod test {
             archivo: String::from("~/test/insert.csv"),
             columnas: Vec::new(),
             valores: Vec::<Vec<String>>::new(),
+            consulta: None,
         };
 
         assert_eq!(operacion, operacion_esperada);
@@ -215,4 +301,239 @@ mod test {
 
         assert!(resultado.is_ok());
     }
+
+    const RESTRICCIONES: &str = "id,email,nombre\n1,ana@mail.com,Ana\n2,juan@mail.com,Juan\n";
+    const ESQUEMA_RESTRICCIONES: &str =
+        "id INT PRIMARY KEY\nemail TEXT UNIQUE\nnombre TEXT NOT NULL\n";
+
+    fn base_con_restricciones(nombre: &str) -> BaseEnMemoria {
+        let base = BaseEnMemoria::new(nombre);
+        base.cargar("clientes", RESTRICCIONES);
+        base.cargar_esquema("clientes", ESQUEMA_RESTRICCIONES)
+            .unwrap();
+        base
+    }
+
+    #[test]
+    pub fn test06_se_hace_un_insert_y_se_repite_la_clave_primaria() {
+        let base = base_con_restricciones("insert_clave_primaria");
+
+        let columnas = vec!["id".to_string(), "email".to_string(), "nombre".to_string()];
+        let valores = vec![vec![
+            "1".to_string(),
+            "otro@mail.com".to_string(),
+            "Otro".to_string(),
+        ]];
+        let operacion = Insert::new(base.archivo("clientes"), columnas, valores);
+
+        let resultado = operacion.insertar();
+
+        assert!(matches!(
+            resultado,
+            Err(MyError::ConstraintViolation(m)) if m.contains("PRIMARY KEY")
+        ));
+    }
+
+    #[test]
+    pub fn test07_no_se_inserta_ninguna_fila_si_se_repiten_valores_en_el_mismo_insert() {
+        let base = base_con_restricciones("insert_repetidos");
+
+        let columnas = vec!["id".to_string(), "email".to_string(), "nombre".to_string()];
+        let valores = vec![
+            vec![
+                "3".to_string(),
+                "maria@mail.com".to_string(),
+                "Maria".to_string(),
+            ],
+            vec![
+                "4".to_string(),
+                "maria@mail.com".to_string(),
+                "Maria".to_string(),
+            ],
+        ];
+        let operacion = Insert::new(base.archivo("clientes"), columnas, valores);
+
+        let resultado = operacion.insertar();
+
+        assert!(matches!(
+            resultado,
+            Err(MyError::ConstraintViolation(m)) if m.contains("UNIQUE")
+        ));
+        assert_eq!(base.volcar("clientes").unwrap(), RESTRICCIONES);
+    }
+
+    #[test]
+    pub fn test08_se_hace_un_insert_sin_una_columna_not_null() {
+        let base = base_con_restricciones("insert_nulo");
+
+        let columnas = vec!["id".to_string(), "email".to_string()];
+        let valores = vec![vec!["3".to_string(), "maria@mail.com".to_string()]];
+        let operacion = Insert::new(base.archivo("clientes"), columnas, valores);
+
+        let resultado = operacion.insertar();
+
+        assert!(matches!(
+            resultado,
+            Err(MyError::ConstraintViolation(m)) if m.contains("NOT NULL")
+        ));
+    }
+
+    #[test]
+    pub fn test09_se_hace_un_insert_que_no_cumple_un_check() {
+        let base = BaseEnMemoria::new("insert_check");
+        base.cargar(
+            "productos",
+            "id,producto,cantidad,maximo\n1,Laptop,2,10\n2,Monitor,1,5\n",
+        );
+        base.cargar_esquema(
+            "productos",
+            "id INT PRIMARY KEY\nproducto TEXT\ncantidad INT CHECK (cantidad > 0)\nmaximo INT\nCHECK (maximo <= 100)\n",
+        )
+        .unwrap();
+
+        let columnas = vec![
+            "id".to_string(),
+            "cantidad".to_string(),
+            "maximo".to_string(),
+        ];
+        let valores = vec![
+            vec!["3".to_string(), "2".to_string(), "4".to_string()],
+            vec!["4".to_string(), "8".to_string(), "400".to_string()],
+        ];
+        let operacion = Insert::new(base.archivo("productos"), columnas, valores);
+
+        let resultado = operacion.insertar();
+
+        assert!(matches!(
+            resultado,
+            Err(MyError::ConstraintViolation(m)) if m.contains("CHECK (maximo <= 100)")
+        ));
+        assert_eq!(base.volcar("productos").unwrap().lines().count(), 3);
+    }
+
+    #[test]
+    pub fn test10_se_completan_los_valores_por_defecto_y_el_id_auto_incremental() {
+        let base = BaseEnMemoria::new("insert_defecto");
+        base.cargar(
+            "pedidos",
+            "id,producto,cantidad,fecha\n1,Laptop,2,2024-01-01\n",
+        );
+        base.cargar_esquema(
+            "pedidos",
+            "id INT PRIMARY KEY AUTO_INCREMENT\nproducto TEXT NOT NULL\ncantidad INT DEFAULT 1\nfecha TEXT DEFAULT CURRENT_DATE\n",
+        )
+        .unwrap();
+        let tabla = base.archivo("pedidos");
+
+        let primero = Insert::new(
+            String::from(&tabla),
+            vec!["producto".to_string()],
+            vec![vec!["Monitor".to_string()]],
+        );
+        let explicito = Insert::new(
+            String::from(&tabla),
+            vec![
+                "id".to_string(),
+                "producto".to_string(),
+                "cantidad".to_string(),
+            ],
+            vec![
+                vec!["10".to_string(), "Mouse".to_string(), "3".to_string()],
+                vec!["".to_string(), "Teclado".to_string(), "4".to_string()],
+            ],
+        );
+
+        primero.insertar().unwrap();
+        explicito.insertar().unwrap();
+        let contenido = base.volcar("pedidos").unwrap();
+        let filas: Vec<Vec<&str>> = contenido
+            .lines()
+            .skip(1)
+            .map(|l| l.split(',').collect())
+            .collect();
+
+        assert_eq!(filas[1][..3], ["2", "Monitor", "1"]);
+        assert_eq!(filas[1][3].len(), "2024-01-01".len());
+        assert_eq!(filas[2][0], "10");
+        assert_eq!(filas[3][..2], ["11", "Teclado"]);
+        assert_eq!(memoria::leer_secuencia(&tabla).unwrap(), Some(12));
+    }
+
+    #[test]
+    pub fn test11_las_filas_insertadas_se_agregan_al_indice_de_la_tabla() {
+        let archivo =
+            pruebas::preparar_tabla("insert_indice", "clientes", CLIENTES, ESQUEMA_CLIENTES);
+        let indice = Indice::new("idx_edad".to_string(), "edad".to_string(), false);
+        CreateIndex::new(archivo.to_string(), indice.clone())
+            .crear()
+            .unwrap();
+        let largo = fs::metadata(&archivo).unwrap().len();
+
+        let columnas = vec!["id".to_string(), "nombre".to_string(), "edad".to_string()];
+        let valores = vec![vec!["4".to_string(), "Eva".to_string(), "30".to_string()]];
+        let resultado = Insert::new(archivo.to_string(), columnas, valores).insertar();
+
+        assert!(resultado.is_ok());
+        assert_eq!(
+            indice.buscar(&archivo, "=", &Valor::Entero(30)).unwrap(),
+            Some(vec![21 + 26, largo])
+        );
+    }
+
+    #[test]
+    pub fn test12_se_insertan_las_filas_de_un_select() {
+        let base = BaseEnMemoria::new("insert_select");
+        base.cargar("clientes", CLIENTES);
+        base.cargar_esquema("clientes", ESQUEMA_CLIENTES).unwrap();
+        base.cargar("origen", CLIENTES);
+        let archivo = base.archivo("clientes");
+        let select = |columnas: &[&str]| {
+            Select::new(
+                base.archivo("origen"),
+                columnas.iter().map(|c| c.to_string()).collect(),
+                Condicion::CondicionSimple(CondicionSimple::new(
+                    "edad".to_string(),
+                    ">".to_string(),
+                    "26".to_string(),
+                )),
+                "".to_string(),
+                true,
+            )
+        };
+        let columnas = vec!["id".to_string(), "nombre".to_string()];
+
+        let resultado = Insert::desde_select(
+            archivo.to_string(),
+            columnas.clone(),
+            select(&["edad", "nombre"]),
+        )
+        .insertar();
+        let de_menos =
+            Insert::desde_select(archivo.to_string(), columnas, select(&["edad"])).insertar();
+
+        assert!(resultado.is_ok());
+        assert!(matches!(de_menos, Err(MyError::InvalidColumn(_))));
+        assert!(base
+            .volcar("clientes")
+            .unwrap()
+            .ends_with("3,Luis,luis@mail.com,41\n30,Ana,,\n41,Luis,,\n"));
+    }
+
+    #[test]
+    pub fn test13_no_se_inserta_un_valor_que_no_es_del_tipo_de_la_columna() {
+        let base = BaseEnMemoria::new("insert_tipos");
+        base.cargar("t", "id,nombre\n1,Ana\n");
+        base.cargar_esquema("t", "id INT\nnombre TEXT").unwrap();
+
+        let resultado = Parser::new()
+            .crear_operacion(
+                base.direccion().to_string(),
+                "INSERT INTO t (id, nombre) VALUES (2, Eva), (abc, Luis)".to_string(),
+            )
+            .unwrap()
+            .realizar_operacion();
+
+        assert!(matches!(resultado, Err(MyError::InvalidColumn(_))));
+        assert_eq!(base.volcar("t").unwrap(), "id,nombre\n1,Ana\n");
+    }
 }
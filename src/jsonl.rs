@@ -0,0 +1,645 @@
+use crate::almacenamiento::{self, Almacenamiento, Reescritura};
+use crate::archivo_temporal::ArchivoTemporal;
+use crate::bloqueo::Bloqueo;
+use crate::esquema::Esquema;
+use crate::my_error::MyError;
+use crate::operadores::{Fila, Operador};
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+///# TablaJsonl
+///Es el almacenamiento de una tabla en un archivo JSON Lines: cada línea es un objeto JSON cuyas
+///claves son las columnas. Las columnas de la tabla son las del esquema (si tiene) seguidas de
+///todas las claves que aparecen en alguna línea, en el orden en el que aparecen por primera vez.
+///Las claves que le faltan a una línea y los valores *null* se leen como valores vacíos.
+///
+///Al escribir, cada fila se guarda como un objeto con todas las columnas: los valores vacíos se
+///escriben como *null*, los que son un número, *true*, *false*, un objeto o un arreglo JSON se
+///escriben tal cual y el resto como cadenas. Al reescribir la tabla, los valores que no cambian
+///conservan el texto que tenían en la línea original (por ejemplo *"42"* sigue siendo una cadena).
+#[derive(Debug, PartialEq)]
+pub struct TablaJsonl {
+    archivo: String,
+}
+
+impl TablaJsonl {
+    ///# TablaJsonl::new()
+    ///Esta función crea una nueva instancia de TablaJsonl.
+    ///
+    ///**Parámetros**
+    ///- 'archivo': Es la dirección del archivo JSON Lines de la tabla.
+    ///
+    ///**Return**
+    ///Devuelve un *struct* de tipo *TablaJsonl*.
+    pub fn new(archivo: &str) -> Self {
+        TablaJsonl {
+            archivo: archivo.to_string(),
+        }
+    }
+
+    fn tiene_esquema(&self) -> bool {
+        Path::new(&Esquema::ruta(&self.archivo)).exists()
+    }
+
+    ///Recorre el archivo una vez para juntar las claves de todas las líneas.
+    fn columnas(&self) -> Result<Vec<String>, MyError> {
+        let mut columnas = match self.tiene_esquema() {
+            true => Esquema::cargar(&self.archivo)?.nombres(),
+            false => Vec::new(),
+        };
+
+        for linea in BufReader::new(abrir_archivo(&self.archivo)?).lines() {
+            let linea = match linea {
+                Ok(l) => l,
+                Err(_e) => return Err(error_de_lectura()),
+            };
+            if linea.trim().is_empty() {
+                continue;
+            }
+            for (clave, _) in leer_objeto(&linea)? {
+                if !columnas.contains(&clave) {
+                    columnas.push(clave);
+                }
+            }
+        }
+        Ok(columnas)
+    }
+}
+
+impl Almacenamiento for TablaJsonl {
+    fn archivo(&self) -> &str {
+        &self.archivo
+    }
+
+    fn bloquear(&self, exclusivo: bool) -> Result<Option<Bloqueo>, MyError> {
+        almacenamiento::bloquear_archivo(&self.archivo, exclusivo)
+    }
+
+    fn esquema(&self) -> Result<Esquema, MyError> {
+        match self.tiene_esquema() {
+            true => Esquema::cargar(&self.archivo),
+            false => Ok(Esquema::sin_tipos(&self.columnas()?)),
+        }
+    }
+
+    fn recorrer(&self, posiciones: Option<Vec<u64>>) -> Result<Box<dyn Operador>, MyError> {
+        Ok(Box::new(RecorridoJsonl {
+            columnas: self.columnas()?,
+            lector: BufReader::new(abrir_archivo(&self.archivo)?),
+            posiciones,
+            siguiente_posicion: 0,
+            ultima_posicion: 0,
+        }))
+    }
+
+    fn anexar(&self, filas: &[Fila]) -> Result<(), MyError> {
+        let columnas = self.columnas()?;
+        let lineas: String = filas
+            .iter()
+            .map(|f| escribir_objeto(&columnas, f) + "\n")
+            .collect();
+        almacenamiento::anexar_lineas(&self.archivo, &lineas)
+    }
+
+    fn reescribir(&self, columnas: &[String]) -> Result<Box<dyn Reescritura>, MyError> {
+        Ok(Box::new(ReescrituraJsonl {
+            original: BufReader::new(abrir_archivo(&self.archivo)?),
+            leido: 0,
+            campos: Vec::new(),
+            temporal: ArchivoTemporal::crear(&self.archivo)?,
+            columnas: columnas.to_vec(),
+        }))
+    }
+
+    fn largo(&self) -> Result<u64, MyError> {
+        almacenamiento::largo_archivo(&self.archivo)
+    }
+
+    fn tablas_con_esquema(&self) -> Result<Vec<String>, MyError> {
+        almacenamiento::tablas_del_directorio(&self.archivo)
+    }
+
+    fn ubicacion_temporal(&self) -> String {
+        String::from(&self.archivo)
+    }
+}
+
+///Lee las líneas de un archivo JSON Lines y las entrega con los valores en el orden de las
+///columnas. La posición de cada fila es el byte en el que empieza su línea.
+struct RecorridoJsonl {
+    columnas: Vec<String>,
+    lector: BufReader<File>,
+    posiciones: Option<Vec<u64>>,
+    siguiente_posicion: u64,
+    ultima_posicion: u64,
+}
+
+impl Operador for RecorridoJsonl {
+    fn columnas(&self) -> &[String] {
+        &self.columnas
+    }
+
+    fn siguiente(&mut self) -> Result<Option<Fila>, MyError> {
+        loop {
+            let mut linea = String::new();
+            let largo = match self.lector.read_line(&mut linea) {
+                Ok(0) => return Ok(None),
+                Ok(l) => l as u64,
+                Err(_e) => return Err(error_de_lectura()),
+            };
+            let posicion = self.siguiente_posicion;
+            self.siguiente_posicion += largo;
+
+            let elegida = match &self.posiciones {
+                Some(p) => p.binary_search(&posicion).is_ok(),
+                None => true,
+            };
+            if linea.trim().is_empty() || !elegida {
+                continue;
+            }
+
+            let objeto = leer_objeto(&linea)?;
+            self.ultima_posicion = posicion;
+            return Ok(Some(
+                self.columnas
+                    .iter()
+                    .map(|c| {
+                        objeto
+                            .iter()
+                            .find(|(clave, _)| clave == c)
+                            .map(|(_, valor)| String::from(valor))
+                            .unwrap_or_default()
+                    })
+                    .collect(),
+            ));
+        }
+    }
+
+    fn posicion(&self) -> Option<u64> {
+        Some(self.ultima_posicion)
+    }
+}
+
+///Escribe las filas en un *ArchivoTemporal*, un objeto por línea, que se renombra sobre la tabla
+///al publicarlo. Lee la tabla original a la par para conservar el texto de los valores de la
+///línea de la que sale cada fila.
+struct ReescrituraJsonl {
+    original: BufReader<File>,
+    leido: u64,
+    campos: Vec<Campo>,
+    temporal: ArchivoTemporal,
+    columnas: Vec<String>,
+}
+
+impl Reescritura for ReescrituraJsonl {
+    fn escribir(&mut self, fila: &[String]) -> Result<(), MyError> {
+        let campos = std::mem::take(&mut self.campos);
+        self.temporal
+            .escribir(&(escribir_campos(&self.columnas, fila, &campos) + "\n"))
+    }
+
+    fn origen(&mut self, posicion: Option<u64>) -> Result<(), MyError> {
+        self.campos.clear();
+        let posicion = match posicion {
+            Some(p) => p,
+            None => return Ok(()),
+        };
+
+        while self.leido <= posicion {
+            let mut linea = String::new();
+            let inicio = self.leido;
+            match self.original.read_line(&mut linea) {
+                Ok(0) => break,
+                Ok(l) => self.leido += l as u64,
+                Err(_e) => return Err(error_de_lectura()),
+            }
+            if inicio == posicion {
+                self.campos = leer_campos(&linea)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn publicar(self: Box<Self>) -> Result<(), MyError> {
+        self.temporal.publicar()
+    }
+}
+
+///# jsonl::leer_objeto()
+///Esta función lee una línea con un objeto JSON y devuelve sus claves con sus valores. Las
+///cadenas se devuelven sin comillas ni escapes, *null* como un valor vacío y los números,
+///booleanos, objetos y arreglos con el mismo texto que tienen en la línea.
+///
+///**Parámetros**
+///- 'linea': Es el texto del objeto.
+///
+///**Return**
+///Devuelve un *Result<Vec<(String, String)>, MyError>*, en caso de que la línea no sea un
+///  objeto JSON válido se devuelve un error del tipo *MyError*.
+pub fn leer_objeto(linea: &str) -> Result<Vec<(String, String)>, MyError> {
+    Ok(leer_campos(linea)?
+        .into_iter()
+        .map(|campo| (campo.clave, campo.valor))
+        .collect())
+}
+
+///Es una clave de un objeto JSON con su valor y el texto con el que está escrito el valor.
+#[derive(Debug)]
+struct Campo {
+    clave: String,
+    valor: String,
+    texto: String,
+}
+
+///Lee un objeto JSON como *leer_objeto()*, conservando además el texto de cada valor.
+fn leer_campos(linea: &str) -> Result<Vec<Campo>, MyError> {
+    let mut lectura = Lectura {
+        texto: linea.as_bytes(),
+        posicion: 0,
+    };
+    let mut objeto = Vec::new();
+
+    lectura.esperar(b'{')?;
+    if lectura.siguiente_es(b'}') {
+        lectura.avanzar();
+    } else {
+        loop {
+            let clave = lectura.cadena()?;
+            lectura.esperar(b':')?;
+            lectura.saltar_espacios();
+            let inicio = lectura.posicion;
+            let valor = lectura.valor()?.unwrap_or_default();
+            let texto = lectura.texto_desde(inicio)?;
+            objeto.retain(|c: &Campo| c.clave != clave);
+            objeto.push(Campo {
+                clave,
+                valor,
+                texto,
+            });
+
+            if lectura.siguiente_es(b',') {
+                lectura.avanzar();
+                continue;
+            }
+            lectura.esperar(b'}')?;
+            break;
+        }
+    }
+
+    match lectura.terminada() {
+        true => Ok(objeto),
+        false => Err(error_de_formato()),
+    }
+}
+
+///# jsonl::escribir_objeto()
+///Esta función arma la línea con el objeto JSON de una fila, con una clave por columna.
+///
+///**Parámetros**
+///- 'columnas': Son los nombres de las columnas.
+///- 'fila': Son los valores de la fila en el orden de las columnas.
+pub fn escribir_objeto(columnas: &[String], fila: &[String]) -> String {
+    escribir_campos(columnas, fila, &[])
+}
+
+///Arma la línea de una fila como *escribir_objeto()*, pero los valores que son iguales a los de
+///la línea original se escriben con el mismo texto que tenían en ella.
+fn escribir_campos(columnas: &[String], fila: &[String], originales: &[Campo]) -> String {
+    let campos: Vec<String> = columnas
+        .iter()
+        .zip(fila)
+        .map(|(columna, valor)| {
+            let original = originales
+                .iter()
+                .find(|c| c.clave == *columna && c.valor == *valor);
+            match original {
+                Some(c) => format!("{}:{}", cadena(columna), c.texto),
+                None => format!("{}:{}", cadena(columna), valor_json(valor)),
+            }
+        })
+        .collect();
+    format!("{{{}}}", campos.join(","))
+}
+
+fn valor_json(valor: &str) -> String {
+    if valor.is_empty() {
+        return "null".to_string();
+    }
+
+    let mut lectura = Lectura {
+        texto: valor.as_bytes(),
+        posicion: 0,
+    };
+    let es_literal = !valor.starts_with('"')
+        && !valor.starts_with(char::is_whitespace)
+        && matches!(lectura.valor(), Ok(Some(_)))
+        && lectura.terminada();
+    match es_literal {
+        true => valor.to_string(),
+        false => cadena(valor),
+    }
+}
+
+fn cadena(texto: &str) -> String {
+    let mut escrita = String::from('"');
+    for c in texto.chars() {
+        match c {
+            '"' => escrita.push_str("\\\""),
+            '\\' => escrita.push_str("\\\\"),
+            '\n' => escrita.push_str("\\n"),
+            '\r' => escrita.push_str("\\r"),
+            '\t' => escrita.push_str("\\t"),
+            c if (c as u32) < 0x20 => escrita.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escrita.push(c),
+        }
+    }
+    escrita.push('"');
+    escrita
+}
+
+///Recorre el texto de un valor JSON byte por byte.
+struct Lectura<'a> {
+    texto: &'a [u8],
+    posicion: usize,
+}
+
+impl Lectura<'_> {
+    fn saltar_espacios(&mut self) {
+        while self
+            .texto
+            .get(self.posicion)
+            .is_some_and(|b| b.is_ascii_whitespace())
+        {
+            self.posicion += 1;
+        }
+    }
+
+    fn siguiente_es(&mut self, byte: u8) -> bool {
+        self.saltar_espacios();
+        self.texto.get(self.posicion) == Some(&byte)
+    }
+
+    fn avanzar(&mut self) {
+        self.posicion += 1;
+    }
+
+    fn esperar(&mut self, byte: u8) -> Result<(), MyError> {
+        match self.siguiente_es(byte) {
+            true => {
+                self.avanzar();
+                Ok(())
+            }
+            false => Err(error_de_formato()),
+        }
+    }
+
+    fn terminada(&mut self) -> bool {
+        self.saltar_espacios();
+        self.posicion == self.texto.len()
+    }
+
+    ///Lee un valor y devuelve su texto, o *None* si es *null*.
+    fn valor(&mut self) -> Result<Option<String>, MyError> {
+        self.saltar_espacios();
+        let inicio = self.posicion;
+        match self.texto.get(self.posicion) {
+            Some(b'"') => Ok(Some(self.cadena()?)),
+            Some(b'{') | Some(b'[') => {
+                self.compuesto()?;
+                Ok(Some(self.texto_desde(inicio)?))
+            }
+            Some(_) => {
+                while self
+                    .texto
+                    .get(self.posicion)
+                    .is_some_and(|b| !b",}] \t\r\n".contains(b))
+                {
+                    self.posicion += 1;
+                }
+                let literal = self.texto_desde(inicio)?;
+                match literal.as_str() {
+                    "null" => Ok(None),
+                    "true" | "false" => Ok(Some(literal)),
+                    _ if es_numero(&literal) => Ok(Some(literal)),
+                    _ => Err(error_de_formato()),
+                }
+            }
+            None => Err(error_de_formato()),
+        }
+    }
+
+    fn cadena(&mut self) -> Result<String, MyError> {
+        self.esperar(b'"')?;
+        let mut bytes = Vec::new();
+        loop {
+            let byte = match self.texto.get(self.posicion) {
+                Some(b) => *b,
+                None => return Err(error_de_formato()),
+            };
+            self.posicion += 1;
+            match byte {
+                b'"' => break,
+                b'\\' => {
+                    let escape = match self.texto.get(self.posicion) {
+                        Some(b) => *b,
+                        None => return Err(error_de_formato()),
+                    };
+                    self.posicion += 1;
+                    let caracter = match escape {
+                        b'"' => '"',
+                        b'\\' => '\\',
+                        b'/' => '/',
+                        b'b' => '\u{8}',
+                        b'f' => '\u{c}',
+                        b'n' => '\n',
+                        b'r' => '\r',
+                        b't' => '\t',
+                        b'u' => self.unicode()?,
+                        _ => return Err(error_de_formato()),
+                    };
+                    let mut buffer = [0; 4];
+                    bytes.extend_from_slice(caracter.encode_utf8(&mut buffer).as_bytes());
+                }
+                b => bytes.push(b),
+            }
+        }
+        String::from_utf8(bytes).map_err(|_e| error_de_formato())
+    }
+
+    fn unicode(&mut self) -> Result<char, MyError> {
+        let primero = self.hexadecimal()?;
+        let codigo = match primero {
+            0xD800..=0xDBFF => {
+                if self.texto.get(self.posicion..self.posicion + 2) != Some(b"\\u") {
+                    return Err(error_de_formato());
+                }
+                self.posicion += 2;
+                let segundo = self.hexadecimal()?;
+                if !(0xDC00..=0xDFFF).contains(&segundo) {
+                    return Err(error_de_formato());
+                }
+                0x10000 + ((primero - 0xD800) << 10) + (segundo - 0xDC00)
+            }
+            c => c,
+        };
+        char::from_u32(codigo).ok_or_else(error_de_formato)
+    }
+
+    fn hexadecimal(&mut self) -> Result<u32, MyError> {
+        let digitos = match self.texto.get(self.posicion..self.posicion + 4) {
+            Some(d) => d,
+            None => return Err(error_de_formato()),
+        };
+        self.posicion += 4;
+        match std::str::from_utf8(digitos).map(|d| u32::from_str_radix(d, 16)) {
+            Ok(Ok(c)) => Ok(c),
+            _ => Err(error_de_formato()),
+        }
+    }
+
+    ///Saltea un objeto o un arreglo completo, incluidos los que tiene adentro.
+    fn compuesto(&mut self) -> Result<(), MyError> {
+        let cierre = match self.texto.get(self.posicion) {
+            Some(b'{') => b'}',
+            _ => b']',
+        };
+        self.avanzar();
+        if self.siguiente_es(cierre) {
+            self.avanzar();
+            return Ok(());
+        }
+
+        loop {
+            if cierre == b'}' {
+                self.cadena()?;
+                self.esperar(b':')?;
+            }
+            self.valor()?;
+            if self.siguiente_es(b',') {
+                self.avanzar();
+                continue;
+            }
+            return self.esperar(cierre);
+        }
+    }
+
+    fn texto_desde(&self, inicio: usize) -> Result<String, MyError> {
+        match std::str::from_utf8(&self.texto[inicio..self.posicion]) {
+            Ok(t) => Ok(t.to_string()),
+            Err(_e) => Err(error_de_formato()),
+        }
+    }
+}
+
+///Corrobora que el texto sea un número con la forma que permite JSON.
+fn es_numero(texto: &str) -> bool {
+    let sin_signo = texto.strip_prefix('-').unwrap_or(texto);
+    let (mantisa, exponente) = match sin_signo.find(['e', 'E']) {
+        Some(p) => (&sin_signo[..p], Some(&sin_signo[p + 1..])),
+        None => (sin_signo, None),
+    };
+    let (entera, decimal) = match mantisa.split_once('.') {
+        Some((e, d)) => (e, Some(d)),
+        None => (mantisa, None),
+    };
+    let digitos = |t: &str| !t.is_empty() && t.bytes().all(|b| b.is_ascii_digit());
+
+    digitos(entera)
+        && (entera == "0" || !entera.starts_with('0'))
+        && decimal.is_none_or(digitos)
+        && exponente.is_none_or(|e| digitos(e.strip_prefix(['+', '-']).unwrap_or(e)))
+}
+
+fn abrir_archivo(archivo: &str) -> Result<File, MyError> {
+    match File::open(archivo) {
+        Ok(f) => Ok(f),
+        Err(_e) => Err(MyError::InvalidTable(
+            "Directorio o nombre de la tabla incorrecto".to_string(),
+        )),
+    }
+}
+
+fn error_de_lectura() -> MyError {
+    MyError::Error("Fallo en la lectura de la tabla".to_string())
+}
+
+fn error_de_formato() -> MyError {
+    MyError::InvalidTable("Una línea de la tabla no es un objeto JSON válido".to_string())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::pruebas::ejecutar;
+    use std::fs;
+
+    #[test]
+    pub fn test01_se_leen_y_se_escriben_objetos_json() {
+        let objeto = leer_objeto(
+            r#"{"id": 1, "nombre": "Ana \"la\" á", "extra": {"a": [1, 2]}, "x": null}"#,
+        )
+        .unwrap();
+        let columnas: Vec<String> = objeto.iter().map(|(c, _)| c.to_string()).collect();
+        let valores: Vec<String> = objeto.iter().map(|(_, v)| v.to_string()).collect();
+
+        assert_eq!(columnas, vec!["id", "nombre", "extra", "x"]);
+        assert_eq!(valores, vec!["1", "Ana \"la\" á", r#"{"a": [1, 2]}"#, ""]);
+        assert_eq!(
+            escribir_objeto(&columnas, &valores),
+            r#"{"id":1,"nombre":"Ana \"la\" á","extra":{"a": [1, 2]},"x":null}"#
+        );
+        assert!(leer_objeto(r#"{"id": 01}"#).is_err());
+        assert!(leer_objeto(r#"{"id": 1} x"#).is_err());
+    }
+
+    #[test]
+    pub fn test02_se_ejecutan_instrucciones_sobre_una_tabla_jsonl() {
+        let directorio = "./test/jsonl";
+        let _ = fs::create_dir_all(directorio);
+        let _ = fs::remove_file(format!("{}/eventos.csv", directorio));
+        fs::write(
+            format!("{}/eventos.jsonl", directorio),
+            "{\"id\": 1, \"tipo\": \"alta\"}\n{\"id\": 2, \"tipo\": \"baja\", \"motivo\": \"precio\"}\n",
+        )
+        .unwrap();
+
+        ejecutar(
+            directorio,
+            "INSERT INTO eventos (id, tipo) VALUES (3, alta)",
+        )
+        .unwrap();
+        ejecutar(directorio, "UPDATE eventos SET motivo = error WHERE id = 1").unwrap();
+        ejecutar(directorio, "DELETE FROM eventos WHERE tipo = baja").unwrap();
+
+        assert_eq!(
+            fs::read_to_string(format!("{}/eventos.jsonl", directorio)).unwrap(),
+            "{\"id\":1,\"tipo\":\"alta\",\"motivo\":\"error\"}\n{\"id\":3,\"tipo\":\"alta\",\"motivo\":null}\n"
+        );
+    }
+
+    #[test]
+    pub fn test03_al_reescribir_se_conserva_el_texto_de_los_valores_que_no_cambian() {
+        let directorio = "./test/jsonl";
+        let _ = fs::create_dir_all(directorio);
+        let _ = fs::remove_file(format!("{}/cambios.csv", directorio));
+        fs::write(
+            format!("{}/cambios.jsonl", directorio),
+            "{\"id\": 1, \"code\": \"42\", \"flag\": \"true\", \"nota\": \"\"}\n\
+             {\"id\": 2, \"code\": \"7\", \"flag\": false, \"nota\": null}\n\
+             {\"id\": 3, \"code\": 5, \"flag\": \"no\", \"nota\": \"x\"}\n\
+             {\"id\": 4, \"code\": 42, \"flag\": true, \"nota\": \"\"}\n",
+        )
+        .unwrap();
+
+        ejecutar(directorio, "DELETE FROM cambios WHERE id = 3").unwrap();
+        ejecutar(directorio, "UPDATE cambios SET nota = listo WHERE id = 2").unwrap();
+
+        assert_eq!(
+            fs::read_to_string(format!("{}/cambios.jsonl", directorio)).unwrap(),
+            "{\"id\":1,\"code\":\"42\",\"flag\":\"true\",\"nota\":\"\"}\n\
+             {\"id\":2,\"code\":\"7\",\"flag\":false,\"nota\":\"listo\"}\n\
+             {\"id\":4,\"code\":42,\"flag\":true,\"nota\":\"\"}\n"
+        );
+    }
+}
@@ -1,10 +1,38 @@
+pub mod almacenamiento;
+pub mod alter_table;
+pub mod ancho_fijo;
+pub mod archivo_temporal;
+pub mod binario;
+pub mod bloqueo;
+pub mod clave_foranea;
 pub mod condicion;
 pub mod condicion_simple;
+pub mod convert_table;
+pub mod copy_to;
+pub mod create_index;
 pub mod delete;
+pub mod esquema;
+pub mod formato;
+pub mod gzip;
+pub mod indice;
 pub mod insert;
+pub mod jsonl;
+pub mod memoria;
 pub mod my_error;
 pub mod operacion;
+pub mod operadores;
+pub mod ordenamiento;
 pub mod parser;
+pub mod particiones;
+pub mod planificador;
+#[cfg(test)]
+mod pruebas;
+pub mod read_csv;
+pub mod restricciones;
+pub mod secuencia;
 pub mod select;
+pub mod sesion;
+pub mod transaccion;
 pub mod update;
 pub mod valor;
+pub mod wal;
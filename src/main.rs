@@ -1,24 +1,77 @@
 use sql_rustico::my_error::MyError;
-use sql_rustico::parser::Parser;
+use sql_rustico::sesion::{self, Sesion};
+use sql_rustico::wal;
 use std::env;
+use std::fs;
+use std::io::{self, BufRead, IsTerminal, Write};
 
 fn main() -> Result<(), MyError> {
     let mut args: Vec<String> = env::args().collect();
     let direccion = args.remove(1);
-    let instruccion = args.remove(1);
 
-    let mut parser = Parser::new();
+    match wal::recuperar(&direccion) {
+        Ok(0) => {}
+        Ok(n) => println!("Se deshicieron {} instrucciones incompletas", n),
+        Err(e) => {
+            println!("{}", e);
+            return Ok(());
+        }
+    }
+
+    let mut sesion = Sesion::new(direccion);
 
-    match parser.crear_operacion(direccion, instruccion) {
-        Ok(o) => {
-            let operacion = o.realizar_operacion();
-            match operacion {
-                Ok(_) => {}
-                Err(e) => println!("{}", e),
+    match args.get(1).map(|a| a.as_str()) {
+        None => consola(&mut sesion),
+        Some("-f") => {
+            let script = match args.get(2).map(fs::read_to_string) {
+                Some(Ok(s)) => s,
+                _ => {
+                    println!(
+                        "{}",
+                        MyError::Error("No se pudo leer el script".to_string())
+                    );
+                    return Ok(());
+                }
             };
+            for instruccion in sesion::separar_instrucciones(&script) {
+                ejecutar(&mut sesion, &instruccion);
+            }
         }
-        Err(e) => println!("{}", e),
-    };
+        Some(instruccion) => ejecutar(&mut sesion, instruccion),
+    }
 
     Ok(())
 }
+
+fn ejecutar(sesion: &mut Sesion, instruccion: &str) {
+    if let Err(e) = sesion.ejecutar(instruccion) {
+        println!("{}", e);
+    }
+}
+
+fn consola(sesion: &mut Sesion) {
+    let interactiva = io::stdin().is_terminal();
+
+    loop {
+        if interactiva {
+            let prompt = match sesion.en_transaccion() {
+                true => "sql*> ",
+                false => "sql> ",
+            };
+            print!("{}", prompt);
+            let _ = io::stdout().flush();
+        }
+
+        let mut linea = String::new();
+        match io::stdin().lock().read_line(&mut linea) {
+            Ok(0) | Err(_) => break,
+            Ok(_) => {}
+        }
+
+        match linea.trim().to_uppercase().as_str() {
+            "" => continue,
+            "EXIT" | "QUIT" => break,
+            _ => ejecutar(sesion, &linea),
+        }
+    }
+}
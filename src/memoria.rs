@@ -0,0 +1,441 @@
+use crate::almacenamiento::{Almacenamiento, Reescritura};
+use crate::bloqueo::Bloqueo;
+use crate::esquema::Esquema;
+use crate::formato::Formato;
+use crate::my_error::MyError;
+use crate::operadores::{Fila, Operador, Recorrido};
+use std::collections::BTreeMap;
+use std::io::Cursor;
+use std::path::Path;
+use std::sync::{Mutex, MutexGuard};
+
+///Es el comienzo de la dirección de las bases en memoria.
+pub const PREFIJO: &str = "memoria://";
+
+///Son las tablas en memoria de todas las bases, por la dirección de cada tabla.
+static TABLAS: Mutex<BTreeMap<String, TablaGuardada>> = Mutex::new(BTreeMap::new());
+
+///Es el contenido de una tabla en memoria, con el mismo formato que el archivo CSV y el de su
+///esquema, y el próximo valor de su secuencia si ya se usó su columna AUTO_INCREMENT.
+#[derive(Debug, Clone)]
+struct TablaGuardada {
+    contenido: String,
+    esquema: Option<String>,
+    secuencia: Option<i64>,
+}
+
+impl TablaGuardada {
+    ///El formato declarado en el esquema o, si no lo declara, el que se detecta en el encabezado.
+    fn formato(&self) -> Result<Formato, MyError> {
+        let detectado = Formato::detectar(self.contenido.lines().next().unwrap_or_default());
+        match &self.esquema {
+            Some(esquema) => Ok(detectado.con_esquema(&Esquema::desde_texto(esquema)?)),
+            None => Ok(detectado),
+        }
+    }
+}
+
+///# BaseEnMemoria
+///Es una base de datos cuyas tablas se guardan en memoria en lugar de en archivos. Su dirección
+///se usa igual que la de un directorio (por ejemplo con *Parser.crear_operacion()*), por lo que
+///las instrucciones se ejecutan sin tocar el disco. Las tablas se cargan y se vuelcan como texto
+///CSV y se descartan junto con la base.
+///
+///**Ejemplo**
+///let base = BaseEnMemoria::new("pruebas");
+///base.cargar("clientes", "id,nombre\n1,Ana\n");
+///let operacion = parser.crear_operacion(base.direccion().to_string(), instruccion)?;
+///operacion.realizar_operacion()?;
+///let contenido = base.volcar("clientes");
+///
+///**Parámetros**
+///- 'direccion': Es la dirección de la base, que empieza con *memoria://*.
+#[derive(Debug, PartialEq)]
+pub struct BaseEnMemoria {
+    direccion: String,
+}
+
+impl BaseEnMemoria {
+    ///# BaseEnMemoria::new()
+    ///Esta función crea una base en memoria vacía. Si ya había una base con el mismo nombre se
+    ///descartan sus tablas.
+    ///
+    ///**Parámetros**
+    ///- 'nombre': Es el nombre de la base; dos bases con distinto nombre no comparten tablas.
+    ///
+    ///**Return**
+    ///Devuelve un *struct* de tipo *BaseEnMemoria*.
+    pub fn new(nombre: &str) -> Self {
+        let base = BaseEnMemoria {
+            direccion: format!("{}{}", PREFIJO, nombre),
+        };
+        base.descartar();
+        base
+    }
+
+    ///# BaseEnMemoria.direccion()
+    ///Devuelve la dirección de la base, que se usa en lugar del directorio de las tablas.
+    pub fn direccion(&self) -> &str {
+        &self.direccion
+    }
+
+    ///# BaseEnMemoria.archivo()
+    ///Devuelve la dirección de una tabla de la base, como la arma el parser.
+    ///
+    ///**Parámetros**
+    ///- 'tabla': Es el nombre de la tabla.
+    pub fn archivo(&self, tabla: &str) -> String {
+        format!("{}/{}.csv", self.direccion, tabla)
+    }
+
+    ///# BaseEnMemoria.cargar()
+    ///Esta función crea una tabla (o reemplaza su contenido) a partir de su texto CSV: la primera
+    ///línea con los nombres de las columnas y una línea por fila.
+    ///
+    ///**Parámetros**
+    ///- 'tabla': Es el nombre de la tabla.
+    ///- 'csv': Es el contenido de la tabla.
+    pub fn cargar(&self, tabla: &str, csv: &str) {
+        let mut contenido = csv.to_string();
+        if !contenido.is_empty() && !contenido.ends_with('\n') {
+            contenido.push('\n');
+        }
+
+        let mut tablas = tablas();
+        let esquema = tablas.remove(&self.archivo(tabla)).and_then(|t| t.esquema);
+        tablas.insert(
+            self.archivo(tabla),
+            TablaGuardada {
+                contenido,
+                esquema,
+                secuencia: None,
+            },
+        );
+    }
+
+    ///# BaseEnMemoria.cargar_esquema()
+    ///Esta función le asigna a una tabla ya cargada el esquema indicado, con el mismo formato que
+    ///el archivo *.esquema*.
+    ///
+    ///**Parámetros**
+    ///- 'tabla': Es el nombre de la tabla.
+    ///- 'esquema': Es el contenido del esquema.
+    ///
+    ///**Return**
+    ///Devuelve un *Result<(), MyError>*, en caso de que la tabla no exista o el esquema no sea
+    ///  válido se devuelve un error del tipo *MyError*.
+    pub fn cargar_esquema(&self, tabla: &str, esquema: &str) -> Result<(), MyError> {
+        Esquema::desde_texto(esquema)?;
+        match tablas().get_mut(&self.archivo(tabla)) {
+            Some(t) => {
+                t.esquema = Some(esquema.to_string());
+                Ok(())
+            }
+            None => Err(tabla_inexistente()),
+        }
+    }
+
+    ///# BaseEnMemoria.volcar()
+    ///Esta función devuelve el contenido actual de una tabla como texto CSV, o *None* si la tabla
+    ///no existe.
+    ///
+    ///**Parámetros**
+    ///- 'tabla': Es el nombre de la tabla.
+    pub fn volcar(&self, tabla: &str) -> Option<String> {
+        tablas()
+            .get(&self.archivo(tabla))
+            .map(|t| String::from(&t.contenido))
+    }
+
+    fn descartar(&self) {
+        let carpeta = format!("{}/", self.direccion);
+        tablas().retain(|archivo, _| !archivo.starts_with(&carpeta));
+    }
+}
+
+impl Drop for BaseEnMemoria {
+    fn drop(&mut self) {
+        self.descartar();
+    }
+}
+
+///# TablaMemoria
+///Es el almacenamiento de una tabla de una *BaseEnMemoria*. No usa bloqueos: cada lectura o
+///escritura de la tabla se hace de una sola vez sobre su contenido en memoria.
+#[derive(Debug, PartialEq)]
+pub struct TablaMemoria {
+    archivo: String,
+}
+
+impl TablaMemoria {
+    ///# TablaMemoria::new()
+    ///Esta función crea una nueva instancia de TablaMemoria.
+    ///
+    ///**Parámetros**
+    ///- 'archivo': Es la dirección de la tabla, que empieza con *memoria://*.
+    ///
+    ///**Return**
+    ///Devuelve un *struct* de tipo *TablaMemoria*.
+    pub fn new(archivo: &str) -> Self {
+        TablaMemoria {
+            archivo: archivo.to_string(),
+        }
+    }
+
+    fn leer(&self) -> Result<TablaGuardada, MyError> {
+        match tablas().get(&self.archivo) {
+            Some(t) => Ok(t.clone()),
+            None => Err(tabla_inexistente()),
+        }
+    }
+}
+
+impl Almacenamiento for TablaMemoria {
+    fn archivo(&self) -> &str {
+        &self.archivo
+    }
+
+    fn bloquear(&self, _exclusivo: bool) -> Result<Option<Bloqueo>, MyError> {
+        Ok(None)
+    }
+
+    fn esquema(&self) -> Result<Esquema, MyError> {
+        let tabla = self.leer()?;
+        match &tabla.esquema {
+            Some(esquema) => Esquema::desde_texto(esquema),
+            None => {
+                let encabezado = tabla.contenido.lines().next().unwrap_or_default();
+                Ok(Esquema::sin_tipos(&tabla.formato()?.separar(encabezado)))
+            }
+        }
+    }
+
+    fn recorrer(&self, posiciones: Option<Vec<u64>>) -> Result<Box<dyn Operador>, MyError> {
+        let tabla = self.leer()?;
+        let formato = tabla.formato()?;
+        Ok(Box::new(
+            Recorrido::desde_lector(Box::new(Cursor::new(tabla.contenido.into_bytes())), formato)?
+                .con_posiciones(posiciones),
+        ))
+    }
+
+    fn anexar(&self, filas: &[Fila]) -> Result<(), MyError> {
+        match tablas().get_mut(&self.archivo) {
+            Some(t) => {
+                let formato = t.formato()?;
+                for fila in filas {
+                    t.contenido.push_str(&(formato.unir(fila) + "\n"));
+                }
+                Ok(())
+            }
+            None => Err(tabla_inexistente()),
+        }
+    }
+
+    fn reescribir(&self, columnas: &[String]) -> Result<Box<dyn Reescritura>, MyError> {
+        let formato = self.leer()?.formato()?;
+        Ok(Box::new(ReescrituraMemoria {
+            archivo: String::from(&self.archivo),
+            contenido: formato.unir_encabezado(columnas) + "\n",
+            formato,
+        }))
+    }
+
+    fn largo(&self) -> Result<u64, MyError> {
+        Ok(self.leer()?.contenido.len() as u64)
+    }
+
+    fn tablas_con_esquema(&self) -> Result<Vec<String>, MyError> {
+        let carpeta = match self.archivo.rfind('/') {
+            Some(p) => &self.archivo[..=p],
+            None => return Err(tabla_inexistente()),
+        };
+
+        Ok(tablas()
+            .iter()
+            .filter(|(archivo, t)| {
+                archivo.starts_with(carpeta) && **archivo != self.archivo && t.esquema.is_some()
+            })
+            .map(|(archivo, _)| String::from(archivo))
+            .collect())
+    }
+
+    fn ubicacion_temporal(&self) -> String {
+        let nombre = Path::new(&self.archivo)
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        std::env::temp_dir()
+            .join(nombre)
+            .to_string_lossy()
+            .to_string()
+    }
+}
+
+///Junta las filas de la tabla nueva y las reemplaza de una vez al publicarlas.
+struct ReescrituraMemoria {
+    archivo: String,
+    contenido: String,
+    formato: Formato,
+}
+
+impl Reescritura for ReescrituraMemoria {
+    fn escribir(&mut self, fila: &[String]) -> Result<(), MyError> {
+        self.contenido.push_str(&(self.formato.unir(fila) + "\n"));
+        Ok(())
+    }
+
+    fn publicar(self: Box<Self>) -> Result<(), MyError> {
+        match tablas().get_mut(&self.archivo) {
+            Some(t) => {
+                t.contenido = self.contenido;
+                Ok(())
+            }
+            None => Err(tabla_inexistente()),
+        }
+    }
+}
+
+///# memoria::leer_secuencia()
+///Esta función devuelve el próximo valor de la secuencia de una tabla en memoria, o *None* si
+///todavía no se usó.
+///
+///**Parámetros**
+///- 'archivo': Es la dirección de la tabla.
+///
+///**Return**
+///Devuelve un *Result<Option<i64>, MyError>*, en caso de que la tabla no exista se devuelve un
+///  error del tipo *MyError*.
+pub fn leer_secuencia(archivo: &str) -> Result<Option<i64>, MyError> {
+    match tablas().get(archivo) {
+        Some(t) => Ok(t.secuencia),
+        None => Err(tabla_inexistente()),
+    }
+}
+
+///# memoria::guardar_secuencia()
+///Esta función guarda el próximo valor de la secuencia de una tabla en memoria, o la borra si
+///es *None*.
+///
+///**Parámetros**
+///- 'archivo': Es la dirección de la tabla.
+///- 'siguiente': Es el próximo valor que va a entregar la secuencia.
+///
+///**Return**
+///Devuelve un *Result<(), MyError>*, en caso de que la tabla no exista se devuelve un error del
+///  tipo *MyError*.
+pub fn guardar_secuencia(archivo: &str, siguiente: Option<i64>) -> Result<(), MyError> {
+    match tablas().get_mut(archivo) {
+        Some(t) => {
+            t.secuencia = siguiente;
+            Ok(())
+        }
+        None => Err(tabla_inexistente()),
+    }
+}
+
+fn tablas() -> MutexGuard<'static, BTreeMap<String, TablaGuardada>> {
+    TABLAS.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn tabla_inexistente() -> MyError {
+    MyError::InvalidTable("Directorio o nombre de la tabla incorrecto".to_string())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::pruebas::ejecutar;
+
+    #[test]
+    pub fn test01_se_ejecutan_instrucciones_sobre_una_base_en_memoria() {
+        let base = BaseEnMemoria::new("memoria_instrucciones");
+        base.cargar("clientes", "id,nombre,edad\n1,Ana,30\n2,Luis,41");
+
+        ejecutar(
+            base.direccion(),
+            "INSERT INTO clientes (id, nombre, edad) VALUES (3, Eva, 19)",
+        )
+        .unwrap();
+        ejecutar(
+            base.direccion(),
+            "UPDATE clientes SET edad = 31 WHERE nombre = Ana",
+        )
+        .unwrap();
+        ejecutar(base.direccion(), "DELETE FROM clientes WHERE id = 2").unwrap();
+
+        assert_eq!(
+            base.volcar("clientes").unwrap(),
+            "id,nombre,edad\n1,Ana,31\n3,Eva,19\n"
+        );
+        assert!(!Path::new("memoria_instrucciones").exists());
+    }
+
+    #[test]
+    pub fn test02_el_esquema_en_memoria_aplica_restricciones_y_claves_foraneas() {
+        let base = BaseEnMemoria::new("memoria_restricciones");
+        base.cargar("clientes", "id,nombre\n1,Ana\n");
+        base.cargar("pedidos", "id,cliente\n7,1\n");
+        base.cargar_esquema("clientes", "id INT PRIMARY KEY\nnombre TEXT")
+            .unwrap();
+        base.cargar_esquema(
+            "pedidos",
+            "id INT PRIMARY KEY\ncliente INT REFERENCES clientes(id) ON DELETE CASCADE",
+        )
+        .unwrap();
+
+        let repetido = ejecutar(
+            base.direccion(),
+            "INSERT INTO clientes (id, nombre) VALUES (1, Eva)",
+        );
+        let sin_padre = ejecutar(
+            base.direccion(),
+            "INSERT INTO pedidos (id, cliente) VALUES (8, 5)",
+        );
+        ejecutar(base.direccion(), "DELETE FROM clientes WHERE id = 1").unwrap();
+
+        assert!(matches!(repetido, Err(MyError::ConstraintViolation(_))));
+        assert!(matches!(sin_padre, Err(MyError::ConstraintViolation(_))));
+        assert_eq!(base.volcar("pedidos").unwrap(), "id,cliente\n");
+    }
+
+    #[test]
+    pub fn test03_las_bases_con_distinto_nombre_no_comparten_tablas() {
+        let base = BaseEnMemoria::new("memoria_separada");
+        base.cargar("clientes", "id\n1\n");
+        let otra = BaseEnMemoria::new("memoria_separada_otra");
+        let direccion = base.archivo("clientes");
+
+        let en_otra = ejecutar(otra.direccion(), "SELECT * FROM clientes");
+        drop(base);
+
+        assert!(matches!(en_otra, Err(MyError::InvalidTable(_))));
+        assert!(TablaMemoria::new(&direccion).largo().is_err());
+    }
+
+    #[test]
+    pub fn test04_las_tablas_en_memoria_admiten_default_y_auto_increment() {
+        let base = BaseEnMemoria::new("memoria_secuencia");
+        base.cargar("t", "id,nombre,ciudad\n4,Ana,Salta\n");
+        base.cargar_esquema(
+            "t",
+            "id INT AUTO_INCREMENT\nnombre TEXT NOT NULL\nciudad TEXT DEFAULT Tandil",
+        )
+        .unwrap();
+
+        ejecutar(base.direccion(), "INSERT INTO t (nombre) VALUES (Eva)").unwrap();
+        ejecutar(base.direccion(), "DELETE FROM t WHERE nombre = Eva").unwrap();
+        ejecutar(
+            base.direccion(),
+            "INSERT INTO t (nombre, ciudad) VALUES (Luis, Jujuy)",
+        )
+        .unwrap();
+
+        assert_eq!(
+            base.volcar("t").unwrap(),
+            "id,nombre,ciudad\n4,Ana,Salta\n6,Luis,Jujuy\n"
+        );
+        assert_eq!(leer_secuencia(&base.archivo("t")).unwrap(), Some(7));
+        assert!(!Path::new("memoria_secuencia").exists());
+    }
+}
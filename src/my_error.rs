@@ -7,6 +7,10 @@ use std::fmt;
 ///- *InvalidTable*: Son los errores relacionados a la tabla.
 ///- *InvalidColumn*: Son los errores relacionados a las columnas de la tabla.
 ///- *InvalidSyntax*: Son los errores relacionados a la sintaxis de las instrucciones escritas.
+///- *ConstraintViolation*: Son los errores que ocurren cuando una instrucción no cumple alguna de
+///  las restricciones declaradas en el esquema de la tabla (NOT NULL, UNIQUE, PRIMARY KEY).
+///- *LockTimeout*: Son los errores que ocurren cuando no se puede bloquear una tabla porque otro
+///  proceso la está usando durante más tiempo que la espera configurada.
 ///- *Error*: Son los todos los otros tipos de errores que ocurren que no estan relacionados a los
 ///  antes mencionados.
 #[derive(Debug)]
@@ -14,6 +18,8 @@ pub enum MyError {
     InvalidTable(String),
     InvalidColumn(String),
     InvalidSyntax(String),
+    ConstraintViolation(String),
+    LockTimeout(String),
     Error(String),
 }
 
@@ -23,6 +29,8 @@ impl fmt::Display for MyError {
             MyError::InvalidTable(ref msg) => write!(f, "INVALID_TABLE: {}", msg),
             MyError::InvalidColumn(ref msg) => write!(f, "INVALID_COLUMN: {}", msg),
             MyError::InvalidSyntax(ref msg) => write!(f, "INVALID_SYNTAX: {}", msg),
+            MyError::ConstraintViolation(ref msg) => write!(f, "CONSTRAINT_VIOLATION: {}", msg),
+            MyError::LockTimeout(ref msg) => write!(f, "LOCK_TIMEOUT: {}", msg),
             MyError::Error(ref msg) => write!(f, "ERROR: {}", msg),
         }
     }
@@ -1,3 +1,7 @@
+use crate::alter_table::AlterTable;
+use crate::convert_table::ConvertTable;
+use crate::copy_to::CopyTo;
+use crate::create_index::CreateIndex;
 use crate::delete::Delete;
 use crate::insert::Insert;
 use crate::my_error::MyError;
@@ -17,12 +21,24 @@ use crate::update::Update;
 ///- 'Delete': Es la operación que representa a la instrucción DELETE.
 ///- 'Update': Es la operación que representa a la instrucción UPDATE.
 ///- 'Select': Es la opereción que representa a la instrucción SELECT.
-#[derive(Debug)]
+///- 'AlterTable': Es la operación que representa a la instrucción ALTER TABLE.
+///- 'CreateIndex': Es la operación que representa a la instrucción CREATE INDEX.
+///- 'ConvertTable': Es la operación que representa a la instrucción CONVERT TABLE.
+///- 'CopyTo': Es la operación que representa a la instrucción COPY ... TO.
+///- 'Explain': Es la operación que representa a la instrucción EXPLAIN SELECT.
+///- 'ExplainAnalyze': Es la operación que representa a la instrucción EXPLAIN ANALYZE SELECT.
+#[derive(Debug, PartialEq)]
 pub enum Operacion {
     Insert(Insert),
     Delete(Delete),
     Update(Update),
     Select(Select),
+    AlterTable(AlterTable),
+    CreateIndex(CreateIndex),
+    ConvertTable(ConvertTable),
+    CopyTo(CopyTo),
+    Explain(Select),
+    ExplainAnalyze(Select),
 }
 
 impl Operacion {
@@ -39,6 +55,12 @@ impl Operacion {
             Operacion::Delete(delete) => delete.eliminar(),
             Operacion::Update(update) => update.update(),
             Operacion::Select(select) => select.seleccionar(),
+            Operacion::AlterTable(alter) => alter.alterar(),
+            Operacion::CreateIndex(create) => create.crear(),
+            Operacion::ConvertTable(convert) => convert.convertir(),
+            Operacion::CopyTo(copy) => copy.copiar(),
+            Operacion::Explain(select) => select.explicar(),
+            Operacion::ExplainAnalyze(select) => select.analizar(),
         }
     }
 }
@@ -0,0 +1,929 @@
+use crate::condicion::{Condicion, CondicionCompilada};
+use crate::formato::Formato;
+use crate::indice;
+use crate::my_error::MyError;
+use crate::ordenamiento::{FilasOrdenadas, OrdenamientoExterno};
+use crate::planificador::Medicion;
+use crate::valor::Valor;
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Seek};
+use std::rc::Rc;
+use std::time::Instant;
+
+///Es una fila de una tabla: sus valores en el orden de las columnas.
+pub type Fila = Vec<String>;
+
+///# Operador
+///Es la interfaz común de las etapas con las que se ejecutan las instrucciones. Cada operador
+///entrega sus filas de a una, pidiéndoselas a su vez al operador que recibe como entrada, por lo
+///que se pueden encadenar sin juntar todas las filas en memoria.
+///
+///**Ejemplo**
+///let recorrido = Recorrido::abrir(direccion_tabla, formato)?;
+///let filtro = Filtro::new(Box::new(recorrido), &condicion)?;
+///let mut proyeccion = Proyeccion::new(Box::new(filtro), &columnas)?;
+///while let Some(fila) = proyeccion.siguiente()? { ... }
+pub trait Operador {
+    ///# Operador.columnas()
+    ///Devuelve los nombres de las columnas de las filas que entrega el operador.
+    fn columnas(&self) -> &[String];
+
+    ///# Operador.siguiente()
+    ///Devuelve la siguiente fila, o *None* si ya no quedan.
+    ///
+    ///**Return**
+    ///Devuelve un *Result<Option<Fila>, MyError>*, en caso de que falle la lectura o el
+    ///  procesamiento de la fila se devuelve un error del tipo *MyError*.
+    fn siguiente(&mut self) -> Result<Option<Fila>, MyError>;
+
+    ///# Operador.posicion()
+    ///Devuelve la posición en la tabla de la última fila entregada, si el operador lee de una
+    ///tabla cuyas filas se ubican por posición (las que usan los índices).
+    fn posicion(&self) -> Option<u64> {
+        None
+    }
+}
+
+///# operadores::recolectar()
+///Esta función pide todas las filas a un operador y las devuelve juntas.
+///
+///**Parámetros**
+///- 'operador': Es el operador del que se quieren las filas.
+///
+///**Return**
+///Devuelve un *Result<Vec<Fila>, MyError>*, en caso de que el operador falle se devuelve un
+///  error del tipo *MyError*.
+pub fn recolectar(operador: &mut dyn Operador) -> Result<Vec<Fila>, MyError> {
+    let mut filas = Vec::new();
+    while let Some(fila) = operador.siguiente()? {
+        filas.push(fila);
+    }
+    Ok(filas)
+}
+
+///Es cualquier lector del que se puede leer una tabla por líneas y posicionarse en un byte.
+pub trait Lector: BufRead + Seek {}
+
+impl<T: BufRead + Seek> Lector for T {}
+
+///# Recorrido
+///Es el operador que lee las filas de una tabla, completa o solo las que empiezan en ciertas
+///posiciones (las que indica un índice). Las columnas son las del encabezado de la tabla.
+///
+///**Parámetros**
+///- 'columnas': Son los nombres de las columnas del encabezado de la tabla.
+///- 'formato': Es el separador y las comillas con los que están escritas las filas.
+///- 'lector': Es el lector del contenido de la tabla.
+///- 'posiciones': Son las posiciones de las filas a leer, o *None* si se lee la tabla completa.
+///- 'siguiente_posicion': Es el byte en el que empieza la próxima fila de la lectura completa.
+///- 'ultima_posicion': Es el byte en el que empieza la última fila entregada.
+///- 'largo_encabezado': Es la cantidad de bytes del encabezado, con su salto de línea.
+pub struct Recorrido {
+    columnas: Vec<String>,
+    formato: Formato,
+    lector: Box<dyn Lector>,
+    posiciones: Option<std::vec::IntoIter<u64>>,
+    siguiente_posicion: u64,
+    ultima_posicion: u64,
+    largo_encabezado: u64,
+}
+
+impl Recorrido {
+    ///# Recorrido::abrir()
+    ///Esta función abre la tabla y lee su encabezado para recorrerla completa.
+    ///
+    ///**Parámetros**
+    ///- 'archivo': Es la dirección del archivo de la tabla.
+    ///- 'formato': Es el formato con el que están escritas las filas.
+    ///
+    ///**Return**
+    ///Devuelve un *Result<Recorrido, MyError>*, en caso de que no se pueda abrir o leer la tabla
+    ///  se devuelve un error del tipo *MyError*.
+    pub fn abrir(archivo: &str, formato: Formato) -> Result<Self, MyError> {
+        match File::open(archivo) {
+            Ok(f) => Recorrido::desde_lector(Box::new(BufReader::new(f)), formato),
+            Err(_e) => Err(MyError::InvalidTable(
+                "Directorio o nombre de la tabla incorrecto".to_string(),
+            )),
+        }
+    }
+
+    ///# Recorrido::desde_lector()
+    ///Esta función lee el encabezado de una tabla que no está en un archivo (por ejemplo, una
+    ///tabla en memoria) para recorrerla completa.
+    ///
+    ///**Parámetros**
+    ///- 'lector': Es el lector del contenido de la tabla, con el mismo formato que el archivo.
+    ///- 'formato': Es el formato con el que están escritas las filas.
+    ///
+    ///**Return**
+    ///Devuelve un *Result<Recorrido, MyError>*, en caso de que no se pueda leer el encabezado se
+    ///  devuelve un error del tipo *MyError*.
+    pub fn desde_lector(mut lector: Box<dyn Lector>, formato: Formato) -> Result<Self, MyError> {
+        let mut encabezado = String::new();
+        let largo = match lector.read_line(&mut encabezado) {
+            Ok(l) => l as u64,
+            Err(_e) => return Err(error_de_lectura()),
+        };
+
+        Ok(Recorrido {
+            columnas: formato.separar(&encabezado),
+            formato,
+            lector,
+            posiciones: None,
+            siguiente_posicion: largo,
+            ultima_posicion: largo,
+            largo_encabezado: largo,
+        })
+    }
+
+    ///# Recorrido.con_posiciones()
+    ///Esta función hace que solo se lean las filas que empiezan en las posiciones indicadas. Si
+    ///las posiciones son *None* se sigue leyendo la tabla completa.
+    ///
+    ///**Parámetros**
+    ///- 'posiciones': Son los bytes en los que empiezan las filas a leer.
+    ///
+    ///**Return**
+    ///Devuelve el mismo *Recorrido*.
+    pub fn con_posiciones(mut self, posiciones: Option<Vec<u64>>) -> Self {
+        self.posiciones = posiciones.map(|p| p.into_iter());
+        self
+    }
+
+    ///# Recorrido.ultima_posicion()
+    ///Devuelve el byte de la tabla en el que empieza la última fila entregada.
+    pub fn ultima_posicion(&self) -> u64 {
+        self.ultima_posicion
+    }
+
+    ///# Recorrido.largo_encabezado()
+    ///Devuelve la cantidad de bytes que ocupa el encabezado de la tabla, con su salto de línea.
+    pub fn largo_encabezado(&self) -> u64 {
+        self.largo_encabezado
+    }
+}
+
+impl Operador for Recorrido {
+    fn columnas(&self) -> &[String] {
+        &self.columnas
+    }
+
+    fn siguiente(&mut self) -> Result<Option<Fila>, MyError> {
+        if let Some(posiciones) = &mut self.posiciones {
+            return match posiciones.next() {
+                Some(posicion) => {
+                    self.ultima_posicion = posicion;
+                    Ok(Some(self.formato.separar_fila(
+                        &indice::leer_fila(&mut self.lector, posicion)?,
+                        self.columnas.len(),
+                    )?))
+                }
+                None => Ok(None),
+            };
+        }
+
+        let mut linea = String::new();
+        match self.lector.read_line(&mut linea) {
+            Ok(0) => Ok(None),
+            Ok(largo) => {
+                self.ultima_posicion = self.siguiente_posicion;
+                self.siguiente_posicion += largo as u64;
+                Ok(Some(
+                    self.formato.separar_fila(&linea, self.columnas.len())?,
+                ))
+            }
+            Err(_e) => Err(error_de_lectura()),
+        }
+    }
+
+    fn posicion(&self) -> Option<u64> {
+        Some(self.ultima_posicion)
+    }
+}
+
+///# Valores
+///Es el operador que entrega filas que ya están en memoria.
+#[derive(Debug)]
+pub struct Valores {
+    columnas: Vec<String>,
+    filas: std::vec::IntoIter<Fila>,
+}
+
+impl Valores {
+    ///# Valores::new()
+    ///Esta función crea una nueva instancia de Valores.
+    ///
+    ///**Parámetros**
+    ///- 'columnas': Son los nombres de las columnas de las filas.
+    ///- 'filas': Son las filas que entrega el operador, en ese orden.
+    ///
+    ///**Return**
+    ///Devuelve un *struct* de tipo *Valores*.
+    pub fn new(columnas: Vec<String>, filas: Vec<Fila>) -> Self {
+        Valores {
+            columnas,
+            filas: filas.into_iter(),
+        }
+    }
+}
+
+impl Operador for Valores {
+    fn columnas(&self) -> &[String] {
+        &self.columnas
+    }
+
+    fn siguiente(&mut self) -> Result<Option<Fila>, MyError> {
+        Ok(self.filas.next())
+    }
+}
+
+///# Filtro
+///Es el operador que entrega solo las filas de su entrada que cumplen una condición. La
+///condición se compila una única vez contra las columnas de la entrada.
+pub struct Filtro {
+    entrada: Box<dyn Operador>,
+    condicion: CondicionCompilada,
+}
+
+impl Filtro {
+    ///# Filtro::new()
+    ///Esta función crea una nueva instancia de Filtro.
+    ///
+    ///**Parámetros**
+    ///- 'entrada': Es el operador que entrega las filas a filtrar.
+    ///- 'condicion': Es la condición que tienen que cumplir las filas.
+    ///
+    ///**Return**
+    ///Devuelve un *Result<Filtro, MyError>*, en caso de que la condición use una columna que la
+    ///  entrada no tiene se devuelve un error del tipo *MyError*.
+    pub fn new(entrada: Box<dyn Operador>, condicion: &Condicion) -> Result<Self, MyError> {
+        let condicion = condicion.compilar(entrada.columnas())?;
+        Ok(Filtro { entrada, condicion })
+    }
+}
+
+impl Operador for Filtro {
+    fn columnas(&self) -> &[String] {
+        self.entrada.columnas()
+    }
+
+    fn siguiente(&mut self) -> Result<Option<Fila>, MyError> {
+        while let Some(fila) = self.entrada.siguiente()? {
+            if self.condicion.verificar(&fila)? {
+                return Ok(Some(fila));
+            }
+        }
+        Ok(None)
+    }
+}
+
+///# Proyeccion
+///Es el operador que entrega de cada fila solo las columnas pedidas, en el orden pedido. Si se
+///pide únicamente *\** se entregan todas las columnas.
+pub struct Proyeccion {
+    entrada: Box<dyn Operador>,
+    columnas: Vec<String>,
+    posiciones: Option<Vec<usize>>,
+}
+
+impl Proyeccion {
+    ///# Proyeccion::new()
+    ///Esta función crea una nueva instancia de Proyeccion.
+    ///
+    ///**Parámetros**
+    ///- 'entrada': Es el operador que entrega las filas completas.
+    ///- 'columnas': Son los nombres de las columnas que se quieren, o solo *\**.
+    ///
+    ///**Return**
+    ///Devuelve un *Result<Proyeccion, MyError>*, en caso de que alguna columna no exista en la
+    ///  entrada se devuelve un error del tipo *MyError*.
+    pub fn new(entrada: Box<dyn Operador>, columnas: &[String]) -> Result<Self, MyError> {
+        if columnas.len() == 1 && columnas[0] == "*" {
+            return Ok(Proyeccion {
+                columnas: entrada.columnas().to_vec(),
+                entrada,
+                posiciones: None,
+            });
+        }
+
+        let mut posiciones = Vec::with_capacity(columnas.len());
+        for columna in columnas {
+            match entrada.columnas().iter().position(|c| c == columna) {
+                Some(p) => posiciones.push(p),
+                None => {
+                    return Err(MyError::InvalidColumn(
+                        "Hay columnas en la instrucción que no existen en la tabla".to_string(),
+                    ))
+                }
+            }
+        }
+
+        Ok(Proyeccion {
+            entrada,
+            columnas: columnas.to_vec(),
+            posiciones: Some(posiciones),
+        })
+    }
+}
+
+impl Operador for Proyeccion {
+    fn columnas(&self) -> &[String] {
+        &self.columnas
+    }
+
+    fn siguiente(&mut self) -> Result<Option<Fila>, MyError> {
+        let fila = match self.entrada.siguiente()? {
+            Some(f) => f,
+            None => return Ok(None),
+        };
+
+        match &self.posiciones {
+            None => Ok(Some(fila)),
+            Some(posiciones) => Ok(Some(
+                posiciones
+                    .iter()
+                    .map(|p| fila.get(*p).cloned().unwrap_or_default())
+                    .collect(),
+            )),
+        }
+    }
+}
+
+///# Orden
+///Es el operador que entrega las filas de su entrada ordenadas por una columna. La primera vez
+///que se le pide una fila consume toda la entrada con un *OrdenamientoExterno*, por lo que si no
+///entran en memoria se ordenan en disco.
+pub struct Orden {
+    entrada: Box<dyn Operador>,
+    ordenamiento: Option<OrdenamientoExterno>,
+    ordenadas: Option<FilasOrdenadas>,
+}
+
+impl Orden {
+    ///# Orden::new()
+    ///Esta función crea una nueva instancia de Orden.
+    ///
+    ///**Parámetros**
+    ///- 'entrada': Es el operador que entrega las filas a ordenar.
+    ///- 'archivo': Es la dirección de la tabla, al lado de la cual se guardan los tramos que se
+    ///  vuelcan a disco.
+    ///- 'columna': Es la columna por la que se ordena.
+    ///- 'asc': Indica si el orden es ascendente.
+    ///- 'presupuesto': Es la cantidad aproximada de bytes que pueden ocupar las filas en memoria.
+    ///
+    ///**Return**
+    ///Devuelve un *Result<Orden, MyError>*, en caso de que la columna no exista en la entrada se
+    ///  devuelve un error del tipo *MyError*.
+    pub fn new(
+        entrada: Box<dyn Operador>,
+        archivo: &str,
+        columna: &str,
+        asc: bool,
+        presupuesto: usize,
+    ) -> Result<Self, MyError> {
+        let posicion = match entrada.columnas().iter().position(|c| c == columna) {
+            Some(p) => p,
+            None => {
+                return Err(MyError::InvalidColumn(
+                    "Columna especificada para ordenar no existe en la tabla".to_string(),
+                ))
+            }
+        };
+
+        Ok(Orden {
+            entrada,
+            ordenamiento: Some(OrdenamientoExterno::new(
+                archivo,
+                posicion,
+                asc,
+                presupuesto,
+            )),
+            ordenadas: None,
+        })
+    }
+}
+
+impl Operador for Orden {
+    fn columnas(&self) -> &[String] {
+        self.entrada.columnas()
+    }
+
+    fn siguiente(&mut self) -> Result<Option<Fila>, MyError> {
+        if let Some(mut ordenamiento) = self.ordenamiento.take() {
+            while let Some(fila) = self.entrada.siguiente()? {
+                ordenamiento.agregar(fila)?;
+            }
+            self.ordenadas = Some(ordenamiento.terminar()?);
+        }
+
+        match &mut self.ordenadas {
+            Some(ordenadas) => ordenadas.next().transpose(),
+            None => Ok(None),
+        }
+    }
+}
+
+///# Limite
+///Es el operador que entrega como máximo una cantidad de filas de su entrada. Una vez alcanzada
+///no le pide más filas a la entrada.
+pub struct Limite {
+    entrada: Box<dyn Operador>,
+    restantes: usize,
+}
+
+impl Limite {
+    ///# Limite::new()
+    ///Esta función crea una nueva instancia de Limite.
+    ///
+    ///**Parámetros**
+    ///- 'entrada': Es el operador que entrega las filas.
+    ///- 'cantidad': Es la cantidad máxima de filas a entregar.
+    ///
+    ///**Return**
+    ///Devuelve un *struct* de tipo *Limite*.
+    pub fn new(entrada: Box<dyn Operador>, cantidad: usize) -> Self {
+        Limite {
+            entrada,
+            restantes: cantidad,
+        }
+    }
+}
+
+impl Operador for Limite {
+    fn columnas(&self) -> &[String] {
+        self.entrada.columnas()
+    }
+
+    fn siguiente(&mut self) -> Result<Option<Fila>, MyError> {
+        if self.restantes == 0 {
+            return Ok(None);
+        }
+        self.restantes -= 1;
+        self.entrada.siguiente()
+    }
+}
+
+///# Agregado
+///Son las funciones que puede calcular una *Agregacion* sobre cada grupo de filas.
+///
+///- Contar: Es la cantidad de filas del grupo (COUNT(*)).
+///- Sumar: Es la suma de los valores numéricos de una columna (SUM(columna)).
+///- Minimo: Es el menor valor de una columna (MIN(columna)).
+///- Maximo: Es el mayor valor de una columna (MAX(columna)).
+///
+///Los valores vacíos no se tienen en cuenta para SUM, MIN y MAX.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Agregado {
+    Contar,
+    Sumar(String),
+    Minimo(String),
+    Maximo(String),
+}
+
+impl Agregado {
+    ///# Agregado::leer()
+    ///Esta función interpreta una función de agregación escrita en las columnas de un SELECT,
+    ///por ejemplo *COUNT(\*)* o *SUM(monto)*, sin distinguir mayúsculas en el nombre de la función.
+    ///
+    ///**Parámetros**
+    ///- 'texto': Es la columna escrita en el SELECT.
+    ///
+    ///**Return**
+    ///Devuelve un *Option<Agregado>*, con *None* si el texto no es una función de agregación.
+    pub fn leer(texto: &str) -> Option<Agregado> {
+        let (funcion, resto) = texto.split_once('(')?;
+        let argumento = resto.strip_suffix(')')?;
+        if argumento.is_empty() || argumento.contains(['(', ')']) {
+            return None;
+        }
+
+        match (funcion.to_uppercase().as_str(), argumento) {
+            ("COUNT", "*") => Some(Agregado::Contar),
+            (_, "*") => None,
+            ("SUM", c) => Some(Agregado::Sumar(c.to_string())),
+            ("MIN", c) => Some(Agregado::Minimo(c.to_string())),
+            ("MAX", c) => Some(Agregado::Maximo(c.to_string())),
+            _ => None,
+        }
+    }
+
+    ///# Agregado.nombre()
+    ///Esta función devuelve el nombre de la columna que calcula el agregado, por ejemplo
+    ///*SUM(monto)*.
+    pub fn nombre(&self) -> String {
+        match self {
+            Agregado::Contar => "COUNT(*)".to_string(),
+            Agregado::Sumar(c) => format!("SUM({})", c),
+            Agregado::Minimo(c) => format!("MIN({})", c),
+            Agregado::Maximo(c) => format!("MAX({})", c),
+        }
+    }
+
+    fn columna(&self) -> Option<&str> {
+        match self {
+            Agregado::Contar => None,
+            Agregado::Sumar(c) | Agregado::Minimo(c) | Agregado::Maximo(c) => Some(c),
+        }
+    }
+}
+
+///Es el valor parcial de un *Agregado* para un grupo.
+#[derive(Debug, Clone)]
+enum Acumulado {
+    Cantidad(usize),
+    Suma(i64),
+    Extremo(Option<Valor>),
+}
+
+///# Agregacion
+///Es el operador que agrupa las filas de su entrada por el valor de una columna (o todas en un
+///único grupo si no se indica ninguna) y entrega una fila por grupo con el valor de la columna
+///del grupo seguido del de cada *Agregado*. Los grupos se entregan en el orden en que aparece
+///su primera fila.
+pub struct Agregacion {
+    entrada: Box<dyn Operador>,
+    columnas: Vec<String>,
+    grupo: Option<usize>,
+    agregados: Vec<(Agregado, Option<usize>)>,
+    resultado: Option<std::vec::IntoIter<Fila>>,
+}
+
+impl Agregacion {
+    ///# Agregacion::new()
+    ///Esta función crea una nueva instancia de Agregacion.
+    ///
+    ///**Parámetros**
+    ///- 'entrada': Es el operador que entrega las filas a agrupar.
+    ///- 'grupo': Es la columna por la que se agrupa, o *None* para un único grupo.
+    ///- 'agregados': Son las funciones a calcular para cada grupo.
+    ///
+    ///**Return**
+    ///Devuelve un *Result<Agregacion, MyError>*, en caso de que alguna columna no exista en la
+    ///  entrada se devuelve un error del tipo *MyError*.
+    pub fn new(
+        entrada: Box<dyn Operador>,
+        grupo: Option<&str>,
+        agregados: Vec<Agregado>,
+    ) -> Result<Self, MyError> {
+        let posicion = |columna: &str| match entrada.columnas().iter().position(|c| c == columna) {
+            Some(p) => Ok(p),
+            None => Err(MyError::InvalidColumn(format!(
+                "La columna {} no existe en la tabla",
+                columna
+            ))),
+        };
+
+        let mut columnas = Vec::new();
+        let grupo = match grupo {
+            Some(g) => {
+                columnas.push(g.to_string());
+                Some(posicion(g)?)
+            }
+            None => None,
+        };
+
+        let mut resueltos = Vec::with_capacity(agregados.len());
+        for agregado in agregados {
+            let columna = match agregado.columna() {
+                Some(c) => Some(posicion(c)?),
+                None => None,
+            };
+            columnas.push(agregado.nombre());
+            resueltos.push((agregado, columna));
+        }
+
+        Ok(Agregacion {
+            entrada,
+            columnas,
+            grupo,
+            agregados: resueltos,
+            resultado: None,
+        })
+    }
+
+    fn agrupar(&mut self) -> Result<Vec<Fila>, MyError> {
+        let inicial: Vec<Acumulado> = self
+            .agregados
+            .iter()
+            .map(|(a, _)| match a {
+                Agregado::Contar => Acumulado::Cantidad(0),
+                Agregado::Sumar(_) => Acumulado::Suma(0),
+                Agregado::Minimo(_) | Agregado::Maximo(_) => Acumulado::Extremo(None),
+            })
+            .collect();
+        let mut posiciones: HashMap<String, usize> = HashMap::new();
+        let mut grupos: Vec<(String, Vec<Acumulado>)> = Vec::new();
+        if self.grupo.is_none() {
+            grupos.push((String::new(), inicial.clone()));
+        }
+
+        while let Some(fila) = self.entrada.siguiente()? {
+            let numero = match self.grupo {
+                None => 0,
+                Some(g) => {
+                    let clave = fila.get(g).cloned().unwrap_or_default();
+                    match posiciones.get(&clave) {
+                        Some(n) => *n,
+                        None => {
+                            posiciones.insert(clave.clone(), grupos.len());
+                            grupos.push((clave, inicial.clone()));
+                            grupos.len() - 1
+                        }
+                    }
+                }
+            };
+
+            for ((agregado, columna), acumulado) in
+                self.agregados.iter().zip(grupos[numero].1.iter_mut())
+            {
+                let valor = match columna {
+                    Some(c) => fila.get(*c).map(|v| v.as_str()).unwrap_or_default(),
+                    None => "",
+                };
+                acumular(agregado, acumulado, valor)?;
+            }
+        }
+
+        Ok(grupos
+            .into_iter()
+            .map(|(clave, acumulados)| {
+                let mut fila: Fila = Vec::with_capacity(acumulados.len() + 1);
+                if self.grupo.is_some() {
+                    fila.push(clave);
+                }
+                fila.extend(acumulados.iter().map(|a| match a {
+                    Acumulado::Cantidad(n) => n.to_string(),
+                    Acumulado::Suma(s) => s.to_string(),
+                    Acumulado::Extremo(v) => v.as_ref().map(|v| v.to_string()).unwrap_or_default(),
+                }));
+                fila
+            })
+            .collect())
+    }
+}
+
+impl Operador for Agregacion {
+    fn columnas(&self) -> &[String] {
+        &self.columnas
+    }
+
+    fn siguiente(&mut self) -> Result<Option<Fila>, MyError> {
+        if self.resultado.is_none() {
+            self.resultado = Some(self.agrupar()?.into_iter());
+        }
+        match &mut self.resultado {
+            Some(filas) => Ok(filas.next()),
+            None => Ok(None),
+        }
+    }
+}
+
+fn acumular(agregado: &Agregado, acumulado: &mut Acumulado, valor: &str) -> Result<(), MyError> {
+    match acumulado {
+        Acumulado::Cantidad(n) => *n += 1,
+        _ if valor.is_empty() => {}
+        Acumulado::Suma(s) => match valor.parse::<i64>() {
+            Ok(v) => match s.checked_add(v) {
+                Some(suma) => *s = suma,
+                None => {
+                    return Err(MyError::Error(format!(
+                        "El resultado de {} excede el rango de los números enteros",
+                        agregado.nombre()
+                    )))
+                }
+            },
+            Err(_e) => {
+                return Err(MyError::InvalidColumn(format!(
+                    "{} solo se puede calcular sobre valores numéricos",
+                    agregado.nombre()
+                )))
+            }
+        },
+        Acumulado::Extremo(extremo) => {
+            let valor = match valor.parse::<i32>() {
+                Ok(v) => Valor::Entero(v),
+                Err(_e) => Valor::Palabra(valor.to_string()),
+            };
+            let reemplazar = match (extremo.as_ref(), agregado) {
+                (None, _) => true,
+                (Some(actual), Agregado::Minimo(_)) => valor < *actual,
+                (Some(actual), _) => valor > *actual,
+            };
+            if reemplazar {
+                *extremo = Some(valor);
+            }
+        }
+    }
+    Ok(())
+}
+
+///# Medido
+///Es el operador que deja pasar las filas de su entrada sin cambiarlas, registrando cuántas
+///entregó y cuánto tardó en obtenerlas (incluido el tiempo de los operadores anteriores). Se usa
+///para mostrar lo que hizo cada etapa en *EXPLAIN ANALYZE*.
+pub struct Medido {
+    entrada: Box<dyn Operador>,
+    medicion: Rc<Cell<Medicion>>,
+}
+
+impl Medido {
+    ///# Medido::new()
+    ///Esta función crea una nueva instancia de Medido.
+    ///
+    ///**Parámetros**
+    ///- 'entrada': Es el operador que se quiere medir.
+    ///
+    ///**Return**
+    ///Devuelve el *Medido* junto con la medición que va actualizando, para leerla al terminar.
+    pub fn new(entrada: Box<dyn Operador>) -> (Self, Rc<Cell<Medicion>>) {
+        let medicion = Rc::new(Cell::new(Medicion::default()));
+        (
+            Medido {
+                entrada,
+                medicion: Rc::clone(&medicion),
+            },
+            medicion,
+        )
+    }
+}
+
+impl Operador for Medido {
+    fn columnas(&self) -> &[String] {
+        self.entrada.columnas()
+    }
+
+    fn siguiente(&mut self) -> Result<Option<Fila>, MyError> {
+        let inicio = Instant::now();
+        let fila = self.entrada.siguiente()?;
+        let mut medicion = self.medicion.get();
+        medicion.registrar(0, fila.is_some() as usize, inicio.elapsed());
+        self.medicion.set(medicion);
+        Ok(fila)
+    }
+}
+
+fn error_de_lectura() -> MyError {
+    MyError::Error("Fallo en la lectura de la tabla".to_string())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::condicion_simple::CondicionSimple;
+
+    fn nombres(columnas: &[&str]) -> Vec<String> {
+        columnas.iter().map(|c| c.to_string()).collect()
+    }
+
+    fn clientes() -> Box<dyn Operador> {
+        Box::new(Valores::new(
+            nombres(&["id", "nombre", "ciudad", "edad"]),
+            vec![
+                nombres(&["1", "Tomas", "Tandil", "25"]),
+                nombres(&["2", "Ana", "Salta", "30"]),
+                nombres(&["3", "Luis", "Tandil", "41"]),
+                nombres(&["4", "Eva", "Salta", ""]),
+            ],
+        ))
+    }
+
+    #[test]
+    pub fn test01_se_recorre_una_tabla_completa_y_por_posiciones() {
+        let archivo = "./test/indice/clientes_copia.csv";
+        let mut completo = Recorrido::abrir(archivo, Formato::default()).unwrap();
+        let primera = completo.siguiente().unwrap().unwrap();
+        let posicion = completo
+            .siguiente()
+            .map(|_| completo.ultima_posicion())
+            .unwrap();
+
+        let mut por_posicion = Recorrido::abrir(archivo, Formato::default())
+            .unwrap()
+            .con_posiciones(Some(vec![posicion]));
+
+        assert_eq!(
+            completo.columnas(),
+            nombres(&["id", "nombre", "email", "edad"])
+        );
+        assert_eq!(completo.largo_encabezado(), 21);
+        assert_eq!(primera, nombres(&["1", "Tomas", "tomas@mail.com", "25"]));
+        assert_eq!(posicion, 47);
+        assert_eq!(
+            recolectar(&mut por_posicion).unwrap(),
+            vec![nombres(&["2", "Ana", "ana@mail.com", "30"])]
+        );
+    }
+
+    #[test]
+    pub fn test02_se_encadenan_filtro_orden_proyeccion_y_limite() {
+        let condicion = Condicion::CondicionSimple(CondicionSimple::new(
+            "ciudad".to_string(),
+            "=".to_string(),
+            "Tandil".to_string(),
+        ));
+        let filtro = Filtro::new(clientes(), &condicion).unwrap();
+        let orden = Orden::new(
+            Box::new(filtro),
+            "./test/clientes.csv",
+            "nombre",
+            true,
+            1024,
+        )
+        .unwrap();
+        let proyeccion = Proyeccion::new(Box::new(orden), &nombres(&["nombre", "id"])).unwrap();
+        let mut limite = Limite::new(Box::new(proyeccion), 1);
+
+        assert_eq!(limite.columnas(), nombres(&["nombre", "id"]));
+        assert_eq!(
+            recolectar(&mut limite).unwrap(),
+            vec![nombres(&["Luis", "3"])]
+        );
+    }
+
+    #[test]
+    pub fn test03_una_proyeccion_sobre_una_columna_inexistente_falla() {
+        let resultado = Proyeccion::new(clientes(), &nombres(&["telefono"]));
+
+        assert!(matches!(resultado, Err(MyError::InvalidColumn(_))));
+    }
+
+    #[test]
+    pub fn test04_se_agregan_las_filas_por_grupo() {
+        let mut agregacion = Agregacion::new(
+            clientes(),
+            Some("ciudad"),
+            vec![
+                Agregado::Contar,
+                Agregado::Sumar("edad".to_string()),
+                Agregado::Minimo("nombre".to_string()),
+                Agregado::Maximo("edad".to_string()),
+            ],
+        )
+        .unwrap();
+
+        assert_eq!(
+            agregacion.columnas(),
+            nombres(&[
+                "ciudad",
+                "COUNT(*)",
+                "SUM(edad)",
+                "MIN(nombre)",
+                "MAX(edad)"
+            ])
+        );
+        assert_eq!(
+            recolectar(&mut agregacion).unwrap(),
+            vec![
+                nombres(&["Tandil", "2", "66", "Luis", "41"]),
+                nombres(&["Salta", "2", "30", "Ana", "30"]),
+            ]
+        );
+    }
+
+    #[test]
+    pub fn test05_sin_grupo_se_agrega_toda_la_entrada_aunque_este_vacia() {
+        let vacia = Box::new(Valores::new(nombres(&["edad"]), Vec::new()));
+        let mut agregacion = Agregacion::new(
+            vacia,
+            None,
+            vec![Agregado::Contar, Agregado::Maximo("edad".to_string())],
+        )
+        .unwrap();
+
+        assert_eq!(
+            recolectar(&mut agregacion).unwrap(),
+            vec![nombres(&["0", ""])]
+        );
+    }
+
+    #[test]
+    pub fn test06_una_suma_que_excede_el_rango_de_los_enteros_falla() {
+        let montos = Box::new(Valores::new(
+            nombres(&["monto"]),
+            vec![nombres(&[&i64::MAX.to_string()]), nombres(&["1"])],
+        ));
+        let mut agregacion =
+            Agregacion::new(montos, None, vec![Agregado::Sumar("monto".to_string())]).unwrap();
+
+        assert!(matches!(
+            recolectar(&mut agregacion),
+            Err(MyError::Error(_))
+        ));
+    }
+
+    #[test]
+    pub fn test07_se_leen_las_funciones_de_agregacion_de_un_select() {
+        assert_eq!(Agregado::leer("count(*)"), Some(Agregado::Contar));
+        assert_eq!(
+            Agregado::leer("SUM(monto)"),
+            Some(Agregado::Sumar("monto".to_string()))
+        );
+        assert_eq!(Agregado::leer("MAX(*)"), None);
+        assert_eq!(Agregado::leer("SUM()"), None);
+        assert_eq!(Agregado::leer("AVG(monto)"), None);
+        assert_eq!(Agregado::leer("monto"), None);
+    }
+}
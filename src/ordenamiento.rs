@@ -0,0 +1,434 @@
+use crate::formato::Formato;
+use crate::my_error::MyError;
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::env;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, BufWriter, Lines, Write};
+use std::mem;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{self, AtomicUsize};
+
+const VARIABLE_MEMORIA: &str = "SQL_RUSTICO_MEMORIA_ORDEN";
+const MEMORIA_POR_DEFECTO: usize = 64 * 1024 * 1024;
+
+///Los tramos encierran entre comillas los valores con comas para leerlos igual que se escribieron.
+const FORMATO_TRAMO: Formato = Formato::new(',', Some('"'));
+
+static CONTADOR: AtomicUsize = AtomicUsize::new(0);
+
+///# OrdenamientoExterno
+///Esta estructura ordena las filas de un ORDER BY sin necesidad de que entren todas en memoria.
+///Las filas se acumulan hasta ocupar el presupuesto de memoria; en ese momento se ordenan y se
+///vuelcan a un tramo en un archivo oculto al lado de la tabla. Al terminar, si se volcó algún
+///tramo, se mezclan todos leyendo una fila de cada uno a la vez. El resultado es el mismo que
+///el de ordenar todas las filas en memoria con *ordenar()*, incluido el orden de las filas con
+///el mismo valor.
+///
+///**Ejemplo**
+///let mut ordenamiento = OrdenamientoExterno::new(direccion_tabla, posicion, asc, presupuesto);
+///ordenamiento.agregar(fila)?;
+///for fila in ordenamiento.terminar()? { ... }
+///
+///**Parámetros**
+///- 'archivo': Es la dirección de la tabla, al lado de la cual se guardan los tramos.
+///- 'posicion': Es la posición de la columna por la que se ordena.
+///- 'asc': Indica si el orden es ascendente.
+///- 'presupuesto': Es la cantidad aproximada de bytes que pueden ocupar las filas en memoria.
+///- 'filas': Son las filas que todavía no se volcaron a un tramo.
+///- 'ocupado': Es la cantidad aproximada de bytes que ocupan *'filas'*.
+///- 'tramos': Son los archivos con las filas ya ordenadas que se volcaron.
+#[derive(Debug)]
+pub struct OrdenamientoExterno {
+    archivo: PathBuf,
+    posicion: usize,
+    asc: bool,
+    presupuesto: usize,
+    filas: Vec<Vec<String>>,
+    ocupado: usize,
+    tramos: Vec<Tramo>,
+}
+
+impl OrdenamientoExterno {
+    ///# OrdenamientoExterno::new()
+    ///Esta función crea una nueva instancia de OrdenamientoExterno.
+    ///
+    ///**Parámetros**
+    ///- 'archivo': Es la dirección de la tabla cuyas filas se ordenan.
+    ///- 'posicion': Es la posición de la columna por la que se ordena.
+    ///- 'asc': Indica si el orden es ascendente.
+    ///- 'presupuesto': Es la cantidad aproximada de bytes que pueden ocupar las filas en memoria.
+    ///
+    ///**Return**
+    ///Devuelve un *struct* de tipo *OrdenamientoExterno*.
+    pub fn new(archivo: &str, posicion: usize, asc: bool, presupuesto: usize) -> Self {
+        OrdenamientoExterno {
+            archivo: PathBuf::from(archivo),
+            posicion,
+            asc,
+            presupuesto,
+            filas: Vec::new(),
+            ocupado: 0,
+            tramos: Vec::new(),
+        }
+    }
+
+    ///# OrdenamientoExterno.agregar()
+    ///Esta función agrega una fila a ordenar. Si con ella las filas en memoria superan el
+    ///presupuesto, se ordenan y se vuelcan a un tramo nuevo.
+    ///
+    ///**Parámetros**
+    ///- 'fila': Son los valores de la fila en el orden de las columnas de la tabla.
+    ///
+    ///**Return**
+    ///Devuelve un *Result<(), MyError>*, en caso de que no se pueda escribir el tramo se devuelve
+    ///  un error del tipo *MyError*.
+    pub fn agregar(&mut self, fila: Vec<String>) -> Result<(), MyError> {
+        self.ocupado += tamanio(&fila);
+        self.filas.push(fila);
+        if self.ocupado > self.presupuesto {
+            self.volcar()?;
+        }
+        Ok(())
+    }
+
+    ///# OrdenamientoExterno.tramos()
+    ///Esta función devuelve la cantidad de tramos que se volcaron a disco hasta el momento.
+    pub fn tramos(&self) -> usize {
+        self.tramos.len()
+    }
+
+    ///# OrdenamientoExterno.terminar()
+    ///Esta función termina de ordenar las filas agregadas. Si no se volcó ningún tramo se ordenan
+    ///en memoria; si no, se vuelcan las que quedan y se mezclan todos los tramos.
+    ///
+    ///**Return**
+    ///Devuelve un *Result<FilasOrdenadas, MyError>* que recorre las filas ya ordenadas, en caso de
+    ///  que no se pueda escribir o leer algún tramo se devuelve un error del tipo *MyError*.
+    pub fn terminar(mut self) -> Result<FilasOrdenadas, MyError> {
+        if self.tramos.is_empty() {
+            ordenar(&mut self.filas, self.posicion, self.asc);
+            return Ok(FilasOrdenadas::Memoria(
+                mem::take(&mut self.filas).into_iter(),
+            ));
+        }
+
+        if !self.filas.is_empty() {
+            self.volcar()?;
+        }
+
+        let mut mezcla = Mezcla {
+            posicion: self.posicion,
+            asc: self.asc,
+            tramos: mem::take(&mut self.tramos),
+            cabezas: BinaryHeap::new(),
+        };
+        for numero in 0..mezcla.tramos.len() {
+            mezcla.avanzar(numero)?;
+        }
+
+        Ok(FilasOrdenadas::Mezcla(mezcla))
+    }
+
+    fn volcar(&mut self) -> Result<(), MyError> {
+        ordenar(&mut self.filas, self.posicion, self.asc);
+        let mut tramo = Tramo::crear(&self.archivo)?;
+        for fila in self.filas.drain(..) {
+            tramo.escribir(&fila)?;
+        }
+        tramo.cerrar()?;
+        self.tramos.push(tramo);
+        self.ocupado = 0;
+        Ok(())
+    }
+}
+
+///# ordenar()
+///Esta función ordena en memoria las filas según el valor de la columna en *'posicion'*. Las
+///filas con el mismo valor quedan en el orden en que llegaron si el orden es ascendente, y en el
+///orden inverso si es descendente.
+///
+///**Parámetros**
+///- 'filas': Son las filas a ordenar.
+///- 'posicion': Es la posición de la columna por la que se ordena.
+///- 'asc': Indica si el orden es ascendente.
+pub fn ordenar(filas: &mut [Vec<String>], posicion: usize, asc: bool) {
+    filas.sort_by(|a, b| clave(a, posicion).cmp(clave(b, posicion)));
+    if !asc {
+        filas.reverse();
+    }
+}
+
+///# presupuesto_configurado()
+///Esta función devuelve cuántos bytes pueden ocupar en memoria las filas de un ORDER BY antes de
+///volcarse a disco, según la variable de entorno *SQL_RUSTICO_MEMORIA_ORDEN* (en bytes).
+pub fn presupuesto_configurado() -> usize {
+    env::var(VARIABLE_MEMORIA)
+        .ok()
+        .and_then(|v| v.trim().parse::<usize>().ok())
+        .unwrap_or(MEMORIA_POR_DEFECTO)
+}
+
+fn clave(fila: &[String], posicion: usize) -> &str {
+    match fila.get(posicion) {
+        Some(v) => v,
+        None => "",
+    }
+}
+
+fn tamanio(fila: &[String]) -> usize {
+    mem::size_of::<Vec<String>>()
+        + fila
+            .iter()
+            .map(|v| v.len() + mem::size_of::<String>())
+            .sum::<usize>()
+}
+
+///# FilasOrdenadas
+///Es el resultado de *OrdenamientoExterno.terminar()*: un iterador sobre las filas ya ordenadas,
+///que están en memoria o se van leyendo de los tramos a medida que se mezclan.
+#[derive(Debug)]
+pub enum FilasOrdenadas {
+    Memoria(std::vec::IntoIter<Vec<String>>),
+    Mezcla(Mezcla),
+}
+
+impl Iterator for FilasOrdenadas {
+    type Item = Result<Vec<String>, MyError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            FilasOrdenadas::Memoria(filas) => filas.next().map(Ok),
+            FilasOrdenadas::Mezcla(mezcla) => mezcla.siguiente().transpose(),
+        }
+    }
+}
+
+///Mezcla los tramos ordenados quedándose, en cada paso, con la primera fila de alguno de ellos.
+///Ante valores iguales gana el tramo que se volcó antes si el orden es ascendente, y el que se
+///volcó después si es descendente, para respetar el mismo orden que *ordenar()*.
+#[derive(Debug)]
+pub struct Mezcla {
+    posicion: usize,
+    asc: bool,
+    tramos: Vec<Tramo>,
+    cabezas: BinaryHeap<Cabeza>,
+}
+
+impl Mezcla {
+    fn avanzar(&mut self, numero: usize) -> Result<(), MyError> {
+        if let Some(fila) = self.tramos[numero].leer()? {
+            self.cabezas.push(Cabeza {
+                fila,
+                tramo: numero,
+                posicion: self.posicion,
+                asc: self.asc,
+            });
+        }
+        Ok(())
+    }
+
+    fn siguiente(&mut self) -> Result<Option<Vec<String>>, MyError> {
+        let cabeza = match self.cabezas.pop() {
+            Some(c) => c,
+            None => return Ok(None),
+        };
+        self.avanzar(cabeza.tramo)?;
+        Ok(Some(cabeza.fila))
+    }
+}
+
+///Primera fila todavía sin devolver de un tramo. El orden está definido para que la cabeza que
+///hay que devolver primero sea la mayor del *BinaryHeap*.
+#[derive(Debug)]
+struct Cabeza {
+    fila: Vec<String>,
+    tramo: usize,
+    posicion: usize,
+    asc: bool,
+}
+
+impl Ord for Cabeza {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let orden = clave(&self.fila, self.posicion)
+            .cmp(clave(&other.fila, other.posicion))
+            .then(self.tramo.cmp(&other.tramo));
+        match self.asc {
+            true => orden.reverse(),
+            false => orden,
+        }
+    }
+}
+
+impl PartialOrd for Cabeza {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Cabeza {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Cabeza {}
+
+///Archivo oculto con una parte de las filas ya ordenadas, una por línea. Se borra al descartarse.
+#[derive(Debug)]
+struct Tramo {
+    ruta: PathBuf,
+    escritor: Option<BufWriter<File>>,
+    lector: Option<Lines<BufReader<File>>>,
+}
+
+impl Tramo {
+    fn crear(archivo: &Path) -> Result<Tramo, MyError> {
+        let nombre = match archivo.file_name() {
+            Some(n) => n.to_string_lossy().to_string(),
+            None => {
+                return Err(MyError::InvalidTable(
+                    "Directorio o nombre de la tabla incorrecto".to_string(),
+                ))
+            }
+        };
+
+        loop {
+            let ruta = archivo.with_file_name(format!(
+                ".{}.{}.{}.orden",
+                nombre,
+                process::id(),
+                CONTADOR.fetch_add(1, atomic::Ordering::Relaxed)
+            ));
+
+            match OpenOptions::new().write(true).create_new(true).open(&ruta) {
+                Ok(f) => {
+                    return Ok(Tramo {
+                        ruta,
+                        escritor: Some(BufWriter::new(f)),
+                        lector: None,
+                    })
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => continue,
+                Err(_e) => {
+                    return Err(MyError::Error(
+                        "No se pudo crear el archivo temporal para ordenar las filas".to_string(),
+                    ))
+                }
+            }
+        }
+    }
+
+    fn escribir(&mut self, fila: &[String]) -> Result<(), MyError> {
+        let resultado = match &mut self.escritor {
+            Some(e) => writeln!(e, "{}", FORMATO_TRAMO.unir(fila)),
+            None => return Err(error_de_tramo()),
+        };
+        resultado.map_err(|_e| error_de_tramo())
+    }
+
+    fn cerrar(&mut self) -> Result<(), MyError> {
+        if let Some(mut escritor) = self.escritor.take() {
+            if escritor.flush().is_err() {
+                return Err(error_de_tramo());
+            }
+        }
+        match File::open(&self.ruta) {
+            Ok(f) => {
+                self.lector = Some(BufReader::new(f).lines());
+                Ok(())
+            }
+            Err(_e) => Err(error_de_tramo()),
+        }
+    }
+
+    fn leer(&mut self) -> Result<Option<Vec<String>>, MyError> {
+        let lector = match &mut self.lector {
+            Some(l) => l,
+            None => return Err(error_de_tramo()),
+        };
+        match lector.next() {
+            Some(Ok(linea)) => Ok(Some(FORMATO_TRAMO.separar(&linea))),
+            Some(Err(_e)) => Err(error_de_tramo()),
+            None => Ok(None),
+        }
+    }
+}
+
+impl Drop for Tramo {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.ruta);
+    }
+}
+
+fn error_de_tramo() -> MyError {
+    MyError::Error("Fallo en el ordenamiento de las filas en disco".to_string())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn filas() -> Vec<Vec<String>> {
+        (0..200)
+            .map(|i| {
+                vec![
+                    i.to_string(),
+                    format!("clave{}", (i * 7) % 13),
+                    format!("valor{}", i),
+                ]
+            })
+            .collect()
+    }
+
+    fn ordenar_en_disco(asc: bool) -> (Vec<Vec<String>>, usize) {
+        let _ = fs::create_dir_all("./test/ordenamiento");
+        let mut ordenamiento =
+            OrdenamientoExterno::new("./test/ordenamiento/filas.csv", 1, asc, 1024);
+        for fila in filas() {
+            ordenamiento.agregar(fila).unwrap();
+        }
+        let tramos = ordenamiento.tramos();
+        let ordenadas = ordenamiento
+            .terminar()
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        (ordenadas, tramos)
+    }
+
+    #[test]
+    pub fn test01_el_ordenamiento_en_disco_da_lo_mismo_que_en_memoria() {
+        for asc in [true, false] {
+            let mut esperadas = filas();
+            ordenar(&mut esperadas, 1, asc);
+
+            let (ordenadas, tramos) = ordenar_en_disco(asc);
+
+            assert!(tramos > 1);
+            assert_eq!(ordenadas, esperadas);
+        }
+    }
+
+    #[test]
+    pub fn test02_sin_superar_el_presupuesto_no_se_vuelcan_tramos() {
+        let mut ordenamiento = OrdenamientoExterno::new(
+            "./test/ordenamiento/memoria.csv",
+            0,
+            true,
+            MEMORIA_POR_DEFECTO,
+        );
+        for valor in ["b", "c", "a"] {
+            ordenamiento.agregar(vec![valor.to_string()]).unwrap();
+        }
+
+        assert_eq!(ordenamiento.tramos(), 0);
+        let ordenadas: Vec<Vec<String>> = ordenamiento
+            .terminar()
+            .unwrap()
+            .map(|f| f.unwrap())
+            .collect();
+        assert_eq!(ordenadas, vec![vec!["a"], vec!["b"], vec!["c"]]);
+    }
+}
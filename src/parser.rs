@@ -1,9 +1,18 @@
+use crate::almacenamiento;
+use crate::alter_table::{AlterTable, Alteracion};
 use crate::condicion::Condicion;
 use crate::condicion_simple::CondicionSimple;
+use crate::convert_table::{ConvertTable, FormatoTabla};
+use crate::copy_to::CopyTo;
+use crate::create_index::CreateIndex;
 use crate::delete::Delete;
+use crate::esquema::Columna;
+use crate::indice::Indice;
 use crate::insert::Insert;
 use crate::my_error::MyError;
 use crate::operacion::Operacion;
+use crate::operadores::Agregado;
+use crate::read_csv::ReadCsv;
 use crate::select::Select;
 use crate::update::Update;
 
@@ -57,6 +66,38 @@ impl Parser {
         self.parsear_orden(archivo, tokens)
     }
 
+    ///# Parser.parsear_condicion()
+    ///Esta función crea la condición que representa a una expresión escrita con la misma sintaxis
+    ///que se usa en el WHERE, por ejemplo *cantidad > 0 AND cantidad < 10*.
+    ///
+    ///**Parámetros**
+    ///- 'expresion': Es el texto de la condición. Si está entre paréntesis se quitan.
+    ///
+    ///**Return**
+    ///Devuelve un *Result<Condicion, MyError>* en caso de que la expresión no sea válida se
+    ///  devuelve un error de tipo *MyError*.
+    pub fn parsear_condicion(&mut self, expresion: &str) -> Result<Condicion, MyError> {
+        let mut texto = expresion.trim();
+        if texto.starts_with('(') && texto.ends_with(')') {
+            texto = &texto[1..texto.len() - 1];
+        }
+
+        let tokens: Vec<String> = texto
+            .split(' ')
+            .filter(|t| !t.is_empty())
+            .map(|s| s.to_string())
+            .collect();
+
+        if tokens.is_empty() {
+            return Err(MyError::InvalidSyntax(
+                "Error en la escritura de la condición".to_string(),
+            ));
+        }
+
+        self.index = 0;
+        self.armar_condicion(&tokens, false)
+    }
+
     fn parsear_orden(
         &mut self,
         archivo: String,
@@ -67,7 +108,12 @@ impl Parser {
            _ if *"DELETE" == tokens[self.index] => self.parsear_delete(archivo, tokens),
            _ if *"UPDATE" == tokens[self.index]  => self.parsear_update(archivo, tokens),
            _ if *"SELECT" == tokens[self.index]  => self.parsear_select(archivo, tokens),
-            _ => Err(MyError::InvalidSyntax("Instruccion inválida. Las instruccines válidas son: INSERT, DELETE, UPDATE, SELECT".to_string())),
+           _ if *"ALTER" == tokens[self.index]  => self.parsear_alter(archivo, tokens),
+           _ if *"CREATE" == tokens[self.index]  => self.parsear_create_index(archivo, tokens),
+           _ if *"CONVERT" == tokens[self.index]  => self.parsear_convert(archivo, tokens),
+           _ if *"COPY" == tokens[self.index]  => self.parsear_copy(archivo, tokens),
+           _ if *"EXPLAIN" == tokens[self.index]  => self.parsear_explain(archivo, tokens),
+            _ => Err(MyError::InvalidSyntax("Instruccion inválida. Las instruccines válidas son: INSERT, DELETE, UPDATE, SELECT, ALTER TABLE, CREATE INDEX, CONVERT TABLE, COPY, EXPLAIN [ANALYZE] SELECT".to_string())),
         }
     }
     fn parsear_select(
@@ -93,45 +139,122 @@ impl Parser {
 
         self.avanzar();
 
-        let nombre_archivo = "/".to_string() + &String::from(&tokens[self.index]) + ".csv";
-        direccion.push_str(&nombre_archivo);
+        let mut lectura: Option<ReadCsv> = None;
+        if tokens[self.index].to_lowercase().starts_with("read_csv(") {
+            let (archivo, read_csv) = self.armar_read_csv(&tokens)?;
+            direccion = archivo;
+            lectura = Some(read_csv);
+        } else {
+            direccion = almacenamiento::resolver(&direccion, &tokens[self.index])?;
+        }
 
         self.avanzar();
         let mut order: String = String::new();
         let mut asc: bool = true;
 
-        if self.index < tokens.len() && tokens[self.index] == *"WHERE" {
+        let condicion: Condicion = if self.index < tokens.len() && tokens[self.index] == *"WHERE" {
             self.avanzar();
-            let condicion: Condicion = match self.armar_condicion(&tokens, false) {
-                Ok(c) => c,
-                Err(e) => return Err(e),
-            };
+            self.armar_condicion(&tokens, false)?
+        } else {
+            Condicion::SiempreTrue
+        };
 
-            if self.index < tokens.len() {
-                match self.armar_orden(&tokens, &mut order, &mut asc) {
-                    Ok(c) => c,
-                    Err(e) => return Err(e),
-                };
+        let grupo = if self.index < tokens.len() && tokens[self.index] == *"GROUP" {
+            Some(self.armar_grupo(&tokens)?)
+        } else {
+            None
+        };
+
+        if self.index < tokens.len() {
+            self.armar_orden(&tokens, &mut order, &mut asc)?;
+        }
+
+        let agregados: Vec<Agregado> = columnas.iter().filter_map(|c| Agregado::leer(c)).collect();
+        let mut select = match agregados.is_empty() && grupo.is_none() {
+            true => Select::new(direccion, columnas, condicion, order, asc),
+            false => {
+                let columnas = Self::corroborar_agrupacion(columnas, &grupo)?;
+                if let Some(agregado) = Agregado::leer(&order) {
+                    order = agregado.nombre();
+                }
+                Select::new(direccion, columnas, condicion, order, asc)
+                    .con_agrupacion(grupo, agregados)
             }
+        };
+        select = match lectura {
+            Some(lectura) => select.con_lectura(lectura),
+            None => select,
+        };
+        Ok(Operacion::Select(select))
+    }
 
-            Ok(Operacion::Select(Select::new(
-                direccion, columnas, condicion, order, asc,
-            )))
-        } else {
-            let condicion: Condicion = Condicion::SiempreTrue;
+    fn armar_grupo(&mut self, tokens: &[String]) -> Result<String, MyError> {
+        self.avanzar();
 
-            if self.index < tokens.len() {
-                match self.armar_orden(&tokens, &mut order, &mut asc) {
-                    Ok(c) => c,
-                    Err(e) => return Err(e),
-                };
+        if self.index == tokens.len() || tokens[self.index] != *"BY" {
+            return Err(MyError::InvalidSyntax(
+                "Error de sintaxis al definir el GROUP BY".to_string(),
+            ));
+        }
+
+        self.avanzar();
+
+        if self.index == tokens.len() || tokens[self.index] == *"ORDER" {
+            return Err(MyError::InvalidSyntax(
+                "Error de sintaxis al definir el GROUP BY".to_string(),
+            ));
+        }
+
+        let grupo = String::from(&tokens[self.index]);
+        self.avanzar();
+        Ok(grupo)
+    }
+
+    fn corroborar_agrupacion(
+        columnas: Vec<String>,
+        grupo: &Option<String>,
+    ) -> Result<Vec<String>, MyError> {
+        let mut resultado = Vec::new();
+        for columna in columnas {
+            match Agregado::leer(&columna) {
+                Some(agregado) => resultado.push(agregado.nombre()),
+                None if Some(&columna) == grupo.as_ref() => resultado.push(columna),
+                None => {
+                    return Err(MyError::InvalidSyntax(format!(
+                    "La columna {} debe estar en el GROUP BY o dentro de una función de agregación",
+                    columna
+                )))
+                }
             }
+        }
+        Ok(resultado)
+    }
+    fn parsear_explain(
+        &mut self,
+        archivo: String,
+        tokens: Vec<String>,
+    ) -> Result<Operacion, MyError> {
+        self.avanzar();
 
-            Ok(Operacion::Select(Select::new(
-                direccion, columnas, condicion, order, asc,
-            )))
+        let analizar = self.index < tokens.len() && tokens[self.index] == *"ANALYZE";
+        if analizar {
+            self.avanzar();
+        }
+
+        if self.index == tokens.len() || tokens[self.index] != *"SELECT" {
+            return Err(MyError::InvalidSyntax(
+                "Error en la sintaxis de la instrucción (EXPLAIN), se espera EXPLAIN [ANALYZE] SELECT ..."
+                    .to_string(),
+            ));
+        }
+
+        match (self.parsear_select(archivo, tokens)?, analizar) {
+            (Operacion::Select(select), false) => Ok(Operacion::Explain(select)),
+            (Operacion::Select(select), true) => Ok(Operacion::ExplainAnalyze(select)),
+            _ => Err(MyError::Error("Error inesperado".to_string())),
         }
     }
+
     fn parsear_update(
         &mut self,
         archivo: String,
@@ -141,8 +264,7 @@ impl Parser {
 
         let mut direccion = archivo;
 
-        let nombre_archivo = "/".to_string() + &String::from(&tokens[self.index]) + ".csv";
-        direccion.push_str(&nombre_archivo);
+        direccion = almacenamiento::resolver(&direccion, &tokens[self.index])?;
         self.avanzar();
 
         if tokens[self.index] != "SET" {
@@ -152,10 +274,7 @@ impl Parser {
         }
 
         self.avanzar();
-        let valores: Vec<Vec<String>> = match self.armar_valores_update(&tokens) {
-            Ok(v) => v,
-            Err(e) => return Err(e),
-        };
+        let valores: Vec<Vec<String>> = self.armar_valores_update(&tokens)?;
 
         if self.index == tokens.len() || tokens[self.index] != *"WHERE" {
             return Err(MyError::InvalidSyntax(
@@ -193,8 +312,7 @@ impl Parser {
         }
         self.avanzar();
 
-        let nombre_archivo = "/".to_string() + &String::from(&tokens[self.index]) + ".csv";
-        direccion.push_str(&nombre_archivo);
+        direccion = almacenamiento::resolver(&direccion, &tokens[self.index])?;
         self.avanzar();
 
         if self.index < tokens.len() && tokens[self.index] == *"WHERE" {
@@ -228,31 +346,31 @@ impl Parser {
 
         let mut columnas: Vec<String> = Vec::new();
         let mut valores: Vec<Vec<String>> = Vec::new();
-        let mut direccion = archivo;
+        let mut direccion = archivo.to_string();
 
         if tokens[self.index] == *"INTO" {
             self.avanzar();
 
-            let nombre_archivo = "/".to_string() + &String::from(&tokens[self.index]) + ".csv";
-            direccion.push_str(&nombre_archivo);
+            direccion = almacenamiento::resolver(&direccion, &tokens[self.index])?;
             self.avanzar();
 
-            match self.leer_columnas(&mut columnas, &tokens) {
-                Ok(l) => l,
-                Err(e) => return Err(e),
-            };
+            self.leer_columnas(&mut columnas, &tokens)?;
             if self.index < tokens.len() && tokens[self.index] == *"VALUES" {
                 self.avanzar();
 
                 while self.index < tokens.len() {
                     let mut aux = Vec::new();
-                    match self.leer_columnas(&mut aux, &tokens) {
-                        Ok(l) => l,
-                        Err(e) => return Err(e),
-                    };
+                    self.leer_columnas(&mut aux, &tokens)?;
 
                     valores.push(aux);
                 }
+            } else if self.index < tokens.len() && tokens[self.index] == *"SELECT" {
+                return match self.parsear_select(archivo, tokens)? {
+                    Operacion::Select(select) => Ok(Operacion::Insert(Insert::desde_select(
+                        direccion, columnas, select,
+                    ))),
+                    _ => Err(MyError::Error("Error inesperado".to_string())),
+                };
             } else {
                 return Err(MyError::InvalidSyntax(
                     "Error en la sintaxis de la instrucción (INSERT)".to_string(),
@@ -267,6 +385,223 @@ impl Parser {
         Ok(Operacion::Insert(Insert::new(direccion, columnas, valores)))
     }
 
+    fn parsear_alter(
+        &mut self,
+        archivo: String,
+        tokens: Vec<String>,
+    ) -> Result<Operacion, MyError> {
+        let error = || {
+            MyError::InvalidSyntax(
+                "Error en la sintaxis de la instrucción (ALTER TABLE)".to_string(),
+            )
+        };
+        self.avanzar();
+
+        if self.index + 2 >= tokens.len() || tokens[self.index] != *"TABLE" {
+            return Err(error());
+        }
+        self.avanzar();
+
+        let mut direccion = archivo;
+        direccion = almacenamiento::resolver(&direccion, &tokens[self.index])?;
+        self.avanzar();
+
+        let accion = String::from(&tokens[self.index]);
+        self.avanzar();
+
+        let alteracion = match accion.as_str() {
+            "ADD" if self.index < tokens.len() && tokens[self.index] == *"COLUMN" => {
+                self.avanzar();
+                let columna = Columna::desde_definicion(&tokens[self.index..])?;
+                self.index = tokens.len();
+                Alteracion::AgregarColumna(columna)
+            }
+            "DROP" if self.index + 1 < tokens.len() && tokens[self.index] == *"COLUMN" => {
+                self.avanzar();
+                let columna = String::from(&tokens[self.index]);
+                self.avanzar();
+                Alteracion::EliminarColumna(columna)
+            }
+            "RENAME" if self.index + 3 < tokens.len() && tokens[self.index] == *"COLUMN" => {
+                self.avanzar();
+                let anterior = String::from(&tokens[self.index]);
+                self.avanzar();
+
+                if tokens[self.index] != *"TO" {
+                    return Err(error());
+                }
+                self.avanzar();
+
+                let nuevo = String::from(&tokens[self.index]);
+                self.avanzar();
+                Alteracion::RenombrarColumna(anterior, nuevo)
+            }
+            "RENAME" if self.index + 1 < tokens.len() && tokens[self.index] == *"TO" => {
+                self.avanzar();
+                let nuevo = String::from(&tokens[self.index]);
+                self.avanzar();
+                Alteracion::RenombrarTabla(nuevo)
+            }
+            _ => return Err(error()),
+        };
+
+        if self.index != tokens.len() {
+            return Err(error());
+        }
+
+        Ok(Operacion::AlterTable(AlterTable::new(
+            direccion, alteracion,
+        )))
+    }
+
+    fn parsear_create_index(
+        &mut self,
+        archivo: String,
+        tokens: Vec<String>,
+    ) -> Result<Operacion, MyError> {
+        let error = || {
+            MyError::InvalidSyntax(
+                "Error en la sintaxis de la instrucción (CREATE INDEX)".to_string(),
+            )
+        };
+        self.avanzar();
+
+        let unico = self.index < tokens.len() && tokens[self.index] == *"UNIQUE";
+        if unico {
+            self.avanzar();
+        }
+
+        if self.index + 3 >= tokens.len()
+            || tokens[self.index] != *"INDEX"
+            || tokens[self.index + 2] != *"ON"
+        {
+            return Err(error());
+        }
+        self.avanzar();
+
+        let nombre = String::from(&tokens[self.index]);
+        self.index += 2;
+
+        let destino = tokens[self.index..]
+            .join(" ")
+            .replace(" (", "(")
+            .replace("( ", "(")
+            .replace(" )", ")");
+        self.index = tokens.len();
+
+        let (tabla, columna) = match destino.strip_suffix(')').and_then(|d| d.split_once('(')) {
+            Some((t, c)) if !t.is_empty() && !c.is_empty() && !c.contains([' ', '(']) => (t, c),
+            _ => return Err(error()),
+        };
+
+        let direccion = almacenamiento::resolver(&archivo, tabla)?;
+
+        Ok(Operacion::CreateIndex(CreateIndex::new(
+            direccion,
+            Indice::new(nombre, columna.to_string(), unico),
+        )))
+    }
+
+    fn parsear_convert(
+        &mut self,
+        archivo: String,
+        tokens: Vec<String>,
+    ) -> Result<Operacion, MyError> {
+        let error = || {
+            MyError::InvalidSyntax(
+                "Error en la sintaxis de la instrucción (CONVERT TABLE)".to_string(),
+            )
+        };
+        self.avanzar();
+
+        if self.index + 4 != tokens.len()
+            || tokens[self.index] != *"TABLE"
+            || tokens[self.index + 2] != *"TO"
+        {
+            return Err(error());
+        }
+        self.avanzar();
+
+        let direccion = almacenamiento::resolver(&archivo, &tokens[self.index])?;
+        self.index += 2;
+
+        let formato = match FormatoTabla::desde_nombre(&tokens[self.index]) {
+            Some(f) => f,
+            None => return Err(error()),
+        };
+        self.avanzar();
+
+        Ok(Operacion::ConvertTable(ConvertTable::new(
+            direccion, formato,
+        )))
+    }
+
+    fn parsear_copy(&mut self, archivo: String, tokens: Vec<String>) -> Result<Operacion, MyError> {
+        self.avanzar();
+
+        if self.index + 3 != tokens.len() || tokens[self.index + 1] != *"TO" {
+            return Err(MyError::InvalidSyntax(
+                "Error en la sintaxis de la instrucción (COPY)".to_string(),
+            ));
+        }
+        let direccion = almacenamiento::resolver(&archivo, &tokens[self.index])?;
+        let destino = match sin_comillas(&tokens[self.index + 2]) {
+            Some(d) if !d.is_empty() => d.to_string(),
+            _ => {
+                return Err(MyError::InvalidSyntax(
+                    "El archivo de destino de COPY debe estar entre comillas simples".to_string(),
+                ))
+            }
+        };
+        self.index += 3;
+
+        Ok(Operacion::CopyTo(CopyTo::new(direccion, destino)))
+    }
+
+    ///Arma la lectura de *read_csv('archivo', delim => ';', header => true)*, que puede ocupar
+    ///varias palabras, y deja el índice en la última. Como las comas de la instrucción se quitan
+    ///al separarla en palabras, el delimitador *','* llega como *''*.
+    fn armar_read_csv(&mut self, tokens: &[String]) -> Result<(String, ReadCsv), MyError> {
+        let error = || MyError::InvalidSyntax("Error en la sintaxis de read_csv".to_string());
+
+        let mut argumentos: Vec<String> = Vec::new();
+        let mut palabra = tokens[self.index]["read_csv(".len()..].to_string();
+        while !palabra.ends_with(')') {
+            argumentos.push(palabra);
+            self.avanzar();
+            if self.index == tokens.len() {
+                return Err(error());
+            }
+            palabra = tokens[self.index].to_string();
+        }
+        argumentos.push(palabra.trim_end_matches(')').to_string());
+        argumentos.retain(|a| !a.is_empty());
+
+        let archivo = match argumentos.first().and_then(|a| sin_comillas(a)) {
+            Some(a) if !a.is_empty() => a.to_string(),
+            _ => return Err(error()),
+        };
+        let mut lectura = ReadCsv::new(&archivo);
+        for opcion in argumentos[1..].chunks(3) {
+            if opcion.len() != 3 || opcion[1] != "=>" {
+                return Err(error());
+            }
+            lectura = match (opcion[0].to_lowercase().as_str(), sin_comillas(&opcion[2])) {
+                ("delim", Some(valor)) => match leer_delimitador(valor) {
+                    Some(delimitador) => lectura.con_delimitador(delimitador),
+                    None => return Err(error()),
+                },
+                ("header", None) => match opcion[2].to_lowercase().as_str() {
+                    "true" => lectura.con_encabezado(true),
+                    "false" => lectura.con_encabezado(false),
+                    _ => return Err(error()),
+                },
+                _ => return Err(error()),
+            };
+        }
+        Ok((archivo, lectura))
+    }
+
     fn avanzar(&mut self) {
         self.index += 1;
     }
@@ -344,7 +679,11 @@ impl Parser {
         let mut r: Condicion = Condicion::SiempreTrue;
         let mut es_primer_condicion = true;
 
-        while self.index < tokens.len() && !(prioridad && tokens[self.index - 1].contains(")")) {
+        while self.index < tokens.len()
+            && !(prioridad && tokens[self.index - 1].contains(")"))
+            && tokens[self.index] != *"ORDER"
+            && tokens[self.index] != *"GROUP"
+        {
             if condiciones.contains(&tokens[self.index]) {
                 let simb = String::from(&tokens[self.index]);
                 self.avanzar();
@@ -356,10 +695,7 @@ impl Parser {
                 }
 
                 let l = match tokens[self.index].contains("(") {
-                    true => match self.armar_condicion(tokens, true) {
-                        Ok(c) => c,
-                        Err(e) => return Err(e),
-                    },
+                    true => self.armar_condicion(tokens, true)?,
                     false => {
                         if tokens[self.index] == *"NOT" {
                             self.avanzar();
@@ -467,6 +803,26 @@ impl Parser {
     }
 }
 
+///Devuelve el texto de un valor escrito entre comillas simples, o *None* si no lo está.
+fn sin_comillas(valor: &str) -> Option<&str> {
+    valor.strip_prefix('\'').and_then(|v| v.strip_suffix('\''))
+}
+
+///Lee el delimitador de *read_csv()*: un único carácter, *TAB* o vacío para la coma.
+fn leer_delimitador(valor: &str) -> Option<char> {
+    let mut caracteres = valor.chars();
+    match (
+        valor.to_uppercase().as_str(),
+        caracteres.next(),
+        caracteres.next(),
+    ) {
+        ("", _, _) => Some(','),
+        ("TAB", _, _) => Some('\t'),
+        (_, Some(c), None) if !c.is_alphanumeric() => Some(c),
+        _ => None,
+    }
+}
+
 impl Default for Parser {
     fn default() -> Self {
         Self::new()
@@ -653,3 +1009,249 @@ pub fn test08_se_parsea_un_select_correctamente() {
 
     assert!(resultado.is_ok());
 }
+
+#[test]
+pub fn test09_se_parsea_un_alter_table_correctamente() {
+    let mut parser = Parser::new();
+
+    let resultado: Result<Operacion, MyError> = parser.crear_operacion(
+        "./test".to_string(),
+        "ALTER TABLE select RENAME COLUMN cantidad TO unidades".to_string(),
+    );
+
+    let operacion_esperada = Operacion::AlterTable(AlterTable::new(
+        "./test/select.csv".to_string(),
+        Alteracion::RenombrarColumna("cantidad".to_string(), "unidades".to_string()),
+    ));
+
+    assert!(matches!(resultado, Ok(o) if o == operacion_esperada));
+}
+
+#[test]
+pub fn test10_se_quiere_parsear_un_alter_table_y_hay_errores_en_la_sintaxis() {
+    let mut parser = Parser::new();
+
+    let resultado: Result<Operacion, MyError> = parser.crear_operacion(
+        "./test".to_string(),
+        "ALTER TABLE select ADD cantidad INT".to_string(),
+    );
+
+    assert!(matches!(resultado, Err(MyError::InvalidSyntax(_))));
+}
+
+#[test]
+pub fn test11_se_parsea_una_condicion_entre_parentesis() {
+    let mut parser = Parser::new();
+
+    let resultado = parser.parsear_condicion("(cantidad > 0 AND producto != Laptop)");
+
+    let condicion_esperada = Condicion::And(
+        Box::new(Condicion::CondicionSimple(CondicionSimple::new(
+            "cantidad".to_string(),
+            ">".to_string(),
+            "0".to_string(),
+        ))),
+        Box::new(Condicion::CondicionSimple(CondicionSimple::new(
+            "producto".to_string(),
+            "!=".to_string(),
+            "Laptop".to_string(),
+        ))),
+    );
+
+    assert!(matches!(resultado, Ok(c) if c == condicion_esperada));
+}
+
+#[test]
+pub fn test12_se_parsea_un_create_index_correctamente() {
+    let mut parser = Parser::new();
+
+    let resultado = parser.crear_operacion(
+        "./test".to_string(),
+        "CREATE UNIQUE INDEX idx_email ON clientes (email)".to_string(),
+    );
+    let con_dos_columnas = Parser::new().crear_operacion(
+        "./test".to_string(),
+        "CREATE INDEX idx_nombre ON clientes(nombre, apellido)".to_string(),
+    );
+
+    let operacion_esperada = Operacion::CreateIndex(CreateIndex::new(
+        "./test/clientes.csv".to_string(),
+        Indice::new("idx_email".to_string(), "email".to_string(), true),
+    ));
+
+    assert!(matches!(resultado, Ok(o) if o == operacion_esperada));
+    assert!(matches!(con_dos_columnas, Err(MyError::InvalidSyntax(_))));
+}
+
+#[test]
+pub fn test13_se_parsea_un_explain_select_con_y_sin_analyze() {
+    let mut parser = Parser::new();
+
+    let resultado = parser.crear_operacion(
+        "./test".to_string(),
+        "EXPLAIN SELECT id FROM select WHERE id_cliente = 1".to_string(),
+    );
+    let sin_select = Parser::new().crear_operacion(
+        "./test".to_string(),
+        "EXPLAIN DELETE FROM select WHERE id_cliente = 1".to_string(),
+    );
+
+    let analizado = Parser::new().crear_operacion(
+        "./test".to_string(),
+        "EXPLAIN ANALYZE SELECT * FROM select ORDER BY id".to_string(),
+    );
+
+    assert!(matches!(resultado, Ok(Operacion::Explain(_))));
+    assert!(matches!(analizado, Ok(Operacion::ExplainAnalyze(_))));
+    assert!(matches!(sin_select, Err(MyError::InvalidSyntax(_))));
+}
+
+#[test]
+pub fn test14_se_parsea_un_insert_con_select() {
+    let resultado = Parser::new().crear_operacion(
+        "./test".to_string(),
+        "INSERT INTO clientes (id, nombre) SELECT id_cliente, producto FROM select WHERE id_cliente = 1"
+            .to_string(),
+    );
+
+    let select = Select::new(
+        "./test/select.csv".to_string(),
+        vec!["id_cliente".to_string(), "producto".to_string()],
+        Condicion::CondicionSimple(CondicionSimple::new(
+            "id_cliente".to_string(),
+            "=".to_string(),
+            "1".to_string(),
+        )),
+        "".to_string(),
+        true,
+    );
+    let operacion_esperada = Operacion::Insert(Insert::desde_select(
+        "./test/clientes.csv".to_string(),
+        vec!["id".to_string(), "nombre".to_string()],
+        select,
+    ));
+
+    assert!(matches!(resultado, Ok(o) if o == operacion_esperada));
+}
+
+#[test]
+pub fn test15_se_parsea_un_convert_table() {
+    let resultado = Parser::new().crear_operacion(
+        "./test".to_string(),
+        "CONVERT TABLE clientes TO BINARY".to_string(),
+    );
+    let formato_invalido = Parser::new().crear_operacion(
+        "./test".to_string(),
+        "CONVERT TABLE clientes TO XML".to_string(),
+    );
+
+    let operacion_esperada = Operacion::ConvertTable(ConvertTable::new(
+        "./test/clientes.csv".to_string(),
+        FormatoTabla::Binario,
+    ));
+
+    assert!(matches!(resultado, Ok(o) if o == operacion_esperada));
+    assert!(matches!(formato_invalido, Err(MyError::InvalidSyntax(_))));
+}
+
+#[test]
+pub fn test16_se_parsea_un_select_desde_read_csv() {
+    let resultado = Parser::new().crear_operacion(
+        "./test".to_string(),
+        "SELECT id FROM read_csv('/datos/ventas.txt', delim => ',', header => false) WHERE id = 1"
+            .to_string(),
+    );
+    let opcion_invalida = Parser::new().crear_operacion(
+        "./test".to_string(),
+        "SELECT * FROM read_csv('/datos/ventas.txt', comillas => true)".to_string(),
+    );
+
+    let operacion_esperada = Operacion::Select(
+        Select::new(
+            "/datos/ventas.txt".to_string(),
+            vec!["id".to_string()],
+            Condicion::CondicionSimple(CondicionSimple::new(
+                "id".to_string(),
+                "=".to_string(),
+                "1".to_string(),
+            )),
+            "".to_string(),
+            true,
+        )
+        .con_lectura(
+            ReadCsv::new("/datos/ventas.txt")
+                .con_delimitador(',')
+                .con_encabezado(false),
+        ),
+    );
+
+    assert!(matches!(resultado, Ok(o) if o == operacion_esperada));
+    assert!(matches!(opcion_invalida, Err(MyError::InvalidSyntax(_))));
+}
+
+#[test]
+pub fn test17_se_parsea_un_copy_to() {
+    let resultado = Parser::new().crear_operacion(
+        "./test".to_string(),
+        "COPY clientes TO '/tmp/clientes.csv'".to_string(),
+    );
+    let sin_comillas = Parser::new().crear_operacion(
+        "./test".to_string(),
+        "COPY clientes TO /tmp/clientes.csv".to_string(),
+    );
+
+    let operacion_esperada = Operacion::CopyTo(CopyTo::new(
+        "./test/clientes.csv".to_string(),
+        "/tmp/clientes.csv".to_string(),
+    ));
+
+    assert!(matches!(resultado, Ok(o) if o == operacion_esperada));
+    assert!(matches!(sin_comillas, Err(MyError::InvalidSyntax(_))));
+}
+
+#[test]
+pub fn test18_se_parsea_un_select_con_agregados_y_group_by() {
+    let resultado = Parser::new().crear_operacion(
+        "./test".to_string(),
+        "SELECT producto, count(*), MAX(cantidad) FROM select WHERE cantidad > 0 GROUP BY producto ORDER BY COUNT(*) DESC"
+            .to_string(),
+    );
+    let columna_sin_agrupar = Parser::new().crear_operacion(
+        "./test".to_string(),
+        "SELECT producto, cantidad, COUNT(*) FROM select GROUP BY producto".to_string(),
+    );
+    let sin_columna = Parser::new().crear_operacion(
+        "./test".to_string(),
+        "SELECT COUNT(*) FROM select GROUP BY".to_string(),
+    );
+
+    let condicion = Condicion::CondicionSimple(CondicionSimple::new(
+        "cantidad".to_string(),
+        ">".to_string(),
+        "0".to_string(),
+    ));
+    let operacion_esperada = Operacion::Select(
+        Select::new(
+            "./test/select.csv".to_string(),
+            vec![
+                "producto".to_string(),
+                "COUNT(*)".to_string(),
+                "MAX(cantidad)".to_string(),
+            ],
+            condicion,
+            "COUNT(*)".to_string(),
+            false,
+        )
+        .con_agrupacion(
+            Some("producto".to_string()),
+            vec![Agregado::Contar, Agregado::Maximo("cantidad".to_string())],
+        ),
+    );
+
+    assert!(matches!(resultado, Ok(o) if o == operacion_esperada));
+    assert!(matches!(
+        columna_sin_agrupar,
+        Err(MyError::InvalidSyntax(_))
+    ));
+    assert!(matches!(sin_columna, Err(MyError::InvalidSyntax(_))));
+}
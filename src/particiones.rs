@@ -0,0 +1,452 @@
+use crate::almacenamiento::{self, Almacenamiento, Reescritura};
+use crate::bloqueo::Bloqueo;
+use crate::condicion::Condicion;
+use crate::esquema::Esquema;
+use crate::memoria;
+use crate::my_error::MyError;
+use crate::operadores::{Fila, Operador};
+use crate::planificador;
+use std::fs;
+use std::path::Path;
+
+///Son los caracteres que convierten la dirección de una tabla en un patrón de archivos.
+const COMODINES: [char; 2] = ['*', '?'];
+
+///# TablaParticionada
+///Es el almacenamiento de una tabla guardada en varios archivos con las mismas columnas: todos
+///los archivos de tabla que hay dentro de un directorio (a cualquier profundidad) o los que
+///coinciden con un patrón como *ventas/2026-10-\*.csv*, donde *\** reemplaza a cualquier texto y
+///*?* a un carácter. Los archivos se leen uno detrás de otro, ordenados por dirección, y cada uno
+///con el almacenamiento que le corresponde a su extensión.
+///
+///Los directorios con nombre *columna=valor* (particiones al estilo de Hive) agregan esa columna a
+///la tabla, con ese valor en todas las filas de los archivos que contienen. Al leer con un WHERE
+///que exige una comparación sobre una columna de partición se saltean los archivos cuyas
+///particiones no la cumplen.
+///
+///Las columnas de la tabla son las del primer archivo seguidas de las de partición; si otro
+///archivo no tiene alguna, sus filas la tienen vacía. Las tablas de varios archivos solo se
+///pueden leer, por lo que no se bloquean.
+#[derive(Debug, PartialEq)]
+pub struct TablaParticionada {
+    archivo: String,
+}
+
+///Es uno de los archivos de la tabla, con los valores de las particiones que lo contienen.
+#[derive(Debug, PartialEq)]
+struct Parte {
+    archivo: String,
+    particiones: Vec<(String, String)>,
+}
+
+impl TablaParticionada {
+    ///# TablaParticionada::new()
+    ///Esta función crea una nueva instancia de TablaParticionada.
+    ///
+    ///**Parámetros**
+    ///- 'archivo': Es la dirección del directorio de la tabla o el patrón de sus archivos.
+    ///
+    ///**Return**
+    ///Devuelve un *struct* de tipo *TablaParticionada*.
+    pub fn new(archivo: &str) -> Self {
+        TablaParticionada {
+            archivo: archivo.to_string(),
+        }
+    }
+
+    ///Devuelve el directorio del que cuelgan todos los archivos de la tabla: la dirección
+    ///completa si es un directorio o la parte del patrón anterior al primer comodín.
+    fn raiz(&self) -> String {
+        let segmentos: Vec<&str> = self
+            .archivo
+            .split('/')
+            .take_while(|s| !s.contains(COMODINES))
+            .collect();
+        match segmentos.is_empty() {
+            true => ".".to_string(),
+            false => segmentos.join("/"),
+        }
+    }
+
+    ///Busca los archivos de la tabla y devuelve cada uno con sus particiones, ordenados por
+    ///dirección.
+    fn partes(&self) -> Result<Vec<Parte>, MyError> {
+        let raiz = self.raiz();
+        let patron: Option<Vec<&str>> = match es_patron(&self.archivo) {
+            true => Some(
+                self.archivo
+                    .split('/')
+                    .skip_while(|s| !s.contains(COMODINES))
+                    .collect(),
+            ),
+            false => None,
+        };
+
+        let mut partes = Vec::new();
+        buscar_partes(
+            Path::new(&raiz),
+            patron.as_deref(),
+            &mut Vec::new(),
+            &mut partes,
+        )?;
+        if partes.is_empty() {
+            return Err(MyError::InvalidTable(format!(
+                "No hay archivos de tabla en {}",
+                self.archivo
+            )));
+        }
+        Ok(partes)
+    }
+
+    ///Devuelve las columnas de la tabla (primero las del primer archivo y después las de
+    ///partición), cuántas son del archivo y los archivos que forman la tabla.
+    fn columnas_y_partes(&self) -> Result<(Vec<String>, usize, Vec<Parte>), MyError> {
+        let partes = self.partes()?;
+        let mut columnas = almacenamiento::abrir(&partes[0].archivo)
+            .recorrer(None)?
+            .columnas()
+            .to_vec();
+        let de_archivo = columnas.len();
+        for (clave, _) in &partes[0].particiones {
+            if !columnas.contains(clave) {
+                columnas.push(clave.to_string());
+            }
+        }
+        Ok((columnas, de_archivo, partes))
+    }
+
+    fn recorrido(&self, condicion: Option<&Condicion>) -> Result<Box<dyn Operador>, MyError> {
+        let (columnas, de_archivo, mut partes) = self.columnas_y_partes()?;
+
+        if let Some(condicion) = condicion {
+            let comparaciones: Vec<_> = planificador::conjunciones(condicion)
+                .into_iter()
+                .filter(|c| columnas[de_archivo..].iter().any(|p| p == c.columna()))
+                .collect();
+            partes.retain(|parte| {
+                comparaciones.iter().all(|comparacion| {
+                    let columna = comparacion.columna().to_string();
+                    let valor = valor_de_particion(parte, &columna);
+                    !matches!(comparacion.verificar(&[columna], &[valor]), Ok(false))
+                })
+            });
+        }
+
+        Ok(Box::new(RecorridoParticionado {
+            columnas,
+            de_archivo,
+            partes: partes.into_iter(),
+            actual: None,
+        }))
+    }
+}
+
+impl Almacenamiento for TablaParticionada {
+    fn archivo(&self) -> &str {
+        &self.archivo
+    }
+
+    fn bloquear(&self, _exclusivo: bool) -> Result<Option<Bloqueo>, MyError> {
+        Ok(None)
+    }
+
+    fn esquema(&self) -> Result<Esquema, MyError> {
+        let raiz = self.raiz();
+        match Path::new(&Esquema::ruta(&raiz)).exists() {
+            true => Esquema::cargar(&raiz),
+            false => Ok(Esquema::sin_tipos(&self.columnas_y_partes()?.0)),
+        }
+    }
+
+    fn recorrer(&self, _posiciones: Option<Vec<u64>>) -> Result<Box<dyn Operador>, MyError> {
+        self.recorrido(None)
+    }
+
+    fn recorrer_donde(
+        &self,
+        _posiciones: Option<Vec<u64>>,
+        condicion: &Condicion,
+    ) -> Result<Box<dyn Operador>, MyError> {
+        self.recorrido(Some(condicion))
+    }
+
+    fn anexar(&self, _filas: &[Fila]) -> Result<(), MyError> {
+        Err(error_de_solo_lectura())
+    }
+
+    fn reescribir(&self, _columnas: &[String]) -> Result<Box<dyn Reescritura>, MyError> {
+        Err(error_de_solo_lectura())
+    }
+
+    fn largo(&self) -> Result<u64, MyError> {
+        Err(error_de_solo_lectura())
+    }
+
+    fn tablas_con_esquema(&self) -> Result<Vec<String>, MyError> {
+        almacenamiento::tablas_del_directorio(&self.raiz())
+    }
+
+    fn ubicacion_temporal(&self) -> String {
+        self.raiz()
+    }
+}
+
+///# particiones::es_particionada()
+///Esta función indica si la dirección de una tabla corresponde a una tabla de varios archivos:
+///un directorio o un patrón con comodines.
+///
+///**Parámetros**
+///- 'archivo': Es la dirección de la tabla.
+pub fn es_particionada(archivo: &str) -> bool {
+    !archivo.starts_with(memoria::PREFIJO) && (es_patron(archivo) || Path::new(archivo).is_dir())
+}
+
+///# particiones::es_patron()
+///Esta función indica si el nombre de una tabla es un patrón de archivos, es decir si tiene
+///algún comodín.
+///
+///**Parámetros**
+///- 'tabla': Es el nombre o la dirección de la tabla.
+pub fn es_patron(tabla: &str) -> bool {
+    tabla.contains(COMODINES)
+}
+
+///Recorre un directorio juntando los archivos de tabla. Si hay un patrón, cada nivel tiene que
+///coincidir con su segmento y los archivos con el último; si no, se toman todos los archivos con
+///una extensión de tabla. Se ignoran los archivos y directorios ocultos.
+fn buscar_partes(
+    directorio: &Path,
+    patron: Option<&[&str]>,
+    particiones: &mut Vec<(String, String)>,
+    partes: &mut Vec<Parte>,
+) -> Result<(), MyError> {
+    let mut entradas: Vec<(String, bool)> = match fs::read_dir(directorio) {
+        Ok(e) => e
+            .flatten()
+            .map(|e| {
+                (
+                    e.file_name().to_string_lossy().to_string(),
+                    e.path().is_dir(),
+                )
+            })
+            .collect(),
+        Err(_e) => {
+            return Err(MyError::InvalidTable(
+                "Directorio o nombre de la tabla incorrecto".to_string(),
+            ))
+        }
+    };
+    entradas.sort();
+
+    for (nombre, es_directorio) in entradas {
+        if nombre.starts_with('.') {
+            continue;
+        }
+        let ruta = directorio.join(&nombre);
+        let (coincide, resto) = match patron {
+            Some([segmento, resto @ ..]) => (coincide_con(segmento, &nombre), Some(resto)),
+            Some([]) => (false, None),
+            None => (true, None),
+        };
+        if !coincide {
+            continue;
+        }
+
+        if es_directorio {
+            if resto.is_some_and(|r| r.is_empty()) {
+                continue;
+            }
+            let particion = nombre
+                .split_once('=')
+                .map(|(clave, valor)| (clave.to_string(), valor.to_string()));
+            if let Some(p) = &particion {
+                particiones.push(p.clone());
+            }
+            buscar_partes(&ruta, resto, particiones, partes)?;
+            if particion.is_some() {
+                particiones.pop();
+            }
+        } else {
+            let archivo = ruta.to_string_lossy().to_string();
+            let es_tabla = match resto {
+                Some(r) => r.is_empty(),
+                None => almacenamiento::sin_extension(&archivo) != archivo,
+            };
+            if es_tabla {
+                partes.push(Parte {
+                    archivo,
+                    particiones: particiones.clone(),
+                });
+            }
+        }
+    }
+    Ok(())
+}
+
+///Indica si un nombre coincide con un segmento de patrón.
+fn coincide_con(patron: &str, nombre: &str) -> bool {
+    let patron: Vec<char> = patron.chars().collect();
+    let nombre: Vec<char> = nombre.chars().collect();
+    coinciden(&patron, &nombre)
+}
+
+fn coinciden(patron: &[char], nombre: &[char]) -> bool {
+    match (patron.first(), nombre.first()) {
+        (None, None) => true,
+        (Some('*'), _) => {
+            coinciden(&patron[1..], nombre)
+                || (!nombre.is_empty() && coinciden(patron, &nombre[1..]))
+        }
+        (Some('?'), Some(_)) => coinciden(&patron[1..], &nombre[1..]),
+        (Some(p), Some(n)) if p == n => coinciden(&patron[1..], &nombre[1..]),
+        _ => false,
+    }
+}
+
+///Lee los archivos de la tabla uno detrás de otro, acomodando los valores de cada fila a las
+///columnas de la tabla.
+struct RecorridoParticionado {
+    columnas: Vec<String>,
+    de_archivo: usize,
+    partes: std::vec::IntoIter<Parte>,
+    actual: Option<(Box<dyn Operador>, Vec<Origen>)>,
+}
+
+///Indica de dónde sale el valor de una columna de la tabla en las filas de un archivo.
+enum Origen {
+    Columna(usize),
+    Valor(String),
+}
+
+impl Operador for RecorridoParticionado {
+    fn columnas(&self) -> &[String] {
+        &self.columnas
+    }
+
+    fn siguiente(&mut self) -> Result<Option<Fila>, MyError> {
+        loop {
+            if let Some((recorrido, origenes)) = &mut self.actual {
+                if let Some(fila) = recorrido.siguiente()? {
+                    return Ok(Some(
+                        origenes
+                            .iter()
+                            .map(|origen| match origen {
+                                Origen::Columna(i) => fila.get(*i).cloned().unwrap_or_default(),
+                                Origen::Valor(v) => v.to_string(),
+                            })
+                            .collect(),
+                    ));
+                }
+            }
+
+            let parte = match self.partes.next() {
+                Some(p) => p,
+                None => return Ok(None),
+            };
+            let recorrido = almacenamiento::abrir(&parte.archivo).recorrer(None)?;
+            let origenes = self
+                .columnas
+                .iter()
+                .enumerate()
+                .map(
+                    |(i, columna)| match recorrido.columnas().iter().position(|c| c == columna) {
+                        Some(p) if i < self.de_archivo => Origen::Columna(p),
+                        _ => Origen::Valor(valor_de_particion(&parte, columna)),
+                    },
+                )
+                .collect();
+            self.actual = Some((recorrido, origenes));
+        }
+    }
+}
+
+///Devuelve el valor que tiene una columna de partición en un archivo, o vacío si el archivo no
+///está dentro de esa partición.
+fn valor_de_particion(parte: &Parte, columna: &str) -> String {
+    parte
+        .particiones
+        .iter()
+        .find(|(clave, _)| clave == columna)
+        .map(|(_, valor)| valor.to_string())
+        .unwrap_or_default()
+}
+
+fn error_de_solo_lectura() -> MyError {
+    MyError::InvalidTable(
+        "Las tablas de varios archivos solo se pueden leer con SELECT".to_string(),
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::condicion_simple::CondicionSimple;
+    use crate::operadores;
+    use crate::pruebas::ejecutar;
+
+    #[test]
+    pub fn test01_se_leen_las_particiones_de_un_directorio_y_se_saltean_las_que_no_cumplen() {
+        let directorio = "./test/particiones";
+        for (fecha, contenido) in [
+            ("2026-10-01", "id,monto\n1,10\n2,20\n"),
+            ("2026-10-02", "id,monto\n3,30\n"),
+        ] {
+            let particion = format!("{}/ventas/fecha={}", directorio, fecha);
+            let _ = fs::create_dir_all(&particion);
+            fs::write(format!("{}/parte.csv", particion), contenido).unwrap();
+        }
+        let tabla = almacenamiento::resolver(directorio, "ventas").unwrap();
+        let condicion = Condicion::CondicionSimple(CondicionSimple::new(
+            "fecha".to_string(),
+            "=".to_string(),
+            "2026-10-02".to_string(),
+        ));
+
+        let todas = operadores::recolectar(
+            almacenamiento::abrir(&tabla)
+                .recorrer(None)
+                .unwrap()
+                .as_mut(),
+        )
+        .unwrap();
+        let podadas = operadores::recolectar(
+            almacenamiento::abrir(&tabla)
+                .recorrer_donde(None, &condicion)
+                .unwrap()
+                .as_mut(),
+        )
+        .unwrap();
+        let insert = ejecutar(directorio, "INSERT INTO ventas (id, monto) VALUES (4, 40)");
+
+        assert_eq!(todas.len(), 3);
+        assert_eq!(todas[0], vec!["1", "10", "2026-10-01"]);
+        assert_eq!(podadas, vec![vec!["3", "30", "2026-10-02"]]);
+        assert!(matches!(insert, Err(MyError::InvalidTable(_))));
+    }
+
+    #[test]
+    pub fn test02_un_patron_elige_solo_los_archivos_que_coinciden() {
+        let directorio = "./test/particiones/diarias";
+        let _ = fs::create_dir_all(directorio);
+        for (dia, id) in [("2026-10-01", 1), ("2026-10-02", 2), ("2026-11-01", 3)] {
+            fs::write(
+                format!("{}/{}.csv", directorio, dia),
+                format!("id,monto\n{},{}\n", id, id * 5),
+            )
+            .unwrap();
+        }
+
+        let tabla =
+            almacenamiento::resolver("./test/particiones", "diarias/2026-10-*.csv").unwrap();
+        let filas = operadores::recolectar(
+            almacenamiento::abrir(&tabla)
+                .recorrer(None)
+                .unwrap()
+                .as_mut(),
+        )
+        .unwrap();
+
+        assert!(es_patron("diarias/2026-10-*.csv"));
+        assert_eq!(filas, vec![vec!["1", "5"], vec!["2", "10"]]);
+    }
+}
@@ -0,0 +1,511 @@
+use crate::condicion::Condicion;
+use crate::condicion_simple::CondicionSimple;
+use crate::esquema::Esquema;
+use crate::indice::Indice;
+use crate::my_error::MyError;
+use crate::operadores::Agregado;
+use crate::valor::Valor;
+use std::fmt;
+use std::path::Path;
+use std::time::Duration;
+
+const OPERADORES_CON_INDICE: [&str; 5] = ["=", "<", ">", "<=", ">="];
+
+///# Acceso
+///Representa la forma en la que se leen las filas de una tabla.
+///
+///**Tipos**
+///- 'Recorrido': Se lee la tabla completa, fila por fila.
+///- 'Indice': Se leen solo las filas que indica un índice para la comparación *columna simbolo
+///  valor*.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Acceso {
+    Recorrido,
+    Indice {
+        indice: Indice,
+        simbolo: String,
+        valor: Valor,
+    },
+}
+
+impl Acceso {
+    ///# Acceso.filas()
+    ///Esta función devuelve las posiciones de las filas que hay que leer de la tabla.
+    ///
+    ///**Parámetros**
+    ///- 'archivo_tabla': Es la dirección del archivo de la tabla.
+    ///
+    ///**Return**
+    ///Devuelve un *Result<Option<Vec<u64>>, MyError>* con las posiciones de las filas en el orden
+    ///  en el que están en la tabla, o *None* si hay que recorrer la tabla completa (también cuando
+    ///  el índice no puede resolver la comparación).
+    pub fn filas(&self, archivo_tabla: &str) -> Result<Option<Vec<u64>>, MyError> {
+        match self {
+            Acceso::Recorrido => Ok(None),
+            Acceso::Indice {
+                indice,
+                simbolo,
+                valor,
+            } => indice.buscar(archivo_tabla, simbolo, valor),
+        }
+    }
+}
+
+///# planificador::elegir_acceso()
+///Esta función decide cómo leer las filas de una tabla que tienen que cumplir una condición. Se
+///buscan las comparaciones que la condición exige siempre (la condición misma o cualquiera de los
+///lados de un AND, a cualquier profundidad) que sean sobre una columna con un índice actualizado y
+///con un operador que el índice puede resolver. Entre ellas se prefiere una igualdad sobre un
+///índice UNIQUE, después cualquier igualdad y por último un rango. Si no hay ninguna se recorre la
+///tabla completa. En todos los casos la condición completa se vuelve a verificar sobre cada fila
+///leída.
+///
+///**Parámetros**
+///- 'archivo_tabla': Es la dirección del archivo de la tabla.
+///- 'esquema': Es el esquema de la tabla, con la definición de sus índices.
+///- 'condicion': Es la condición del WHERE.
+///
+///**Return**
+///Devuelve el *Acceso* elegido.
+pub fn elegir_acceso(archivo_tabla: &str, esquema: &Esquema, condicion: &Condicion) -> Acceso {
+    let mut elegido: Option<(usize, &Indice, &CondicionSimple)> = None;
+
+    for simple in conjunciones(condicion) {
+        if !OPERADORES_CON_INDICE.contains(&simple.simbolo()) {
+            continue;
+        }
+
+        let indice = match esquema
+            .indices()
+            .iter()
+            .find(|i| i.columna() == simple.columna() && i.esta_actualizado(archivo_tabla))
+        {
+            Some(i) => i,
+            None => continue,
+        };
+
+        let costo = match (simple.simbolo(), indice.es_unico()) {
+            ("=", true) => 0,
+            ("=", false) => 1,
+            _ => 2,
+        };
+        if elegido.is_none_or(|(c, _, _)| costo < c) {
+            elegido = Some((costo, indice, simple));
+        }
+    }
+
+    match elegido {
+        Some((_, indice, simple)) => Acceso::Indice {
+            indice: indice.clone(),
+            simbolo: simple.simbolo().to_string(),
+            valor: simple.valor().clone(),
+        },
+        None => Acceso::Recorrido,
+    }
+}
+
+///# planificador::conjunciones()
+///Esta función devuelve las comparaciones que se tienen que cumplir para que se cumpla la
+///condición: la condición misma o las de cualquiera de los lados de un AND.
+pub fn conjunciones(condicion: &Condicion) -> Vec<&CondicionSimple> {
+    match condicion {
+        Condicion::CondicionSimple(simple) => vec![simple],
+        Condicion::And(cond1, cond2) => {
+            let mut simples = conjunciones(cond1);
+            simples.extend(conjunciones(cond2));
+            simples
+        }
+        _ => Vec::new(),
+    }
+}
+
+///# Plan
+///Esta estructura describe cómo se va a ejecutar un SELECT, de la hoja a la raíz: el acceso a la
+///tabla, el filtro con la condición del WHERE, la agrupación del GROUP BY y las funciones de
+///agregación, el ordenamiento del ORDER BY y la proyección de las columnas pedidas. Se muestra como un árbol con *EXPLAIN SELECT ...*.
+///
+///**Ejemplo**
+///let plan = Plan::new(direccion_tabla, &esquema, &condicion, orden, asc, columnas);
+///println!("{}", plan);
+///
+///**Parámetros**
+///- 'tabla': Es el nombre de la tabla.
+///- 'acceso': Es la forma elegida de leer las filas de la tabla.
+///- 'filtro': Es la condición que se verifica sobre cada fila leída, si hay una.
+///- 'agrupacion': Es la descripción de la agrupación y sus agregados, si la consulta agrupa.
+///- 'orden': Es la columna por la que se ordena y si es ascendente, si hay ORDER BY.
+///- 'columnas': Son las columnas que se muestran.
+#[derive(Debug, PartialEq)]
+pub struct Plan {
+    tabla: String,
+    acceso: Acceso,
+    filtro: Option<String>,
+    agrupacion: Option<String>,
+    orden: Option<(String, bool)>,
+    columnas: Vec<String>,
+}
+
+impl Plan {
+    ///# Plan::new()
+    ///Esta función arma el plan de un SELECT, eligiendo el acceso a la tabla con
+    ///*planificador::elegir_acceso()*.
+    ///
+    ///**Parámetros**
+    ///- 'archivo_tabla': Es la dirección del archivo de la tabla.
+    ///- 'esquema': Es el esquema de la tabla.
+    ///- 'condicion': Es la condición del WHERE.
+    ///- 'orden': Es la columna del ORDER BY, o un texto vacío si no hay.
+    ///- 'asc': Indica si el orden es ascendente.
+    ///- 'columnas': Son las columnas que se muestran.
+    ///
+    ///**Return**
+    ///Devuelve un *struct* de tipo *Plan*.
+    pub fn new(
+        archivo_tabla: &str,
+        esquema: &Esquema,
+        condicion: &Condicion,
+        orden: &str,
+        asc: bool,
+        columnas: &[String],
+    ) -> Self {
+        let tabla = Path::new(archivo_tabla)
+            .file_stem()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+
+        Plan {
+            tabla,
+            acceso: elegir_acceso(archivo_tabla, esquema, condicion),
+            filtro: match condicion {
+                Condicion::SiempreTrue => None,
+                c => Some(c.to_string()),
+            },
+            agrupacion: None,
+            orden: match orden.is_empty() {
+                true => None,
+                false => Some((orden.to_string(), asc)),
+            },
+            columnas: columnas.to_vec(),
+        }
+    }
+
+    ///# Plan.con_agrupacion()
+    ///Esta función agrega al plan la etapa que agrupa las filas y calcula los agregados.
+    ///
+    ///**Parámetros**
+    ///- 'grupo': Es la columna del GROUP BY, o *None* si se agrupan todas las filas.
+    ///- 'agregados': Son las funciones de agregación que se calculan.
+    ///
+    ///**Return**
+    ///Devuelve el mismo *Plan*.
+    pub fn con_agrupacion(mut self, grupo: Option<&str>, agregados: &[Agregado]) -> Self {
+        let nombres: Vec<String> = agregados.iter().map(|a| a.nombre()).collect();
+        self.agrupacion = Some(match grupo {
+            Some(g) => format!("Agrupación por {} ({})", g, nombres.join(", ")),
+            None => format!("Agrupación ({})", nombres.join(", ")),
+        });
+        self
+    }
+
+    pub fn acceso(&self) -> &Acceso {
+        &self.acceso
+    }
+
+    ///# Plan.analisis()
+    ///Esta función arma el árbol del plan agregando a cada etapa las filas que entraron y
+    ///salieron y el tiempo que tardó, según las mediciones de su ejecución.
+    ///
+    ///**Parámetros**
+    ///- 'mediciones': Son las mediciones de la ejecución del plan.
+    ///
+    ///**Return**
+    ///Devuelve un *String* con el árbol del plan.
+    pub fn analisis(&self, mediciones: &Mediciones) -> String {
+        let mut medidas = vec![mediciones.proyeccion];
+        if self.orden.is_some() {
+            medidas.push(mediciones.orden);
+        }
+        if self.agrupacion.is_some() {
+            medidas.push(mediciones.agrupacion);
+        }
+        if self.filtro.is_some() {
+            medidas.push(mediciones.filtro);
+        }
+
+        let etapas = self.etapas();
+        let ultima = etapas.len() - 1;
+        let mut analizadas: Vec<String> = etapas
+            .iter()
+            .zip(medidas.iter())
+            .map(|(etapa, m)| {
+                format!(
+                    "{} [filas: {} -> {}, tiempo: {:.3} ms]",
+                    etapa,
+                    m.filas_entrada,
+                    m.filas_salida,
+                    m.duracion.as_secs_f64() * 1000.0
+                )
+            })
+            .collect();
+        analizadas.push(format!(
+            "{} [filas: {}, tiempo: {:.3} ms]",
+            etapas[ultima],
+            mediciones.acceso.filas_salida,
+            mediciones.acceso.duracion.as_secs_f64() * 1000.0
+        ));
+
+        arbol(&analizadas)
+    }
+
+    ///# Plan.etapas()
+    ///Esta función devuelve la descripción de cada etapa del plan, de la raíz a la hoja.
+    pub fn etapas(&self) -> Vec<String> {
+        let mut etapas = vec![format!("Proyección ({})", self.columnas.join(", "))];
+
+        if let Some((columna, asc)) = &self.orden {
+            let sentido = match asc {
+                true => "ASC",
+                false => "DESC",
+            };
+            etapas.push(format!("Ordenamiento ({} {})", columna, sentido));
+        }
+
+        if let Some(agrupacion) = &self.agrupacion {
+            etapas.push(agrupacion.clone());
+        }
+
+        if let Some(filtro) = &self.filtro {
+            etapas.push(format!("Filtro ({})", filtro));
+        }
+
+        etapas.push(match &self.acceso {
+            Acceso::Recorrido => format!("Recorrido completo de {}", self.tabla),
+            Acceso::Indice {
+                indice,
+                simbolo,
+                valor,
+            } => format!(
+                "Búsqueda en el índice {} de {} ({} {} {})",
+                indice.nombre(),
+                self.tabla,
+                indice.columna(),
+                simbolo,
+                valor
+            ),
+        });
+
+        etapas
+    }
+}
+
+impl fmt::Display for Plan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", arbol(&self.etapas()))
+    }
+}
+
+///Arma el árbol de un plan a partir de sus etapas, de la raíz a la hoja.
+fn arbol(etapas: &[String]) -> String {
+    let mut texto = String::new();
+    for (nivel, etapa) in etapas.iter().enumerate() {
+        match nivel {
+            0 => texto.push_str(&format!("{}\n", etapa)),
+            n => texto.push_str(&format!("{}-> {}\n", "  ".repeat(n), etapa)),
+        }
+    }
+    texto
+}
+
+///# Medicion
+///Esta estructura registra lo que hizo una etapa de un plan al ejecutarse: cuántas filas le
+///llegaron, cuántas pasaron a la etapa siguiente y cuánto tiempo tardó en total.
+///
+///**Parámetros**
+///- 'filas_entrada': Es la cantidad de filas que recibió la etapa.
+///- 'filas_salida': Es la cantidad de filas que devolvió la etapa.
+///- 'duracion': Es el tiempo total que tardó la etapa.
+#[derive(Debug, Default, PartialEq, Clone, Copy)]
+pub struct Medicion {
+    filas_entrada: usize,
+    filas_salida: usize,
+    duracion: Duration,
+}
+
+impl Medicion {
+    ///# Medicion::new()
+    ///Esta función crea una nueva instancia de Medicion.
+    ///
+    ///**Parámetros**
+    ///- 'filas_entrada': Es la cantidad de filas que recibió la etapa.
+    ///- 'filas_salida': Es la cantidad de filas que devolvió la etapa.
+    ///- 'duracion': Es el tiempo que tardó la etapa.
+    ///
+    ///**Return**
+    ///Devuelve un *struct* de tipo *Medicion*.
+    pub fn new(filas_entrada: usize, filas_salida: usize, duracion: Duration) -> Self {
+        Medicion {
+            filas_entrada,
+            filas_salida,
+            duracion,
+        }
+    }
+
+    ///# Medicion.registrar()
+    ///Esta función suma a la medición lo que hizo la etapa en una parte de su trabajo, por
+    ///ejemplo al procesar una fila.
+    pub fn registrar(&mut self, filas_entrada: usize, filas_salida: usize, duracion: Duration) {
+        self.filas_entrada += filas_entrada;
+        self.filas_salida += filas_salida;
+        self.duracion += duracion;
+    }
+
+    pub fn filas_entrada(&self) -> usize {
+        self.filas_entrada
+    }
+
+    pub fn filas_salida(&self) -> usize {
+        self.filas_salida
+    }
+
+    pub fn duracion(&self) -> Duration {
+        self.duracion
+    }
+}
+
+///# Mediciones
+///Esta estructura agrupa las mediciones de cada etapa de la ejecución de un SELECT, para
+///mostrarlas con *EXPLAIN ANALYZE*.
+///
+///**Parámetros**
+///- 'acceso': Es la lectura de las filas de la tabla, completa o con un índice.
+///- 'filtro': Es la verificación de la condición del WHERE sobre cada fila leída.
+///- 'agrupacion': Es el cálculo de las funciones de agregación de cada grupo.
+///- 'orden': Es el ordenamiento del ORDER BY.
+///- 'proyeccion': Es el armado de las columnas pedidas de cada fila.
+#[derive(Debug, Default, PartialEq, Clone, Copy)]
+pub struct Mediciones {
+    pub acceso: Medicion,
+    pub filtro: Medicion,
+    pub agrupacion: Medicion,
+    pub orden: Medicion,
+    pub proyeccion: Medicion,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::indice;
+    use crate::pruebas::{self, CLIENTES, ESQUEMA_CLIENTES};
+    use std::fs;
+
+    fn tabla_con_indices(prueba: &str) -> (String, Esquema) {
+        let archivo = pruebas::preparar_tabla(prueba, "clientes", CLIENTES, ESQUEMA_CLIENTES);
+        let mut esquema = Esquema::cargar(&archivo).unwrap();
+        for (columna, unico) in [("edad", false), ("email", true)] {
+            esquema.agregar_indice(Indice::new(
+                format!("idx_{}", columna),
+                columna.to_string(),
+                unico,
+            ));
+        }
+        indice::reconstruir(&archivo, &esquema).unwrap();
+        (archivo, esquema)
+    }
+
+    fn simple(columna: &str, simbolo: &str, valor: &str) -> Box<Condicion> {
+        Box::new(Condicion::CondicionSimple(CondicionSimple::new(
+            columna.to_string(),
+            simbolo.to_string(),
+            valor.to_string(),
+        )))
+    }
+
+    #[test]
+    pub fn test01_se_prefiere_la_igualdad_sobre_un_indice_unique_dentro_de_un_and() {
+        let (archivo, esquema) = tabla_con_indices("planificar_and");
+        let condicion = Condicion::And(
+            Box::new(Condicion::And(
+                simple("edad", ">", "20"),
+                simple("nombre", "=", "Ana"),
+            )),
+            simple("email", "=", "ana@mail.com"),
+        );
+
+        let acceso = elegir_acceso(&archivo, &esquema, &condicion);
+
+        assert!(matches!(
+            &acceso,
+            Acceso::Indice { indice, simbolo, .. }
+                if indice.columna() == "email" && simbolo == "="
+        ));
+        assert_eq!(acceso.filas(&archivo).unwrap(), Some(vec![47]));
+    }
+
+    #[test]
+    pub fn test02_se_recorre_la_tabla_si_ninguna_comparacion_puede_usar_un_indice() {
+        let (archivo, esquema) = tabla_con_indices("planificar_or");
+        let condiciones = [
+            Condicion::Or(simple("edad", "=", "30"), simple("nombre", "=", "Ana")),
+            Condicion::Not(simple("edad", "=", "30")),
+            *simple("edad", "!=", "30"),
+            *simple("nombre", "=", "Ana"),
+        ];
+
+        for condicion in &condiciones {
+            assert_eq!(
+                elegir_acceso(&archivo, &esquema, condicion),
+                Acceso::Recorrido
+            );
+        }
+
+        fs::write(&archivo, "id,nombre,email,edad\n").unwrap();
+        assert_eq!(
+            elegir_acceso(&archivo, &esquema, &simple("edad", "=", "30")),
+            Acceso::Recorrido
+        );
+    }
+
+    #[test]
+    pub fn test03_se_muestra_el_arbol_del_plan() {
+        let (archivo, esquema) = tabla_con_indices("planificar_arbol");
+        let condicion = Condicion::And(simple("edad", ">=", "30"), simple("nombre", "!=", "Ana"));
+
+        let plan = Plan::new(
+            &archivo,
+            &esquema,
+            &condicion,
+            "nombre",
+            false,
+            &["nombre".to_string(), "edad".to_string()],
+        );
+
+        assert_eq!(
+            plan.to_string(),
+            "Proyección (nombre, edad)\n  -> Ordenamiento (nombre DESC)\n    -> Filtro (edad >= 30 AND nombre != Ana)\n      -> Búsqueda en el índice idx_edad de clientes (edad >= 30)\n"
+        );
+    }
+
+    #[test]
+    pub fn test04_el_analisis_muestra_las_filas_y_el_tiempo_de_cada_etapa() {
+        let (archivo, esquema) = tabla_con_indices("planificar_analisis");
+        let plan = Plan::new(
+            &archivo,
+            &esquema,
+            &simple("nombre", "=", "Ana"),
+            "",
+            true,
+            &["*".to_string()],
+        );
+        let mut mediciones = Mediciones {
+            acceso: Medicion::new(3, 3, Duration::from_micros(1500)),
+            proyeccion: Medicion::new(1, 1, Duration::from_micros(5)),
+            ..Default::default()
+        };
+        mediciones.filtro.registrar(3, 1, Duration::from_micros(20));
+
+        assert_eq!(
+            plan.analisis(&mediciones),
+            "Proyección (*) [filas: 1 -> 1, tiempo: 0.005 ms]\n  -> Filtro (nombre = Ana) [filas: 3 -> 1, tiempo: 0.020 ms]\n    -> Recorrido completo de clientes [filas: 3, tiempo: 1.500 ms]\n"
+        );
+    }
+}
@@ -0,0 +1,54 @@
+use crate::esquema::Esquema;
+use crate::my_error::MyError;
+use crate::parser::Parser;
+use std::env;
+use std::fs;
+
+///Es la tabla de clientes con la que se prueban los índices.
+pub(crate) const CLIENTES: &str = "id,nombre,email,edad\n1,Tomas,tomas@mail.com,25\n2,Ana,ana@mail.com,30\n3,Luis,luis@mail.com,41\n";
+
+///Es el esquema de la tabla de clientes.
+pub(crate) const ESQUEMA_CLIENTES: &str = "id INT PRIMARY KEY\nnombre TEXT\nemail TEXT\nedad INT\n";
+
+///# pruebas::directorio()
+///Esta función crea un directorio vacío para una prueba dentro del directorio temporal del
+///sistema, borrando lo que haya dejado una ejecución anterior, y devuelve su dirección. Cada
+///prueba usa su propio directorio para no compartir archivos con las demás.
+///
+///**Parámetros**
+///- 'prueba': Es el nombre del directorio, distinto para cada prueba.
+pub(crate) fn directorio(prueba: &str) -> String {
+    let directorio = env::temp_dir().join("sql_rustico").join(prueba);
+    let _ = fs::remove_dir_all(&directorio);
+    fs::create_dir_all(&directorio).unwrap();
+    directorio.to_string_lossy().to_string()
+}
+
+///# pruebas::preparar_tabla()
+///Esta función crea una tabla CSV con su esquema en el directorio vacío de una prueba, a partir
+///de su contenido, y devuelve la dirección de la tabla.
+///
+///**Parámetros**
+///- 'prueba': Es el nombre del directorio de la prueba.
+///- 'tabla': Es el nombre de la tabla.
+///- 'csv': Es el contenido de la tabla.
+///- 'esquema': Es el contenido del archivo *.esquema*.
+pub(crate) fn preparar_tabla(prueba: &str, tabla: &str, csv: &str, esquema: &str) -> String {
+    let archivo = format!("{}/{}.csv", directorio(prueba), tabla);
+    fs::write(&archivo, csv).unwrap();
+    fs::write(Esquema::ruta(&archivo), esquema).unwrap();
+    archivo
+}
+
+///# pruebas::ejecutar()
+///Esta función arma y realiza una instrucción sobre un directorio o una base en memoria, igual
+///que el programa principal.
+///
+///**Parámetros**
+///- 'direccion': Es el directorio o la dirección de la base en memoria.
+///- 'instruccion': Es la instrucción que se quiere ejecutar.
+pub(crate) fn ejecutar(direccion: &str, instruccion: &str) -> Result<String, MyError> {
+    Parser::new()
+        .crear_operacion(direccion.to_string(), instruccion.to_string())?
+        .realizar_operacion()
+}
@@ -0,0 +1,206 @@
+use crate::almacenamiento::{self, Almacenamiento, Reescritura};
+use crate::bloqueo::Bloqueo;
+use crate::esquema::Esquema;
+use crate::formato::Formato;
+use crate::my_error::MyError;
+use crate::operadores::{Fila, Operador, Recorrido};
+
+///# ReadCsv
+///Es el almacenamiento de un archivo CSV cualquiera que se consulta con la función
+///*read_csv('archivo', delim => ';', header => true)* en el FROM de un SELECT, sin que sea una
+///tabla del directorio. No se usa el esquema del archivo: todas sus columnas son TEXT y no tiene
+///índices. El archivo solo se puede leer y no se bloquea, para no crear archivos de bloqueo al
+///lado de un archivo cualquiera (que puede estar en un directorio de solo lectura).
+///
+///**Ejemplo**
+///let lectura = ReadCsv::new("/datos/ventas.csv").con_delimitador(';');
+///let mut filas = lectura.recorrer(None)?;
+///
+///**Parámetros**
+///- 'archivo': Es la dirección del archivo.
+///- 'delimitador': Es el separador de los valores, o *None* para detectarlo en la primera línea.
+///- 'encabezado': Indica si la primera línea tiene los nombres de las columnas. Si no los tiene
+///  las columnas se llaman *columna1*, *columna2*, etc.
+#[derive(Debug, PartialEq, Clone)]
+pub struct ReadCsv {
+    archivo: String,
+    delimitador: Option<char>,
+    encabezado: bool,
+}
+
+impl ReadCsv {
+    ///# ReadCsv::new()
+    ///Esta función crea una nueva instancia de ReadCsv, que detecta el separador y toma la
+    ///primera línea como encabezado.
+    ///
+    ///**Parámetros**
+    ///- 'archivo': Es la dirección del archivo.
+    ///
+    ///**Return**
+    ///Devuelve un *struct* de tipo *ReadCsv*.
+    pub fn new(archivo: &str) -> Self {
+        ReadCsv {
+            archivo: archivo.to_string(),
+            delimitador: None,
+            encabezado: true,
+        }
+    }
+
+    ///# ReadCsv.con_delimitador()
+    ///Esta función fija el separador de los valores en lugar de detectarlo.
+    ///
+    ///**Parámetros**
+    ///- 'delimitador': Es el carácter que separa los valores.
+    pub fn con_delimitador(mut self, delimitador: char) -> Self {
+        self.delimitador = Some(delimitador);
+        self
+    }
+
+    ///# ReadCsv.con_encabezado()
+    ///Esta función indica si la primera línea del archivo tiene los nombres de las columnas.
+    ///
+    ///**Parámetros**
+    ///- 'encabezado': Es *false* si la primera línea ya es una fila.
+    pub fn con_encabezado(mut self, encabezado: bool) -> Self {
+        self.encabezado = encabezado;
+        self
+    }
+
+    fn formato(&self) -> Result<Formato, MyError> {
+        let detectado = Formato::detectar(&almacenamiento::primera_linea(&self.archivo)?);
+        match self.delimitador {
+            Some(d) => Ok(detectado.con_delimitador(d)),
+            None => Ok(detectado),
+        }
+    }
+}
+
+impl Almacenamiento for ReadCsv {
+    fn archivo(&self) -> &str {
+        &self.archivo
+    }
+
+    fn bloquear(&self, _exclusivo: bool) -> Result<Option<Bloqueo>, MyError> {
+        Ok(None)
+    }
+
+    fn esquema(&self) -> Result<Esquema, MyError> {
+        Ok(Esquema::sin_tipos(self.recorrer(None)?.columnas()))
+    }
+
+    fn recorrer(&self, _posiciones: Option<Vec<u64>>) -> Result<Box<dyn Operador>, MyError> {
+        let recorrido = Recorrido::abrir(&self.archivo, self.formato()?)?;
+        match self.encabezado {
+            true => Ok(Box::new(recorrido)),
+            false => Ok(Box::new(SinEncabezado::new(recorrido))),
+        }
+    }
+
+    fn anexar(&self, _filas: &[Fila]) -> Result<(), MyError> {
+        Err(error_de_solo_lectura())
+    }
+
+    fn reescribir(&self, _columnas: &[String]) -> Result<Box<dyn Reescritura>, MyError> {
+        Err(error_de_solo_lectura())
+    }
+
+    fn largo(&self) -> Result<u64, MyError> {
+        Err(error_de_solo_lectura())
+    }
+
+    fn tablas_con_esquema(&self) -> Result<Vec<String>, MyError> {
+        Ok(Vec::new())
+    }
+
+    fn ubicacion_temporal(&self) -> String {
+        String::from(&self.archivo)
+    }
+}
+
+///Lee un archivo cuya primera línea ya es una fila: la entrega antes que las demás y nombra las
+///columnas por su número.
+struct SinEncabezado {
+    columnas: Vec<String>,
+    primera: Option<Fila>,
+    recorrido: Recorrido,
+}
+
+impl SinEncabezado {
+    fn new(recorrido: Recorrido) -> Self {
+        let primera = match recorrido.largo_encabezado() {
+            0 => None,
+            _ => Some(recorrido.columnas().to_vec()),
+        };
+        let columnas = (1..=recorrido.columnas().len())
+            .map(|i| format!("columna{}", i))
+            .collect();
+        SinEncabezado {
+            columnas,
+            primera,
+            recorrido,
+        }
+    }
+}
+
+impl Operador for SinEncabezado {
+    fn columnas(&self) -> &[String] {
+        &self.columnas
+    }
+
+    fn siguiente(&mut self) -> Result<Option<Fila>, MyError> {
+        match self.primera.take() {
+            Some(fila) => Ok(Some(fila)),
+            None => self.recorrido.siguiente(),
+        }
+    }
+}
+
+fn error_de_solo_lectura() -> MyError {
+    MyError::InvalidTable("Los archivos de read_csv solo se pueden leer con SELECT".to_string())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::operadores;
+    use crate::parser::Parser;
+    use std::fs;
+
+    #[test]
+    pub fn test01_se_lee_un_archivo_con_y_sin_encabezado() {
+        let directorio = "./test/read_csv";
+        let archivo = format!("{}/ventas.txt", directorio);
+        let _ = fs::create_dir_all(directorio);
+        fs::write(&archivo, "id;monto\n1;10\n2;20\n").unwrap();
+        let _ = fs::remove_file(Bloqueo::ruta(&archivo));
+
+        let con_encabezado = ReadCsv::new(&archivo);
+        let columnas = con_encabezado.recorrer(None).unwrap().columnas().to_vec();
+        let sin_encabezado = operadores::recolectar(
+            ReadCsv::new(&archivo)
+                .con_delimitador(';')
+                .con_encabezado(false)
+                .recorrer(None)
+                .unwrap()
+                .as_mut(),
+        )
+        .unwrap();
+        Parser::new()
+            .crear_operacion(
+                directorio.to_string(),
+                format!("SELECT * FROM read_csv('{}')", archivo),
+            )
+            .unwrap()
+            .realizar_operacion()
+            .unwrap();
+
+        assert!(!Bloqueo::ruta(&archivo).exists());
+        assert_eq!(columnas, vec!["id", "monto"]);
+        assert_eq!(
+            operadores::recolectar(con_encabezado.recorrer(None).unwrap().as_mut()).unwrap(),
+            vec![vec!["1", "10"], vec!["2", "20"]]
+        );
+        assert_eq!(sin_encabezado[0], vec!["id", "monto"]);
+        assert_eq!(sin_encabezado.len(), 3);
+    }
+}
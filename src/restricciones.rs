@@ -0,0 +1,365 @@
+use crate::condicion::CondicionCompilada;
+use crate::esquema::{Esquema, TipoDato};
+use crate::my_error::MyError;
+use crate::parser::Parser;
+use std::collections::HashMap;
+
+///# VerificadorRestricciones
+///Esta estructura se encarga de corroborar que las filas que se escriben en una tabla cumplan las
+///restricciones NOT NULL, UNIQUE, PRIMARY KEY y CHECK declaradas en su esquema y que cada valor
+///sea del tipo de su columna.
+///
+///**Ejemplo**
+///let mut verificador = VerificadorRestricciones::new(&esquema, &columnas_tabla);
+///verificador.registrar(&fila_existente)?;
+///verificador.verificar(&fila_nueva)?;
+///
+///**Parámetros**
+///- 'tipos': Son las columnas que no son de tipo TEXT junto con su posición en la tabla.
+///- 'no_nulas': Son las columnas que no admiten valores vacíos junto con su posición en la tabla
+///  y la restricción que lo exige.
+///- 'unicas': Son las columnas que no admiten valores repetidos.
+///- 'checks': Son las condiciones de los CHECK, ya compiladas contra las columnas de la tabla,
+///  junto con la descripción de dónde se declararon.
+#[derive(Debug)]
+pub struct VerificadorRestricciones {
+    tipos: Vec<(usize, String, TipoDato)>,
+    no_nulas: Vec<(usize, String, &'static str)>,
+    unicas: Vec<ColumnaUnica>,
+    checks: Vec<(String, CondicionCompilada)>,
+}
+
+///Guarda los valores que ya tiene una columna única. El bool indica si el valor pertenece a una
+///fila escrita por la instrucción (true) o a una fila que ya estaba en la tabla (false).
+#[derive(Debug)]
+struct ColumnaUnica {
+    posicion: usize,
+    nombre: String,
+    restriccion: &'static str,
+    valores: HashMap<String, bool>,
+}
+
+impl VerificadorRestricciones {
+    ///# VerificadorRestricciones::new()
+    ///Esta función crea una nueva instancia de VerificadorRestricciones.
+    ///
+    ///**Parámetros**
+    ///- 'esquema': Es el esquema de la tabla que tiene las restricciones declaradas.
+    ///- 'columnas_tabla': Son los nombres de las columnas en el orden en el que están en la tabla.
+    ///
+    ///**Return**
+    ///Devuelve un *Result<VerificadorRestricciones, MyError>*, en caso de que alguna expresión
+    ///  CHECK del esquema no sea válida o use una columna que la tabla no tiene se devuelve un error del tipo *MyError*.
+    pub fn new(esquema: &Esquema, columnas_tabla: &[String]) -> Result<Self, MyError> {
+        let mut tipos = Vec::new();
+        let mut no_nulas = Vec::new();
+        let mut unicas = Vec::new();
+        let mut checks = Vec::new();
+
+        for check in esquema.checks() {
+            let condicion = Parser::new()
+                .parsear_condicion(check)?
+                .compilar(columnas_tabla)?;
+            checks.push((format!("CHECK {} de la tabla", check), condicion));
+        }
+
+        for columna in esquema.columnas() {
+            let posicion = match columnas_tabla.iter().position(|c| c == columna.nombre()) {
+                Some(p) => p,
+                None => continue,
+            };
+
+            if *columna.tipo() != TipoDato::Texto {
+                tipos.push((
+                    posicion,
+                    columna.nombre().to_string(),
+                    columna.tipo().clone(),
+                ));
+            }
+
+            if let Some(check) = columna.check() {
+                let condicion = Parser::new()
+                    .parsear_condicion(check)?
+                    .compilar(columnas_tabla)?;
+                let descripcion = format!("CHECK {} de la columna {}", check, columna.nombre());
+                checks.push((descripcion, condicion));
+            }
+
+            let restriccion = match columna.es_clave_primaria() {
+                true => "PRIMARY KEY",
+                false => "UNIQUE",
+            };
+
+            if columna.es_no_nula() {
+                let restriccion_nula = match columna.es_clave_primaria() {
+                    true => "PRIMARY KEY",
+                    false => "NOT NULL",
+                };
+                no_nulas.push((posicion, columna.nombre().to_string(), restriccion_nula));
+            }
+
+            if columna.es_unica() {
+                unicas.push(ColumnaUnica {
+                    posicion,
+                    nombre: columna.nombre().to_string(),
+                    restriccion,
+                    valores: HashMap::new(),
+                });
+            }
+        }
+
+        Ok(VerificadorRestricciones {
+            tipos,
+            no_nulas,
+            unicas,
+            checks,
+        })
+    }
+
+    ///# VerificadorRestricciones.necesita_filas_existentes()
+    ///Esta función indica si hace falta registrar las filas que ya están en la tabla, lo cual solo
+    ///ocurre si alguna columna no admite valores repetidos.
+    pub fn necesita_filas_existentes(&self) -> bool {
+        !self.unicas.is_empty()
+    }
+
+    ///# VerificadorRestricciones.registrar()
+    ///Esta función registra una fila que ya estaba en la tabla y que la instrucción no modifica.
+    ///Solo falla si repite un valor único de una fila escrita por la instrucción.
+    ///
+    ///**Parámetros**
+    ///- 'fila': Son los valores de la fila en el orden de las columnas de la tabla.
+    ///
+    ///**Return**
+    ///Devuelve un *Result<(), MyError>*, en caso de que se viole alguna restricción se devuelve un
+    ///  error del tipo *MyError*.
+    pub fn registrar(&mut self, fila: &[String]) -> Result<(), MyError> {
+        for unica in &mut self.unicas {
+            let valor = match fila.get(unica.posicion) {
+                Some(v) if !v.is_empty() => v,
+                _ => continue,
+            };
+
+            match unica.valores.get(valor) {
+                Some(true) => return Err(unica.violacion(valor)),
+                Some(false) => {}
+                None => {
+                    unica.valores.insert(valor.to_string(), false);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    ///# VerificadorRestricciones.verificar()
+    ///Esta función corrobora que una fila escrita por la instrucción tenga valores del tipo de
+    ///cada columna y cumpla todas las restricciones, teniendo en cuenta las filas registradas y
+    ///verificadas previamente.
+    ///
+    ///**Parámetros**
+    ///- 'fila': Son los valores de la fila en el orden de las columnas de la tabla.
+    ///
+    ///**Return**
+    ///Devuelve un *Result<(), MyError>*, en caso de que se viole alguna restricción se devuelve un
+    ///  error del tipo *MyError* que indica cuál es.
+    pub fn verificar(&mut self, fila: &[String]) -> Result<(), MyError> {
+        for (posicion, nombre, tipo) in &self.tipos {
+            let valor = fila.get(*posicion).map(|v| v.as_str()).unwrap_or_default();
+            if !tipo.acepta(valor) {
+                return Err(MyError::InvalidColumn(format!(
+                    "El valor {} de la columna {} no es del tipo {}",
+                    valor,
+                    nombre,
+                    tipo.nombre()
+                )));
+            }
+        }
+
+        for (posicion, nombre, restriccion) in &self.no_nulas {
+            if fila.get(*posicion).is_none_or(|v| v.is_empty()) {
+                return Err(MyError::ConstraintViolation(format!(
+                    "Se viola la restricción {} de la columna {}: el valor no puede ser vacío",
+                    restriccion, nombre
+                )));
+            }
+        }
+
+        for (descripcion, condicion) in &self.checks {
+            if condicion.evaluar(fila)? == Some(false) {
+                return Err(MyError::ConstraintViolation(format!(
+                    "Se viola la restricción {}",
+                    descripcion
+                )));
+            }
+        }
+
+        for unica in &mut self.unicas {
+            let valor = match fila.get(unica.posicion) {
+                Some(v) if !v.is_empty() => v,
+                _ => continue,
+            };
+
+            if unica.valores.contains_key(valor) {
+                return Err(unica.violacion(valor));
+            }
+            unica.valores.insert(valor.to_string(), true);
+        }
+
+        Ok(())
+    }
+}
+
+impl ColumnaUnica {
+    fn violacion(&self, valor: &str) -> MyError {
+        MyError::ConstraintViolation(format!(
+            "Se viola la restricción {} de la columna {}: el valor {} está repetido",
+            self.restriccion, self.nombre, valor
+        ))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::esquema::{Columna, TipoDato};
+
+    fn armar_verificador() -> VerificadorRestricciones {
+        let definiciones = [
+            vec!["id", "INT", "PRIMARY", "KEY"],
+            vec!["email", "TEXT", "UNIQUE"],
+            vec!["nombre", "TEXT", "NOT", "NULL"],
+        ];
+        let mut columnas: Vec<Columna> = Vec::new();
+        for definicion in definiciones {
+            let tokens: Vec<String> = definicion.iter().map(|s| s.to_string()).collect();
+            columnas.push(Columna::desde_definicion(&tokens).unwrap());
+        }
+        columnas.push(Columna::new("edad".to_string(), TipoDato::Entero));
+
+        let nombres: Vec<String> = columnas.iter().map(|c| c.nombre().to_string()).collect();
+        VerificadorRestricciones::new(&Esquema::new(columnas), &nombres).unwrap()
+    }
+
+    fn fila(valores: &[&str]) -> Vec<String> {
+        valores.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    pub fn test01_una_fila_que_cumple_las_restricciones_es_valida() {
+        let mut verificador = armar_verificador();
+
+        verificador
+            .registrar(&fila(&["1", "ana@mail.com", "Ana", "30"]))
+            .unwrap();
+        let resultado = verificador.verificar(&fila(&["2", "juan@mail.com", "Juan", ""]));
+
+        assert!(resultado.is_ok());
+    }
+
+    #[test]
+    pub fn test02_falla_si_una_columna_not_null_esta_vacia() {
+        let mut verificador = armar_verificador();
+
+        let resultado = verificador.verificar(&fila(&["1", "ana@mail.com", "", "30"]));
+
+        assert!(
+            matches!(resultado, Err(MyError::ConstraintViolation(m)) if m.contains("NOT NULL"))
+        );
+    }
+
+    #[test]
+    pub fn test03_falla_si_se_repite_la_clave_primaria_de_una_fila_existente() {
+        let mut verificador = armar_verificador();
+
+        verificador
+            .registrar(&fila(&["1", "ana@mail.com", "Ana", "30"]))
+            .unwrap();
+        let resultado = verificador.verificar(&fila(&["1", "otro@mail.com", "Otro", "30"]));
+
+        assert!(
+            matches!(resultado, Err(MyError::ConstraintViolation(m)) if m.contains("PRIMARY KEY"))
+        );
+    }
+
+    #[test]
+    pub fn test04_falla_si_dos_filas_nuevas_repiten_un_valor_unico() {
+        let mut verificador = armar_verificador();
+
+        verificador
+            .verificar(&fila(&["1", "ana@mail.com", "Ana", "30"]))
+            .unwrap();
+        let resultado = verificador.verificar(&fila(&["2", "ana@mail.com", "Ana", "30"]));
+
+        assert!(matches!(resultado, Err(MyError::ConstraintViolation(m)) if m.contains("UNIQUE")));
+    }
+
+    #[test]
+    pub fn test05_falla_si_una_fila_existente_repite_el_valor_de_una_fila_nueva() {
+        let mut verificador = armar_verificador();
+
+        verificador
+            .verificar(&fila(&["3", "ana@mail.com", "Ana", "30"]))
+            .unwrap();
+        let resultado = verificador.registrar(&fila(&["3", "juan@mail.com", "Juan", "40"]));
+
+        assert!(resultado.is_err());
+    }
+
+    #[test]
+    pub fn test06_falla_si_una_fila_no_cumple_un_check() {
+        let tokens: Vec<String> = ["cantidad", "INT", "CHECK", "(cantidad", ">", "0)"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let mut esquema = Esquema::new(vec![Columna::desde_definicion(&tokens).unwrap()]);
+        esquema.agregar_check("(cantidad < 10)".to_string());
+        let mut verificador =
+            VerificadorRestricciones::new(&esquema, &["cantidad".to_string()]).unwrap();
+
+        let valida = verificador.verificar(&fila(&["5"]));
+        let cero = verificador.verificar(&fila(&["0"]));
+        let diez = verificador.verificar(&fila(&["10"]));
+
+        assert!(valida.is_ok());
+        assert!(
+            matches!(cero, Err(MyError::ConstraintViolation(m)) if m.contains("CHECK (cantidad > 0) de la columna cantidad"))
+        );
+        assert!(
+            matches!(diez, Err(MyError::ConstraintViolation(m)) if m.contains("CHECK (cantidad < 10) de la tabla"))
+        );
+    }
+
+    #[test]
+    pub fn test07_un_check_sobre_un_valor_vacio_no_se_viola() {
+        let tokens: Vec<String> = ["cantidad", "INT", "CHECK", "(cantidad", ">", "0)"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let mut esquema = Esquema::new(vec![Columna::desde_definicion(&tokens).unwrap()]);
+        esquema.agregar_check("(NOT (cantidad = 4))".to_string());
+        let mut verificador =
+            VerificadorRestricciones::new(&esquema, &["cantidad".to_string()]).unwrap();
+
+        let vacio = verificador.verificar(&fila(&[""]));
+        let cuatro = verificador.verificar(&fila(&["4"]));
+
+        assert!(vacio.is_ok());
+        assert!(
+            matches!(cuatro, Err(MyError::ConstraintViolation(m)) if m.contains("de la tabla"))
+        );
+    }
+
+    #[test]
+    pub fn test08_falla_si_un_valor_no_es_del_tipo_de_su_columna() {
+        let mut verificador = armar_verificador();
+
+        let id = verificador.verificar(&fila(&["abc", "ana@mail.com", "Ana", "30"]));
+        let edad = verificador.verificar(&fila(&["1", "ana@mail.com", "Ana", "treinta"]));
+        let texto = verificador.verificar(&fila(&["1", "123", "Ana", ""]));
+
+        assert!(matches!(id, Err(MyError::InvalidColumn(m)) if m.contains("columna id")));
+        assert!(matches!(edad, Err(MyError::InvalidColumn(m)) if m.contains("columna edad")));
+        assert!(texto.is_ok());
+    }
+}
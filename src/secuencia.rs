@@ -0,0 +1,251 @@
+use crate::almacenamiento;
+use crate::memoria;
+use crate::my_error::MyError;
+use crate::transaccion;
+use crate::wal;
+use std::fs::{self, File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+
+///# Secuencia
+///Esta estructura lleva el siguiente valor de la columna AUTO_INCREMENT de una tabla. El valor se
+///guarda en un archivo con el mismo nombre que la tabla y extensión *.secuencia*. Mientras la
+///secuencia está abierta el archivo queda bloqueado, de modo que dos sesiones que insertan en la
+///misma tabla a la vez no pueden entregar el mismo valor. La secuencia de una tabla en memoria se
+///guarda junto con la tabla.
+///
+///**Ejemplo**
+///let mut secuencia = Secuencia::abrir(direccion_tabla, posicion_columna)?;
+///let id = secuencia.siguiente();
+///secuencia.guardar()?;
+///
+///**Parámetros**
+///- 'ruta': Es la dirección del archivo de la secuencia.
+///- 'destino': Es donde se guarda la secuencia: su archivo, bloqueado de forma exclusiva, o la
+///  tabla en memoria.
+///- 'siguiente': Es el próximo valor que se va a entregar.
+#[derive(Debug)]
+pub struct Secuencia {
+    ruta: String,
+    destino: Destino,
+    siguiente: i64,
+}
+
+///Es donde se guarda el valor de una *Secuencia*.
+#[derive(Debug)]
+enum Destino {
+    Archivo(File),
+    Memoria(String),
+}
+
+impl Secuencia {
+    ///# Secuencia::ruta()
+    ///Esta función devuelve la dirección del archivo de secuencia que le corresponde a una tabla.
+    ///Si la tabla es una copia dentro de una transacción, la secuencia es la de la tabla de la
+    ///base.
+    ///
+    ///**Parámetros**
+    ///- 'archivo_tabla': Es la dirección del archivo de la tabla.
+    pub fn ruta(archivo_tabla: &str) -> String {
+        transaccion::ruta_en_la_base(&format!(
+            "{}.secuencia",
+            almacenamiento::sin_extension(archivo_tabla)
+        ))
+    }
+
+    ///# Secuencia::abrir()
+    ///Esta función abre y bloquea la secuencia de una tabla, esperando si otra sesión la tiene
+    ///abierta. Si la tabla todavía no tiene archivo de secuencia se empieza por el mayor valor de
+    ///la columna más uno.
+    ///
+    ///**Parámetros**
+    ///- 'archivo_tabla': Es la dirección del archivo de la tabla.
+    ///- 'posicion': Es la posición de la columna AUTO_INCREMENT en la tabla.
+    ///
+    ///**Return**
+    ///Devuelve un *Result<Secuencia, MyError>*, en caso de que no se pueda leer o bloquear la
+    ///  secuencia se devuelve un error del tipo *MyError*.
+    pub fn abrir(archivo_tabla: &str, posicion: usize) -> Result<Secuencia, MyError> {
+        let error =
+            || MyError::Error("Fallo en la lectura de la secuencia de la tabla".to_string());
+
+        let ruta = Secuencia::ruta(archivo_tabla);
+        if archivo_tabla.starts_with(memoria::PREFIJO) {
+            let siguiente = match memoria::leer_secuencia(archivo_tabla)? {
+                Some(v) => v,
+                None => maximo_de_columna(archivo_tabla, posicion)? + 1,
+            };
+            return Ok(Secuencia {
+                ruta,
+                destino: Destino::Memoria(archivo_tabla.to_string()),
+                siguiente,
+            });
+        }
+
+        let mut archivo = match OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&ruta)
+        {
+            Ok(f) => f,
+            Err(_e) => return Err(error()),
+        };
+
+        if archivo.lock().is_err() {
+            return Err(error());
+        }
+
+        let mut contenido = String::new();
+        if archivo.read_to_string(&mut contenido).is_err() {
+            return Err(error());
+        }
+
+        let siguiente = match contenido.trim() {
+            "" => maximo_de_columna(archivo_tabla, posicion)? + 1,
+            valor => match valor.parse::<i64>() {
+                Ok(v) => v,
+                Err(_e) => return Err(error()),
+            },
+        };
+
+        Ok(Secuencia {
+            ruta,
+            destino: Destino::Archivo(archivo),
+            siguiente,
+        })
+    }
+
+    ///# Secuencia.siguiente()
+    ///Esta función entrega el próximo valor de la secuencia y la avanza.
+    pub fn siguiente(&mut self) -> String {
+        let valor = self.siguiente;
+        self.siguiente += 1;
+        valor.to_string()
+    }
+
+    ///# Secuencia.observar()
+    ///Esta función registra un valor escrito explícitamente en la columna, para que la secuencia
+    ///no vuelva a entregarlo.
+    ///
+    ///**Parámetros**
+    ///- 'valor': Es el valor escrito en la columna AUTO_INCREMENT.
+    pub fn observar(&mut self, valor: &str) {
+        if let Ok(v) = valor.parse::<i64>() {
+            if v >= self.siguiente {
+                self.siguiente = v + 1;
+            }
+        }
+    }
+
+    ///# Secuencia.guardar()
+    ///Esta función escribe el próximo valor en el archivo de la secuencia y la libera.
+    ///
+    ///**Return**
+    ///Devuelve un *Result<(), MyError>*, en caso de que no se pueda escribir el archivo se
+    ///  devuelve un error del tipo *MyError*.
+    pub fn guardar(self) -> Result<(), MyError> {
+        let mut archivo = match self.destino {
+            Destino::Archivo(archivo) => archivo,
+            Destino::Memoria(tabla) => {
+                return memoria::guardar_secuencia(&tabla, Some(self.siguiente))
+            }
+        };
+
+        wal::registrar_copia(&self.ruta)?;
+        let resultado = archivo
+            .set_len(0)
+            .and_then(|_| archivo.seek(SeekFrom::Start(0)))
+            .and_then(|_| archivo.write_all(format!("{}\n", self.siguiente).as_bytes()))
+            .and_then(|_| archivo.sync_all());
+
+        match resultado {
+            Ok(_) => Ok(()),
+            Err(_e) => Err(MyError::Error(
+                "Fallo en la escritura de la secuencia de la tabla".to_string(),
+            )),
+        }
+    }
+}
+
+fn maximo_de_columna(archivo_tabla: &str, posicion: usize) -> Result<i64, MyError> {
+    let mut recorrido = almacenamiento::abrir(archivo_tabla).recorrer(None)?;
+
+    let mut maximo = 0;
+    while let Some(fila) = recorrido.siguiente()? {
+        if let Some(Ok(v)) = fila.get(posicion).map(|v| v.parse::<i64>()) {
+            maximo = maximo.max(v);
+        }
+    }
+
+    Ok(maximo)
+}
+
+///# secuencia::eliminar()
+///Esta función borra el archivo de secuencia de una tabla, si existe.
+///
+///**Return**
+///Devuelve un *Result<(), MyError>*, en caso de que no se pueda registrar el borrado en el
+///  registro de la instrucción se devuelve un error del tipo *MyError*.
+pub fn eliminar(archivo_tabla: &str) -> Result<(), MyError> {
+    if archivo_tabla.starts_with(memoria::PREFIJO) {
+        let _ = memoria::guardar_secuencia(archivo_tabla, None);
+        return Ok(());
+    }
+
+    let ruta = Secuencia::ruta(archivo_tabla);
+    wal::registrar(&ruta)?;
+    let _ = fs::remove_file(ruta);
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn test01_una_secuencia_nueva_empieza_despues_del_mayor_valor_de_la_tabla() {
+        let tabla = "./test/secuencia/secuencia_nueva.csv";
+        let _ = fs::create_dir_all("./test/secuencia");
+        let _ = fs::write(tabla, "id,nombre\n3,Ana\n7,Juan\n");
+        let _ = eliminar(tabla);
+
+        let mut secuencia = Secuencia::abrir(tabla, 0).unwrap();
+        let primero = secuencia.siguiente();
+        secuencia.observar("10");
+        let segundo = secuencia.siguiente();
+        secuencia.guardar().unwrap();
+
+        assert_eq!(primero, "8");
+        assert_eq!(segundo, "11");
+        assert_eq!(fs::read_to_string(Secuencia::ruta(tabla)).unwrap(), "12\n");
+    }
+
+    #[test]
+    pub fn test02_dos_sesiones_no_entregan_el_mismo_valor() {
+        let tabla = "./test/secuencia/secuencia_concurrente.csv";
+        let _ = fs::create_dir_all("./test/secuencia");
+        let _ = fs::write(tabla, "id\n");
+        let _ = eliminar(tabla);
+
+        let hilos: Vec<_> = (0..4)
+            .map(|_| {
+                std::thread::spawn(move || {
+                    let mut valores = Vec::new();
+                    for _ in 0..25 {
+                        let mut secuencia = Secuencia::abrir(tabla, 0).unwrap();
+                        valores.push(secuencia.siguiente());
+                        secuencia.guardar().unwrap();
+                    }
+                    valores
+                })
+            })
+            .collect();
+
+        let mut valores: Vec<String> = hilos.into_iter().flat_map(|h| h.join().unwrap()).collect();
+        valores.sort();
+        valores.dedup();
+
+        assert_eq!(valores.len(), 100);
+    }
+}
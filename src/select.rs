@@ -1,7 +1,15 @@
+use crate::almacenamiento::{self, Almacenamiento};
 use crate::condicion::Condicion;
 use crate::my_error::MyError;
-use std::fs::File;
-use std::io::{BufRead, BufReader};
+use crate::operadores::{
+    self, Agregacion, Agregado, Fila, Filtro, Medido, Operador, Orden, Proyeccion,
+};
+use crate::ordenamiento;
+use crate::planificador::{Medicion, Mediciones, Plan};
+use crate::read_csv::ReadCsv;
+use std::cell::Cell;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
 
 ///# Select
 ///Esta estructura proporciona todo lo necesario para la implementación de la instrucción SELECT en
@@ -22,6 +30,10 @@ use std::io::{BufRead, BufReader};
 ///  tienen que mostrar en caso de pedirlo.
 ///- 'asc': Es el valor que representa, en caso de pedirlo, a ordenar de manera ascendente si es
 ///  true o descendente si es false.
+///- 'lectura': Es el archivo que se lee con *read_csv()* en el FROM, si la consulta no es sobre
+///  una tabla del directorio.
+///- 'agrupacion': Es la columna del GROUP BY (si hay) y las funciones de agregación que se
+///  calculan, si la consulta agrupa las filas.
 #[derive(Debug, PartialEq)]
 pub struct Select {
     archivo: String,
@@ -29,6 +41,8 @@ pub struct Select {
     condicion: Condicion,
     order: String,
     asc: bool,
+    lectura: Option<ReadCsv>,
+    agrupacion: Option<(Option<String>, Vec<Agregado>)>,
 }
 
 impl Select {
@@ -62,138 +76,221 @@ impl Select {
             condicion,
             order,
             asc,
+            lectura: None,
+            agrupacion: None,
         }
     }
+
+    ///# Select.con_lectura()
+    ///Esta función hace que el SELECT lea el archivo indicado con *read_csv()* en lugar de la
+    ///tabla de su dirección.
+    ///
+    ///**Parámetros**
+    ///- 'lectura': Es el archivo y la forma de leerlo.
+    ///
+    ///**Return**
+    ///Devuelve el mismo *Select*.
+    pub fn con_lectura(mut self, lectura: ReadCsv) -> Self {
+        self.lectura = Some(lectura);
+        self
+    }
+
+    ///# Select.con_agrupacion()
+    ///Esta función hace que el SELECT agrupe las filas que cumplen la condición y muestre una
+    ///fila por grupo con las funciones de agregación calculadas. Las columnas pedidas solo
+    ///pueden ser la del grupo y las de los agregados.
+    ///
+    ///**Parámetros**
+    ///- 'grupo': Es la columna del GROUP BY, o *None* para agrupar todas las filas.
+    ///- 'agregados': Son las funciones de agregación que se calculan.
+    ///
+    ///**Return**
+    ///Devuelve el mismo *Select*.
+    pub fn con_agrupacion(mut self, grupo: Option<String>, agregados: Vec<Agregado>) -> Self {
+        self.agrupacion = Some((grupo, agregados));
+        self
+    }
+
+    fn abrir(&self) -> Box<dyn Almacenamiento> {
+        match &self.lectura {
+            Some(lectura) => Box::new(lectura.clone()),
+            None => almacenamiento::abrir(&self.archivo),
+        }
+    }
+    ///# Select.explicar()
+    ///Esta función realiza la instrucción EXPLAIN SELECT de sql: muestra por pantalla el plan con
+    ///el que se ejecutaría el SELECT, sin ejecutarlo.
+    ///
+    ///**Return**
+    ///Devuelve un *Result<String, MyError>* en caso que durante la ejecución de la función no haya
+    ///  ocurrido ningún error se devuelve un *String* para indicar que la operación se realizó
+    ///  correctamente, en caso contrario se retorna un error del tipo *MyError*.
+    pub fn explicar(&self) -> Result<String, MyError> {
+        let almacenamiento = self.abrir();
+        let _bloqueo = almacenamiento.bloquear(false)?;
+        print!("{}", self.planificar(almacenamiento.as_ref())?);
+        Ok("Proceso completo".to_string())
+    }
+
+    fn planificar(&self, almacenamiento: &dyn Almacenamiento) -> Result<Plan, MyError> {
+        let esquema = almacenamiento.esquema()?;
+        let plan = Plan::new(
+            &self.archivo,
+            &esquema,
+            &self.condicion,
+            &self.order,
+            self.asc,
+            &self.columnas,
+        );
+        match &self.agrupacion {
+            Some((grupo, agregados)) => Ok(plan.con_agrupacion(grupo.as_deref(), agregados)),
+            None => Ok(plan),
+        }
+    }
+
+    ///# Select.analizar()
+    ///Esta función realiza la instrucción EXPLAIN ANALYZE SELECT de sql: ejecuta el SELECT sin
+    ///mostrar sus filas y muestra por pantalla el plan con las filas que entraron y salieron de
+    ///cada etapa y el tiempo que tardó cada una.
+    ///
+    ///**Return**
+    ///Devuelve un *Result<String, MyError>* en caso que durante la ejecución de la función no haya
+    ///  ocurrido ningún error se devuelve un *String* para indicar que la operación se realizó
+    ///  correctamente, en caso contrario se retorna un error del tipo *MyError*.
+    pub fn analizar(&self) -> Result<String, MyError> {
+        let mut mediciones = Mediciones::default();
+        let plan = self.ejecutar(&mut mediciones, &mut |_| {})?;
+        print!("{}", plan.analisis(&mediciones));
+        Ok("Proceso completo".to_string())
+    }
+
     ///# Selcet.seleccionar()
-    ///Esta función realiza la instrucción SELECT de sql.
+    ///Esta función realiza la instrucción SELECT de sql. Las filas se leen según el plan que arma
+    ///el planificador: si la condición exige una comparación sobre una columna que tiene índice
+    ///solo se leen las filas que indica el índice, en lugar de recorrer toda la tabla.
     ///
     ///**Return**
     ///Devuelve un *Result<String, MyError>* en caso que durante la ejecución de la función no haya
     ///  ocurrido ningún erro se devuelve un *String* para indicar que la opreción se realizó
     ///  correctamente, en caso contrario se retorna un error del tipo *MyError*.
     pub fn seleccionar(&self) -> Result<String, MyError> {
-        let archivo = match File::open(&self.archivo) {
-            Ok(f) => f,
-            Err(_e) => {
-                return Err(MyError::InvalidTable(
-                    "Directorio o nombre de la tabla incorrecto".to_string(),
-                ))
-            }
-        };
-
-        let mut buffer = BufReader::new(archivo);
-
-        let mut linea = String::new();
-        match buffer.read_line(&mut linea) {
-            Ok(l) => l,
-            Err(_e) => {
-                return Err(MyError::Error(
-                    "Fallo en el proceso de selección".to_string(),
-                ))
-            }
-        };
+        self.ejecutar(&mut Mediciones::default(), &mut |linea| {
+            println!("{}", linea)
+        })?;
 
-        let columnas: Vec<String> = linea
-            .replace("\n", "")
-            .split(",")
-            .map(|s| s.to_string())
-            .collect();
+        Ok("Proceso completo".to_string())
+    }
 
-        let _ = match self.corroborar_columnas(&columnas) {
-            Ok(i) => i,
-            Err(e) => return Err(e),
-        };
+    ///# Select.filas()
+    ///Esta función ejecuta el SELECT y devuelve sus filas en lugar de mostrarlas, para usarlas
+    ///por ejemplo en INSERT ... SELECT.
+    ///
+    ///**Return**
+    ///Devuelve un *Result<(Vec<String>, Vec<Fila>), MyError>* con los nombres de las columnas
+    ///  pedidas y las filas, en caso contrario se retorna un error del tipo *MyError*.
+    pub fn filas(&self) -> Result<(Vec<String>, Vec<Fila>), MyError> {
+        let almacenamiento = self.abrir();
+        let _bloqueo = almacenamiento.bloquear(false)?;
+        let (_, mut operador, _) = self.armar(almacenamiento.as_ref())?;
+        let columnas = operador.columnas().to_vec();
+        let filas = operadores::recolectar(operador.as_mut())?;
+        Ok((columnas, filas))
+    }
 
-        let mut lineas_elegidas: Vec<Vec<String>> = Vec::<Vec<String>>::new();
-
-        for line in buffer.lines() {
-            let linea = match line {
-                Ok(l) => l,
-                Err(_e) => {
-                    return Err(MyError::Error(
-                        "Fallo en el proceso de selección".to_string(),
-                    ))
-                }
-            };
-
-            let datos: Vec<String> = linea
-                .replace("\n", "")
-                .split(",")
-                .map(|s| s.to_string())
-                .collect();
-
-            let verificacion = match self.condicion.verificar(&columnas, &datos) {
-                Ok(c) => c,
-                Err(e) => return Err(e),
-            };
-
-            if verificacion {
-                lineas_elegidas.push(datos);
-            }
+    ///Ejecuta el SELECT pasándole a 'mostrar' cada línea que hay que mostrar (la primera es el
+    ///encabezado) a medida que se obtiene, y devuelve el plan usado registrando en 'mediciones'
+    ///lo que hizo cada etapa.
+    fn ejecutar(
+        &self,
+        mediciones: &mut Mediciones,
+        mostrar: &mut dyn FnMut(String),
+    ) -> Result<Plan, MyError> {
+        let almacenamiento = self.abrir();
+        let _bloqueo = almacenamiento.bloquear(false)?;
+        let (plan, mut operador, etapas) = self.armar(almacenamiento.as_ref())?;
+
+        mostrar(self.encabezado(operador.columnas()));
+        while let Some(fila) = operador.siguiente()? {
+            mostrar(self.formatear(&fila));
         }
 
-        let mut aux = String::new();
-        for c in &self.columnas {
-            if !aux.is_empty() {
-                aux.push(',');
-            }
+        *mediciones = etapas.mediciones();
+        Ok(plan)
+    }
 
-            aux.push_str(c);
+    ///Arma la cadena de operadores del SELECT según el plan: el recorrido de la tabla (completa o
+    ///por las posiciones del índice), el filtro del WHERE, el ordenamiento del ORDER BY si la
+    ///columna existe y la proyección de las columnas pedidas. Cada etapa se envuelve en un
+    ///*Medido* para poder mostrarla en EXPLAIN ANALYZE.
+    fn armar(
+        &self,
+        almacenamiento: &dyn Almacenamiento,
+    ) -> Result<(Plan, Box<dyn Operador>, Etapas), MyError> {
+        let plan = self.planificar(almacenamiento)?;
+        let inicio = Instant::now();
+        let candidatas = plan.acceso().filas(&self.archivo)?;
+        let busqueda = inicio.elapsed();
+
+        let recorrido = almacenamiento.recorrer_donde(candidatas, &self.condicion)?;
+        if self.agrupacion.is_none() {
+            self.corroborar_columnas(recorrido.columnas())?;
         }
-        let _ = self.ordenar_lineas_elegidas(&mut lineas_elegidas, &columnas);
-        self.mostrar_lineas_elegidas(lineas_elegidas, columnas);
 
-        Ok("Proceso completo".to_string())
-    }
+        let (acceso, medicion_acceso) = Medido::new(recorrido);
+        let filtro = Filtro::new(Box::new(acceso), &self.condicion)?;
+        let (filtro, medicion_filtro) = Medido::new(Box::new(filtro));
 
-    fn ordenar_lineas_elegidas(
-        &self,
-        lineas: &mut [Vec<String>],
-        col: &[String],
-    ) -> Result<String, MyError> {
-        let index = match col.iter().position(|c| *c == self.order) {
-            Some(i) => i,
-            None => {
-                return Err(MyError::InvalidColumn(
-                    "Columna especificada para ordenar no existe en la tabla".to_string(),
-                ))
+        let (filas, medicion_agrupacion): (Box<dyn Operador>, _) = match &self.agrupacion {
+            Some((grupo, agregados)) => {
+                let agregacion =
+                    Agregacion::new(Box::new(filtro), grupo.as_deref(), agregados.clone())?;
+                let (agregacion, medicion) = Medido::new(Box::new(agregacion));
+                (Box::new(agregacion), Some(medicion))
             }
+            None => (Box::new(filtro), None),
         };
-
-        match self.asc {
-            true => lineas.sort_by_key(|l| String::from(&l[index])),
-            false => {
-                lineas.sort_by_key(|l| String::from(&l[index]));
-                lineas.reverse();
+        let ordenar = !self.order.is_empty() && filas.columnas().contains(&self.order);
+
+        let (operador, medicion_orden): (Box<dyn Operador>, _) = match ordenar {
+            true => {
+                let orden = Orden::new(
+                    filas,
+                    &almacenamiento.ubicacion_temporal(),
+                    &self.order,
+                    self.asc,
+                    ordenamiento::presupuesto_configurado(),
+                )?;
+                let (orden, medicion) = Medido::new(Box::new(orden));
+                (Box::new(orden), Some(medicion))
             }
+            false => (filas, None),
         };
 
-        Ok("Todo ok".to_string())
+        let proyeccion = Proyeccion::new(operador, &self.columnas)?;
+        let (proyeccion, medicion_proyeccion) = Medido::new(Box::new(proyeccion));
+
+        let etapas = Etapas {
+            busqueda,
+            acceso: medicion_acceso,
+            filtro: medicion_filtro,
+            agrupacion: medicion_agrupacion,
+            orden: medicion_orden,
+            proyeccion: medicion_proyeccion,
+        };
+        Ok((plan, Box::new(proyeccion), etapas))
     }
 
-    fn mostrar_lineas_elegidas(&self, lineas: Vec<Vec<String>>, col: Vec<String>) {
+    fn encabezado(&self, col: &[String]) -> String {
         match self.columnas.contains(&"*".to_string()) {
-            true => println!("{}", col.join(", ")),
-            false => println!("{}", self.columnas.join(", ")),
+            true => col.join(", "),
+            false => self.columnas.join(", "),
         }
+    }
 
-        for l in &lineas {
-            let mut aux = String::new();
-
-            if self.columnas.contains(&String::from("*")) {
-                aux = l.join(", ");
-            } else {
-                for c in &self.columnas {
-                    let pos = col.iter().position(|d| *d == *c).unwrap_or(0);
-
-                    if !aux.is_empty() {
-                        aux.push(',');
-                    }
-
-                    aux.push_str(&l[pos]);
-                }
-            }
-            println!("{}", aux);
+    fn formatear(&self, fila: &[String]) -> String {
+        match self.columnas.contains(&"*".to_string()) {
+            true => fila.join(", "),
+            false => fila.join(","),
         }
     }
 
@@ -214,10 +311,62 @@ impl Select {
     }
 }
 
+///Son las mediciones de cada operador del SELECT. Como cada *Medido* incluye el tiempo de los
+///operadores anteriores, el de cada etapa se obtiene restándole el de la etapa previa.
+struct Etapas {
+    busqueda: Duration,
+    acceso: Rc<Cell<Medicion>>,
+    filtro: Rc<Cell<Medicion>>,
+    agrupacion: Option<Rc<Cell<Medicion>>>,
+    orden: Option<Rc<Cell<Medicion>>>,
+    proyeccion: Rc<Cell<Medicion>>,
+}
+
+impl Etapas {
+    fn mediciones(&self) -> Mediciones {
+        let acceso = self.acceso.get();
+        let filtro = self.filtro.get();
+        let proyeccion = self.proyeccion.get();
+        let etapa = |actual: Medicion, previa: Medicion| {
+            Medicion::new(
+                previa.filas_salida(),
+                actual.filas_salida(),
+                actual.duracion().saturating_sub(previa.duracion()),
+            )
+        };
+
+        let salteada = |previa: Medicion| {
+            Medicion::new(previa.filas_salida(), previa.filas_salida(), Duration::ZERO)
+        };
+        let (agrupacion, previa) = match &self.agrupacion {
+            Some(agrupacion) => (etapa(agrupacion.get(), filtro), agrupacion.get()),
+            None => (salteada(filtro), filtro),
+        };
+        let (orden, previa) = match &self.orden {
+            Some(orden) => (etapa(orden.get(), previa), orden.get()),
+            None => (salteada(previa), previa),
+        };
+
+        Mediciones {
+            acceso: Medicion::new(
+                acceso.filas_salida(),
+                acceso.filas_salida(),
+                acceso.duracion() + self.busqueda,
+            ),
+            filtro: etapa(filtro, acceso),
+            agrupacion,
+            orden,
+            proyeccion: etapa(proyeccion, previa),
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
     use crate::condicion_simple::CondicionSimple;
+    use crate::operacion::Operacion;
+    use crate::parser::Parser;
 
     #[test]
     pub fn test01_se_crea_un_select_correctamente() {
@@ -235,6 +384,8 @@ mod test {
             condicion: Condicion::SiempreTrue,
             order: "".to_string(),
             asc: false,
+            lectura: None,
+            agrupacion: None,
         };
 
         assert_eq!(select_esperado, select);
@@ -289,4 +440,48 @@ mod test {
 
         assert!(resultado.is_ok());
     }
+
+    #[test]
+    pub fn test04_se_agrupan_las_filas_de_un_select_parseado() {
+        let operacion = Parser::new().crear_operacion(
+            "./test".to_string(),
+            "SELECT id_cliente, COUNT(*), SUM(cantidad) FROM select WHERE id_cliente > 3 GROUP BY id_cliente ORDER BY SUM(cantidad) DESC"
+                .to_string(),
+        );
+        let select = match operacion {
+            Ok(Operacion::Select(select)) => select,
+            _ => panic!("Se esperaba un SELECT"),
+        };
+
+        let (columnas, filas) = select.filas().unwrap();
+
+        assert_eq!(columnas, vec!["id_cliente", "COUNT(*)", "SUM(cantidad)"]);
+        assert_eq!(
+            filas,
+            vec![
+                vec!["6".to_string(), "3".to_string(), "6".to_string()],
+                vec!["4".to_string(), "2".to_string(), "3".to_string()],
+                vec!["5".to_string(), "2".to_string(), "2".to_string()],
+            ]
+        );
+        let plan = select.planificar(select.abrir().as_ref()).unwrap();
+        assert!(plan
+            .etapas()
+            .contains(&"Agrupación por id_cliente (COUNT(*), SUM(cantidad))".to_string()));
+    }
+
+    #[test]
+    pub fn test05_un_agregado_sobre_una_columna_inexistente_falla() {
+        let operacion = Parser::new().crear_operacion(
+            "./test".to_string(),
+            "SELECT MAX(precio) FROM select".to_string(),
+        );
+
+        match operacion {
+            Ok(Operacion::Select(select)) => {
+                assert!(matches!(select.filas(), Err(MyError::InvalidColumn(_))))
+            }
+            _ => panic!("Se esperaba un SELECT"),
+        }
+    }
 }
@@ -0,0 +1,549 @@
+use crate::my_error::MyError;
+use crate::operacion::Operacion;
+use crate::parser::Parser;
+use crate::transaccion::Transaccion;
+use crate::wal::Wal;
+
+///# Sesion
+///Esta estructura ejecuta una secuencia de instrucciones sobre una base de datos, ya sea desde un
+///script o desde la consola. Además de las instrucciones que entiende el *Parser* maneja las
+///transacciones: BEGIN, COMMIT y ROLLBACK, y dentro de ellas SAVEPOINT, ROLLBACK TO y RELEASE.
+///
+///**Ejemplo**
+///let mut sesion = Sesion::new(directorio);
+///sesion.ejecutar("BEGIN")?;
+///sesion.ejecutar("DELETE FROM clientes WHERE id = 1")?;
+///sesion.ejecutar("COMMIT")?;
+///
+///**Parámetros**
+///- 'directorio': Es el directorio de la base de datos.
+///- 'transaccion': Es la transacción en curso, si hay una. Si la sesión termina sin COMMIT se
+///  descarta.
+#[derive(Debug)]
+pub struct Sesion {
+    directorio: String,
+    transaccion: Option<Transaccion>,
+}
+
+impl Sesion {
+    ///# Sesion::new()
+    ///Esta función crea una nueva instancia de Sesion.
+    ///
+    ///**Parámetros**
+    ///- 'directorio': Es el directorio de la base de datos.
+    ///
+    ///**Return**
+    ///Devuelve un *struct* de tipo *Sesion*.
+    pub fn new(directorio: String) -> Self {
+        Sesion {
+            directorio,
+            transaccion: None,
+        }
+    }
+
+    ///# Sesion.ejecutar()
+    ///Esta función ejecuta una instrucción. Si hay una transacción en curso la instrucción trabaja
+    ///sobre las copias de la transacción y sus cambios recién llegan a la base con COMMIT. En
+    ///los dos casos la instrucción se ejecuta con un registro de escritura anticipada: si falla,
+    ///los archivos que llegó a modificar (por ejemplo, tablas hijas de un ON DELETE CASCADE)
+    ///vuelven a su estado anterior, también dentro de la transacción.
+    ///
+    ///**Parámetros**
+    ///- 'instruccion': Es la instrucción que se quiere ejecutar, con o sin ';' al final.
+    ///
+    ///**Return**
+    ///Devuelve un *Result<String, MyError>* en caso que durante la ejecución no haya ocurrido
+    ///  ningún error se devuelve un *String* para indicar que la instrucción se realizó
+    ///  correctamente, en caso contrario se devuelve un error de tipo *MyError*.
+    pub fn ejecutar(&mut self, instruccion: &str) -> Result<String, MyError> {
+        let instruccion = instruccion
+            .trim()
+            .trim_end_matches(';')
+            .split_whitespace()
+            .collect::<Vec<&str>>()
+            .join(" ");
+
+        match instruccion.to_uppercase().as_str() {
+            "BEGIN" | "BEGIN TRANSACTION" | "START TRANSACTION" => {
+                if self.transaccion.is_some() {
+                    return Err(MyError::InvalidSyntax(
+                        "Ya hay una transacción en curso".to_string(),
+                    ));
+                }
+                self.transaccion = Some(Transaccion::iniciar(&self.directorio)?);
+                Ok(String::from("Se inició la transacción"))
+            }
+            "COMMIT" | "END" => self.terminar_transaccion()?.confirmar(),
+            "ROLLBACK" => Ok(self.terminar_transaccion()?.deshacer()),
+            orden
+                if orden.starts_with("SAVEPOINT ")
+                    || orden.starts_with("ROLLBACK TO ")
+                    || orden.starts_with("RELEASE ") =>
+            {
+                self.manejar_punto(&instruccion)
+            }
+            _ => {
+                let directorio = match &self.transaccion {
+                    Some(t) => t.directorio_de_trabajo(),
+                    None => String::from(&self.directorio),
+                };
+                let operacion = Parser::new().crear_operacion(directorio.clone(), instruccion)?;
+                realizar_con_registro(&directorio, operacion)
+            }
+        }
+    }
+
+    ///# Sesion.en_transaccion()
+    ///Esta función indica si hay una transacción en curso.
+    pub fn en_transaccion(&self) -> bool {
+        self.transaccion.is_some()
+    }
+
+    fn manejar_punto(&mut self, instruccion: &str) -> Result<String, MyError> {
+        let palabras: Vec<&str> = instruccion.split(' ').collect();
+        let orden = palabras[0].to_uppercase();
+
+        let mut resto = match orden.as_str() {
+            "ROLLBACK" => &palabras[2..],
+            _ => &palabras[1..],
+        };
+        if orden != "SAVEPOINT" && resto.len() == 2 && resto[0].eq_ignore_ascii_case("SAVEPOINT") {
+            resto = &resto[1..];
+        }
+
+        let nombre = match resto {
+            [nombre] => *nombre,
+            _ => {
+                return Err(MyError::InvalidSyntax(
+                    "Las instrucciones válidas son: SAVEPOINT nombre, ROLLBACK TO [SAVEPOINT] nombre, RELEASE [SAVEPOINT] nombre".to_string(),
+                ))
+            }
+        };
+
+        let transaccion = match self.transaccion.as_mut() {
+            Some(t) => t,
+            None => {
+                return Err(MyError::InvalidSyntax(
+                    "Los savepoints solo se pueden usar dentro de una transacción".to_string(),
+                ))
+            }
+        };
+
+        match orden.as_str() {
+            "SAVEPOINT" => transaccion.guardar_punto(nombre),
+            "ROLLBACK" => transaccion.volver_a_punto(nombre),
+            _ => transaccion.liberar_punto(nombre),
+        }
+    }
+
+    fn terminar_transaccion(&mut self) -> Result<Transaccion, MyError> {
+        match self.transaccion.take() {
+            Some(t) => Ok(t),
+            None => Err(MyError::InvalidSyntax(
+                "No hay ninguna transacción en curso".to_string(),
+            )),
+        }
+    }
+}
+
+///Ejecuta una operación sobre un directorio con un registro de escritura anticipada, salvo que
+///solo lea.
+fn realizar_con_registro(directorio: &str, operacion: Operacion) -> Result<String, MyError> {
+    if let Operacion::Select(_) | Operacion::Explain(_) | Operacion::ExplainAnalyze(_) = operacion {
+        return operacion.realizar_operacion();
+    }
+
+    let registro = Wal::iniciar(directorio)?;
+    match operacion.realizar_operacion() {
+        Ok(r) => {
+            registro.confirmar()?;
+            Ok(r)
+        }
+        Err(e) => {
+            registro.deshacer()?;
+            Err(e)
+        }
+    }
+}
+
+///# sesion::separar_instrucciones()
+///Esta función separa el contenido de un script en instrucciones. Las instrucciones terminan con
+///';' y pueden ocupar varias líneas; las líneas que empiezan con '--' son comentarios.
+///
+///**Parámetros**
+///- 'script': Es el contenido del script.
+///
+///**Return**
+///Devuelve un *Vec<String>* con las instrucciones en el orden en el que aparecen.
+pub fn separar_instrucciones(script: &str) -> Vec<String> {
+    let sin_comentarios: Vec<&str> = script
+        .lines()
+        .filter(|l| !l.trim_start().starts_with("--"))
+        .collect();
+
+    sin_comentarios
+        .join("\n")
+        .split(';')
+        .map(|i| i.split_whitespace().collect::<Vec<&str>>().join(" "))
+        .filter(|i| !i.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::fs;
+    use std::path::Path;
+
+    fn preparar_base(directorio: &str) {
+        let _ = fs::remove_dir_all(directorio);
+        let _ = fs::create_dir_all(directorio);
+        let _ = fs::copy(
+            "./test/update_copia.csv",
+            format!("{}/ordenes.csv", directorio),
+        );
+    }
+
+    fn archivos(directorio: &str) -> Vec<String> {
+        let mut nombres: Vec<String> = fs::read_dir(directorio)
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().to_string())
+            .filter(|n| !n.ends_with(".lock"))
+            .collect();
+        nombres.sort();
+        nombres
+    }
+
+    #[test]
+    pub fn test01_se_separan_las_instrucciones_de_un_script() {
+        let script = "-- limpieza\nBEGIN;\nDELETE FROM ordenes\n  WHERE id = 1;\nCOMMIT;\n";
+
+        let instrucciones = separar_instrucciones(script);
+
+        assert_eq!(
+            instrucciones,
+            vec!["BEGIN", "DELETE FROM ordenes WHERE id = 1", "COMMIT"]
+        );
+    }
+
+    #[test]
+    pub fn test02_los_cambios_de_una_transaccion_se_publican_con_commit() {
+        let directorio = "./test/sesion/commit";
+        preparar_base(directorio);
+        let original = fs::read_to_string("./test/update_copia.csv").unwrap();
+        let mut sesion = Sesion::new(directorio.to_string());
+
+        sesion.ejecutar("BEGIN").unwrap();
+        sesion
+            .ejecutar("UPDATE ordenes SET cantidad = 9 WHERE id = 101")
+            .unwrap();
+        sesion
+            .ejecutar("DELETE FROM ordenes WHERE id = 103")
+            .unwrap();
+        let durante = fs::read_to_string(format!("{}/ordenes.csv", directorio)).unwrap();
+        sesion.ejecutar("COMMIT;").unwrap();
+        let despues = fs::read_to_string(format!("{}/ordenes.csv", directorio)).unwrap();
+
+        assert_eq!(durante, original);
+        assert!(despues.contains("\n101,1,Laptop,9\n"));
+        assert!(!despues.contains("\n103,"));
+        assert_eq!(archivos(directorio), vec!["ordenes.csv"]);
+    }
+
+    #[test]
+    pub fn test03_rollback_descarta_los_cambios_de_la_transaccion() {
+        let directorio = "./test/sesion/rollback";
+        preparar_base(directorio);
+        let original = fs::read_to_string("./test/update_copia.csv").unwrap();
+        let mut sesion = Sesion::new(directorio.to_string());
+
+        sesion.ejecutar("BEGIN").unwrap();
+        sesion.ejecutar("DELETE FROM ordenes").unwrap();
+        sesion.ejecutar("ROLLBACK").unwrap();
+        let commit_sin_transaccion = sesion.ejecutar("COMMIT");
+
+        assert_eq!(
+            fs::read_to_string(format!("{}/ordenes.csv", directorio)).unwrap(),
+            original
+        );
+        assert!(commit_sin_transaccion.is_err());
+        assert_eq!(archivos(directorio), vec!["ordenes.csv"]);
+    }
+
+    #[test]
+    pub fn test04_una_instruccion_fuera_de_una_transaccion_no_deja_registro() {
+        let directorio = "./test/sesion/registro";
+        preparar_base(directorio);
+        let mut sesion = Sesion::new(directorio.to_string());
+
+        let resultado = sesion.ejecutar("DELETE FROM ordenes WHERE id = 101");
+        let fallida = sesion.ejecutar("DELETE FROM inexistente WHERE id = 101");
+
+        assert!(resultado.is_ok());
+        assert!(fallida.is_err());
+        assert!(!fs::read_to_string(format!("{}/ordenes.csv", directorio))
+            .unwrap()
+            .contains("\n101,"));
+        assert_eq!(archivos(directorio), vec!["ordenes.csv"]);
+    }
+
+    #[test]
+    pub fn test05_rollback_to_deshace_solo_los_cambios_posteriores_al_savepoint() {
+        let directorio = "./test/sesion/savepoint";
+        preparar_base(directorio);
+        let mut sesion = Sesion::new(directorio.to_string());
+
+        sesion.ejecutar("BEGIN").unwrap();
+        sesion
+            .ejecutar("DELETE FROM ordenes WHERE id = 101")
+            .unwrap();
+        sesion.ejecutar("SAVEPOINT paso_uno").unwrap();
+        sesion
+            .ejecutar("DELETE FROM ordenes WHERE id = 103")
+            .unwrap();
+        sesion.ejecutar("SAVEPOINT paso_dos").unwrap();
+        sesion.ejecutar("ROLLBACK TO SAVEPOINT paso_uno").unwrap();
+        let liberar_posterior = sesion.ejecutar("RELEASE paso_dos");
+        sesion.ejecutar("RELEASE SAVEPOINT paso_uno").unwrap();
+        let volver_a_liberado = sesion.ejecutar("ROLLBACK TO paso_uno");
+        sesion.ejecutar("COMMIT").unwrap();
+
+        let contenido = fs::read_to_string(format!("{}/ordenes.csv", directorio)).unwrap();
+
+        assert!(liberar_posterior.is_err());
+        assert!(volver_a_liberado.is_err());
+        assert!(!contenido.contains("\n101,"));
+        assert!(contenido.contains("\n103,"));
+        assert!(sesion.ejecutar("SAVEPOINT fuera").is_err());
+    }
+
+    #[test]
+    pub fn test06_commit_falla_si_otra_sesion_modifico_la_tabla_durante_la_transaccion() {
+        let directorio = "./test/sesion/conflicto";
+        preparar_base(directorio);
+        let mut transaccion = Sesion::new(directorio.to_string());
+        let mut otra = Sesion::new(directorio.to_string());
+
+        transaccion.ejecutar("BEGIN").unwrap();
+        transaccion
+            .ejecutar("DELETE FROM ordenes WHERE id = 101")
+            .unwrap();
+        otra.ejecutar("DELETE FROM ordenes WHERE id = 103").unwrap();
+        let commit = transaccion.ejecutar("COMMIT");
+        let contenido = fs::read_to_string(format!("{}/ordenes.csv", directorio)).unwrap();
+
+        assert!(matches!(commit, Err(MyError::Error(m)) if m.contains("ordenes.csv")));
+        assert!(contenido.contains("\n101,"));
+        assert!(!contenido.contains("\n103,"));
+        assert_eq!(archivos(directorio), vec!["ordenes.csv"]);
+    }
+
+    #[test]
+    pub fn test07_dos_transacciones_usan_la_secuencia_de_la_base() {
+        let directorio = "./test/sesion/secuencia";
+        let _ = fs::remove_dir_all(directorio);
+        let _ = fs::create_dir_all(directorio);
+        fs::write(format!("{}/clientes.csv", directorio), "id,nombre\n").unwrap();
+        fs::write(
+            format!("{}/clientes.esquema", directorio),
+            "id INT AUTO_INCREMENT\nnombre TEXT\n",
+        )
+        .unwrap();
+        let mut primera = Sesion::new(directorio.to_string());
+        let mut segunda = Sesion::new(directorio.to_string());
+
+        primera.ejecutar("BEGIN").unwrap();
+        segunda.ejecutar("BEGIN").unwrap();
+        primera
+            .ejecutar("INSERT INTO clientes (nombre) VALUES (Ana)")
+            .unwrap();
+        segunda
+            .ejecutar("INSERT INTO clientes (nombre) VALUES (Juan)")
+            .unwrap();
+        primera.ejecutar("COMMIT").unwrap();
+        segunda.ejecutar("ROLLBACK").unwrap();
+
+        assert_eq!(
+            fs::read_to_string(format!("{}/clientes.csv", directorio)).unwrap(),
+            "id,nombre\n1,Ana\n"
+        );
+        assert_eq!(
+            fs::read_to_string(format!("{}/clientes.secuencia", directorio)).unwrap(),
+            "3\n"
+        );
+    }
+
+    #[test]
+    pub fn test08_una_tabla_particionada_se_puede_leer_dentro_de_una_transaccion() {
+        let directorio = "./test/sesion/particiones";
+        preparar_base(directorio);
+        let particion = format!("{}/ventas/fecha=2026-10-01", directorio);
+        fs::create_dir_all(&particion).unwrap();
+        fs::write(format!("{}/parte.csv", particion), "id,monto\n1,10\n").unwrap();
+        let mut sesion = Sesion::new(directorio.to_string());
+
+        sesion.ejecutar("BEGIN").unwrap();
+        let select = sesion.ejecutar("SELECT id, fecha FROM ventas");
+        sesion.ejecutar("COMMIT").unwrap();
+
+        assert!(select.is_ok());
+        assert_eq!(archivos(directorio), vec!["ordenes.csv", "ventas"]);
+    }
+
+    #[test]
+    pub fn test09_una_instruccion_que_falla_dentro_de_una_transaccion_no_deja_cambios() {
+        let directorio = "./test/sesion/cascada";
+        let _ = fs::remove_dir_all(directorio);
+        let _ = fs::create_dir_all(directorio);
+        for (tabla, contenido, esquema) in [
+            ("clientes", "id,nombre\n1,Ana\n", "id INT PRIMARY KEY\nnombre TEXT\n"),
+            (
+                "pedidos",
+                "id,id_cliente\n10,1\n",
+                "id INT\nid_cliente INT REFERENCES clientes(id) ON UPDATE CASCADE\n",
+            ),
+            (
+                "facturas",
+                "id,id_cliente\n20,1\n",
+                "id INT\nid_cliente INT CHECK (id_cliente < 5) REFERENCES clientes(id) ON UPDATE CASCADE\n",
+            ),
+        ] {
+            fs::write(format!("{}/{}.csv", directorio, tabla), contenido).unwrap();
+            fs::write(format!("{}/{}.esquema", directorio, tabla), esquema).unwrap();
+        }
+        let mut sesion = Sesion::new(directorio.to_string());
+
+        sesion.ejecutar("BEGIN").unwrap();
+        let update = sesion.ejecutar("UPDATE clientes SET id = 7 WHERE id = 1");
+        sesion.ejecutar("COMMIT").unwrap();
+
+        assert!(matches!(update, Err(MyError::ConstraintViolation(_))));
+        for (tabla, contenido) in [
+            ("clientes", "id,nombre\n1,Ana\n"),
+            ("pedidos", "id,id_cliente\n10,1\n"),
+            ("facturas", "id,id_cliente\n20,1\n"),
+        ] {
+            assert_eq!(
+                fs::read_to_string(format!("{}/{}.csv", directorio, tabla)).unwrap(),
+                contenido
+            );
+        }
+    }
+
+    #[test]
+    pub fn test10_los_savepoints_se_guardan_en_disco_y_se_borran_al_liberarlos() {
+        let directorio = "./test/sesion/savepoint_en_disco";
+        preparar_base(directorio);
+        let mut sesion = Sesion::new(directorio.to_string());
+
+        sesion.ejecutar("BEGIN").unwrap();
+        sesion
+            .ejecutar("DELETE FROM ordenes WHERE id = 101")
+            .unwrap();
+        sesion.ejecutar("SAVEPOINT inicio").unwrap();
+        sesion.ejecutar("DELETE FROM ordenes").unwrap();
+        let trabajo = match &sesion.transaccion {
+            Some(t) => t.directorio_de_trabajo(),
+            None => panic!("No hay transacción"),
+        };
+        let copia = format!("{}/.punto.0/ordenes.csv", trabajo);
+        let guardado = fs::read_to_string(&copia).unwrap();
+        sesion.ejecutar("ROLLBACK TO inicio").unwrap();
+        let restaurado = fs::read_to_string(format!("{}/ordenes.csv", trabajo)).unwrap();
+        sesion.ejecutar("RELEASE inicio").unwrap();
+
+        assert!(guardado.contains("\n103,"));
+        assert!(!guardado.contains("\n101,"));
+        assert_eq!(restaurado, guardado);
+        assert!(!Path::new(&copia).exists());
+        sesion.ejecutar("COMMIT").unwrap();
+        assert_eq!(archivos(directorio), vec!["ordenes.csv"]);
+    }
+
+    #[test]
+    pub fn test11_la_transaccion_copia_cada_tabla_recien_cuando_la_usa() {
+        let directorio = "./test/sesion/copia_por_tabla";
+        preparar_base(directorio);
+        let _ = fs::copy(
+            "./test/update_copia.csv",
+            format!("{}/clientes.csv", directorio),
+        );
+        let mut sesion = Sesion::new(directorio.to_string());
+
+        sesion.ejecutar("BEGIN").unwrap();
+        let trabajo = match &sesion.transaccion {
+            Some(t) => t.directorio_de_trabajo(),
+            None => panic!("No hay transacción"),
+        };
+        let al_iniciar = archivos(&trabajo);
+        sesion
+            .ejecutar("DELETE FROM ordenes WHERE id = 101")
+            .unwrap();
+        let con_ordenes = archivos(&trabajo);
+        sesion.ejecutar("SAVEPOINT uno").unwrap();
+        sesion.ejecutar("DELETE FROM clientes").unwrap();
+        sesion.ejecutar("ROLLBACK TO uno").unwrap();
+        sesion.ejecutar("COMMIT").unwrap();
+
+        assert!(al_iniciar.is_empty());
+        assert_eq!(con_ordenes, vec!["ordenes.csv"]);
+        assert_eq!(
+            fs::read_to_string(format!("{}/clientes.csv", directorio)).unwrap(),
+            fs::read_to_string("./test/update_copia.csv").unwrap()
+        );
+        assert!(!fs::read_to_string(format!("{}/ordenes.csv", directorio))
+            .unwrap()
+            .contains("\n101,"));
+    }
+
+    #[test]
+    pub fn test12_un_savepoint_solo_copia_los_archivos_que_cambiaron_desde_el_anterior() {
+        let directorio = "./test/sesion/savepoint_diferencial";
+        preparar_base(directorio);
+        let mut sesion = Sesion::new(directorio.to_string());
+
+        sesion.ejecutar("BEGIN").unwrap();
+        sesion.ejecutar("SELECT id FROM ordenes").unwrap();
+        sesion.ejecutar("SAVEPOINT sin_cambios").unwrap();
+        sesion
+            .ejecutar("DELETE FROM ordenes WHERE id = 101")
+            .unwrap();
+        sesion.ejecutar("SAVEPOINT uno").unwrap();
+        sesion.ejecutar("SAVEPOINT dos").unwrap();
+        sesion
+            .ejecutar("DELETE FROM ordenes WHERE id = 103")
+            .unwrap();
+        let trabajo = match &sesion.transaccion {
+            Some(t) => t.directorio_de_trabajo(),
+            None => panic!("No hay transacción"),
+        };
+        let copias: Vec<Vec<String>> = (0..3)
+            .map(|n| format!("{}/.punto.{}", trabajo, n))
+            .map(|copia| match Path::new(&copia).exists() {
+                true => archivos(&copia),
+                false => Vec::new(),
+            })
+            .collect();
+        sesion.ejecutar("ROLLBACK TO dos").unwrap();
+        let en_dos = fs::read_to_string(format!("{}/ordenes.csv", trabajo)).unwrap();
+        sesion.ejecutar("ROLLBACK TO sin_cambios").unwrap();
+        let al_inicio = fs::read_to_string(format!("{}/ordenes.csv", trabajo)).unwrap();
+        sesion.ejecutar("COMMIT").unwrap();
+
+        assert_eq!(
+            copias,
+            vec![
+                Vec::<String>::new(),
+                vec!["ordenes.csv".to_string()],
+                Vec::new()
+            ]
+        );
+        assert!(!en_dos.contains("\n101,"));
+        assert!(en_dos.contains("\n103,"));
+        assert_eq!(
+            al_inicio,
+            fs::read_to_string("./test/update_copia.csv").unwrap()
+        );
+        assert_eq!(archivos(directorio), vec!["ordenes.csv"]);
+    }
+}
@@ -0,0 +1,704 @@
+use crate::almacenamiento;
+use crate::archivo_temporal::{self, ArchivoTemporal};
+use crate::bloqueo::Bloqueo;
+use crate::esquema::Esquema;
+use crate::my_error::MyError;
+use crate::particiones;
+use crate::wal::{self, Wal};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs::{self, File};
+use std::hash::Hasher;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, MutexGuard};
+use std::time::SystemTime;
+
+static CONTADOR: AtomicUsize = AtomicUsize::new(0);
+
+///Son las tablas que ya se copiaron al directorio de trabajo de cada transacción abierta, por la
+///dirección de ese directorio.
+static PREPARADAS: Mutex<BTreeMap<PathBuf, Preparacion>> = Mutex::new(BTreeMap::new());
+
+const PREFIJO_TRABAJO: &str = ".transaccion.";
+
+///El largo de un archivo y un resumen de su contenido, para saber si cambió sin guardar una copia.
+type Huella = (u64, u64);
+
+///El largo de un archivo y su fecha de modificación, para saber sin leerlo si cambió desde que se
+///copió o desde el último SAVEPOINT.
+type Estado = (u64, SystemTime);
+
+///Son las tablas que una transacción ya copió a su directorio de trabajo (aunque no existieran en
+///la base), la huella de cada archivo copiado, con la que el COMMIT detecta si otra sesión lo
+///modificó, y el estado con el que quedó la copia, con el que los SAVEPOINT detectan si la
+///transacción lo modificó.
+#[derive(Debug, Default)]
+struct Preparacion {
+    tablas: HashSet<String>,
+    originales: HashMap<String, Huella>,
+    estados: HashMap<String, Estado>,
+}
+
+impl Preparacion {
+    ///Copia los archivos de una tabla de la base al directorio de trabajo, salvo que ya se haya
+    ///copiado: los que se llaman como la tabla (o son un subdirectorio con ese nombre, como los
+    ///de las tablas particionadas), los que empiezan con su nombre seguido de un punto (el de la
+    ///tabla y su esquema) y los de sus índices.
+    fn preparar_tabla(&mut self, base: &Path, trabajo: &Path, tabla: &str) -> Result<(), MyError> {
+        if !self.tablas.insert(tabla.to_string()) {
+            return Ok(());
+        }
+
+        let mut nombres = Vec::new();
+        for nombre in nombres_visibles(base)? {
+            if nombre != tabla && !nombre.starts_with(&format!("{}.", tabla)) {
+                continue;
+            }
+            let ruta = base.join(&nombre);
+            if ruta.is_dir() {
+                agregar_archivos(base, Path::new(&nombre), &mut nombres)?;
+            } else if ruta.is_file() && !nombre.ends_with(".secuencia") {
+                nombres.push(nombre);
+            }
+        }
+
+        if base.join(format!("{}.esquema", tabla)).is_file() {
+            let archivo = almacenamiento::resolver(&base.to_string_lossy(), tabla)?;
+            for indice in Esquema::cargar(&archivo)?.indices() {
+                if let Some(nombre) = Path::new(&indice.ruta(&archivo)).file_name() {
+                    nombres.push(nombre.to_string_lossy().to_string());
+                }
+            }
+        }
+
+        for nombre in nombres {
+            self.copiar_original(base, trabajo, nombre)?;
+        }
+        Ok(())
+    }
+
+    ///Copia un archivo de la base al directorio de trabajo y guarda su huella, salvo que la
+    ///transacción ya lo tenga (porque lo copió antes o porque lo creó ella).
+    fn copiar_original(
+        &mut self,
+        base: &Path,
+        trabajo: &Path,
+        nombre: String,
+    ) -> Result<(), MyError> {
+        let copia = trabajo.join(&nombre);
+        let original = base.join(&nombre);
+        if self.originales.contains_key(&nombre) || copia.exists() || !original.is_file() {
+            return Ok(());
+        }
+
+        if copiar(&original, &copia).is_err() {
+            return Err(MyError::Error(format!(
+                "No se pudo copiar {} a la transacción",
+                nombre
+            )));
+        }
+        self.estados.insert(nombre.clone(), estado(&copia)?);
+        self.originales.insert(nombre, huella(&copia)?);
+        Ok(())
+    }
+}
+
+///# Transaccion
+///Esta estructura representa una transacción abierta con BEGIN. Al iniciarla se crea un
+///directorio de trabajo oculto y todas las instrucciones de la transacción se ejecutan sobre él:
+///cada tabla (con su esquema, sus índices y, si es particionada, sus subdirectorios) se copia
+///desde la base la primera vez que una instrucción la usa, por lo que las tablas que la
+///transacción no toca no se copian. Con COMMIT los archivos que cambiaron se publican en el
+///directorio de la base; con ROLLBACK (o si la transacción se descarta) las copias se borran sin
+///tocar la base.
+///
+///Las secuencias de AUTO_INCREMENT no se copian: las instrucciones de la transacción usan las de
+///la base, bloqueándolas como cualquier otra sesión, por lo que dos transacciones nunca entregan
+///el mismo valor. Los valores entregados no se devuelven con ROLLBACK.
+///
+///**Ejemplo**
+///let transaccion = Transaccion::iniciar(directorio)?;
+///let parser = Parser::new().crear_operacion(transaccion.directorio_de_trabajo(), instruccion);
+///transaccion.confirmar()?;
+///
+///**Parámetros**
+///- 'directorio': Es el directorio de la base de datos.
+///- 'trabajo': Es el directorio oculto con las copias sobre las que trabaja la transacción.
+///- 'puntos': Son los SAVEPOINT de la transacción, en el orden en el que se crearon.
+#[derive(Debug)]
+pub struct Transaccion {
+    directorio: PathBuf,
+    trabajo: PathBuf,
+    puntos: Vec<Punto>,
+}
+
+///Es un SAVEPOINT: su nombre, el directorio oculto (dentro del de trabajo) con la copia de los
+///archivos que cambiaron desde el SAVEPOINT anterior (o desde que se copiaron de la base), el
+///estado de cada archivo del directorio de trabajo en ese momento y los archivos que la
+///transacción ya había copiado de la base.
+#[derive(Debug)]
+struct Punto {
+    nombre: String,
+    copia: PathBuf,
+    estados: HashMap<String, Estado>,
+    copiados: HashSet<String>,
+}
+
+impl Transaccion {
+    ///# Transaccion::iniciar()
+    ///Esta función inicia una transacción sobre el directorio de una base de datos, sin copiar
+    ///todavía ninguna tabla.
+    ///
+    ///**Parámetros**
+    ///- 'directorio': Es el directorio de la base de datos.
+    ///
+    ///**Return**
+    ///Devuelve un *Result<Transaccion, MyError>*, en caso de que no se pueda crear el directorio
+    ///  de trabajo se devuelve un error del tipo *MyError*.
+    pub fn iniciar(directorio: &str) -> Result<Transaccion, MyError> {
+        let directorio = PathBuf::from(directorio);
+        let trabajo = directorio.join(format!(
+            "{}{}.{}",
+            PREFIJO_TRABAJO,
+            process::id(),
+            CONTADOR.fetch_add(1, Ordering::Relaxed)
+        ));
+
+        if !directorio.is_dir() || fs::create_dir(&trabajo).is_err() {
+            return Err(MyError::Error(
+                "No se pudo iniciar la transacción".to_string(),
+            ));
+        }
+        preparadas().insert(trabajo.clone(), Preparacion::default());
+
+        Ok(Transaccion {
+            directorio,
+            trabajo,
+            puntos: Vec::new(),
+        })
+    }
+
+    ///# Transaccion.directorio_de_trabajo()
+    ///Esta función devuelve el directorio sobre el que se tienen que ejecutar las instrucciones de
+    ///la transacción.
+    pub fn directorio_de_trabajo(&self) -> String {
+        self.trabajo.to_string_lossy().to_string()
+    }
+
+    ///# Transaccion.guardar_punto()
+    ///Esta función crea un SAVEPOINT con el estado actual de la transacción. Solo se copian, a un
+    ///directorio oculto dentro del de trabajo, los archivos que cambiaron desde el SAVEPOINT
+    ///anterior (o desde que se copiaron de la base, si no hay uno); los demás se recuperan de
+    ///los SAVEPOINT anteriores o de la base. Para saber si un archivo cambió se usan su largo y
+    ///su fecha de modificación, sin leerlo. Si ya existía uno con el mismo nombre, los ROLLBACK
+    ///TO y RELEASE usan el más reciente.
+    ///
+    ///**Parámetros**
+    ///- 'nombre': Es el nombre del SAVEPOINT.
+    ///
+    ///**Return**
+    ///Devuelve un *Result<String, MyError>*, en caso de que no se pueda leer el directorio de
+    ///  trabajo se devuelve un error del tipo *MyError*.
+    pub fn guardar_punto(&mut self, nombre: &str) -> Result<String, MyError> {
+        let copia = self.trabajo.join(format!(".punto.{}", self.puntos.len()));
+        let _ = fs::remove_dir_all(&copia);
+        let (originales, al_copiar) = self.preparados();
+        let anteriores = self.puntos.last().map(|p| &p.estados);
+
+        let mut estados = HashMap::new();
+        for nombre in listar_archivos(&self.trabajo)? {
+            let actual = estado(&self.trabajo.join(&nombre))?;
+            let previo = anteriores
+                .and_then(|e| e.get(&nombre))
+                .or_else(|| al_copiar.get(&nombre));
+            if previo != Some(&actual)
+                && copiar(&self.trabajo.join(&nombre), &copia.join(&nombre)).is_err()
+            {
+                return Err(MyError::Error(
+                    "No se pudo copiar el directorio de la transacción".to_string(),
+                ));
+            }
+            estados.insert(nombre, actual);
+        }
+
+        self.puntos.push(Punto {
+            nombre: nombre.to_string(),
+            copia,
+            estados,
+            copiados: originales.into_keys().collect(),
+        });
+        Ok(format!("Se creó el savepoint {}", nombre))
+    }
+
+    ///# Transaccion.volver_a_punto()
+    ///Esta función realiza el ROLLBACK TO de un SAVEPOINT: descarta los cambios hechos después de
+    ///crearlo, conservando los anteriores, y elimina los SAVEPOINT posteriores. El SAVEPOINT
+    ///sigue existiendo, por lo que se puede volver a él más de una vez.
+    ///
+    ///**Parámetros**
+    ///- 'nombre': Es el nombre del SAVEPOINT.
+    ///
+    ///**Return**
+    ///Devuelve un *Result<String, MyError>*, en caso de que el SAVEPOINT no exista o no se pueda
+    ///  restaurar el directorio de trabajo se devuelve un error del tipo *MyError*.
+    pub fn volver_a_punto(&mut self, nombre: &str) -> Result<String, MyError> {
+        let posicion = self.posicion_punto(nombre)?;
+        self.descartar_puntos(posicion + 1);
+        self.restaurar_archivos(posicion)?;
+        Ok(format!("Se volvió al savepoint {}", nombre))
+    }
+
+    ///# Transaccion.liberar_punto()
+    ///Esta función realiza el RELEASE de un SAVEPOINT: lo elimina junto con los posteriores, sin
+    ///deshacer ningún cambio.
+    ///
+    ///**Parámetros**
+    ///- 'nombre': Es el nombre del SAVEPOINT.
+    ///
+    ///**Return**
+    ///Devuelve un *Result<String, MyError>*, en caso de que el SAVEPOINT no exista se devuelve un
+    ///  error del tipo *MyError*.
+    pub fn liberar_punto(&mut self, nombre: &str) -> Result<String, MyError> {
+        let posicion = self.posicion_punto(nombre)?;
+        self.descartar_puntos(posicion);
+        Ok(format!("Se liberó el savepoint {}", nombre))
+    }
+
+    fn descartar_puntos(&mut self, desde: usize) {
+        for punto in self.puntos.drain(desde..) {
+            let _ = fs::remove_dir_all(punto.copia);
+        }
+    }
+
+    ///Deja el directorio de trabajo con los archivos que tenía al crear un SAVEPOINT. Cada archivo
+    ///que cambió desde entonces se copia del último SAVEPOINT (hasta ese) que lo guardó o, si
+    ///ninguno lo guardó, de la base. Los archivos que la transacción copió de la base después de
+    ///crearlo también vuelven a su contenido original, y los que creó después se borran.
+    fn restaurar_archivos(&self, posicion: usize) -> Result<(), MyError> {
+        let punto = &self.puntos[posicion];
+        let (originales, al_copiar) = self.preparados();
+        let error =
+            || MyError::Error("No se pudo preparar el directorio de la transacción".to_string());
+
+        for nombre in listar_archivos(&self.trabajo)? {
+            if !punto.estados.contains_key(&nombre)
+                && (punto.copiados.contains(&nombre) || !originales.contains_key(&nombre))
+                && fs::remove_file(self.trabajo.join(&nombre)).is_err()
+            {
+                return Err(error());
+            }
+        }
+
+        for (nombre, esperado) in &punto.estados {
+            let ruta = self.trabajo.join(nombre);
+            if ruta.is_file() && estado(&ruta)? == *esperado {
+                continue;
+            }
+            let guardado = self.puntos[..=posicion]
+                .iter()
+                .rev()
+                .map(|p| p.copia.join(nombre))
+                .find(|c| c.is_file());
+            match guardado {
+                Some(c) => {
+                    if copiar(&c, &ruta).is_err() {
+                        return Err(error());
+                    }
+                }
+                None => self.restaurar_original(nombre, originales.get(nombre))?,
+            }
+        }
+
+        for (nombre, original) in &originales {
+            let ruta = self.trabajo.join(nombre);
+            let sin_cambios = ruta.is_file() && al_copiar.get(nombre) == Some(&estado(&ruta)?);
+            if !punto.copiados.contains(nombre) && !sin_cambios {
+                self.restaurar_original(nombre, Some(original))?;
+            }
+        }
+        Ok(())
+    }
+
+    ///Vuelve a copiar un archivo de la base al directorio de trabajo, verificando que sea el
+    ///mismo que la transacción copió la primera vez.
+    fn restaurar_original(&self, nombre: &str, original: Option<&Huella>) -> Result<(), MyError> {
+        let base = self.directorio.join(nombre);
+        if !base.is_file() || Some(&huella(&base)?) != original {
+            return Err(MyError::Error(format!(
+                "Otra sesión modificó {} durante la transacción, por lo que no se puede volver al savepoint",
+                nombre
+            )));
+        }
+        match copiar(&base, &self.trabajo.join(nombre)) {
+            Ok(_) => Ok(()),
+            Err(_e) => Err(MyError::Error(
+                "No se pudo preparar el directorio de la transacción".to_string(),
+            )),
+        }
+    }
+
+    ///Devuelve la huella de los archivos que la transacción copió de la base.
+    fn originales(&self) -> HashMap<String, Huella> {
+        self.preparados().0
+    }
+
+    ///Devuelve la huella de los archivos que la transacción copió de la base y el estado con el
+    ///que quedó cada copia.
+    fn preparados(&self) -> (HashMap<String, Huella>, HashMap<String, Estado>) {
+        match preparadas().get(&self.trabajo) {
+            Some(p) => (p.originales.clone(), p.estados.clone()),
+            None => (HashMap::new(), HashMap::new()),
+        }
+    }
+
+    fn posicion_punto(&self, nombre: &str) -> Result<usize, MyError> {
+        match self.puntos.iter().rposition(|p| p.nombre == nombre) {
+            Some(p) => Ok(p),
+            None => Err(MyError::InvalidSyntax(format!(
+                "El savepoint {} no existe",
+                nombre
+            ))),
+        }
+    }
+
+    ///# Transaccion.confirmar()
+    ///Esta función realiza el COMMIT de la transacción: los archivos nuevos o modificados se
+    ///escriben en la base con un reemplazo atómico cada uno y los que ya no existen en el
+    ///directorio de trabajo (por ejemplo, por un RENAME TO) se borran. Mientras se publican, los
+    ///archivos que cambian quedan bloqueados de forma exclusiva. Si otra sesión modificó alguno
+    ///de esos archivos después de que la transacción lo copió, la transacción no se confirma para
+    ///no pisar sus cambios. La publicación se hace con un registro de escritura anticipada, así que si falla a
+    ///mitad de camino (o el programa se cae) ningún archivo de la base queda con los cambios.
+    ///
+    ///**Return**
+    ///Devuelve un *Result<String, MyError>*, en caso de que otra sesión haya modificado alguno de
+    ///  los archivos o falle la publicación de alguno se devuelve un error del tipo *MyError*.
+    pub fn confirmar(self) -> Result<String, MyError> {
+        let mut finales = HashMap::new();
+        for nombre in listar_archivos(&self.trabajo)? {
+            let huella = huella(&self.trabajo.join(&nombre))?;
+            finales.insert(nombre, huella);
+        }
+        let registro = Wal::iniciar(&self.directorio.to_string_lossy())?;
+
+        match self.publicar(&self.originales(), &finales) {
+            Ok(_) => registro.confirmar()?,
+            Err(e) => {
+                registro.deshacer()?;
+                return Err(e);
+            }
+        }
+
+        Ok(String::from("Se confirmó la transacción"))
+    }
+
+    ///# Transaccion.deshacer()
+    ///Esta función realiza el ROLLBACK de la transacción, descartando todos sus cambios.
+    pub fn deshacer(self) -> String {
+        String::from("Se deshizo la transacción")
+    }
+
+    fn publicar(
+        &self,
+        originales: &HashMap<String, Huella>,
+        finales: &HashMap<String, Huella>,
+    ) -> Result<(), MyError> {
+        let mut modificados: Vec<&String> = finales
+            .keys()
+            .chain(originales.keys())
+            .filter(|n| finales.get(*n) != originales.get(*n))
+            .collect();
+        modificados.sort();
+        modificados.dedup();
+
+        let mut bloqueos = Vec::new();
+        for nombre in &modificados {
+            let ruta = self.directorio.join(nombre).to_string_lossy().to_string();
+            if Path::new(&ruta).is_file() {
+                bloqueos.push(Bloqueo::exclusivo(&ruta)?);
+            }
+        }
+
+        for nombre in &modificados {
+            let ruta = self.directorio.join(nombre);
+            let actual = match ruta.is_file() {
+                true => Some(huella(&ruta)?),
+                false => None,
+            };
+            if actual.as_ref() != originales.get(*nombre) {
+                return Err(MyError::Error(format!(
+                    "Otra sesión modificó {} durante la transacción, por lo que no se confirma",
+                    nombre
+                )));
+            }
+        }
+
+        let error = || MyError::Error("No se pudo publicar la transacción".to_string());
+        for nombre in modificados {
+            let destino = self.directorio.join(nombre);
+            if !finales.contains_key(nombre) {
+                wal::registrar(&destino.to_string_lossy())?;
+                let _ = fs::remove_file(destino);
+                continue;
+            }
+
+            if let Some(padre) = destino.parent() {
+                if fs::create_dir_all(padre).is_err() {
+                    return Err(error());
+                }
+            }
+            let mut temporal = ArchivoTemporal::crear(&destino.to_string_lossy())?;
+            let copiado = File::open(self.trabajo.join(nombre))
+                .and_then(|mut origen| io::copy(&mut origen, &mut temporal));
+            if copiado.is_err() {
+                return Err(error());
+            }
+            temporal.publicar()?;
+            if conservar_fecha(&self.trabajo.join(nombre), &destino).is_err() {
+                return Err(error());
+            }
+        }
+        archivo_temporal::sincronizar_directorio(&self.trabajo);
+
+        Ok(())
+    }
+}
+
+impl Drop for Transaccion {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.trabajo);
+        preparadas().remove(&self.trabajo);
+    }
+}
+
+///# transaccion::preparar()
+///Esta función copia al directorio de trabajo de una transacción los archivos de una tabla, la
+///primera vez que una de sus instrucciones la usa. Si la tabla es un patrón se copia el
+///subdirectorio donde empieza, o toda la base si el patrón empieza con un comodín. Si el
+///directorio no es el de trabajo de una transacción abierta no se hace nada.
+///
+///**Parámetros**
+///- 'directorio': Es el directorio sobre el que se ejecuta la instrucción.
+///- 'tabla': Es el nombre de la tabla, como aparece en la instrucción.
+///
+///**Return**
+///Devuelve un *Result<(), MyError>*, en caso de que no se pueda copiar algún archivo se
+///  devuelve un error del tipo *MyError*.
+pub(crate) fn preparar(directorio: &str, tabla: &str) -> Result<(), MyError> {
+    let trabajo = PathBuf::from(directorio);
+    let base = match directorio_de_la_base(&trabajo) {
+        Some(b) => b,
+        None => return Ok(()),
+    };
+    let mut preparadas = preparadas();
+    let preparacion = match preparadas.get_mut(&trabajo) {
+        Some(p) => p,
+        None => return Ok(()),
+    };
+
+    let primero = tabla.split('/').next().unwrap_or(tabla);
+    let tablas = match particiones::es_patron(primero) {
+        true => nombres_visibles(&base)?,
+        false => vec![primero.to_string()],
+    };
+    for tabla in tablas {
+        preparacion.preparar_tabla(&base, &trabajo, &tabla)?;
+    }
+    Ok(())
+}
+
+///# transaccion::preparar_referencias()
+///Esta función copia al directorio de trabajo de una transacción las tablas de la base cuyo
+///esquema tiene una FOREIGN KEY que hace referencia a una tabla, para poder verificar y propagar
+///los cambios sobre ella. Si el directorio no es el de trabajo de una transacción abierta no se
+///hace nada.
+///
+///**Parámetros**
+///- 'directorio': Es el directorio sobre el que se ejecuta la instrucción.
+///- 'tabla': Es el nombre de la tabla referenciada.
+///
+///**Return**
+///Devuelve un *Result<(), MyError>*, en caso de que no se pueda leer algún esquema o copiar
+///  algún archivo se devuelve un error del tipo *MyError*.
+pub(crate) fn preparar_referencias(directorio: &str, tabla: &str) -> Result<(), MyError> {
+    let trabajo = PathBuf::from(directorio);
+    let base = match directorio_de_la_base(&trabajo) {
+        Some(b) => b,
+        None => return Ok(()),
+    };
+    let mut preparadas = preparadas();
+    let preparacion = match preparadas.get_mut(&trabajo) {
+        Some(p) => p,
+        None => return Ok(()),
+    };
+
+    for nombre in nombres_visibles(&base)? {
+        let hija = match nombre.strip_suffix(".esquema") {
+            Some(h) if !preparacion.tablas.contains(h) => h,
+            _ => continue,
+        };
+        let archivo = almacenamiento::resolver(&base.to_string_lossy(), hija)?;
+        let referencia = Esquema::cargar(&archivo)?
+            .columnas()
+            .iter()
+            .any(|c| c.referencia().is_some_and(|r| r.tabla() == tabla));
+        if referencia {
+            preparacion.preparar_tabla(&base, &trabajo, hija)?;
+        }
+    }
+    Ok(())
+}
+
+///# transaccion::ruta_en_la_base()
+///Esta función devuelve la dirección que tiene en el directorio de la base un archivo del
+///directorio de trabajo de una transacción. Las direcciones que no están dentro de un directorio
+///de trabajo se devuelven sin cambios.
+///
+///**Parámetros**
+///- 'archivo': Es la dirección del archivo.
+///
+///**Return**
+///Devuelve un *String* con la dirección del archivo en la base.
+pub fn ruta_en_la_base(archivo: &str) -> String {
+    let ruta = Path::new(archivo);
+    let trabajo = match ruta.parent() {
+        Some(t) => t,
+        None => return archivo.to_string(),
+    };
+    let es_de_trabajo = trabajo
+        .file_name()
+        .is_some_and(|n| n.to_string_lossy().starts_with(PREFIJO_TRABAJO));
+
+    match (es_de_trabajo, trabajo.parent(), ruta.file_name()) {
+        (true, Some(base), Some(nombre)) => base.join(nombre).to_string_lossy().to_string(),
+        _ => archivo.to_string(),
+    }
+}
+
+///Devuelve el directorio de la base de un directorio de trabajo, o *None* si el directorio no es
+///el de trabajo de una transacción.
+fn directorio_de_la_base(trabajo: &Path) -> Option<PathBuf> {
+    let es_de_trabajo = trabajo
+        .file_name()
+        .is_some_and(|n| n.to_string_lossy().starts_with(PREFIJO_TRABAJO));
+    match es_de_trabajo {
+        true => trabajo.parent().map(Path::to_path_buf),
+        false => None,
+    }
+}
+
+fn preparadas() -> MutexGuard<'static, BTreeMap<PathBuf, Preparacion>> {
+    PREPARADAS.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+///Devuelve la dirección relativa de los archivos de un directorio que forman parte de la base:
+///los archivos comunes que no están ocultos, también dentro de los subdirectorios (como los de
+///las tablas particionadas). Las secuencias no forman parte de la copia.
+fn listar_archivos(directorio: &Path) -> Result<Vec<String>, MyError> {
+    let mut nombres = Vec::new();
+    agregar_archivos(directorio, Path::new(""), &mut nombres)?;
+    Ok(nombres)
+}
+
+///Devuelve los nombres de las entradas de un directorio que no están ocultas.
+fn nombres_visibles(directorio: &Path) -> Result<Vec<String>, MyError> {
+    let entradas = match fs::read_dir(directorio) {
+        Ok(e) => e,
+        Err(_e) => {
+            return Err(MyError::InvalidTable(
+                "No se pudo leer el directorio de la base".to_string(),
+            ))
+        }
+    };
+    Ok(entradas
+        .flatten()
+        .map(|e| e.file_name().to_string_lossy().to_string())
+        .filter(|n| !n.starts_with('.'))
+        .collect())
+}
+
+fn agregar_archivos(
+    directorio: &Path,
+    relativo: &Path,
+    nombres: &mut Vec<String>,
+) -> Result<(), MyError> {
+    let entradas = match fs::read_dir(directorio.join(relativo)) {
+        Ok(e) => e,
+        Err(_e) => {
+            return Err(MyError::InvalidTable(
+                "No se pudo leer el directorio de la base".to_string(),
+            ))
+        }
+    };
+
+    for entrada in entradas.flatten() {
+        let nombre = entrada.file_name().to_string_lossy().to_string();
+        if nombre.starts_with('.') {
+            continue;
+        }
+
+        let ruta = relativo.join(&nombre);
+        if entrada.path().is_dir() {
+            agregar_archivos(directorio, &ruta, nombres)?;
+        } else if entrada.path().is_file() && !nombre.ends_with(".secuencia") {
+            nombres.push(ruta.to_string_lossy().to_string());
+        }
+    }
+
+    Ok(())
+}
+
+///Copia un archivo conservando su fecha de modificación, para que los índices de las tablas
+///copiadas sigan correspondiendo a la copia.
+fn copiar(origen: &Path, destino: &Path) -> io::Result<()> {
+    if let Some(padre) = destino.parent() {
+        fs::create_dir_all(padre)?;
+    }
+    fs::copy(origen, destino)?;
+    conservar_fecha(origen, destino)
+}
+
+fn conservar_fecha(origen: &Path, destino: &Path) -> io::Result<()> {
+    let modificado = fs::metadata(origen)?.modified()?;
+    File::options()
+        .write(true)
+        .open(destino)?
+        .set_modified(modificado)
+}
+
+fn estado(ruta: &Path) -> Result<Estado, MyError> {
+    match fs::metadata(ruta).and_then(|m| Ok((m.len(), m.modified()?))) {
+        Ok(e) => Ok(e),
+        Err(_e) => Err(MyError::Error(
+            "No se pudo leer un archivo de la transacción".to_string(),
+        )),
+    }
+}
+
+///Calcula la huella de un archivo leyéndolo por partes, sin cargarlo entero en memoria.
+fn huella(ruta: &Path) -> Result<Huella, MyError> {
+    let error = || MyError::Error("No se pudo leer un archivo de la transacción".to_string());
+    let mut archivo = match File::open(ruta) {
+        Ok(a) => a,
+        Err(_e) => return Err(error()),
+    };
+
+    let mut resumen = DefaultHasher::new();
+    let mut largo = 0;
+    let mut bloque = [0u8; 8192];
+    loop {
+        match archivo.read(&mut bloque) {
+            Ok(0) => break,
+            Ok(n) => {
+                resumen.write(&bloque[..n]);
+                largo += n as u64;
+            }
+            Err(_e) => return Err(error()),
+        }
+    }
+
+    Ok((largo, resumen.finish()))
+}
@@ -1,7 +1,11 @@
+use crate::almacenamiento;
+use crate::clave_foranea::{self, TablaHija, VerificadorReferencias};
 use crate::condicion::Condicion;
+use crate::indice::{self, ActualizadorIndices};
 use crate::my_error::MyError;
-use std::fs::{self, File, OpenOptions};
-use std::io::{BufRead, BufReader, Write};
+use crate::planificador;
+use crate::restricciones::VerificadorRestricciones;
+use std::collections::HashMap;
 
 ///# Update
 ///Esta estructura proporciona todo lo necesario para implementar la instrucción UPDATE de sql.
@@ -16,11 +20,16 @@ use std::io::{BufRead, BufReader, Write};
 ///- 'valores': Es un array que contiene todo los valores que se quieren modificar y en que columna
 ///  se encuentran.
 ///- 'condicion': Tiene la condición que deben cumplir las filas para que se le modifique el valor.
+///- 'en_cascada': Si el update es consecuencia de un ON UPDATE de una FOREIGN KEY, es la columna
+///  que hace referencia y el valor nuevo que le corresponde a cada valor anterior. Cada fila se
+///  modifica según el valor que tenía antes del update, por lo que se pueden intercambiar valores,
+///  y los valores nuevos ya existen en la tabla padre por lo que no se vuelven a verificar.
 #[derive(Debug, PartialEq)]
 pub struct Update {
     archivo: String,
     valores: Vec<Vec<String>>,
     condicion: Condicion,
+    en_cascada: Option<(String, HashMap<String, String>)>,
 }
 
 impl Update {
@@ -41,92 +50,93 @@ impl Update {
             archivo,
             valores,
             condicion,
+            en_cascada: None,
         }
     }
+
+    pub(crate) fn en_cascada(mut self, columna: &str, reemplazos: HashMap<String, String>) -> Self {
+        self.en_cascada = Some((columna.to_string(), reemplazos));
+        self
+    }
     ///# Update.update()
-    ///Esta función realiza la instrucción UPDATE de sql.
+    ///Esta función realiza la instrucción UPDATE de sql. Si alguna de las filas modificadas no
+    ///cumple las restricciones del esquema de la tabla no se modifica ninguna. Si otras tablas
+    ///hacen referencia a los valores modificados se aplica la acción ON UPDATE declarada en su
+    ///FOREIGN KEY. Si la condición compara una columna que tiene índice y el índice indica que
+    ///ninguna fila la cumple, la tabla no se reescribe; los índices de la tabla se actualizan con
+    ///las filas modificadas.
     ///
     ///**Reuturn**
     ///Devuelve un *Result<String, MyError>* en caso que durante la ejecución de la función no haya
     ///  ocurrido ningún error se devuelve un *String* para indicar que la operación se realizó
     ///  correctamente, en caso contrario se decvuelve un error del tipo *MyError*.
     pub fn update(&self) -> Result<String, MyError> {
-        let archivo = match File::open(&self.archivo) {
-            Ok(f) => f,
-            Err(_e) => {
-                return Err(MyError::InvalidTable(
-                    "Directorio o nombre de la tabla incorrectos".to_string(),
-                ))
-            }
-        };
-        let mut buffer = BufReader::new(archivo);
-
-        let mut archivo_temporal = match OpenOptions::new()
-            .write(true)
-            .truncate(true)
-            .open("archivo_temporal.csv")
-        {
-            Ok(f) => f,
-            Err(_e) => {
-                return Err(MyError::Error(
-                    "Fallo en el proceso de modificación de la tabla".to_string(),
-                ))
-            }
-        };
+        let almacenamiento = almacenamiento::abrir(&self.archivo);
+        let _bloqueo = almacenamiento.bloquear(true)?;
+        let mut recorrido = almacenamiento.recorrer(None)?;
+        let columnas_vec = recorrido.columnas().to_vec();
 
-        let mut columnas = String::new();
-        match buffer.read_line(&mut columnas) {
-            Ok(c) => c,
-            Err(_e) => {
-                return Err(MyError::Error(
-                    "Fallo en el proceso de modificación de la tabla".to_string(),
-                ))
-            }
+        let condicion = self.condicion.compilar(&columnas_vec)?;
+        let esquema = almacenamiento.esquema()?;
+        let candidatas = planificador::elegir_acceso(&self.archivo, &esquema, &self.condicion)
+            .filas(&self.archivo)?;
+        if candidatas.as_ref().is_some_and(|c| c.is_empty()) {
+            return Ok(String::from("Se completo el update correctamente"));
+        }
+
+        let mut reescritura = almacenamiento.reescribir(&columnas_vec)?;
+        let mut indices = ActualizadorIndices::new(&self.archivo, &esquema, &columnas_vec)?;
+        let mut verificador = VerificadorRestricciones::new(&esquema, &columnas_vec)?;
+        let referencias = match self.en_cascada {
+            Some(_) => None,
+            None => Some(VerificadorReferencias::new(
+                &self.archivo,
+                &esquema,
+                &columnas_vec,
+            )?),
         };
+        let hijas = TablaHija::buscar(&self.archivo)?;
+        let mut cambios: Vec<(Vec<String>, Vec<String>)> = Vec::new();
 
-        let _ = archivo_temporal.write_all(columnas.as_bytes());
-
-        let columnas_vec: Vec<String> = columnas
-            .replace("\n", "")
-            .split(',')
-            .map(|s| s.to_string())
-            .collect();
-
-        for line in buffer.lines() {
-            let linea_actual = match line {
-                Ok(l) => l + &String::from("\n"),
-                Err(_e) => {
-                    return Err(MyError::Error(
-                        "Fallo en el proceso de edición de la tabla".to_string(),
-                    ))
-                }
+        while let Some(valores) = recorrido.siguiente()? {
+            let es_candidata = match &candidatas {
+                Some(c) => recorrido
+                    .posicion()
+                    .is_some_and(|p| c.binary_search(&p).is_ok()),
+                None => true,
             };
 
-            let valores: &Vec<String> = &linea_actual
-                .replace("\n", "")
-                .split(',')
-                .map(|s| s.to_string())
-                .collect();
-
-            let verificacion = match self.condicion.verificar(&columnas_vec, valores) {
-                Ok(c) => c,
-                Err(e) => return Err(e),
-            };
+            let verificacion = es_candidata && condicion.verificar(&valores)?;
+            reescritura.origen(recorrido.posicion())?;
 
             if verificacion {
-                let linea_nueva = self.crear_linea_nueva(valores, &columnas_vec);
-                let _ = archivo_temporal.write_all(linea_nueva.as_bytes());
+                let fila_nueva = self.crear_fila_nueva(&valores, &columnas_vec);
+                verificador.verificar(&fila_nueva)?;
+                if let Some(r) = &referencias {
+                    r.verificar(&fila_nueva)?;
+                }
+                reescritura.escribir(&fila_nueva)?;
+                indices.agregar(&fila_nueva);
+                if !hijas.is_empty() {
+                    cambios.push((valores, fila_nueva));
+                }
             } else {
-                let _ = archivo_temporal.write_all(linea_actual.as_bytes());
+                verificador.registrar(&valores)?;
+                reescritura.escribir(&valores)?;
+                indices.agregar(&valores);
             }
         }
 
-        let _ = fs::rename("archivo_temporal.csv", &self.archivo);
+        let indices_nuevos = indices.preparar()?;
+        let _referencias = clave_foranea::propagar_actualizacion(&hijas, &columnas_vec, &cambios)?;
+
+        reescritura.publicar()?;
+        indice::publicar(&self.archivo, indices_nuevos)?;
         Ok(String::from("Se completo el update correctamente"))
     }
 
-    fn crear_linea_nueva(&self, linea: &[String], columnas: &[String]) -> String {
-        let mut linea_nueva: String = String::new();
+    fn crear_fila_nueva(&self, linea: &[String], columnas: &[String]) -> Vec<String> {
+        let mut fila_nueva: Vec<String> = Vec::new();
         let mut aux: Vec<&String> = Vec::new();
 
         for i in 0..self.valores.len() {
@@ -138,19 +148,23 @@ impl Update {
         }
 
         for j in 0..columnas.len() {
-            if !linea_nueva.is_empty() {
-                linea_nueva.push(',');
-            }
-
             if aux.contains(&&columnas[j]) {
                 let index = aux.iter().position(|e| **e == columnas[j]).unwrap_or(0);
-                linea_nueva.push_str(&self.valores[index][1]);
+                fila_nueva.push(String::from(&self.valores[index][1]));
             } else {
-                linea_nueva.push_str(&linea[j]);
+                fila_nueva.push(String::from(&linea[j]));
+            }
+        }
+
+        if let Some((columna, reemplazos)) = &self.en_cascada {
+            if let Some(pos) = columnas.iter().position(|c| c == columna) {
+                if let Some(nuevo) = reemplazos.get(&linea[pos]) {
+                    fila_nueva[pos] = String::from(nuevo);
+                }
             }
         }
 
-        linea_nueva + &String::from("\n")
+        fila_nueva
     }
 }
 
@@ -158,6 +172,13 @@ impl Update {
 mod test {
     use super::*;
     use crate::condicion_simple::CondicionSimple;
+    use crate::create_index::CreateIndex;
+    use crate::indice::Indice;
+    use crate::memoria::BaseEnMemoria;
+    use crate::parser::Parser;
+    use crate::pruebas::{self, CLIENTES, ESQUEMA_CLIENTES};
+    use crate::valor::Valor;
+    use std::fs;
 
     #[test]
     pub fn test01_se_crea_correctamente_un_update() {
@@ -171,6 +192,7 @@ mod test {
             archivo: String::from("./test/update.csv"),
             valores: Vec::<Vec<String>>::new(),
             condicion: Condicion::SiempreTrue,
+            en_cascada: None,
         };
 
         assert_eq!(operacion, operacion_esperada);
@@ -192,4 +214,116 @@ mod test {
 
         assert!(resultado.is_ok());
     }
+
+    #[test]
+    pub fn test03_no_se_realiza_un_update_que_no_cumple_un_check() {
+        let productos = "id,producto,cantidad,maximo\n1,Laptop,2,10\n2,Monitor,1,5\n";
+        let base = BaseEnMemoria::new("update_check");
+        base.cargar("productos", productos);
+        base.cargar_esquema(
+            "productos",
+            "id INT PRIMARY KEY\nproducto TEXT\ncantidad INT CHECK (cantidad > 0)\nmaximo INT\nCHECK (maximo <= 100)\n",
+        )
+        .unwrap();
+
+        let valores = vec![vec![String::from("cantidad"), String::from("0")]];
+        let operacion = Update::new(base.archivo("productos"), valores, Condicion::SiempreTrue);
+
+        let resultado = operacion.update();
+
+        assert!(matches!(resultado, Err(MyError::ConstraintViolation(_))));
+        assert_eq!(base.volcar("productos").unwrap(), productos);
+    }
+
+    #[test]
+    pub fn test04_no_se_realiza_un_update_que_repite_la_clave_primaria() {
+        let base = BaseEnMemoria::new("update_clave_primaria");
+        base.cargar(
+            "clientes",
+            "id,email,nombre\n1,ana@mail.com,Ana\n2,juan@mail.com,Juan\n",
+        );
+        base.cargar_esquema(
+            "clientes",
+            "id INT PRIMARY KEY\nemail TEXT UNIQUE\nnombre TEXT NOT NULL\n",
+        )
+        .unwrap();
+
+        let valores = vec![vec![String::from("id"), String::from("2")]];
+        let condicion = Condicion::CondicionSimple(CondicionSimple::new(
+            "id".to_string(),
+            "=".to_string(),
+            "1".to_string(),
+        ));
+        let operacion = Update::new(base.archivo("clientes"), valores, condicion);
+
+        let resultado = operacion.update();
+
+        assert!(matches!(
+            resultado,
+            Err(MyError::ConstraintViolation(m)) if m.contains("PRIMARY KEY")
+        ));
+    }
+
+    #[test]
+    pub fn test05_no_se_realiza_un_update_que_repite_un_valor_de_un_indice_unique() {
+        let archivo =
+            pruebas::preparar_tabla("update_indice", "clientes", CLIENTES, ESQUEMA_CLIENTES);
+        let indice = Indice::new("idx_email".to_string(), "email".to_string(), true);
+        CreateIndex::new(archivo.to_string(), indice.clone())
+            .crear()
+            .unwrap();
+
+        let condicion = Condicion::CondicionSimple(CondicionSimple::new(
+            "id".to_string(),
+            "=".to_string(),
+            "1".to_string(),
+        ));
+        let repetido = Update::new(
+            archivo.to_string(),
+            vec![vec![String::from("email"), String::from("ana@mail.com")]],
+            condicion,
+        )
+        .update();
+        let condicion = Condicion::CondicionSimple(CondicionSimple::new(
+            "email".to_string(),
+            "=".to_string(),
+            "tomas@mail.com".to_string(),
+        ));
+        let nuevo = Update::new(
+            archivo.to_string(),
+            vec![vec![String::from("email"), String::from("tomi@mail.com")]],
+            condicion,
+        )
+        .update();
+
+        assert!(matches!(
+            repetido,
+            Err(MyError::ConstraintViolation(m)) if m.contains("idx_email")
+        ));
+        assert!(nuevo.is_ok());
+        assert_eq!(
+            indice
+                .buscar(&archivo, "=", &Valor::Palabra("tomi@mail.com".to_string()))
+                .unwrap(),
+            Some(vec![21])
+        );
+    }
+
+    #[test]
+    pub fn test06_no_se_realiza_un_update_con_un_valor_que_no_es_del_tipo_de_la_columna() {
+        let base = BaseEnMemoria::new("update_tipos");
+        base.cargar("t", "id,edad\n1,30\n");
+        base.cargar_esquema("t", "id INT\nedad INT").unwrap();
+
+        let resultado = Parser::new()
+            .crear_operacion(
+                base.direccion().to_string(),
+                "UPDATE t SET edad = treinta WHERE id = 1".to_string(),
+            )
+            .unwrap()
+            .realizar_operacion();
+
+        assert!(matches!(resultado, Err(MyError::InvalidColumn(_))));
+        assert_eq!(base.volcar("t").unwrap(), "id,edad\n1,30\n");
+    }
 }
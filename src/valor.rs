@@ -1,5 +1,16 @@
-#[derive(Debug, PartialEq, PartialOrd)]
+use std::fmt;
+
+#[derive(Debug, PartialEq, PartialOrd, Clone)]
 pub enum Valor {
     Entero(i32),
     Palabra(String),
 }
+
+impl fmt::Display for Valor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Valor::Entero(v) => write!(f, "{}", v),
+            Valor::Palabra(p) => write!(f, "{}", p),
+        }
+    }
+}
@@ -0,0 +1,574 @@
+use crate::archivo_temporal;
+use crate::my_error::MyError;
+use std::fs::{self, File, OpenOptions};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+
+const FIN: &str = "FIN";
+
+///# Wal
+///Esta estructura es el registro de escritura anticipada (write-ahead log) de una instrucción.
+///Mientras está abierta, antes de modificar por primera vez un archivo de la base se guarda en el
+///registro cómo estaba ese archivo: un enlace duro a la versión anterior si se va a reemplazar
+///(una copia completa si se reescribe en el lugar), el largo original si solo se le agregan filas
+///al final, o que no existía si se va a crear. Si la instrucción termina bien el registro se
+///cierra con *FIN* y se borra; si falla, o si el programa se cae en el medio, con lo registrado se
+///devuelve cada archivo a su estado anterior.
+///
+///El registro es un directorio oculto *.wal.<id del proceso>* dentro del directorio de la base,
+///con un archivo *registro* (una línea por archivo modificado, con su dirección relativa a la
+///base) y las versiones anteriores numeradas. El directorio se arma con otro nombre y recién toma
+///el definitivo cuando *registro* ya existe y está bloqueado; el bloqueo dura mientras la
+///instrucción se ejecuta, así la recuperación no toca el registro de un proceso que sigue vivo.
+///
+///**Ejemplo**
+///let wal = Wal::iniciar(directorio)?;
+///operacion.realizar_operacion()?;
+///wal.confirmar()?;
+///
+///**Parámetros**
+///- 'directorio': Es el directorio del registro.
+///- 'registro': Es el archivo *registro*, abierto y bloqueado.
+#[derive(Debug)]
+pub struct Wal {
+    directorio: PathBuf,
+    registro: File,
+}
+
+impl Wal {
+    ///# Wal::iniciar()
+    ///Esta función abre el registro de una instrucción sobre la base de un directorio.
+    ///
+    ///**Parámetros**
+    ///- 'directorio': Es el directorio de la base de datos.
+    ///
+    ///**Return**
+    ///Devuelve un *Result<Wal, MyError>*, en caso de que no se pueda crear el registro se
+    ///  devuelve un error del tipo *MyError*.
+    pub fn iniciar(directorio: &str) -> Result<Wal, MyError> {
+        let ruta = ruta_del_registro(Path::new(directorio));
+        let temporal = Path::new(directorio).join(format!(".wal_nuevo.{}", process::id()));
+        let error = || MyError::Error("No se pudo crear el registro de la instrucción".to_string());
+
+        if ruta.exists() {
+            return Err(MyError::Error(
+                "Ya hay una instrucción en curso sobre la base".to_string(),
+            ));
+        }
+        let _ = fs::remove_dir_all(&temporal);
+        if fs::create_dir(&temporal).is_err() {
+            return Err(error());
+        }
+
+        let registro = match OpenOptions::new()
+            .append(true)
+            .create_new(true)
+            .open(temporal.join("registro"))
+        {
+            Ok(f) => f,
+            Err(_e) => {
+                let _ = fs::remove_dir_all(&temporal);
+                return Err(error());
+            }
+        };
+
+        if registro.lock().is_err() || fs::rename(&temporal, &ruta).is_err() {
+            let _ = fs::remove_dir_all(&temporal);
+            return Err(error());
+        }
+        archivo_temporal::sincronizar_directorio(&ruta);
+
+        Ok(Wal {
+            directorio: ruta,
+            registro,
+        })
+    }
+
+    ///# Wal.confirmar()
+    ///Esta función marca la instrucción como terminada y borra el registro.
+    ///
+    ///**Return**
+    ///Devuelve un *Result<(), MyError>*, en caso de que no se pueda escribir el registro se
+    ///  devuelve un error del tipo *MyError*.
+    pub fn confirmar(mut self) -> Result<(), MyError> {
+        if self
+            .registro
+            .write_all(format!("{}\n", FIN).as_bytes())
+            .and_then(|_| self.registro.sync_data())
+            .is_err()
+        {
+            return Err(MyError::Error(
+                "No se pudo cerrar el registro de la instrucción".to_string(),
+            ));
+        }
+
+        let _ = fs::remove_dir_all(&self.directorio);
+        Ok(())
+    }
+
+    ///# Wal.deshacer()
+    ///Esta función devuelve todos los archivos registrados a su estado anterior y borra el
+    ///registro. Se usa cuando la instrucción falla después de haber modificado algún archivo.
+    ///
+    ///**Return**
+    ///Devuelve un *Result<(), MyError>*, en caso de que no se pueda restaurar algún archivo se
+    ///  devuelve un error del tipo *MyError* y el registro se conserva para la recuperación.
+    pub fn deshacer(self) -> Result<(), MyError> {
+        restaurar(&self.directorio)?;
+        let _ = fs::remove_dir_all(&self.directorio);
+        Ok(())
+    }
+}
+
+///# wal::registrar()
+///Esta función guarda en el registro de la instrucción en curso el estado de un archivo antes de
+///reemplazarlo, crearlo o borrarlo. La versión anterior no se copia: se guarda un enlace duro al
+///archivo original, que queda intacto porque quien escribe publica un archivo nuevo en su lugar
+///(solo si el sistema no admite enlaces se copia). Si ningún directorio que contiene al archivo
+///tiene un registro abierto por este proceso, o si el archivo ya se registró, no hace nada.
+///
+///**Parámetros**
+///- 'archivo': Es la dirección del archivo que se va a reemplazar, crear o borrar.
+///
+///**Return**
+///Devuelve un *Result<(), MyError>*, en caso de que no se pueda guardar la versión anterior se
+///  devuelve un error del tipo *MyError*.
+pub fn registrar(archivo: &str) -> Result<(), MyError> {
+    registrar_entrada(archivo, |numero, ruta_wal| {
+        guardar_version(archivo, numero, ruta_wal, true)
+    })
+}
+
+///# wal::registrar_copia()
+///Esta función es como *registrar*, pero guarda una copia completa del archivo. Se usa para los
+///archivos que se reescriben en el lugar, donde un enlace al original cambiaría junto con él.
+///
+///**Parámetros**
+///- 'archivo': Es la dirección del archivo que se va a reescribir en el lugar.
+///
+///**Return**
+///Devuelve un *Result<(), MyError>*, en caso de que no se pueda guardar la copia se devuelve un
+///  error del tipo *MyError*.
+pub fn registrar_copia(archivo: &str) -> Result<(), MyError> {
+    registrar_entrada(archivo, |numero, ruta_wal| {
+        guardar_version(archivo, numero, ruta_wal, false)
+    })
+}
+
+///# wal::registrar_anexo()
+///Esta función guarda en el registro de la instrucción en curso el largo de un archivo antes de
+///agregarle filas al final, lo que alcanza para deshacer el agregado.
+///
+///**Parámetros**
+///- 'archivo': Es la dirección del archivo al que se le van a agregar filas.
+///- 'largo': Es el largo en bytes que tiene el archivo antes de escribir.
+///
+///**Return**
+///Devuelve un *Result<(), MyError>*, en caso de que no se pueda escribir el registro se devuelve
+///  un error del tipo *MyError*.
+pub fn registrar_anexo(archivo: &str, largo: u64) -> Result<(), MyError> {
+    registrar_entrada(archivo, |_, _| Ok(format!("ANEXO {}", largo)))
+}
+
+///# wal::recuperar()
+///Esta función se ejecuta al iniciar el programa. Busca en el directorio de la base los
+///registros que quedaron de instrucciones que no terminaron (porque el programa se cayó) y
+///devuelve los archivos que modificaron a su estado anterior. Los registros que están
+///bloqueados pertenecen a procesos que siguen ejecutándose y no se tocan, como tampoco los
+///directorios que no tienen el archivo *registro*.
+///
+///**Parámetros**
+///- 'directorio': Es el directorio de la base de datos.
+///
+///**Return**
+///Devuelve un *Result<usize, MyError>* con la cantidad de instrucciones deshechas, en caso de que
+///  no se pueda restaurar algún archivo se devuelve un error del tipo *MyError*.
+pub fn recuperar(directorio: &str) -> Result<usize, MyError> {
+    let entradas = match fs::read_dir(directorio) {
+        Ok(e) => e,
+        Err(_e) => {
+            return Err(MyError::InvalidTable(
+                "No se pudo leer el directorio de la base".to_string(),
+            ))
+        }
+    };
+
+    let mut deshechas = 0;
+    for entrada in entradas.flatten() {
+        let ruta = entrada.path();
+        if !entrada.file_name().to_string_lossy().starts_with(".wal.") || !ruta.is_dir() {
+            continue;
+        }
+
+        let registro = match File::open(ruta.join("registro")) {
+            Ok(r) => r,
+            Err(_e) => continue,
+        };
+        if registro.try_lock().is_err() {
+            continue;
+        }
+
+        if !leer_registro(&ruta)?.iter().any(|l| l == FIN) {
+            restaurar(&ruta)?;
+            deshechas += 1;
+        }
+        let _ = fs::remove_dir_all(&ruta);
+    }
+
+    Ok(deshechas)
+}
+
+fn ruta_del_registro(directorio: &Path) -> PathBuf {
+    directorio.join(format!(".wal.{}", process::id()))
+}
+
+///Guarda la línea de un archivo en el registro abierto por este proceso en el directorio más
+///cercano que lo contiene, así los archivos de los subdirectorios (como las particiones) quedan
+///en el registro de la base. El archivo se anota con su dirección relativa a ese directorio.
+fn registrar_entrada<F>(archivo: &str, estado_anterior: F) -> Result<(), MyError>
+where
+    F: FnOnce(usize, &Path) -> Result<String, MyError>,
+{
+    let ruta = Path::new(archivo);
+    let carpeta = |d: &Path| match d.as_os_str().is_empty() {
+        true => PathBuf::from("."),
+        false => d.to_path_buf(),
+    };
+    let base = match ruta
+        .parent()
+        .unwrap_or(Path::new(""))
+        .ancestors()
+        .find(|d| ruta_del_registro(&carpeta(d)).is_dir())
+    {
+        Some(b) => b,
+        None => return Ok(()),
+    };
+    let nombre = match ruta.strip_prefix(base) {
+        Ok(n) if n.file_name().is_some() => n.to_string_lossy().to_string(),
+        _ => return Ok(()),
+    };
+    let ruta_wal = ruta_del_registro(&carpeta(base));
+
+    let lineas = leer_registro(&ruta_wal)?;
+    for linea in &lineas {
+        if leer_entrada(linea)?.nombre() == Some(&nombre[..]) {
+            return Ok(());
+        }
+    }
+
+    let estado = estado_anterior(lineas.len(), &ruta_wal)?;
+    let escrito = OpenOptions::new()
+        .append(true)
+        .open(ruta_wal.join("registro"))
+        .and_then(|mut r| {
+            r.write_all(format!("{} {}\n", estado, escapar(&nombre)).as_bytes())?;
+            r.sync_data()
+        });
+
+    match escrito {
+        Ok(_) => Ok(()),
+        Err(_e) => Err(MyError::Error(
+            "No se pudo escribir el registro de la instrucción".to_string(),
+        )),
+    }
+}
+
+///Guarda en el registro la versión anterior de un archivo: que no existía, un enlace duro al
+///original si *enlazar* es verdadero y el sistema lo permite, o una copia completa.
+fn guardar_version(
+    archivo: &str,
+    numero: usize,
+    ruta_wal: &Path,
+    enlazar: bool,
+) -> Result<String, MyError> {
+    let ruta = Path::new(archivo);
+    if !ruta.exists() {
+        return Ok("AUSENTE".to_string());
+    }
+
+    let copia = ruta_wal.join(numero.to_string());
+    let guardado = match enlazar && fs::hard_link(ruta, &copia).is_ok() {
+        true => Ok(()),
+        false => fs::copy(ruta, &copia).and_then(|_| File::open(&copia)?.sync_all()),
+    };
+    match guardado {
+        Ok(_) => {
+            archivo_temporal::sincronizar_directorio(&copia);
+            Ok(format!("COPIA {}", numero))
+        }
+        Err(_e) => Err(MyError::Error(
+            "No se pudo registrar el estado anterior de la tabla".to_string(),
+        )),
+    }
+}
+
+fn leer_registro(ruta_wal: &Path) -> Result<Vec<String>, MyError> {
+    let mut contenido = String::new();
+    match File::open(ruta_wal.join("registro")).and_then(|mut r| r.read_to_string(&mut contenido)) {
+        Ok(_) => Ok(contenido.lines().map(|l| l.to_string()).collect()),
+        Err(_e) => Err(MyError::Error(
+            "No se pudo leer el registro de la instrucción".to_string(),
+        )),
+    }
+}
+
+///Una línea del registro: el estado anterior de un archivo seguido de su nombre, o *FIN*.
+enum Entrada {
+    Ausente(String),
+    Anexo(u64, String),
+    Copia(usize, String),
+    Fin,
+}
+
+impl Entrada {
+    fn nombre(&self) -> Option<&str> {
+        match self {
+            Entrada::Ausente(nombre) | Entrada::Anexo(_, nombre) | Entrada::Copia(_, nombre) => {
+                Some(nombre)
+            }
+            Entrada::Fin => None,
+        }
+    }
+}
+
+///Lee una línea del registro. El nombre del archivo va al final, después de los campos del
+///estado, por lo que puede tener espacios; los saltos de línea y las barras invertidas se guardan
+///escapados. Una línea que no se entiende es un error, para no dar por deshecho un cambio que no
+///se deshizo.
+fn leer_entrada(linea: &str) -> Result<Entrada, MyError> {
+    let error = || MyError::Error("El registro de la instrucción está dañado".to_string());
+    if linea == FIN {
+        return Ok(Entrada::Fin);
+    }
+
+    let (estado, resto) = linea.split_once(' ').ok_or_else(error)?;
+    match estado {
+        "AUSENTE" => Ok(Entrada::Ausente(desescapar(resto).ok_or_else(error)?)),
+        "ANEXO" | "COPIA" => {
+            let (numero, nombre) = resto.split_once(' ').ok_or_else(error)?;
+            let nombre = desescapar(nombre).ok_or_else(error)?;
+            match estado {
+                "ANEXO" => Ok(Entrada::Anexo(numero.parse().map_err(|_| error())?, nombre)),
+                _ => Ok(Entrada::Copia(numero.parse().map_err(|_| error())?, nombre)),
+            }
+        }
+        _ => Err(error()),
+    }
+}
+
+fn escapar(nombre: &str) -> String {
+    nombre
+        .replace('\\', "\\\\")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+}
+
+fn desescapar(nombre: &str) -> Option<String> {
+    let mut resultado = String::new();
+    let mut caracteres = nombre.chars();
+    while let Some(c) = caracteres.next() {
+        match c {
+            '\\' => match caracteres.next()? {
+                '\\' => resultado.push('\\'),
+                'n' => resultado.push('\n'),
+                'r' => resultado.push('\r'),
+                _ => return None,
+            },
+            c => resultado.push(c),
+        }
+    }
+    if resultado.is_empty() {
+        return None;
+    }
+    Some(resultado)
+}
+
+///Devuelve cada archivo registrado a su estado anterior, empezando por el último registrado. La
+///versión guardada se enlaza con otro nombre antes de publicarla, así sigue en el registro si la
+///restauración se corta y hay que repetirla.
+fn restaurar(ruta_wal: &Path) -> Result<(), MyError> {
+    let directorio = match ruta_wal.parent() {
+        Some(d) => d,
+        None => Path::new("."),
+    };
+    let error = || MyError::Error("No se pudo deshacer la instrucción incompleta".to_string());
+
+    for linea in leer_registro(ruta_wal)?.iter().rev() {
+        let restaurado = match leer_entrada(linea)? {
+            Entrada::Ausente(nombre) => match fs::remove_file(directorio.join(nombre)) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e),
+                _ => Ok(()),
+            },
+            Entrada::Anexo(largo, nombre) => OpenOptions::new()
+                .write(true)
+                .open(directorio.join(nombre))
+                .and_then(|f| {
+                    f.set_len(largo)?;
+                    f.sync_all()
+                }),
+            Entrada::Copia(numero, nombre) => {
+                let copia = ruta_wal.join(numero.to_string());
+                let temporal = ruta_wal.join(format!("{}.restaurando", numero));
+                let _ = fs::remove_file(&temporal);
+                fs::hard_link(&copia, &temporal)
+                    .or_else(|_| {
+                        fs::copy(&copia, &temporal)?;
+                        File::open(&temporal)?.sync_all()
+                    })
+                    .and_then(|_| fs::rename(&temporal, directorio.join(&nombre)))
+                    .map(|_| archivo_temporal::sincronizar_directorio(&directorio.join(&nombre)))
+            }
+            Entrada::Fin => Ok(()),
+        };
+
+        if restaurado.is_err() {
+            return Err(error());
+        }
+    }
+
+    archivo_temporal::sincronizar_directorio(ruta_wal);
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn preparar_base(directorio: &str) {
+        let _ = fs::remove_dir_all(directorio);
+        let _ = fs::create_dir_all(directorio);
+        let _ = fs::write(format!("{}/clientes.csv", directorio), "id,nombre\n1,Ana\n");
+        let _ = fs::write(format!("{}/pedidos.csv", directorio), "id,cliente\n7,1\n");
+    }
+
+    fn reemplazar(archivo: &str, contenido: &str) {
+        let nuevo = format!("{}.nuevo", archivo);
+        fs::write(&nuevo, contenido).unwrap();
+        fs::rename(&nuevo, archivo).unwrap();
+    }
+
+    #[test]
+    pub fn test01_se_deshacen_los_cambios_de_una_instruccion_que_fallo() {
+        let directorio = "./test/wal/deshacer";
+        preparar_base(directorio);
+        let clientes = format!("{}/clientes.csv", directorio);
+        let pedidos = format!("{}/pedidos.csv", directorio);
+        let nueva = format!("{}/nueva.csv", directorio);
+
+        let wal = Wal::iniciar(directorio).unwrap();
+        registrar(&clientes).unwrap();
+        reemplazar(&clientes, "id,nombre\n");
+        registrar(&clientes).unwrap();
+        reemplazar(&clientes, "roto");
+        registrar_anexo(&pedidos, 15).unwrap();
+        fs::write(&pedidos, "id,cliente\n7,1\n8,1\n").unwrap();
+        registrar(&nueva).unwrap();
+        fs::write(&nueva, "id\n").unwrap();
+        wal.deshacer().unwrap();
+
+        assert_eq!(fs::read_to_string(&clientes).unwrap(), "id,nombre\n1,Ana\n");
+        assert_eq!(fs::read_to_string(&pedidos).unwrap(), "id,cliente\n7,1\n");
+        assert!(!Path::new(&nueva).exists());
+        assert_eq!(fs::read_dir(directorio).unwrap().count(), 2);
+    }
+
+    #[test]
+    pub fn test02_la_recuperacion_deshace_solo_los_registros_sin_terminar() {
+        let directorio = "./test/wal/recuperar";
+        preparar_base(directorio);
+        let clientes = format!("{}/clientes.csv", directorio);
+        let registro_caido = format!("{}/.wal.caido", directorio);
+        let registro_terminado = format!("{}/.wal.terminado", directorio);
+
+        fs::create_dir_all(&registro_caido).unwrap();
+        fs::copy(&clientes, format!("{}/0", registro_caido)).unwrap();
+        fs::write(
+            format!("{}/registro", registro_caido),
+            "COPIA 0 clientes.csv\n",
+        )
+        .unwrap();
+        fs::create_dir_all(&registro_terminado).unwrap();
+        fs::write(
+            format!("{}/registro", registro_terminado),
+            "AUSENTE pedidos.csv\nFIN\n",
+        )
+        .unwrap();
+        fs::write(&clientes, "id,nombre\n").unwrap();
+
+        let deshechas = recuperar(directorio).unwrap();
+
+        assert_eq!(deshechas, 1);
+        assert_eq!(fs::read_to_string(&clientes).unwrap(), "id,nombre\n1,Ana\n");
+        assert!(Path::new(&format!("{}/pedidos.csv", directorio)).exists());
+        assert_eq!(fs::read_dir(directorio).unwrap().count(), 2);
+    }
+
+    #[test]
+    pub fn test03_se_restauran_archivos_con_espacios_y_no_se_borran_registros_a_medio_crear() {
+        let directorio = "./test/wal/nombres";
+        preparar_base(directorio);
+        let ventas = format!("{}/ventas 2024.csv", directorio);
+        let nueva = format!("{}/nueva tabla.csv", directorio);
+        let a_medio_crear = format!("{}/.wal.a_medio_crear", directorio);
+        let danado = format!("{}/.wal.danado", directorio);
+        fs::write(&ventas, "id\n1\n").unwrap();
+
+        let wal = Wal::iniciar(directorio).unwrap();
+        registrar(&ventas).unwrap();
+        reemplazar(&ventas, "id\n");
+        registrar(&ventas).unwrap();
+        registrar(&nueva).unwrap();
+        fs::write(&nueva, "id\n").unwrap();
+        wal.deshacer().unwrap();
+
+        fs::create_dir_all(&a_medio_crear).unwrap();
+        fs::create_dir_all(&danado).unwrap();
+        fs::write(format!("{}/registro", danado), "BORRADO clientes.csv\n").unwrap();
+        let recuperacion = recuperar(directorio);
+
+        assert_eq!(fs::read_to_string(&ventas).unwrap(), "id\n1\n");
+        assert!(!Path::new(&nueva).exists());
+        assert!(Path::new(&a_medio_crear).is_dir());
+        assert!(matches!(recuperacion, Err(MyError::Error(_))));
+        assert!(Path::new(&danado).is_dir());
+        assert_eq!(
+            desescapar(&escapar("a\\b\nc d")),
+            Some("a\\b\nc d".to_string())
+        );
+    }
+
+    #[test]
+    pub fn test04_se_restauran_los_archivos_de_los_subdirectorios_y_los_reescritos_en_el_lugar() {
+        let directorio = "./test/wal/subdirectorios";
+        preparar_base(directorio);
+        let particion = format!("{}/ventas/fecha=2026-10-01", directorio);
+        let parte = format!("{}/parte.csv", particion);
+        let nueva = format!("{}/parte 2.csv", particion);
+        let clientes = format!("{}/clientes.csv", directorio);
+        fs::create_dir_all(&particion).unwrap();
+        fs::write(&parte, "id,monto\n1,10\n").unwrap();
+
+        let wal = Wal::iniciar(directorio).unwrap();
+        registrar(&parte).unwrap();
+        reemplazar(&parte, "id,monto\n");
+        registrar(&nueva).unwrap();
+        fs::write(&nueva, "id,monto\n2,20\n").unwrap();
+        registrar_copia(&clientes).unwrap();
+        fs::write(&clientes, "id,nombre\n").unwrap();
+        let registro = leer_registro(&ruta_del_registro(Path::new(directorio))).unwrap();
+        wal.deshacer().unwrap();
+
+        assert_eq!(
+            registro,
+            vec![
+                "COPIA 0 ventas/fecha=2026-10-01/parte.csv",
+                "AUSENTE ventas/fecha=2026-10-01/parte 2.csv",
+                "COPIA 2 clientes.csv",
+            ]
+        );
+        assert_eq!(fs::read_to_string(&parte).unwrap(), "id,monto\n1,10\n");
+        assert!(!Path::new(&nueva).exists());
+        assert_eq!(fs::read_to_string(&clientes).unwrap(), "id,nombre\n1,Ana\n");
+    }
+}